@@ -0,0 +1,24 @@
+-- Add migration script here
+CREATE TABLE oidc_login_states(
+    state TEXT NOT NULL,
+    PRIMARY KEY (state),
+    code_verifier TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    issued_on timestamptz NOT NULL,
+    expires_on timestamptz NOT NULL,
+    used BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE TABLE user_identities(
+    id uuid NOT NULL,
+    PRIMARY KEY (id),
+    user_id uuid NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(user_id),
+    provider TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    email_address TEXT NOT NULL,
+    created_at timestamptz NOT NULL
+);
+
+CREATE UNIQUE INDEX user_identities_provider_subject_idx ON user_identities (provider, subject);
+CREATE INDEX user_identities_user_id_idx ON user_identities (user_id);
@@ -0,0 +1,27 @@
+-- Add migration script here
+CREATE TABLE audit_log(
+    id uuid PRIMARY KEY,
+    occurred_at timestamptz NOT NULL,
+    actor_user_id uuid,
+    action TEXT NOT NULL,
+    target_type TEXT,
+    target_id TEXT,
+    ip_address TEXT,
+    user_agent TEXT,
+    payload jsonb NOT NULL DEFAULT '{}'::jsonb
+);
+
+CREATE INDEX audit_log_occurred_at_idx ON audit_log (occurred_at);
+CREATE INDEX audit_log_actor_user_id_idx ON audit_log (actor_user_id);
+CREATE INDEX audit_log_action_idx ON audit_log (action);
+CREATE INDEX audit_log_target_id_idx ON audit_log (target_id);
+
+CREATE FUNCTION reject_audit_log_modification() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_append_only
+    BEFORE UPDATE OR DELETE ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION reject_audit_log_modification();
@@ -0,0 +1,28 @@
+-- Add migration script here
+CREATE TABLE user_sessions(
+    id uuid PRIMARY KEY,
+    user_id uuid NOT NULL REFERENCES users (user_id),
+    login_method TEXT NOT NULL,
+    ip_address TEXT,
+    user_agent TEXT,
+    created_at timestamptz NOT NULL,
+    expires_at timestamptz NOT NULL,
+    revoked_at timestamptz
+);
+
+CREATE INDEX user_sessions_user_id_idx ON user_sessions (user_id);
+
+CREATE TABLE login_history(
+    id uuid PRIMARY KEY,
+    user_id uuid,
+    email_address TEXT,
+    login_method TEXT NOT NULL,
+    succeeded BOOLEAN NOT NULL,
+    failure_reason TEXT,
+    ip_address TEXT,
+    user_agent TEXT,
+    session_id uuid,
+    occurred_at timestamptz NOT NULL
+);
+
+CREATE INDEX login_history_user_id_idx ON login_history (user_id);
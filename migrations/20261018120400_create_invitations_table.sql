@@ -0,0 +1,15 @@
+-- Add migration script here
+CREATE TABLE invitations(
+    id uuid PRIMARY KEY,
+    email_address TEXT NOT NULL,
+    user_group TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    invited_by uuid,
+    created_at timestamptz NOT NULL,
+    expires_at timestamptz NOT NULL,
+    accepted_at timestamptz,
+    accepted_user_id uuid REFERENCES users (user_id),
+    revoked_at timestamptz
+);
+
+CREATE INDEX invitations_email_address_idx ON invitations (email_address);
@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE subscriptions ADD COLUMN billing_source TEXT NOT NULL DEFAULT 'Stripe';
+ALTER TABLE subscriptions ALTER COLUMN stripe_subscription_id DROP NOT NULL;
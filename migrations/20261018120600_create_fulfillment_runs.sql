@@ -0,0 +1,29 @@
+-- Add migration script here
+CREATE TABLE fulfillment_runs(
+    id uuid PRIMARY KEY,
+    issue_date DATE NOT NULL,
+    cutoff timestamptz NOT NULL,
+    created_at timestamptz NOT NULL,
+    created_by uuid,
+    recipient_count INT NOT NULL,
+    duplicate_count INT NOT NULL
+);
+
+CREATE INDEX fulfillment_runs_issue_date_idx ON fulfillment_runs (issue_date);
+
+CREATE TABLE fulfillment_run_entries(
+    run_id uuid NOT NULL REFERENCES fulfillment_runs (id) ON DELETE CASCADE,
+    sequence INT NOT NULL,
+    subscription_id uuid NOT NULL,
+    subscriber_id uuid NOT NULL,
+    name TEXT NOT NULL,
+    address_line_1 TEXT NOT NULL,
+    address_line_2 TEXT,
+    city TEXT NOT NULL,
+    state TEXT NOT NULL,
+    postal_code TEXT NOT NULL,
+    merged_subscription_ids uuid[] NOT NULL DEFAULT '{}',
+    PRIMARY KEY (run_id, sequence)
+);
+
+CREATE INDEX fulfillment_run_entries_subscription_id_idx ON fulfillment_run_entries (subscription_id);
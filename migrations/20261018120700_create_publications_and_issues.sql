@@ -0,0 +1,28 @@
+-- Add migration script here
+CREATE TABLE publications(
+    id uuid NOT NULL,
+    PRIMARY KEY (id),
+    title TEXT NOT NULL,
+    description TEXT NULL,
+    created_at timestamptz NOT NULL
+);
+
+CREATE TABLE issues(
+    id uuid NOT NULL,
+    PRIMARY KEY (id),
+    publication_id uuid NOT NULL REFERENCES publications (id) ON DELETE CASCADE,
+    issue_number INT NOT NULL,
+    title TEXT NULL,
+    publish_date DATE NOT NULL,
+    cutoff_date DATE NOT NULL,
+    digital_asset_reference TEXT NULL,
+    created_at timestamptz NOT NULL,
+    UNIQUE (publication_id, issue_number)
+);
+
+CREATE INDEX issues_publication_id_publish_date_idx ON issues (publication_id, publish_date);
+
+ALTER TABLE subscriptions
+    ADD COLUMN publication_id uuid NULL REFERENCES publications (id);
+
+CREATE INDEX subscriptions_publication_id_idx ON subscriptions (publication_id);
@@ -0,0 +1,28 @@
+-- Add migration script here
+CREATE TABLE issue_broadcasts(
+    id uuid PRIMARY KEY,
+    issue_id uuid NOT NULL REFERENCES issues (id) ON DELETE CASCADE,
+    test_send BOOLEAN NOT NULL,
+    status TEXT NOT NULL,
+    created_at timestamptz NOT NULL,
+    created_by uuid,
+    completed_at timestamptz,
+    recipient_count INT NOT NULL
+);
+
+-- An issue goes out to subscribers once; test sends can be repeated.
+CREATE UNIQUE INDEX issue_broadcasts_issue_id_idx ON issue_broadcasts (issue_id) WHERE NOT test_send;
+
+CREATE TABLE issue_broadcast_recipients(
+    id uuid PRIMARY KEY,
+    broadcast_id uuid NOT NULL REFERENCES issue_broadcasts (id) ON DELETE CASCADE,
+    subscription_id uuid,
+    email_address TEXT NOT NULL,
+    status TEXT NOT NULL,
+    attempts INT NOT NULL DEFAULT 0,
+    last_error TEXT,
+    sent_at timestamptz,
+    UNIQUE (broadcast_id, email_address)
+);
+
+CREATE INDEX issue_broadcast_recipients_status_idx ON issue_broadcast_recipients (broadcast_id, status);
@@ -0,0 +1,18 @@
+-- Add migration script here
+CREATE TABLE email_outbox(
+    id uuid PRIMARY KEY,
+    recipients TEXT[] NOT NULL,
+    subject TEXT NOT NULL,
+    html_content TEXT NOT NULL,
+    text_content TEXT NOT NULL,
+    status TEXT NOT NULL,
+    attempts INT NOT NULL DEFAULT 0,
+    last_error TEXT,
+    -- When a pending email is next due, or when a claim on a sending one lapses.
+    next_attempt_at timestamptz NOT NULL,
+    created_at timestamptz NOT NULL,
+    sent_at timestamptz
+);
+
+CREATE INDEX email_outbox_due_idx ON email_outbox (next_attempt_at) WHERE status IN ('Pending', 'Sending');
+CREATE INDEX email_outbox_status_idx ON email_outbox (status, created_at);
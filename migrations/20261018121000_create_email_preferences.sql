@@ -0,0 +1,9 @@
+-- Add migration script here
+-- A subscriber without a row has the defaults.
+CREATE TABLE email_preferences(
+    subscriber_id uuid PRIMARY KEY,
+    issues BOOLEAN NOT NULL DEFAULT true,
+    renewal_reminders BOOLEAN NOT NULL DEFAULT true,
+    promotions BOOLEAN NOT NULL DEFAULT false,
+    updated_at timestamptz NOT NULL
+);
@@ -0,0 +1,20 @@
+-- Add migration script here
+-- Sign-ups to the free email newsletter, which need no account. A member is
+-- only on the list once they confirm from the emailed link.
+CREATE TABLE newsletter_members(
+    id uuid PRIMARY KEY,
+    email_address TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    status TEXT NOT NULL,
+    -- Of the pending confirmation link; cleared once it is used.
+    token_hash TEXT UNIQUE,
+    token_expires_at timestamptz,
+    -- What the member agreed to, and from where.
+    consent_text TEXT NOT NULL,
+    consent_ip_address TEXT,
+    consent_user_agent TEXT,
+    consented_at timestamptz NOT NULL,
+    confirmed_at timestamptz,
+    confirmed_ip_address TEXT,
+    created_at timestamptz NOT NULL
+);
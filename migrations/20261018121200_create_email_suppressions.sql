@@ -0,0 +1,10 @@
+-- Add migration script here
+-- Addresses the email provider reported as bounced, complained about or
+-- unsubscribed. Nothing is sent to them until an admin clears the row.
+CREATE TABLE email_suppressions(
+    email_address TEXT PRIMARY KEY,
+    reason TEXT NOT NULL,
+    detail TEXT NULL,
+    created_at timestamptz NOT NULL,
+    updated_at timestamptz NOT NULL
+);
@@ -0,0 +1,15 @@
+-- Add migration script here
+-- Where staff hear about subscription events: an email address, a
+-- Slack-compatible incoming webhook or a URL that takes a JSON POST.
+CREATE TABLE notification_channels(
+    id uuid PRIMARY KEY,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    target TEXT NOT NULL,
+    event_types TEXT[] NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    last_notified_at timestamptz NULL,
+    last_error TEXT NULL,
+    created_at timestamptz NOT NULL,
+    updated_at timestamptz NOT NULL
+);
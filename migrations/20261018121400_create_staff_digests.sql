@@ -0,0 +1,12 @@
+-- Add migration script here
+-- One row per staff digest sent, so each period goes out once however many
+-- servers are running.
+CREATE TABLE staff_digests(
+    id uuid PRIMARY KEY,
+    frequency TEXT NOT NULL,
+    period_start timestamptz NOT NULL,
+    period_end timestamptz NOT NULL,
+    report jsonb NOT NULL,
+    created_at timestamptz NOT NULL,
+    UNIQUE (frequency, period_end)
+);
@@ -0,0 +1,13 @@
+-- Add migration script here
+-- Every email the server would send, kept only in the local environment so
+-- developers and tests can read it back through GET /dev/emails.
+CREATE TABLE captured_emails(
+    id uuid PRIMARY KEY,
+    recipients TEXT[] NOT NULL,
+    subject TEXT NOT NULL,
+    html_content TEXT NOT NULL,
+    text_content TEXT NOT NULL,
+    unsubscribe_url TEXT NULL,
+    created_at timestamptz NOT NULL
+);
+CREATE INDEX captured_emails_created_at_idx ON captured_emails (created_at);
@@ -0,0 +1,30 @@
+-- Add migration script here
+-- Third-party systems told about subscription changes, and every delivery
+-- made to them. Deliveries outlive their endpoint as a log.
+CREATE TABLE webhook_endpoints(
+    id uuid PRIMARY KEY,
+    url TEXT NOT NULL,
+    description TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    event_types TEXT[] NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    created_at timestamptz NOT NULL,
+    updated_at timestamptz NOT NULL
+);
+
+CREATE TABLE webhook_deliveries(
+    id uuid PRIMARY KEY,
+    endpoint_id uuid NOT NULL,
+    event_id uuid NOT NULL,
+    event_type TEXT NOT NULL,
+    payload jsonb NOT NULL,
+    status TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_response_status INTEGER NULL,
+    last_error TEXT NULL,
+    next_attempt_at timestamptz NOT NULL,
+    created_at timestamptz NOT NULL,
+    delivered_at timestamptz NULL
+);
+CREATE INDEX webhook_deliveries_due_idx ON webhook_deliveries (status, next_attempt_at);
+CREATE INDEX webhook_deliveries_endpoint_idx ON webhook_deliveries (endpoint_id, created_at);
@@ -0,0 +1,18 @@
+-- Add migration script here
+-- Background work that has to survive a crash or a deploy. Workers claim due
+-- jobs with FOR UPDATE SKIP LOCKED, so several servers can share the table.
+CREATE TABLE jobs(
+    id uuid PRIMARY KEY,
+    kind TEXT NOT NULL,
+    payload jsonb NOT NULL,
+    status TEXT NOT NULL,
+    attempts INT NOT NULL DEFAULT 0,
+    last_error TEXT,
+    -- When a pending job is next due, or when a claim on a running one lapses.
+    run_at timestamptz NOT NULL,
+    created_at timestamptz NOT NULL,
+    finished_at timestamptz
+);
+
+CREATE INDEX jobs_due_idx ON jobs (run_at) WHERE status IN ('Pending', 'Running');
+CREATE INDEX jobs_status_idx ON jobs (status, created_at);
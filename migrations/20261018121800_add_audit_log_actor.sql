@@ -0,0 +1,4 @@
+-- Add migration script here
+-- Names the actor of changes that were not made by a user, e.g. `cli:<os user>`
+-- for the admin command line.
+ALTER TABLE audit_log ADD COLUMN actor TEXT;
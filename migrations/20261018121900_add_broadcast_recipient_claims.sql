@@ -0,0 +1,7 @@
+-- Add migration script here
+-- A claimed recipient belongs to the worker that claimed it until the claim
+-- expires; only then may another worker send to it, e.g. after a crash.
+ALTER TABLE issue_broadcast_recipients ADD COLUMN claimed_until timestamptz;
+
+-- Recipients claimed before claims expired can be taken over straight away.
+UPDATE issue_broadcast_recipients SET claimed_until = now() WHERE status = 'Sending';
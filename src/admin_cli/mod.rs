@@ -0,0 +1,769 @@
+//! Operations tasks for the `newsletter-admin` binary. Commands talk to the
+//! database through the same brokers as the HTTP handlers and are recorded in the
+//! audit log with `newsletter-admin` as the user agent and `cli:<os user>` as the actor.
+
+use anyhow::{anyhow, bail, Context};
+use std::path::PathBuf;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::password_hashing::hash_password;
+use crate::auth::request_metadata::RequestMetadata;
+use crate::background::staff_notifier::{post_to_channels, staff_email, StaffNotice};
+use crate::background::subscriber_emails::subscription_cancelled_email;
+use crate::background::subscription_history_storer::queue_subscription_history_event;
+use crate::configuration::{current_environment, Environment};
+use crate::db::email_outbox_db_broker::queue_email;
+use crate::db::fulfillment_db_broker::{get_fulfillment_run, get_fulfillment_run_entries};
+use crate::db::schema_migrations::{
+    get_migration_status, run_pending_migrations, MigrationState, MigrationStatus,
+};
+use crate::db::seed_db_broker::store_seed_data;
+use crate::db::session_db_broker::revoke_all_user_sessions;
+use crate::db::subscribers_db_broker::insert_subscriber;
+use crate::db::subscriptions_db_broker::{
+    cancel_subscription_by_subscription_id, retrieve_subscription_by_subscription_id,
+};
+use crate::db::users::{
+    demote_admin_to_user, get_all_users, get_user_by_user_id, insert_user, lock_enabled_admin_ids,
+    promote_user_to_admin, update_password,
+};
+use crate::db::webhook_event_db_broker::set_webhook_events_to_unprocessed;
+use crate::domain::audit_models::{AuditAction, AuditEvent};
+use crate::domain::fulfillment_models::{render_csv, render_fixed_width, CreateFulfillmentRun};
+use crate::domain::mailing_label_models::{render_labels, LabelLayout};
+use crate::domain::notification_channel_models::StaffEventType;
+use crate::domain::subscriber_models::NewSubscriber;
+use crate::domain::subscription_history_models::HistoryEventType;
+use crate::domain::subscription_models::{BillingSource, OverTheWireSubscription};
+use crate::domain::user_models::{OverTheWireUser, UserGroup};
+use crate::domain::valid_email::ValidEmail;
+use crate::domain::valid_name::ValidName;
+use crate::routes::audit::record_audit_event;
+use crate::routes::fulfillment::store_fulfillment_run;
+use crate::routes::subscription_import::import_subscriptions;
+use crate::seed::{generate_seed_data, SeedOptions, DEFAULT_SEED_PASSWORD};
+use crate::stripe_client::StripeClient;
+use crate::util::{generate_random_token, standardize_email};
+
+pub const ADMIN_CLI_USER_AGENT: &str = "newsletter-admin";
+
+/// The audit log actor for commands, naming the operator's account when it is known.
+pub fn cli_actor() -> String {
+    match std::env::var("USER").or_else(|_| std::env::var("USERNAME")) {
+        Ok(user) if !user.is_empty() => format!("cli:{}", user),
+        _ => "cli".to_string(),
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "newsletter-admin",
+    about = "Operations tasks for the newsletter signup service"
+)]
+pub struct Cli {
+    /// How results are printed.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text, global = true)]
+    pub output: OutputFormat,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Create an admin account, e.g. to bootstrap the first one.
+    CreateAdmin {
+        #[arg(long)]
+        email: String,
+        #[arg(long)]
+        name: String,
+        /// A random password is generated and printed when omitted.
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Make an existing user an admin.
+    Promote {
+        #[arg(long)]
+        user_id: Uuid,
+    },
+    /// Turn an admin back into a user. The last enabled admin cannot be demoted.
+    Demote {
+        #[arg(long)]
+        user_id: Uuid,
+    },
+    /// List all users.
+    ListUsers,
+    /// Set a new password for a user.
+    ResetPassword {
+        #[arg(long)]
+        user_id: Uuid,
+        /// A random password is generated and printed when omitted.
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Cancel a subscription here and, if Stripe bills it, in Stripe.
+    CancelSubscription {
+        #[arg(long)]
+        subscription_id: Uuid,
+    },
+    /// Import paper and digital subscribers billed outside of Stripe from a CSV file.
+    ImportSubscriptions {
+        #[arg(long)]
+        file: PathBuf,
+        /// Validate the file and report what would happen without storing anything.
+        #[arg(long)]
+        dry_run: bool,
+        /// Used for rows without a billing_source column value.
+        #[arg(long, value_enum, default_value_t = ImportBillingSource::Complimentary)]
+        billing_source: ImportBillingSource,
+    },
+    /// Build and store the paper mailing list for an issue, and print or save it.
+    FulfillmentExport {
+        /// YYYY-MM-DD
+        #[arg(long)]
+        issue_date: NaiveDate,
+        /// Leave out subscriptions cancelled before this RFC 3339 time; defaults to
+        /// the start of the issue date (UTC).
+        #[arg(long)]
+        cutoff: Option<DateTime<Utc>>,
+        #[arg(long, value_enum, default_value_t = FileFormat::Csv)]
+        format: FileFormat,
+        /// Write the file here instead of printing it.
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Write the mailing labels of a stored fulfillment run to a PDF file.
+    FulfillmentLabels {
+        #[arg(long)]
+        run_id: Uuid,
+        #[arg(long, value_enum, default_value_t = LabelFormat::Avery5160)]
+        layout: LabelFormat,
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Mark stored Stripe webhook events as unprocessed so they are handled again.
+    ReplayWebhookEvents {
+        /// Event ids to replay; may be repeated.
+        #[arg(long = "id")]
+        ids: Vec<Uuid>,
+        /// Replay every event received at or after this RFC 3339 time.
+        #[arg(long)]
+        since: Option<DateTime<Utc>>,
+    },
+    /// Fill a development database with fake users, subscriptions and Stripe records.
+    /// The same seed always produces the same data.
+    Seed {
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+        #[arg(long, default_value_t = 25)]
+        users: usize,
+        /// Password for every seeded account.
+        #[arg(long, default_value = DEFAULT_SEED_PASSWORD)]
+        password: String,
+    },
+    /// Inspect or apply database migrations.
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MigrateAction {
+    /// Show which migrations are applied, pending or unexpected.
+    Status,
+    /// Apply pending migrations.
+    Up {
+        /// Only list what would be applied.
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportBillingSource {
+    Complimentary,
+    External,
+}
+
+impl From<ImportBillingSource> for BillingSource {
+    fn from(billing_source: ImportBillingSource) -> Self {
+        match billing_source {
+            ImportBillingSource::Complimentary => BillingSource::Complimentary,
+            ImportBillingSource::External => BillingSource::External,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    Csv,
+    FixedWidth,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelFormat {
+    #[value(name = "avery-5160")]
+    Avery5160,
+    #[value(name = "thermal-4x6")]
+    Thermal4x6,
+}
+
+impl From<LabelFormat> for LabelLayout {
+    fn from(format: LabelFormat) -> Self {
+        match format {
+            LabelFormat::Avery5160 => LabelLayout::Avery5160,
+            LabelFormat::Thermal4x6 => LabelLayout::Thermal4x6,
+        }
+    }
+}
+
+/// The result of a command in both output formats.
+#[derive(Debug)]
+pub struct CommandOutput {
+    pub text: String,
+    pub json: serde_json::Value,
+}
+
+impl CommandOutput {
+    pub fn render(&self, format: OutputFormat) -> String {
+        match format {
+            OutputFormat::Text => self.text.clone(),
+            OutputFormat::Json => {
+                serde_json::to_string_pretty(&self.json).expect("Was not able to serialize.")
+            }
+        }
+    }
+}
+
+pub async fn run(
+    command: Command,
+    pool: &PgPool,
+    stripe_client: &StripeClient,
+) -> Result<CommandOutput, anyhow::Error> {
+    let metadata = RequestMetadata {
+        ip_address: None,
+        user_agent: Some(ADMIN_CLI_USER_AGENT.to_string()),
+    };
+
+    match command {
+        Command::CreateAdmin {
+            email,
+            name,
+            password,
+        } => create_admin(email, name, password, &metadata, pool).await,
+        Command::Promote { user_id } => promote(user_id, &metadata, pool).await,
+        Command::Demote { user_id } => demote(user_id, &metadata, pool).await,
+        Command::ListUsers => list_users(pool).await,
+        Command::ResetPassword { user_id, password } => {
+            reset_password(user_id, password, &metadata, pool).await
+        }
+        Command::CancelSubscription { subscription_id } => {
+            cancel_subscription(subscription_id, &metadata, pool, stripe_client).await
+        }
+        Command::ImportSubscriptions {
+            file,
+            dry_run,
+            billing_source,
+        } => import_subscriptions_file(file, dry_run, billing_source, &metadata, pool).await,
+        Command::FulfillmentExport {
+            issue_date,
+            cutoff,
+            format,
+            out,
+        } => {
+            let create_fulfillment_run = CreateFulfillmentRun { issue_date, cutoff };
+            fulfillment_export(create_fulfillment_run, format, out, &metadata, pool).await
+        }
+        Command::FulfillmentLabels {
+            run_id,
+            layout,
+            out,
+        } => fulfillment_labels(run_id, layout.into(), out, pool).await,
+        Command::ReplayWebhookEvents { ids, since } => {
+            replay_webhook_events(ids, since, pool).await
+        }
+        Command::Seed {
+            seed,
+            users,
+            password,
+        } => seed_database(SeedOptions { seed, users }, password, pool).await,
+        Command::Migrate { action } => migrate(action, pool).await,
+    }
+}
+
+async fn create_admin(
+    email: String,
+    name: String,
+    password: Option<String>,
+    metadata: &RequestMetadata,
+    pool: &PgPool,
+) -> Result<CommandOutput, anyhow::Error> {
+    let email_address = ValidEmail::parse(standardize_email(&email)).map_err(|e| anyhow!(e))?;
+    let name = ValidName::parse(name).map_err(|e| anyhow!(e))?;
+    let generated = password.is_none();
+    let password = password.unwrap_or_else(generate_random_token);
+    let hashed_password = hash_password(password.clone()).await;
+
+    let mut transaction = pool.begin().await?;
+    let user_id = match insert_user(
+        email_address.as_ref(),
+        &hashed_password,
+        UserGroup::ADMIN,
+        &mut transaction,
+    )
+    .await
+    {
+        Ok(user_id) => user_id,
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => bail!(
+            "A user with the email address {} already exists",
+            email_address
+        ),
+        Err(e) => return Err(e.into()),
+    };
+    insert_subscriber(
+        &NewSubscriber {
+            name,
+            email_address: email_address.clone(),
+            user_id: user_id.clone(),
+        },
+        &mut transaction,
+    )
+    .await
+    .map_err(|e| anyhow!("{:?}", e))?;
+    transaction.commit().await?;
+
+    record_audit_event(
+        AuditEvent::new(&cli_actor(), AuditAction::CreateAdmin).with_target("user", &user_id),
+        metadata,
+        pool,
+    )
+    .await;
+
+    let mut text = format!("Created admin {} ({})", email_address, user_id);
+    let mut output = json!({ "user_id": user_id, "email_address": email_address.as_ref() });
+    if generated {
+        text.push_str(&format!("\nGenerated password: {}", password));
+        output["password"] = json!(password);
+    }
+    Ok(CommandOutput { text, json: output })
+}
+
+async fn promote(
+    user_id: Uuid,
+    metadata: &RequestMetadata,
+    pool: &PgPool,
+) -> Result<CommandOutput, anyhow::Error> {
+    let user = get_user_by_user_id(&user_id.to_string(), pool)
+        .await
+        .with_context(|| format!("No user with id {}", user_id))?;
+    promote_user_to_admin(user_id, pool).await?;
+
+    record_audit_event(
+        AuditEvent::new(&cli_actor(), AuditAction::PromoteUser)
+            .with_target("user", user_id)
+            .with_payload(json!({ "user_group": UserGroup::ADMIN.as_str() })),
+        metadata,
+        pool,
+    )
+    .await;
+
+    Ok(CommandOutput {
+        text: format!("Promoted {} ({}) to ADMIN", user.email_address, user_id),
+        json: json!({ "user_id": user_id, "user_group": UserGroup::ADMIN.as_str() }),
+    })
+}
+
+async fn demote(
+    user_id: Uuid,
+    metadata: &RequestMetadata,
+    pool: &PgPool,
+) -> Result<CommandOutput, anyhow::Error> {
+    let user = get_user_by_user_id(&user_id.to_string(), pool)
+        .await
+        .with_context(|| format!("No user with id {}", user_id))?;
+
+    let mut transaction = pool.begin().await?;
+    let admin_ids = lock_enabled_admin_ids(&mut transaction).await?;
+    if admin_ids.contains(&user_id) && admin_ids.len() <= 1 {
+        transaction.rollback().await?;
+        bail!(
+            "{} is the last enabled admin and cannot be demoted",
+            user_id
+        );
+    }
+    demote_admin_to_user(user_id, &mut transaction).await?;
+    transaction.commit().await?;
+
+    record_audit_event(
+        AuditEvent::new(&cli_actor(), AuditAction::DemoteUser)
+            .with_target("user", user_id)
+            .with_payload(json!({ "user_group": UserGroup::USER.as_str() })),
+        metadata,
+        pool,
+    )
+    .await;
+
+    Ok(CommandOutput {
+        text: format!("Demoted {} ({}) to USER", user.email_address, user_id),
+        json: json!({ "user_id": user_id, "user_group": UserGroup::USER.as_str() }),
+    })
+}
+
+async fn list_users(pool: &PgPool) -> Result<CommandOutput, anyhow::Error> {
+    let users: Vec<OverTheWireUser> = get_all_users(pool)
+        .await?
+        .into_iter()
+        .map(OverTheWireUser::from)
+        .collect();
+
+    let text = users
+        .iter()
+        .map(|user| {
+            format!(
+                "{}\t{}\t{}{}",
+                user.user_id,
+                user.email_address,
+                user.user_group.as_str(),
+                if user.disabled { "\tdisabled" } else { "" }
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(CommandOutput {
+        text,
+        json: serde_json::to_value(users)?,
+    })
+}
+
+async fn reset_password(
+    user_id: Uuid,
+    password: Option<String>,
+    metadata: &RequestMetadata,
+    pool: &PgPool,
+) -> Result<CommandOutput, anyhow::Error> {
+    let user = get_user_by_user_id(&user_id.to_string(), pool)
+        .await
+        .with_context(|| format!("No user with id {}", user_id))?;
+
+    let generated = password.is_none();
+    let password = password.unwrap_or_else(generate_random_token);
+    let hashed_password = hash_password(password.clone()).await;
+    // Whoever knew the old password may still hold a session.
+    let mut transaction = pool.begin().await?;
+    update_password(&user.email_address, &hashed_password, &mut transaction).await?;
+    revoke_all_user_sessions(user_id, &mut transaction).await?;
+    transaction.commit().await?;
+
+    record_audit_event(
+        AuditEvent::new(&cli_actor(), AuditAction::ResetPassword).with_target("user", user_id),
+        metadata,
+        pool,
+    )
+    .await;
+
+    let mut text = format!("Reset the password of {} ({})", user.email_address, user_id);
+    let mut output = json!({ "user_id": user_id });
+    if generated {
+        text.push_str(&format!("\nGenerated password: {}", password));
+        output["password"] = json!(password);
+    }
+    Ok(CommandOutput { text, json: output })
+}
+
+async fn cancel_subscription(
+    subscription_id: Uuid,
+    metadata: &RequestMetadata,
+    pool: &PgPool,
+    stripe_client: &StripeClient,
+) -> Result<CommandOutput, anyhow::Error> {
+    let subscription = retrieve_subscription_by_subscription_id(subscription_id, pool)
+        .await
+        .with_context(|| format!("No subscription with id {}", subscription_id))?;
+    if !subscription.active {
+        return Ok(CommandOutput {
+            text: format!("Subscription {} was already cancelled", subscription_id),
+            json: json!({ "subscription_id": subscription_id, "cancelled": false }),
+        });
+    }
+
+    // Stripe is called before the transaction is opened, so a slow or failing
+    // call does not hold the subscription's row lock.
+    if subscription.billing_source == BillingSource::Stripe {
+        stripe_client
+            .cancel_stripe_subscription(subscription.stripe_subscription_id.clone())
+            .await
+            .context("Stripe refused to cancel the subscription")?;
+    }
+
+    let mut transaction = pool.begin().await?;
+    let cancelled_on =
+        cancel_subscription_by_subscription_id(subscription_id, &mut transaction).await?;
+    let cancelled = OverTheWireSubscription {
+        active: false,
+        subscription_cancelled_on_date: Some(cancelled_on),
+        ..subscription.clone()
+    };
+    // The server's outbox worker delivers them.
+    let staff_notice = StaffNotice::new(StaffEventType::Cancellation, cancelled.clone());
+    let emails = [
+        subscription_cancelled_email(&subscription),
+        staff_email(&staff_notice, pool).await,
+    ];
+    for email in emails.iter().flatten() {
+        queue_email(email, &mut transaction).await?;
+    }
+    // The server's job worker records the history and sends the webhooks.
+    queue_subscription_history_event(&cancelled, HistoryEventType::Cancelled, &mut transaction)
+        .await?;
+    transaction
+        .commit()
+        .await
+        .context("Cancelled in Stripe, but could not record the cancellation")?;
+
+    post_to_channels(&staff_notice, pool).await;
+    record_audit_event(
+        AuditEvent::new(&cli_actor(), AuditAction::CancelSubscription)
+            .with_target("subscription", subscription_id)
+            .with_payload(json!({
+                "subscriber_id": subscription.subscriber_id,
+                "stripe_subscription_id": subscription.stripe_subscription_id,
+            })),
+        metadata,
+        pool,
+    )
+    .await;
+
+    Ok(CommandOutput {
+        text: format!("Cancelled subscription {}", subscription_id),
+        json: json!({ "subscription_id": subscription_id, "cancelled": true }),
+    })
+}
+
+async fn import_subscriptions_file(
+    file: PathBuf,
+    dry_run: bool,
+    billing_source: ImportBillingSource,
+    metadata: &RequestMetadata,
+    pool: &PgPool,
+) -> Result<CommandOutput, anyhow::Error> {
+    let data =
+        std::fs::read(&file).with_context(|| format!("Could not read {}", file.display()))?;
+    let report = import_subscriptions(
+        &data,
+        dry_run,
+        billing_source.into(),
+        &cli_actor(),
+        metadata,
+        pool,
+    )
+    .await
+    .map_err(|e| anyhow!(e))?;
+
+    let verb = if dry_run { "Would create" } else { "Created" };
+    let mut text = format!(
+        "{} {}, skipped {}, invalid {}, failed {}",
+        verb, report.created, report.skipped, report.invalid, report.failed
+    );
+    for row in report.rows.iter().filter(|row| !row.errors.is_empty()) {
+        text.push_str(&format!(
+            "\nline {}\t{:?}\t{}",
+            row.line,
+            row.status,
+            row.errors.join("; ")
+        ));
+    }
+    Ok(CommandOutput {
+        text,
+        json: serde_json::to_value(report)?,
+    })
+}
+
+async fn fulfillment_export(
+    create_fulfillment_run: CreateFulfillmentRun,
+    format: FileFormat,
+    out: Option<PathBuf>,
+    metadata: &RequestMetadata,
+    pool: &PgPool,
+) -> Result<CommandOutput, anyhow::Error> {
+    let (run, entries) =
+        store_fulfillment_run(&create_fulfillment_run, &cli_actor(), metadata, pool).await?;
+    let file = match format {
+        FileFormat::Csv => render_csv(&entries),
+        FileFormat::FixedWidth => render_fixed_width(&entries),
+    };
+
+    let text = match &out {
+        Some(out) => {
+            std::fs::write(out, &file)
+                .with_context(|| format!("Could not write {}", out.display()))?;
+            format!(
+                "Run {} for {}: {} recipient(s), {} duplicate(s) merged, written to {}",
+                run.id,
+                run.issue_date,
+                run.recipient_count,
+                run.duplicate_count,
+                out.display()
+            )
+        }
+        None => file.trim_end_matches('\n').to_string(),
+    };
+    Ok(CommandOutput {
+        text,
+        json: json!({ "run": run, "entries": entries, "out": out }),
+    })
+}
+
+async fn fulfillment_labels(
+    run_id: Uuid,
+    layout: LabelLayout,
+    out: PathBuf,
+    pool: &PgPool,
+) -> Result<CommandOutput, anyhow::Error> {
+    let run = match get_fulfillment_run(run_id, pool).await {
+        Err(sqlx::Error::RowNotFound) => bail!("No fulfillment run with id {}", run_id),
+        run => run?,
+    };
+    let entries = get_fulfillment_run_entries(run_id, pool).await?;
+    let pdf = render_labels(
+        &entries,
+        layout,
+        &format!("Mailing labels {}", run.issue_date),
+    )
+    .map_err(|e| anyhow!("Could not render the labels: {}", e))?;
+    std::fs::write(&out, pdf).with_context(|| format!("Could not write {}", out.display()))?;
+
+    let pages = layout.page_count(entries.len());
+    Ok(CommandOutput {
+        text: format!(
+            "{} label(s) on {} page(s) for {}, written to {}",
+            entries.len(),
+            pages,
+            run.issue_date,
+            out.display()
+        ),
+        json: json!({ "run": run, "labels": entries.len(), "pages": pages, "out": out }),
+    })
+}
+
+async fn replay_webhook_events(
+    ids: Vec<Uuid>,
+    since: Option<DateTime<Utc>>,
+    pool: &PgPool,
+) -> Result<CommandOutput, anyhow::Error> {
+    if ids.is_empty() && since.is_none() {
+        bail!("Pass at least one --id or --since");
+    }
+    let replayed = set_webhook_events_to_unprocessed(&ids, since, pool).await?;
+
+    Ok(CommandOutput {
+        text: format!("Marked {} webhook event(s) for replay", replayed.len()),
+        json: json!({ "replayed": replayed }),
+    })
+}
+
+async fn seed_database(
+    options: SeedOptions,
+    password: String,
+    pool: &PgPool,
+) -> Result<CommandOutput, anyhow::Error> {
+    if matches!(current_environment(), Environment::Production) {
+        bail!("Refusing to seed a production database");
+    }
+
+    let data = generate_seed_data(options);
+    store_seed_data(&data, &hash_password(password).await, pool).await?;
+
+    let admin = data.users.first().map(|user| user.email_address.clone());
+    let mut text = format!(
+        "Seeded {} user(s), {} subscription(s), {} history event(s), {} checkout session(s) and {} webhook event(s) from seed {}",
+        data.users.len(),
+        data.subscriptions.len(),
+        data.history_events.len(),
+        data.checkout_sessions.len(),
+        data.webhook_events.len(),
+        options.seed
+    );
+    if let Some(admin) = &admin {
+        text.push_str(&format!("\nAdmin: {}", admin));
+    }
+    Ok(CommandOutput {
+        text,
+        json: json!({
+            "seed": options.seed,
+            "users": data.users.len(),
+            "subscriptions": data.subscriptions.len(),
+            "history_events": data.history_events.len(),
+            "checkout_sessions": data.checkout_sessions.len(),
+            "webhook_events": data.webhook_events.len(),
+            "admin_email_address": admin,
+        }),
+    })
+}
+
+async fn migrate(action: MigrateAction, pool: &PgPool) -> Result<CommandOutput, anyhow::Error> {
+    let statuses = get_migration_status(pool).await?;
+
+    match action {
+        MigrateAction::Status => Ok(CommandOutput {
+            text: statuses
+                .iter()
+                .map(|status| {
+                    format!(
+                        "{}\t{:?}\t{}",
+                        status.version, status.state, status.description
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            json: json!({ "migrations": statuses }),
+        }),
+        MigrateAction::Up { dry_run } => {
+            let unexpected: Vec<&MigrationStatus> = statuses
+                .iter()
+                .filter(|status| {
+                    !matches!(
+                        status.state,
+                        MigrationState::Applied | MigrationState::Pending
+                    )
+                })
+                .collect();
+            if let Some(status) = unexpected.first() {
+                bail!(
+                    "Refusing to migrate: migration {} is {:?}",
+                    status.version,
+                    status.state
+                );
+            }
+
+            let pending: Vec<&MigrationStatus> = statuses
+                .iter()
+                .filter(|status| status.state == MigrationState::Pending)
+                .collect();
+            if !dry_run {
+                run_pending_migrations(pool).await?;
+            }
+
+            let verb = if dry_run { "Would apply" } else { "Applied" };
+            let mut text = format!("{} {} migration(s)", verb, pending.len());
+            for status in &pending {
+                text.push_str(&format!("\n{}\t{}", status.version, status.description));
+            }
+            Ok(CommandOutput {
+                text,
+                json: json!({ "dry_run": dry_run, "migrations": pending }),
+            })
+        }
+    }
+}
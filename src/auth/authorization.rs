@@ -40,10 +40,25 @@ pub fn is_authorized_user_only(user_id: String, token: Claims) -> bool {
     false
 }
 
+pub fn is_authorized_fulfillment_or_admin(user_id: String, token: Claims) -> bool {
+    if (user_id == token.user_id)
+        && (token.group == UserGroup::ADMIN || token.group == UserGroup::FULFILLMENT)
+    {
+        return true;
+    }
+    tracing::event!(
+        Level::ERROR,
+        "User ID: {} is not authorized on a fulfillment API",
+        user_id
+    );
+    false
+}
+
 #[cfg(test)]
 mod tests {
     use crate::auth::authorization::{
-        is_authorized_admin_only, is_authorized_user_only, is_authorized_user_or_admin,
+        is_authorized_admin_only, is_authorized_fulfillment_or_admin, is_authorized_user_only,
+        is_authorized_user_or_admin,
     };
     use crate::auth::token::Claims;
     use crate::domain::user_models::UserGroup;
@@ -62,6 +77,7 @@ mod tests {
             sub: "".to_string(),
             exp: 0,
             iat: 0,
+            session_id: None,
         };
 
         assert_eq!(true, is_authorized_admin_only(user_id, claims));
@@ -83,6 +99,7 @@ mod tests {
                     sub: "".to_string(),
                     exp: 0,
                     iat: 0,
+                    session_id: None,
                 }
             )
         );
@@ -99,6 +116,7 @@ mod tests {
                     sub: "".to_string(),
                     exp: 0,
                     iat: 0,
+                    session_id: None,
                 }
             )
         );
@@ -115,6 +133,7 @@ mod tests {
                     sub: "".to_string(),
                     exp: 0,
                     iat: 0,
+                    session_id: None,
                 }
             )
         );
@@ -136,6 +155,7 @@ mod tests {
                     sub: "".to_string(),
                     exp: 0,
                     iat: 0,
+                    session_id: None,
                 }
             )
         );
@@ -152,6 +172,7 @@ mod tests {
                     sub: "".to_string(),
                     exp: 0,
                     iat: 0,
+                    session_id: None,
                 }
             )
         );
@@ -173,6 +194,7 @@ mod tests {
                     sub: "".to_string(),
                     exp: 0,
                     iat: 0,
+                    session_id: None,
                 }
             )
         );
@@ -194,6 +216,7 @@ mod tests {
                     sub: "".to_string(),
                     exp: 0,
                     iat: 0,
+                    session_id: None,
                 }
             )
         );
@@ -215,6 +238,7 @@ mod tests {
                     sub: "".to_string(),
                     exp: 0,
                     iat: 0,
+                    session_id: None,
                 }
             )
         );
@@ -231,8 +255,63 @@ mod tests {
                     sub: "".to_string(),
                     exp: 0,
                     iat: 0,
+                    session_id: None,
                 }
             )
         );
     }
+
+    #[test]
+    fn is_authorized_fulfillment_or_admin_passes() {
+        let user_id = Uuid::new_v4().to_string();
+
+        for group in [UserGroup::FULFILLMENT, UserGroup::ADMIN] {
+            assert!(is_authorized_fulfillment_or_admin(
+                user_id.clone(),
+                Claims {
+                    user_id: user_id.clone(),
+                    group,
+                    iss: "".to_string(),
+                    aud: "".to_string(),
+                    sub: "".to_string(),
+                    exp: 0,
+                    iat: 0,
+                    session_id: None,
+                }
+            ));
+        }
+    }
+
+    #[test]
+    fn is_authorized_fulfillment_or_admin_does_not_pass() {
+        let user_id = Uuid::new_v4().to_string();
+
+        assert!(!is_authorized_fulfillment_or_admin(
+            user_id.clone(),
+            Claims {
+                user_id: user_id.clone(),
+                group: UserGroup::USER,
+                iss: "".to_string(),
+                aud: "".to_string(),
+                sub: "".to_string(),
+                exp: 0,
+                iat: 0,
+                session_id: None,
+            }
+        ));
+
+        assert!(!is_authorized_fulfillment_or_admin(
+            user_id.clone(),
+            Claims {
+                user_id: Uuid::new_v4().to_string(),
+                group: UserGroup::FULFILLMENT,
+                iss: "".to_string(),
+                aud: "".to_string(),
+                sub: "".to_string(),
+                exp: 0,
+                iat: 0,
+                session_id: None,
+            }
+        ));
+    }
 }
@@ -1,3 +1,4 @@
 pub mod authorization;
 pub mod password_hashing;
+pub mod request_metadata;
 pub mod token;
@@ -0,0 +1,34 @@
+use std::convert::Infallible;
+use std::future::{ready, Ready};
+
+use actix_web::dev::Payload;
+use actix_web::http::header;
+use actix_web::{FromRequest, HttpRequest};
+
+/// Where a request came from, as recorded alongside security-relevant events.
+/// The IP address honours `Forwarded` / `X-Forwarded-For`, so it is only as
+/// trustworthy as the proxy in front of the service.
+#[derive(Debug, Clone, Default)]
+pub struct RequestMetadata {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl FromRequest for RequestMetadata {
+    type Error = Infallible;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let ip_address = req.connection_info().realip_remote_addr().map(String::from);
+        let user_agent = req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+
+        ready(Ok(RequestMetadata {
+            ip_address,
+            user_agent,
+        }))
+    }
+}
@@ -5,13 +5,18 @@ use std::time::SystemTime;
 
 use actix_web::dev::Payload;
 use actix_web::http::StatusCode;
+use actix_web::web::Data;
 use actix_web::{FromRequest, HttpRequest, ResponseError};
 use actix_web_httpauth::extractors::bearer::BearerAuth;
 use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use secrecy::ExposeSecret;
 use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
 
 use crate::configuration::get_configuration;
+use crate::db::session_db_broker::is_session_active;
+use crate::db::users::get_user_access;
 use crate::domain::user_models::UserGroup;
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -23,6 +28,9 @@ pub struct Claims {
     pub sub: String,
     pub exp: u64,
     pub iat: u64,
+    /// The `user_sessions` row behind this token, absent on tokens minted outside a login.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -61,17 +69,90 @@ impl FromRequest for Claims {
 
     fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
         let extractor = BearerAuth::extract(req);
+        let pool = req.app_data::<Data<PgPool>>().cloned();
+        let resetting_password = req.path() == PASSWORD_RESET_PATH;
 
         Box::pin(async move {
             let credentials = extractor.await.map_err(|_| TokenError::AuthError)?;
-            validate_token(String::from(credentials.token()))
+            let claims = validate_token(String::from(credentials.token()))?;
+            if let Some(pool) = pool {
+                reject_restricted_user(&claims, resetting_password, &pool).await?;
+                reject_revoked_session(&claims, &pool).await?;
+            }
+            Ok(claims)
         })
     }
 }
 
+/// The only route a user who must reset their password can call.
+const PASSWORD_RESET_PATH: &str = "/forgot_password/reset_password";
+
+/// A signed token stays valid until it expires, so disabling an account or forcing a
+/// password reset has to be checked against the database on every request.
+async fn reject_restricted_user(
+    claims: &Claims,
+    resetting_password: bool,
+    pool: &PgPool,
+) -> Result<(), TokenError> {
+    let user_id = match Uuid::parse_str(&claims.user_id) {
+        Ok(user_id) => user_id,
+        Err(_) => return Ok(()),
+    };
+
+    match get_user_access(user_id, pool).await {
+        Ok(access) if access.disabled => {
+            tracing::info!("Rejected a token for disabled user {}", user_id);
+            Err(TokenError::AuthError)
+        }
+        Ok(access) if access.password_reset_required && !resetting_password => {
+            tracing::info!(
+                "Rejected a token for user {} who must reset their password",
+                user_id
+            );
+            Err(TokenError::AuthError)
+        }
+        Ok(_) => Ok(()),
+        Err(_) => Err(TokenError::AuthError),
+    }
+}
+
+async fn reject_revoked_session(claims: &Claims, pool: &PgPool) -> Result<(), TokenError> {
+    let session_id = match &claims.session_id {
+        Some(session_id) => Uuid::parse_str(session_id).map_err(|_| TokenError::AuthError)?,
+        None => return Ok(()),
+    };
+
+    match is_session_active(session_id, pool).await {
+        Ok(true) => Ok(()),
+        Ok(false) => {
+            tracing::info!("Rejected a token for revoked session {}", session_id);
+            Err(TokenError::AuthError)
+        }
+        Err(_) => Err(TokenError::AuthError),
+    }
+}
+
 pub fn generate_token(user_id: String, user_group: UserGroup) -> String {
+    encode_token(user_id, user_group, None, get_now_in_seconds())
+}
+
+/// Mints a token tied to a stored session so it can be revoked before it expires.
+pub fn generate_session_token(
+    user_id: String,
+    user_group: UserGroup,
+    session_id: Uuid,
+    issued_at: u64,
+) -> String {
+    encode_token(user_id, user_group, Some(session_id.to_string()), issued_at)
+}
+
+fn encode_token(
+    user_id: String,
+    user_group: UserGroup,
+    session_id: Option<String>,
+    now: u64,
+) -> String {
     let auth_config = get_configuration().unwrap().auth_config;
-    let now = get_now_in_seconds();
     let claims = Claims {
         user_id: user_id.clone(),
         group: user_group,
@@ -80,6 +161,7 @@ pub fn generate_token(user_id: String, user_group: UserGroup) -> String {
         sub: user_id,
         iat: now,
         exp: get_expires_at(Option::Some(now)),
+        session_id,
     };
     let header = Header::new(Algorithm::HS512);
     let key = EncodingKey::from_secret(auth_config.signing_key.expose_secret().as_bytes());
@@ -0,0 +1,33 @@
+use sqlx::PgPool;
+
+use crate::background::staff_notifier::{notify_staff, StaffNotice};
+use crate::db::subscriptions_db_broker::retrieve_active_subscriptions_by_email;
+use crate::domain::email_suppression_models::DeliveryEvent;
+use crate::email_client::EmailClient;
+
+/// Tells staff when email to an active subscription hard-bounces, so that
+/// someone can get a working address from the subscriber.
+pub async fn notify_of_bounced_subscription_email(
+    event: &DeliveryEvent,
+    email_client: &EmailClient,
+    pool: &PgPool,
+) {
+    let subscriptions =
+        match retrieve_active_subscriptions_by_email(&event.email_address, pool).await {
+            Ok(subscriptions) if subscriptions.is_empty() => return,
+            Ok(subscriptions) => subscriptions,
+            Err(e) => {
+                tracing::error!(
+                    "Cannot look up the subscriptions of a bounced address: {:?}",
+                    e
+                );
+                return;
+            }
+        };
+    notify_staff(
+        StaffNotice::email_bounced(event.clone(), subscriptions),
+        email_client,
+        pool,
+    )
+    .await;
+}
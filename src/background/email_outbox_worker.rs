@@ -0,0 +1,115 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db::email_outbox_db_broker::{
+    claim_due_emails, claim_email, mark_email_failed, mark_email_sent, queue_email,
+};
+use crate::domain::email_outbox_models::{OutboxEmail, OutboxStatus};
+use crate::domain::valid_email::ValidEmail;
+use crate::email_client::EmailClient;
+
+const BATCH_SIZE: i64 = 20;
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Sends due emails from the outbox for as long as the server runs.
+pub fn start_email_outbox_worker(email_client: EmailClient, pool: &PgPool) {
+    let new_pool = pool.clone();
+
+    tokio::spawn(async move {
+        loop {
+            match send_due_emails(&email_client, &new_pool).await {
+                Ok(sent) if sent as i64 >= BATCH_SIZE => continue,
+                Ok(_) => {}
+                Err(e) => tracing::error!("Could not read the email outbox: {:?}", e),
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+/// Returns how many emails were attempted.
+pub async fn send_due_emails(
+    email_client: &EmailClient,
+    pool: &PgPool,
+) -> Result<usize, sqlx::Error> {
+    let emails = claim_due_emails(BATCH_SIZE, pool).await?;
+    let attempted = emails.len();
+    for email in emails {
+        deliver(email, email_client, pool).await?;
+    }
+    Ok(attempted)
+}
+
+/// Tries a freshly queued email straight away, once its transaction has
+/// committed. If that fails the worker retries it later.
+pub async fn send_queued_email(id: Uuid, email_client: &EmailClient, pool: &PgPool) {
+    let result = match claim_email(id, pool).await {
+        Ok(Some(email)) => deliver(email, email_client, pool).await.map(|_| ()),
+        // The worker got to it first.
+        Ok(None) => Ok(()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        tracing::error!("Could not send queued email {}: {:?}", id, e);
+    }
+}
+
+/// For emails that do not go with a change to the database: queues the email
+/// on its own and sends it.
+pub async fn queue_and_send_email(email: &OutboxEmail, email_client: &EmailClient, pool: &PgPool) {
+    let queued = match pool.begin().await {
+        Ok(mut transaction) => match queue_email(email, &mut transaction).await {
+            Ok(_) => transaction.commit().await,
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
+    };
+    match queued {
+        Ok(_) => send_queued_email(email.id, email_client, pool).await,
+        Err(e) => tracing::error!("Could not queue email {}: {:?}", email.id, e),
+    }
+}
+
+async fn deliver(
+    email: OutboxEmail,
+    email_client: &EmailClient,
+    pool: &PgPool,
+) -> Result<OutboxStatus, sqlx::Error> {
+    let recipients: Result<Vec<ValidEmail>, String> = email
+        .recipients
+        .iter()
+        .map(|recipient| ValidEmail::parse(recipient.clone()))
+        .collect();
+    let result = match recipients {
+        Ok(recipients) => email_client
+            .send_email(
+                recipients,
+                &email.subject,
+                &email.html_content,
+                &email.text_content,
+            )
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(report) => {
+            let note = (!report.suppressed.is_empty())
+                .then(|| format!("Suppressed: {}", report.suppressed.join(", ")));
+            mark_email_sent(&email, note.as_deref(), pool).await?;
+            Ok(OutboxStatus::Sent)
+        }
+        Err(e) => {
+            let status = mark_email_failed(&email, &e, pool).await?;
+            tracing::warn!(
+                "Could not send email {} (attempt {}, now {}): {}",
+                email.id,
+                email.attempts,
+                status.as_str(),
+                e
+            );
+            Ok(status)
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::configuration::get_configuration;
+use crate::db::broadcast_db_broker::{
+    claim_broadcast_recipients, complete_broadcast_if_done, get_broadcast, get_next_claim_expiry,
+    get_unfinished_broadcasts, set_recipient_status,
+};
+use crate::db::publications_db_broker::{get_issue, get_publication};
+use crate::db::subscriptions_db_broker::retrieve_subscription_by_subscription_id;
+use crate::domain::broadcast_models::{issue_email, BroadcastRecipient, RecipientStatus};
+use crate::domain::email_preference_models::{
+    one_click_unsubscribe_url, unsubscribe_link, EmailCategory,
+};
+use crate::domain::publication_models::{Issue, Publication};
+use crate::domain::valid_email::ValidEmail;
+use crate::email_client::EmailClient;
+
+/// How often a sender with nothing left to claim checks on the recipients
+/// claimed elsewhere.
+const CLAIM_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+pub fn start_broadcast(broadcast_id: Uuid, email_client: EmailClient, pool: &PgPool) {
+    let new_pool = pool.clone();
+
+    tokio::spawn(async move {
+        if let Err(e) = send_broadcast(broadcast_id, &email_client, &new_pool).await {
+            tracing::error!("Broadcast {} stopped: {:?}", broadcast_id, e);
+        }
+    });
+}
+
+/// Picks up the broadcasts that were interrupted by a restart. Recipients the
+/// stopped server had claimed are sent to once their claims expire.
+pub async fn resume_broadcasts(email_client: &EmailClient, pool: &PgPool) {
+    match get_unfinished_broadcasts(pool).await {
+        Ok(broadcast_ids) => {
+            for broadcast_id in broadcast_ids {
+                tracing::info!("Resuming broadcast {}", broadcast_id);
+                start_broadcast(broadcast_id, email_client.clone(), pool);
+            }
+        }
+        Err(e) => tracing::error!("Could not resume broadcasts: {:?}", e),
+    }
+}
+
+/// Sends the issue to the broadcast's pending recipients, one email each, in
+/// batches with a pause in between so the email provider's rate limit holds.
+pub async fn send_broadcast(
+    broadcast_id: Uuid,
+    email_client: &EmailClient,
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    let settings = get_configuration()
+        .unwrap()
+        .application_feature_settings
+        .clone();
+    let broadcast = get_broadcast(broadcast_id, pool).await?;
+    let issue = get_issue(broadcast.issue_id, pool).await?;
+    let publication = get_publication(issue.publication_id, pool).await?;
+
+    loop {
+        let recipients =
+            claim_broadcast_recipients(broadcast_id, settings.broadcast_batch_size, pool).await?;
+        if recipients.is_empty() {
+            // Wait for the recipients claimed elsewhere to be sent, or for the
+            // claims to run out so they can be taken over.
+            match get_next_claim_expiry(broadcast_id, pool).await? {
+                Some(claimed_until) => {
+                    let wait = (claimed_until - Utc::now())
+                        .to_std()
+                        .unwrap_or_default()
+                        .min(CLAIM_POLL_INTERVAL);
+                    tokio::time::sleep(wait).await;
+                    continue;
+                }
+                None => break,
+            }
+        }
+        let full_batch = recipients.len() as i64 >= settings.broadcast_batch_size;
+
+        for recipient in recipients {
+            let result = send_to_recipient(
+                &recipient,
+                &publication,
+                &issue,
+                broadcast.test_send,
+                email_client,
+                pool,
+            )
+            .await;
+            match result {
+                Ok(()) => {
+                    set_recipient_status(recipient.id, RecipientStatus::Sent, None, pool).await?
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Could not send broadcast {} to a recipient: {}",
+                        broadcast_id,
+                        e
+                    );
+                    set_recipient_status(recipient.id, RecipientStatus::Failed, Some(e), pool)
+                        .await?
+                }
+            }
+        }
+
+        if full_batch {
+            tokio::time::sleep(settings.broadcast_batch_delay()).await;
+        }
+    }
+
+    complete_broadcast_if_done(broadcast_id, pool).await?;
+    Ok(())
+}
+
+/// Subscribers get links to unsubscribe from issue emails; the admin's test
+/// sends have none.
+async fn send_to_recipient(
+    recipient: &BroadcastRecipient,
+    publication: &Publication,
+    issue: &Issue,
+    test_send: bool,
+    email_client: &EmailClient,
+    pool: &PgPool,
+) -> Result<(), String> {
+    let address = ValidEmail::parse(recipient.email_address.clone())?;
+    let subscriber_id = match recipient.subscription_id {
+        Some(subscription_id) => Some(
+            retrieve_subscription_by_subscription_id(subscription_id, pool)
+                .await
+                .map_err(|e| e.to_string())?
+                .subscriber_id,
+        ),
+        None => None,
+    };
+
+    match subscriber_id {
+        Some(subscriber_id) => {
+            let email = issue_email(
+                publication,
+                issue,
+                test_send,
+                Some(&unsubscribe_link(subscriber_id, EmailCategory::Issues)),
+            );
+            email_client
+                .send_email_with_unsubscribe(
+                    vec![address],
+                    &email.subject,
+                    &email.html_content,
+                    &email.text_content,
+                    &one_click_unsubscribe_url(subscriber_id, EmailCategory::Issues),
+                )
+                .await
+        }
+        None => {
+            let email = issue_email(publication, issue, test_send, None);
+            email_client
+                .send_email(
+                    vec![address],
+                    &email.subject,
+                    &email.html_content,
+                    &email.text_content,
+                )
+                .await
+        }
+    }
+    .map_err(|e| e.to_string())
+    .and_then(|report| match report.suppressed.is_empty() {
+        true => Ok(()),
+        false => Err("The address is on the suppression list".to_string()),
+    })
+}
@@ -0,0 +1,127 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::background::new_subscription_notifier::notify_subscriber;
+use crate::background::staff_notifier::{send_staff_notice, StaffNotice};
+use crate::background::subscription_history_storer::record_subscription_history_event;
+use crate::db::jobs_db_broker::{claim_due_job, mark_job_done, mark_job_failed, queue_job};
+use crate::domain::job_models::{Job, JobPayload, JobStatus};
+use crate::email_client::EmailClient;
+
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+/// How long shutdown waits for the job in hand. A job still running after
+/// that is retried by the next worker once its claim lapses.
+const DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// The running job worker, to be drained when the server stops.
+pub struct JobWorker {
+    shutdown: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
+impl JobWorker {
+    /// Stops claiming jobs and waits for the one in hand to finish.
+    pub async fn drain(self) {
+        let _ = self.shutdown.send(true);
+        match tokio::time::timeout(DRAIN_TIMEOUT, self.handle).await {
+            Ok(_) => tracing::info!("The job worker has stopped"),
+            Err(_) => tracing::warn!("Stopped waiting for the job worker to finish its job"),
+        }
+    }
+}
+
+/// Runs due jobs, one at a time, until drained.
+pub fn start_job_worker(email_client: EmailClient, pool: &PgPool) -> JobWorker {
+    let new_pool = pool.clone();
+    let (shutdown, mut stopping) = watch::channel(false);
+
+    let handle = tokio::spawn(async move {
+        while !*stopping.borrow() {
+            match run_due_job(&email_client, &new_pool).await {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(e) => tracing::error!("Could not read the jobs: {:?}", e),
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                _ = stopping.changed() => {}
+            }
+        }
+    });
+
+    JobWorker { shutdown, handle }
+}
+
+/// Runs the job that has been due longest. Returns whether there was one.
+pub async fn run_due_job(email_client: &EmailClient, pool: &PgPool) -> Result<bool, sqlx::Error> {
+    let job = match claim_due_job(pool).await? {
+        Some(job) => job,
+        None => return Ok(false),
+    };
+
+    match run(&job, email_client, pool).await {
+        Ok(()) => mark_job_done(&job, pool).await?,
+        Err(e) => {
+            let status = mark_job_failed(&job, &e, pool).await?;
+            let report = format!(
+                "Job {} ({}) failed (attempt {}, now {}): {}",
+                job.id,
+                job.kind,
+                job.attempts,
+                status.as_str(),
+                e
+            );
+            if status == JobStatus::Dead {
+                tracing::error!("{}", report);
+            } else {
+                tracing::warn!("{}", report);
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Queues the job on its own, for work that does not go with a change to the
+/// database. The worker runs it once `run_at` has passed.
+pub async fn schedule_job(
+    payload: &JobPayload,
+    run_at: DateTime<Utc>,
+    pool: &PgPool,
+) -> Result<Uuid, sqlx::Error> {
+    let job = Job::new(payload, run_at);
+    let mut transaction = pool.begin().await?;
+    queue_job(&job, &mut transaction).await?;
+    transaction.commit().await?;
+    Ok(job.id)
+}
+
+async fn run(job: &Job, email_client: &EmailClient, pool: &PgPool) -> Result<(), String> {
+    match job.payload()? {
+        JobPayload::StoreSubscriptionHistory {
+            subscription,
+            event_type,
+            occurred_at,
+        } => {
+            record_subscription_history_event(job.id, &subscription, event_type, occurred_at, pool)
+                .await
+                .map_err(|e| e.to_string())
+        }
+        JobPayload::NotifyNewSubscription { subscription_id } => {
+            notify_subscriber(job.id, subscription_id, email_client, pool)
+                .await
+                .map_err(|e| e.to_string())
+        }
+        JobPayload::NotifyStaff {
+            event_type,
+            subscription,
+        } => {
+            let notice = StaffNotice::new(event_type, *subscription);
+            send_staff_notice(job.id, &notice, email_client, pool)
+                .await
+                .map_err(|e| e.to_string())
+        }
+    }
+}
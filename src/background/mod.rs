@@ -1,2 +1,11 @@
+pub mod bounced_email_notifier;
+pub mod email_outbox_worker;
+pub mod issue_broadcaster;
+pub mod job_worker;
+pub mod new_device_notifier;
 pub mod new_subscription_notifier;
+pub mod staff_digest;
+pub mod staff_notifier;
+pub mod subscriber_emails;
 pub mod subscription_history_storer;
+pub mod webhook_dispatcher;
@@ -0,0 +1,82 @@
+use chrono::{DateTime, Utc};
+use serde_json::json;
+
+use crate::auth::request_metadata::RequestMetadata;
+use crate::domain::email_outbox_models::OutboxEmail;
+use crate::domain::session_models::LoginMethod;
+use crate::domain::valid_email::ValidEmail;
+use crate::email_client::templates::{render_email, EmailTemplate, RenderedEmail, DEFAULT_LOCALE};
+
+/// The notice for a login from a device or network the user has not used before, for the
+/// caller to queue with the session. None when the account's address cannot be mailed.
+pub fn new_device_notice(
+    email_address: &str,
+    login_method: LoginMethod,
+    metadata: &RequestMetadata,
+) -> Option<OutboxEmail> {
+    let recipient = match ValidEmail::parse(email_address.to_string()) {
+        Ok(recipient) => recipient,
+        Err(e) => {
+            tracing::error!("Cannot send a new device notice: {}", e);
+            return None;
+        }
+    };
+    Some(OutboxEmail::from_template(
+        vec![recipient],
+        new_device_email(login_method, metadata, Utc::now()),
+    ))
+}
+
+fn describe(value: &Option<String>) -> &str {
+    value.as_deref().unwrap_or("unknown")
+}
+
+fn new_device_email(
+    login_method: LoginMethod,
+    metadata: &RequestMetadata,
+    signed_in_on: DateTime<Utc>,
+) -> RenderedEmail {
+    render_email(
+        EmailTemplate::NewDeviceLogin,
+        DEFAULT_LOCALE,
+        json!({
+            "signed_in_on": signed_in_on.to_string(),
+            "login_method": login_method.as_str(),
+            "ip_address": describe(&metadata.ip_address),
+            "user_agent": describe(&metadata.user_agent),
+        }),
+    )
+    .expect("Email templates render")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use crate::auth::request_metadata::RequestMetadata;
+    use crate::background::new_device_notifier::new_device_email;
+    use crate::domain::session_models::LoginMethod;
+
+    fn get_metadata() -> RequestMetadata {
+        RequestMetadata {
+            ip_address: Some("203.0.113.7".to_string()),
+            user_agent: Some("<script>alert(1)</script>".to_string()),
+        }
+    }
+
+    #[test]
+    fn text_content_works() {
+        let content =
+            new_device_email(LoginMethod::Password, &get_metadata(), Utc::now()).text_content;
+        assert!(content.contains("203.0.113.7"));
+        assert!(content.contains("Password"));
+    }
+
+    #[test]
+    fn html_content_escapes_the_user_agent() {
+        let content = new_device_email(LoginMethod::Otp, &get_metadata(), Utc::now()).html_content;
+        assert!(content.contains("203.0.113.7"));
+        assert!(content.contains("&lt;script&gt;"));
+        assert!(!content.contains("<script>"));
+    }
+}
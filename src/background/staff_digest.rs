@@ -0,0 +1,114 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+
+use crate::background::staff_notifier::{notify_staff, StaffNotice};
+use crate::configuration::StaffDigestSettings;
+use crate::db::staff_digest_db_broker::{
+    claim_staff_digest, count_history_events_by_subscription_type, get_failed_payments,
+    get_next_print_cutoff, get_paper_anniversaries, get_pending_address_changes,
+};
+use crate::domain::staff_digest_models::{next_anniversary, StaffDigest, UpcomingRenewal};
+use crate::domain::subscription_history_models::HistoryEventType;
+use crate::email_client::EmailClient;
+
+const RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Sends each digest when it falls due, for as long as the server runs. A
+/// digest missed while the server was down goes out when it starts.
+pub fn start_staff_digest_scheduler(
+    settings: StaffDigestSettings,
+    email_client: EmailClient,
+    pool: &PgPool,
+) {
+    let new_pool = pool.clone();
+
+    tokio::spawn(async move {
+        loop {
+            let wait = match send_due_staff_digest(&settings, Utc::now(), &email_client, &new_pool)
+                .await
+            {
+                Ok(_) => {
+                    let next_due = settings.last_due(Utc::now()) + settings.frequency.period();
+                    (next_due - Utc::now()).to_std().unwrap_or(RETRY_INTERVAL)
+                }
+                Err(e) => {
+                    tracing::error!("Could not send the staff digest: {:?}", e);
+                    RETRY_INTERVAL
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+    });
+}
+
+/// Sends the digest for the latest period due by `now`, unless it was sent
+/// already. Returns the digest if this call sent it.
+pub async fn send_due_staff_digest(
+    settings: &StaffDigestSettings,
+    now: DateTime<Utc>,
+    email_client: &EmailClient,
+    pool: &PgPool,
+) -> Result<Option<StaffDigest>, sqlx::Error> {
+    let digest = build_staff_digest(settings, settings.last_due(now), pool).await?;
+    if !claim_staff_digest(&digest, pool).await? {
+        return Ok(None);
+    }
+
+    notify_staff(StaffNotice::digest(digest.clone()), email_client, pool).await;
+    Ok(Some(digest))
+}
+
+/// The digest for the period that ends at `period_end`.
+pub async fn build_staff_digest(
+    settings: &StaffDigestSettings,
+    period_end: DateTime<Utc>,
+    pool: &PgPool,
+) -> Result<StaffDigest, sqlx::Error> {
+    let period_start = period_end - settings.frequency.period();
+    let new_subscriptions = count_history_events_by_subscription_type(
+        HistoryEventType::Created,
+        period_start,
+        period_end,
+        pool,
+    )
+    .await?;
+    let cancelled_subscriptions = count_history_events_by_subscription_type(
+        HistoryEventType::Cancelled,
+        period_start,
+        period_end,
+        pool,
+    )
+    .await?;
+
+    let today = period_end.date_naive();
+    let horizon = today + Duration::days(settings.renewal_lookahead_days);
+    let mut upcoming_renewals: Vec<UpcomingRenewal> = get_paper_anniversaries(pool)
+        .await?
+        .into_iter()
+        .filter_map(|(subscription_id, subscription_name, month, day)| {
+            let renewal_date = next_anniversary(month, day, today)?;
+            (renewal_date < horizon).then_some(UpcomingRenewal {
+                subscription_id,
+                subscription_name,
+                renewal_date,
+            })
+        })
+        .collect();
+    upcoming_renewals.sort_by(|a, b| {
+        (a.renewal_date, &a.subscription_name).cmp(&(b.renewal_date, &b.subscription_name))
+    });
+
+    Ok(StaffDigest {
+        frequency: settings.frequency,
+        period_start,
+        period_end,
+        net_change: StaffDigest::total(&new_subscriptions)
+            - StaffDigest::total(&cancelled_subscriptions),
+        new_subscriptions,
+        cancelled_subscriptions,
+        failed_payments: get_failed_payments(period_start, period_end, pool).await?,
+        upcoming_renewals,
+        next_print_cutoff: get_next_print_cutoff(today, pool).await?,
+        pending_address_changes: get_pending_address_changes(pool).await?,
+    })
+}
@@ -0,0 +1,393 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use reqwest::Client;
+use serde_json::{json, Value};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::background::email_outbox_worker::{queue_and_send_email, send_queued_email};
+use crate::db::email_outbox_db_broker::queue_email_once;
+use crate::db::jobs_db_broker::queue_job;
+use crate::db::notification_channels_db_broker::{
+    get_notification_channels_for_event, record_notification_channel_delivery,
+};
+use crate::domain::email_outbox_models::OutboxEmail;
+use crate::domain::email_suppression_models::DeliveryEvent;
+use crate::domain::job_models::{Job, JobPayload};
+use crate::domain::notification_channel_models::{
+    NotificationChannel, NotificationChannelKind, StaffEventType,
+};
+use crate::domain::staff_digest_models::StaffDigest;
+use crate::domain::subscription_models::OverTheWireSubscription;
+use crate::domain::valid_email::ValidEmail;
+use crate::email_client::templates::{render_email, EmailTemplate, RenderedEmail, DEFAULT_LOCALE};
+use crate::email_client::EmailClient;
+
+const POST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Something staff are told about, through every notification channel
+/// subscribed to its event type.
+#[derive(Debug, Clone)]
+pub struct StaffNotice {
+    pub event_type: StaffEventType,
+    pub subscriptions: Vec<OverTheWireSubscription>,
+    /// What bounced, for `EmailBounced`.
+    pub bounce: Option<DeliveryEvent>,
+    /// The report, for `Digest`.
+    pub digest: Option<StaffDigest>,
+}
+
+impl StaffNotice {
+    pub fn new(event_type: StaffEventType, subscription: OverTheWireSubscription) -> Self {
+        StaffNotice {
+            event_type,
+            subscriptions: vec![subscription],
+            bounce: None,
+            digest: None,
+        }
+    }
+
+    pub fn email_bounced(
+        event: DeliveryEvent,
+        subscriptions: Vec<OverTheWireSubscription>,
+    ) -> Self {
+        StaffNotice {
+            event_type: StaffEventType::EmailBounced,
+            subscriptions,
+            bounce: Some(event),
+            digest: None,
+        }
+    }
+
+    pub fn digest(digest: StaffDigest) -> Self {
+        StaffNotice {
+            event_type: StaffEventType::Digest,
+            subscriptions: vec![],
+            bounce: None,
+            digest: Some(digest),
+        }
+    }
+
+    pub fn email(&self) -> RenderedEmail {
+        let (template, values) = match self.event_type {
+            StaffEventType::NewSubscription => (
+                EmailTemplate::NewSubscription,
+                json!({ "subscription": self.subscriptions.first() }),
+            ),
+            StaffEventType::EmailBounced => (
+                EmailTemplate::SubscriptionEmailBounced,
+                json!({
+                    "email_address": self.bounce.as_ref().map(|bounce| &bounce.email_address),
+                    "detail": self.bounce.as_ref().and_then(|bounce| bounce.detail.as_ref()),
+                    "subscriptions": self.subscriptions,
+                }),
+            ),
+            StaffEventType::Digest => (
+                EmailTemplate::StaffDigest,
+                self.digest.as_ref().map_or(json!({}), |digest| {
+                    json!({
+                        "digest": digest,
+                        "period_start": digest.period_start.format("%Y-%m-%d").to_string(),
+                        "period_end": digest.period_end.format("%Y-%m-%d").to_string(),
+                        "new_total": StaffDigest::total(&digest.new_subscriptions),
+                        "cancelled_total": StaffDigest::total(&digest.cancelled_subscriptions),
+                    })
+                }),
+            ),
+            _ => (
+                EmailTemplate::StaffSubscriptionEvent,
+                json!({
+                    "event_type": self.event_type.as_str(),
+                    "subscription": self.subscriptions.first(),
+                }),
+            ),
+        };
+        render_email(template, DEFAULT_LOCALE, values).expect("Email templates render")
+    }
+
+    /// The email's subject, then a line for the bounce and each subscription,
+    /// or the digest's totals.
+    pub fn chat_text(&self) -> String {
+        let mut lines = vec![self.email().subject];
+        if let Some(digest) = &self.digest {
+            lines.extend([
+                format!(
+                    "New: {}, cancelled: {}, net change: {}",
+                    StaffDigest::total(&digest.new_subscriptions),
+                    StaffDigest::total(&digest.cancelled_subscriptions),
+                    digest.net_change
+                ),
+                format!("Failed payments: {}", digest.failed_payments.len()),
+                format!(
+                    "Upcoming paper renewals: {}",
+                    digest.upcoming_renewals.len()
+                ),
+                format!(
+                    "Address changes for the next print run: {}",
+                    digest.pending_address_changes.len()
+                ),
+            ]);
+        }
+        if let Some(bounce) = &self.bounce {
+            lines.push(format!(
+                "{}: {}",
+                bounce.email_address,
+                bounce.detail.as_deref().unwrap_or("no detail")
+            ));
+        }
+        lines.extend(self.subscriptions.iter().map(|subscription| {
+            format!(
+                "{} ({} subscription for {}) {}",
+                subscription.subscription_name,
+                subscription.subscription_type.as_str(),
+                subscription.subscription_email_address,
+                subscription.id
+            )
+        }));
+        lines.join("\n")
+    }
+
+    /// The body posted to webhook channels.
+    pub fn payload(&self) -> Value {
+        json!({
+            "event_type": self.event_type.as_str(),
+            "occurred_at": Utc::now(),
+            "subscriptions": self.subscriptions,
+            "bounce": self.bounce.as_ref().map(|bounce| json!({
+                "email_address": bounce.email_address,
+                "detail": bounce.detail,
+            })),
+            "digest": self.digest,
+        })
+    }
+}
+
+/// For notices that do not go with a change to the database: emails and
+/// posts to every channel subscribed to the notice.
+pub async fn notify_staff(notice: StaffNotice, email_client: &EmailClient, pool: &PgPool) {
+    if let Some(email) = staff_email(&notice, pool).await {
+        queue_and_send_email(&email, email_client, pool).await;
+    }
+    post_staff_notice(notice, pool);
+}
+
+/// Queues a job to tell staff about the subscription, as the change leaves it,
+/// in the caller's transaction, so staff hear of the change if and only if it
+/// is committed.
+pub async fn queue_staff_notice(
+    event_type: StaffEventType,
+    subscription: &OverTheWireSubscription,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), sqlx::Error> {
+    let job = Job::new(
+        &JobPayload::NotifyStaff {
+            event_type,
+            subscription: Box::new(subscription.clone()),
+        },
+        Utc::now(),
+    );
+    queue_job(&job, transaction).await
+}
+
+/// Runs a staff notice job: queues the staff email under the job's id, so a
+/// job that runs again sends it once, then posts to the Slack and webhook
+/// channels. An error leaves the job to be retried; failed posts are recorded
+/// on their channels instead.
+pub async fn send_staff_notice(
+    job_id: Uuid,
+    notice: &StaffNotice,
+    email_client: &EmailClient,
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    if let Some(mut email) = staff_email(notice, pool).await {
+        email.id = job_id;
+        let mut transaction = pool.begin().await?;
+        queue_email_once(&email, &mut transaction).await?;
+        transaction.commit().await?;
+        send_queued_email(email.id, email_client, pool).await;
+    }
+    post_to_channels(notice, pool).await;
+    Ok(())
+}
+
+/// One email to every enabled email channel subscribed to the notice, to be
+/// queued with the change it reports. `None` when there are no such channels.
+pub async fn staff_email(notice: &StaffNotice, pool: &PgPool) -> Option<OutboxEmail> {
+    let recipients: Vec<ValidEmail> = channels_for(notice, pool)
+        .await
+        .into_iter()
+        .filter(|channel| channel.kind == NotificationChannelKind::Email)
+        .filter_map(|channel| ValidEmail::parse(channel.target).ok())
+        .collect();
+    if recipients.is_empty() {
+        return None;
+    }
+
+    Some(OutboxEmail::from_template(recipients, notice.email()))
+}
+
+/// Posts the notice to its Slack and webhook channels in the background.
+pub fn post_staff_notice(notice: StaffNotice, pool: &PgPool) {
+    let new_pool = pool.clone();
+
+    tokio::spawn(async move {
+        post_to_channels(&notice, &new_pool).await;
+    });
+}
+
+/// Posts the notice to every enabled Slack and webhook channel subscribed to
+/// it, recording on each channel how it went.
+pub async fn post_to_channels(notice: &StaffNotice, pool: &PgPool) {
+    for channel in channels_for(notice, pool).await {
+        let body = match channel.kind {
+            NotificationChannelKind::Email => continue,
+            NotificationChannelKind::Slack => json!({ "text": notice.chat_text() }),
+            NotificationChannelKind::Webhook => notice.payload(),
+        };
+        let error = post(&channel.target, &body)
+            .await
+            .err()
+            .map(|e| e.to_string());
+        if let Some(error) = &error {
+            tracing::warn!(
+                "Could not post to notification channel {}: {}",
+                channel.id,
+                error
+            );
+        }
+        if let Err(e) =
+            record_notification_channel_delivery(channel.id, error.as_deref(), pool).await
+        {
+            tracing::error!("Could not record a notification channel delivery: {:?}", e);
+        }
+    }
+}
+
+async fn channels_for(notice: &StaffNotice, pool: &PgPool) -> Vec<NotificationChannel> {
+    get_notification_channels_for_event(notice.event_type, pool)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::error!("Cannot look up the notification channels: {:?}", e);
+            vec![]
+        })
+}
+
+async fn post(url: &str, body: &Value) -> Result<(), reqwest::Error> {
+    Client::builder()
+        .timeout(POST_TIMEOUT)
+        .build()?
+        .post(url)
+        .json(body)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::background::staff_notifier::StaffNotice;
+    use crate::domain::email_suppression_models::{DeliveryEvent, SuppressionReason};
+    use crate::domain::notification_channel_models::StaffEventType;
+    use crate::domain::subscription_models::{
+        BillingSource, OverTheWireSubscription, SubscriptionType,
+    };
+    use chrono::{Datelike, Utc};
+    use uuid::Uuid;
+
+    fn get_sub() -> OverTheWireSubscription {
+        OverTheWireSubscription {
+            id: Uuid::new_v4(),
+            subscriber_id: Uuid::new_v4(),
+            subscription_name: "Joe Smith".to_string(),
+            subscription_mailing_address_line_1: "123 Main".to_string(),
+            subscription_mailing_address_line_2: "n/a".to_string(),
+            subscription_city: "Kansas City".to_string(),
+            subscription_state: "MO".to_string(),
+            subscription_postal_code: "64105".to_string(),
+            subscription_email_address: "someone@gmail.com".to_string(),
+            subscription_creation_date: Utc::now(),
+            subscription_cancelled_on_date: None,
+            subscription_anniversary_day: Utc::now().day(),
+            subscription_anniversary_month: Utc::now().month(),
+            subscription_renewal_date: "".to_string(),
+            active: true,
+            subscription_type: SubscriptionType::Digital,
+            stripe_subscription_id: Uuid::new_v4().to_string(),
+            billing_source: BillingSource::Stripe,
+            publication_id: None,
+        }
+    }
+
+    fn new_subscription_email(subscription: OverTheWireSubscription) -> StaffNotice {
+        StaffNotice::new(StaffEventType::NewSubscription, subscription)
+    }
+
+    #[test]
+    fn text_content_works() {
+        let subscription = get_sub();
+
+        let email_text_content = new_subscription_email(subscription.clone())
+            .email()
+            .text_content;
+        assert!(email_text_content.contains(subscription.subscription_name.as_str()))
+    }
+
+    #[test]
+    fn html_content_works() {
+        let subscription = get_sub();
+
+        let email_html_content = new_subscription_email(subscription.clone())
+            .email()
+            .html_content;
+        assert!(email_html_content.contains(subscription.subscription_name.as_str()))
+    }
+
+    #[test]
+    fn html_content_escapes_subscriber_fields() {
+        let mut subscription = get_sub();
+        subscription.subscription_name = "<b>Joe</b>".to_string();
+
+        let email = new_subscription_email(subscription).email();
+        assert!(email.html_content.contains("&lt;b&gt;Joe&lt;&#x2f;b&gt;"));
+        assert!(email.text_content.contains("<b>Joe</b>"));
+    }
+
+    #[test]
+    fn each_event_type_has_its_own_subject() {
+        let subject = |event_type| StaffNotice::new(event_type, get_sub()).email().subject;
+
+        assert_eq!("New Subscription", subject(StaffEventType::NewSubscription));
+        assert_eq!(
+            "Subscription Cancelled",
+            subject(StaffEventType::Cancellation)
+        );
+        assert_eq!(
+            "Subscription Payment Failed",
+            subject(StaffEventType::PaymentFailure)
+        );
+        assert_eq!(
+            "Subscription Address Changed",
+            subject(StaffEventType::AddressChange)
+        );
+    }
+
+    #[test]
+    fn chat_text_names_the_bounce_and_subscriptions() {
+        let subscription = get_sub();
+        let notice = StaffNotice::email_bounced(
+            DeliveryEvent {
+                email_address: "someone@gmail.com".to_string(),
+                reason: SuppressionReason::HardBounce,
+                detail: Some("550 User unknown".to_string()),
+            },
+            vec![subscription.clone()],
+        );
+
+        let text = notice.chat_text();
+        assert!(text.starts_with("Subscription email bounced: someone@gmail.com\n"));
+        assert!(text.contains("someone@gmail.com: 550 User unknown"));
+        assert!(text.contains(&subscription.id.to_string()));
+    }
+}
@@ -0,0 +1,214 @@
+use serde_json::json;
+
+use crate::configuration::get_configuration;
+use crate::domain::email_outbox_models::OutboxEmail;
+use crate::domain::subscriber_models::NewSubscriber;
+use crate::domain::subscription_models::{OverTheWireSubscription, SubscriptionType};
+use crate::domain::valid_email::ValidEmail;
+use crate::email_client::templates::{render_email, EmailTemplate, DEFAULT_LOCALE};
+
+/// Sent after `sign_up`.
+pub fn welcome_email(subscriber: &NewSubscriber) -> OutboxEmail {
+    let email = render_email(
+        EmailTemplate::Welcome,
+        DEFAULT_LOCALE,
+        json!({
+            "name": subscriber.name.as_ref(),
+            "manage_link": manage_link(),
+        }),
+    )
+    .expect("Email templates render");
+
+    OutboxEmail::from_template(vec![subscriber.email_address.clone()], email)
+}
+
+/// Sent to the subscriber once a checkout completes. `price` is what Stripe
+/// charged, when it is known.
+pub fn subscription_confirmation_email(
+    subscription: &OverTheWireSubscription,
+    price: Option<String>,
+) -> Option<OutboxEmail> {
+    subscription_email(
+        EmailTemplate::SubscriptionConfirmation,
+        subscription,
+        json!({
+            "price": price,
+            "address_lines": mailing_address(subscription),
+            "renewal_date": subscription.subscription_renewal_date,
+        }),
+    )
+}
+
+pub fn subscription_cancelled_email(subscription: &OverTheWireSubscription) -> Option<OutboxEmail> {
+    let cancelled_on = subscription
+        .subscription_cancelled_on_date
+        .unwrap_or_else(chrono::Utc::now)
+        .date_naive();
+    subscription_email(
+        EmailTemplate::SubscriptionCancelled,
+        subscription,
+        json!({ "cancelled_on": cancelled_on }),
+    )
+}
+
+/// Sent when a Paper subscription's mailing address changes.
+pub fn address_changed_email(
+    before: &OverTheWireSubscription,
+    after: &OverTheWireSubscription,
+) -> Option<OutboxEmail> {
+    if !mailing_address_changed(before, after) {
+        return None;
+    }
+    let address_lines = mailing_address(after)?;
+    subscription_email(
+        EmailTemplate::SubscriptionAddressChanged,
+        after,
+        json!({ "address_lines": address_lines }),
+    )
+}
+
+/// Whether a Paper subscription now goes to a different mailing address.
+pub fn mailing_address_changed(
+    before: &OverTheWireSubscription,
+    after: &OverTheWireSubscription,
+) -> bool {
+    let address_lines = mailing_address(after);
+    address_lines.is_some() && mailing_address(before) != address_lines
+}
+
+/// Amounts come from Stripe in the currency's smallest unit.
+pub fn format_price(amount: u64, currency: Option<&str>) -> String {
+    format!(
+        "{}.{:02} {}",
+        amount / 100,
+        amount % 100,
+        currency.unwrap_or("usd").to_uppercase()
+    )
+}
+
+/// Goes to the subscription's own email address, which need not be the
+/// account's. `None` if that address is not valid.
+fn subscription_email(
+    template: EmailTemplate,
+    subscription: &OverTheWireSubscription,
+    mut values: serde_json::Value,
+) -> Option<OutboxEmail> {
+    let recipient = match ValidEmail::parse(subscription.subscription_email_address.clone()) {
+        Ok(recipient) => recipient,
+        Err(e) => {
+            tracing::warn!("Not emailing subscription {}: {}", subscription.id, e);
+            return None;
+        }
+    };
+    values["name"] = json!(subscription.subscription_name);
+    values["plan"] = json!(subscription.subscription_type.as_str());
+    values["manage_link"] = json!(manage_link());
+    let email = render_email(template, DEFAULT_LOCALE, values).expect("Email templates render");
+
+    Some(OutboxEmail::from_template(vec![recipient], email))
+}
+
+/// Only Paper subscriptions are mailed anywhere.
+fn mailing_address(subscription: &OverTheWireSubscription) -> Option<Vec<String>> {
+    if !matches!(subscription.subscription_type, SubscriptionType::Paper) {
+        return None;
+    }
+    let city_line = format!(
+        "{}, {} {}",
+        subscription.subscription_city,
+        subscription.subscription_state,
+        subscription.subscription_postal_code
+    );
+    Some(
+        [
+            subscription.subscription_mailing_address_line_1.trim(),
+            subscription.subscription_mailing_address_line_2.trim(),
+            city_line.trim(),
+        ]
+        .into_iter()
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect(),
+    )
+}
+
+fn manage_link() -> String {
+    format!(
+        "{}/subscriber",
+        get_configuration().unwrap().application.web_app_host
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    use crate::background::subscriber_emails::{
+        address_changed_email, format_price, subscription_confirmation_email,
+    };
+    use crate::domain::subscription_models::{
+        BillingSource, OverTheWireSubscription, SubscriptionType,
+    };
+
+    fn paper_subscription() -> OverTheWireSubscription {
+        OverTheWireSubscription {
+            id: Uuid::new_v4(),
+            subscriber_id: Uuid::new_v4(),
+            subscription_name: "Joe Smith".to_string(),
+            subscription_mailing_address_line_1: "123 Main".to_string(),
+            subscription_mailing_address_line_2: "".to_string(),
+            subscription_city: "Kansas City".to_string(),
+            subscription_state: "MO".to_string(),
+            subscription_postal_code: "64105".to_string(),
+            subscription_email_address: "joe@example.com".to_string(),
+            subscription_creation_date: Utc::now(),
+            subscription_cancelled_on_date: None,
+            subscription_anniversary_day: 1,
+            subscription_anniversary_month: 1,
+            subscription_renewal_date: "1/1/2027".to_string(),
+            active: true,
+            subscription_type: SubscriptionType::Paper,
+            stripe_subscription_id: "sub_123".to_string(),
+            billing_source: BillingSource::Stripe,
+            publication_id: None,
+        }
+    }
+
+    #[test]
+    fn prices_are_shown_in_major_units() {
+        assert_eq!("25.00 USD", format_price(2500, Some("usd")));
+        assert_eq!("5.07 EUR", format_price(507, Some("eur")));
+        assert_eq!("0.00 USD", format_price(0, None));
+    }
+
+    #[test]
+    fn the_confirmation_includes_the_plan_price_address_and_renewal() {
+        let email =
+            subscription_confirmation_email(&paper_subscription(), Some("25.00 USD".to_string()))
+                .unwrap();
+        assert_eq!(vec!["joe@example.com".to_string()], email.recipients);
+        assert!(email.text_content.contains("Plan: Paper"));
+        assert!(email.text_content.contains("25.00 USD"));
+        assert!(email
+            .text_content
+            .contains("Mailing address: 123 Main, Kansas City, MO 64105"));
+        assert!(email.text_content.contains("Next renewal: 1/1/2027"));
+    }
+
+    #[test]
+    fn only_a_changed_paper_address_is_reported() {
+        let before = paper_subscription();
+        let mut after = before.clone();
+        after.subscription_name = "Joseph Smith".to_string();
+        assert!(address_changed_email(&before, &after).is_none());
+
+        after.subscription_city = "Independence".to_string();
+        let email = address_changed_email(&before, &after).unwrap();
+        assert!(email.text_content.contains("Independence, MO 64105"));
+
+        let mut digital = after.clone();
+        digital.subscription_type = SubscriptionType::Digital;
+        assert!(address_changed_email(&before, &digital).is_none());
+    }
+}
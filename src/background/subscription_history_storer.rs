@@ -1,27 +1,65 @@
-use crate::db::subscription_history_db_broker::insert_subscription_history_event;
-use crate::db::subscriptions_db_broker::retrieve_subscription_by_subscription_id;
+use crate::background::webhook_dispatcher::{queue_subscription_event, send_webhook_delivery};
+use crate::db::jobs_db_broker::queue_job;
+use crate::db::subscription_history_db_broker::insert_subscription_history_event_once;
+use crate::domain::job_models::{Job, JobPayload};
 use crate::domain::subscription_history_models::HistoryEventType;
-use sqlx::PgPool;
+use crate::domain::subscription_models::OverTheWireSubscription;
+use crate::domain::webhook_endpoint_models::SubscriptionEventType;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
-pub fn store_subscription_history_event(
-    subscription_id: Uuid,
+/// Queues a job to record the subscription, as the change leaves it, in the
+/// caller's transaction, so the history is stored if and only if the change is
+/// committed.
+pub async fn queue_subscription_history_event(
+    subscription: &OverTheWireSubscription,
     subscription_change_event_type: HistoryEventType,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), sqlx::Error> {
+    let job = Job::new(
+        &JobPayload::StoreSubscriptionHistory {
+            subscription: Box::new(subscription.clone()),
+            event_type: subscription_change_event_type,
+            occurred_at: Utc::now(),
+        },
+        Utc::now(),
+    );
+    queue_job(&job, transaction).await
+}
+
+/// Records the change in the subscription's history and queues it for the
+/// webhook endpoints subscribed to it, both under the job's id, so a job that
+/// is retried stores and announces the change once.
+pub async fn record_subscription_history_event(
+    job_id: Uuid,
+    subscription: &OverTheWireSubscription,
+    subscription_change_event_type: HistoryEventType,
+    occurred_at: DateTime<Utc>,
     pool: &PgPool,
-) {
-    let new_pool = pool.clone();
-    tokio::spawn(async move {
-        if let Ok(subscription) =
-            retrieve_subscription_by_subscription_id(subscription_id, &new_pool).await
-        {
-            if insert_subscription_history_event(
-                subscription,
-                subscription_change_event_type,
-                &new_pool,
-            )
-            .await
-            .is_ok()
-            {}
-        }
-    });
+) -> Result<(), sqlx::Error> {
+    let event_type = SubscriptionEventType::from_history(&subscription_change_event_type);
+    let mut transaction = pool.begin().await?;
+    insert_subscription_history_event_once(
+        job_id,
+        subscription,
+        subscription_change_event_type,
+        occurred_at,
+        &mut transaction,
+    )
+    .await?;
+    let deliveries = queue_subscription_event(
+        job_id,
+        event_type,
+        occurred_at,
+        subscription,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    for id in deliveries {
+        send_webhook_delivery(id, pool).await;
+    }
+    Ok(())
 }
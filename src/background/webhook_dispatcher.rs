@@ -0,0 +1,137 @@
+use chrono::{DateTime, Utc};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Client;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::db::webhook_endpoints_db_broker::{
+    claim_due_webhook_deliveries, claim_webhook_delivery, get_webhook_endpoint,
+    mark_webhook_delivered, mark_webhook_delivery_failed, queue_webhook_deliveries,
+};
+use crate::domain::subscription_models::OverTheWireSubscription;
+use crate::domain::webhook_endpoint_models::{
+    subscription_event_payload, webhook_signature, SubscriptionEventType, WebhookDelivery,
+    WebhookDeliveryStatus, EVENT_ID_HEADER, SIGNATURE_HEADER,
+};
+
+const BATCH_SIZE: i64 = 20;
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+const POST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Retries due webhook deliveries for as long as the server runs.
+pub fn start_webhook_delivery_worker(pool: &PgPool) {
+    let new_pool = pool.clone();
+
+    tokio::spawn(async move {
+        loop {
+            match deliver_due_webhooks(&new_pool).await {
+                Ok(attempted) if attempted as i64 >= BATCH_SIZE => continue,
+                Ok(_) => {}
+                Err(e) => tracing::error!("Could not read the webhook deliveries: {:?}", e),
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+/// Returns how many deliveries were attempted.
+pub async fn deliver_due_webhooks(pool: &PgPool) -> Result<usize, sqlx::Error> {
+    let deliveries = claim_due_webhook_deliveries(BATCH_SIZE, pool).await?;
+    let attempted = deliveries.len();
+    for delivery in deliveries {
+        deliver(delivery, pool).await?;
+    }
+    Ok(attempted)
+}
+
+/// Queues the event for every endpoint subscribed to it in the caller's
+/// transaction. Once that is committed, `send_webhook_delivery` makes the
+/// first attempts and leaves failed ones to the worker.
+pub async fn queue_subscription_event(
+    event_id: Uuid,
+    event_type: SubscriptionEventType,
+    occurred_at: DateTime<Utc>,
+    subscription: &OverTheWireSubscription,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<Vec<Uuid>, sqlx::Error> {
+    let payload = subscription_event_payload(event_id, event_type, occurred_at, subscription);
+    queue_webhook_deliveries(event_id, event_type, &payload, transaction).await
+}
+
+/// Tries a queued delivery straight away, unless the worker got to it first.
+pub async fn send_webhook_delivery(id: Uuid, pool: &PgPool) {
+    let result = match claim_webhook_delivery(id, pool).await {
+        Ok(Some(delivery)) => deliver(delivery, pool).await.map(|_| ()),
+        Ok(None) => Ok(()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        tracing::error!("Could not send webhook delivery {}: {:?}", id, e);
+    }
+}
+
+async fn deliver(
+    delivery: WebhookDelivery,
+    pool: &PgPool,
+) -> Result<WebhookDeliveryStatus, sqlx::Error> {
+    let (response_status, result) = match get_webhook_endpoint(delivery.endpoint_id, pool).await {
+        Ok(endpoint) if endpoint.enabled => post(&endpoint.url, &endpoint.secret, &delivery).await,
+        Ok(_) => (None, Err("The endpoint is disabled".to_string())),
+        Err(sqlx::Error::RowNotFound) => (None, Err("The endpoint was deleted".to_string())),
+        Err(e) => return Err(e),
+    };
+
+    match result {
+        Ok(()) => {
+            mark_webhook_delivered(&delivery, response_status.unwrap_or_default(), pool).await?;
+            Ok(WebhookDeliveryStatus::Delivered)
+        }
+        Err(e) => {
+            let status = mark_webhook_delivery_failed(&delivery, response_status, &e, pool).await?;
+            tracing::warn!(
+                "Could not deliver webhook {} (attempt {}, now {}): {}",
+                delivery.id,
+                delivery.attempts,
+                status.as_str(),
+                e
+            );
+            Ok(status)
+        }
+    }
+}
+
+/// Posts the payload, signed for this attempt. Any 2xx response counts as
+/// delivered.
+async fn post(
+    url: &str,
+    secret: &str,
+    delivery: &WebhookDelivery,
+) -> (Option<i32>, Result<(), String>) {
+    let body = delivery.payload.to_string();
+    let signature = webhook_signature(secret, Utc::now().timestamp(), &body);
+    let client = match Client::builder().timeout(POST_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => return (None, Err(e.to_string())),
+    };
+    let response = client
+        .post(url)
+        .header(CONTENT_TYPE, "application/json")
+        .header(EVENT_ID_HEADER, delivery.event_id.to_string())
+        .header(SIGNATURE_HEADER, signature)
+        .body(body)
+        .send()
+        .await;
+
+    match response {
+        Ok(response) => {
+            let status = response.status();
+            let result = if status.is_success() {
+                Ok(())
+            } else {
+                Err(format!("The endpoint responded {}", status))
+            };
+            (Some(status.as_u16() as i32), result)
+        }
+        Err(e) => (None, Err(e.to_string())),
+    }
+}
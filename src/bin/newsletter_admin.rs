@@ -0,0 +1,27 @@
+use clap::Parser;
+
+use newsletter_signup_service::admin_cli::{run, Cli};
+use newsletter_signup_service::configuration::{current_environment, get_configuration};
+use newsletter_signup_service::startup::get_connection_pool;
+use newsletter_signup_service::stripe_client::StripeClient;
+use newsletter_signup_service::telemetry::{get_subscriber, init_subscriber};
+
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+    let cli = Cli::parse();
+    // Logs go to stderr so that stdout only carries the command's output.
+    let subscriber = get_subscriber("newsletter-admin".into(), "warn".into(), std::io::stderr);
+    init_subscriber(subscriber);
+
+    let configuration = get_configuration().expect("Failed to read configuration.");
+    let pool = get_connection_pool(&configuration.database, &current_environment());
+    let stripe_client = StripeClient::new(
+        configuration.stripe_client.base_url.clone(),
+        configuration.stripe_client.api_secret_key.clone(),
+        configuration.stripe_client.timeout(),
+    );
+
+    let output = run(cli.command, &pool, &stripe_client).await?;
+    println!("{}", output.render(cli.output));
+    Ok(())
+}
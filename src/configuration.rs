@@ -1,4 +1,5 @@
 use cached::proc_macro::once;
+use chrono::{DateTime, Datelike, Utc, Weekday};
 use config::Config;
 use secrecy::{ExposeSecret, SecretString};
 use serde_aux::field_attributes::deserialize_number_from_string;
@@ -16,6 +17,8 @@ pub struct Settings {
     pub email_client: EmailClientSettings,
     pub stripe_client: StripeClientSettings,
     pub application_feature_settings: ApplicationFeatureSettings,
+    #[serde(default)]
+    pub oidc: Option<OidcSettings>,
 }
 
 #[derive(serde::Deserialize, Clone)]
@@ -29,8 +32,81 @@ pub struct ApplicationSettings {
 
 #[derive(serde::Deserialize, Clone)]
 pub struct ApplicationFeatureSettings {
-    #[serde(deserialize_with = "deserialize_vec_from_string_or_vec")]
+    /// Only used to make the first notification channels, each an email
+    /// channel for every staff event, while there are none.
+    #[serde(default, deserialize_with = "deserialize_vec_from_string_or_vec")]
     pub subscription_notification_addresses: Vec<String>,
+    /// How many issue emails go out before the broadcaster pauses.
+    #[serde(default = "default_broadcast_batch_size")]
+    pub broadcast_batch_size: i64,
+    #[serde(default = "default_broadcast_batch_delay_milliseconds")]
+    pub broadcast_batch_delay_milliseconds: u64,
+    /// A periodic report to the `Digest` notification channels; off unless set.
+    #[serde(default)]
+    pub staff_digest: Option<StaffDigestSettings>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct StaffDigestSettings {
+    pub frequency: DigestFrequency,
+    /// The hour, in UTC, the digest goes out.
+    #[serde(
+        default = "default_staff_digest_hour",
+        deserialize_with = "deserialize_number_from_string"
+    )]
+    pub hour: u32,
+    /// The day weekly digests go out.
+    #[serde(default = "default_staff_digest_weekday")]
+    pub weekday: Weekday,
+    /// How far ahead the digest lists paper renewals.
+    #[serde(
+        default = "default_renewal_lookahead_days",
+        deserialize_with = "deserialize_number_from_string"
+    )]
+    pub renewal_lookahead_days: i64,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestFrequency {
+    Daily,
+    Weekly,
+}
+
+impl DigestFrequency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DigestFrequency::Daily => "daily",
+            DigestFrequency::Weekly => "weekly",
+        }
+    }
+
+    pub fn period(&self) -> chrono::Duration {
+        match self {
+            DigestFrequency::Daily => chrono::Duration::days(1),
+            DigestFrequency::Weekly => chrono::Duration::weeks(1),
+        }
+    }
+}
+
+fn default_staff_digest_hour() -> u32 {
+    7
+}
+
+fn default_staff_digest_weekday() -> Weekday {
+    Weekday::Mon
+}
+
+fn default_renewal_lookahead_days() -> i64 {
+    14
+}
+
+fn default_broadcast_batch_size() -> i64 {
+    50
+}
+
+fn default_broadcast_batch_delay_milliseconds() -> u64 {
+    1000
 }
 
 #[derive(serde::Deserialize, Clone)]
@@ -42,6 +118,9 @@ pub struct DatabaseSettings {
     pub host: String,
     pub database_name: String,
     pub require_ssl: bool,
+    /// Apply pending migrations when the server starts. When off, migrations are run
+    /// with `newsletter-admin migrate up` and the server only checks the schema version.
+    pub auto_migrate: bool,
 }
 
 #[derive(serde::Deserialize, Clone)]
@@ -49,6 +128,9 @@ pub struct AuthConfig {
     pub audience: String,
     pub issuer: String,
     pub signing_key: SecretString,
+    /// Signs unsubscribe links, which never expire, so that they share nothing
+    /// with the session tokens signed by `signing_key`.
+    pub unsubscribe_signing_key: SecretString,
 }
 
 #[derive(serde::Deserialize, Clone)]
@@ -60,6 +142,64 @@ pub struct EmailClientSettings {
     pub reply_to_name: String,
     pub api_key: SecretString,
     pub timeout_milliseconds: u64,
+    /// Which provider delivers email. `base_url` and `api_key` are used by the
+    /// HTTP providers, `smtp` by `smtp` and `file_directory` by `file`.
+    #[serde(default)]
+    pub transport: EmailTransportKind,
+    #[serde(default)]
+    pub smtp: Option<SmtpSettings>,
+    #[serde(default = "default_email_file_directory")]
+    pub file_directory: String,
+    /// The secret path segment of the URL the provider posts delivery events
+    /// to. Without it the webhook is not served.
+    #[serde(default)]
+    pub webhook_token: Option<SecretString>,
+    /// Also keep every email in `captured_emails`, readable at `GET /dev/emails`.
+    /// Ignored in production.
+    #[serde(default)]
+    pub capture_emails: bool,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailTransportKind {
+    #[default]
+    Mailtrap,
+    SendGrid,
+    Smtp,
+    /// Writes each email to `file_directory` as an `.eml` file.
+    File,
+    /// Only logs each email.
+    Console,
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct SmtpSettings {
+    pub host: String,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub port: u16,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<SecretString>,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// TLS from the start, usually port 465.
+    Tls,
+    /// Upgrade with STARTTLS, usually port 587.
+    #[default]
+    StartTls,
+    /// Plain text, for a local catcher such as MailHog.
+    None,
+}
+
+fn default_email_file_directory() -> String {
+    "target/emails".to_string()
 }
 
 #[derive(serde::Deserialize, Clone)]
@@ -75,6 +215,22 @@ pub struct StripeClientSettings {
     pub paper_price_id: String,
 }
 
+#[derive(serde::Deserialize, Clone)]
+pub struct OidcSettings {
+    pub provider_name: String,
+    pub issuer_url: String,
+    pub client_id: String,
+    pub client_secret: SecretString,
+    pub redirect_url: String,
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: String,
+    pub timeout_milliseconds: u64,
+}
+
+fn default_oidc_scopes() -> String {
+    "openid email profile".to_string()
+}
+
 impl EmailClientSettings {
     pub fn sender(&self) -> Result<ValidEmail, String> {
         ValidEmail::parse(self.sender_email.clone())
@@ -83,6 +239,47 @@ impl EmailClientSettings {
     pub fn timeout(&self) -> std::time::Duration {
         std::time::Duration::from_millis(self.timeout_milliseconds)
     }
+
+    /// Whether emails are captured, which is never the case in production.
+    pub fn captures_emails(&self, environment: &Environment) -> bool {
+        self.capture_emails && !matches!(environment, Environment::Production)
+    }
+}
+
+impl ApplicationFeatureSettings {
+    pub fn broadcast_batch_delay(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.broadcast_batch_delay_milliseconds)
+    }
+}
+
+impl StaffDigestSettings {
+    /// Everything but the frequency left at its default.
+    pub fn new(frequency: DigestFrequency) -> Self {
+        StaffDigestSettings {
+            frequency,
+            hour: default_staff_digest_hour(),
+            weekday: default_staff_digest_weekday(),
+            renewal_lookahead_days: default_renewal_lookahead_days(),
+        }
+    }
+
+    /// The latest time, at or before `now`, a digest was due.
+    pub fn last_due(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let mut due = now
+            .date_naive()
+            .and_hms_opt(self.hour.min(23), 0, 0)
+            .unwrap()
+            .and_utc();
+        if due > now {
+            due -= chrono::Duration::days(1);
+        }
+        if self.frequency == DigestFrequency::Weekly {
+            while due.weekday() != self.weekday {
+                due -= chrono::Duration::days(1);
+            }
+        }
+        due
+    }
 }
 
 impl StripeClientSettings {
@@ -91,6 +288,12 @@ impl StripeClientSettings {
     }
 }
 
+impl OidcSettings {
+    pub fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.timeout_milliseconds)
+    }
+}
+
 #[once(result = true)]
 pub fn get_configuration() -> Result<Settings, config::ConfigError> {
     let base_path = std::env::current_dir().expect("Failed to determine the current directory");
@@ -178,9 +381,25 @@ impl DatabaseSettings {
 
 #[cfg(test)]
 mod tests {
-    use crate::configuration::get_configuration;
+    use crate::configuration::{
+        get_configuration, DigestFrequency, Environment, StaffDigestSettings,
+    };
+    use chrono::{DateTime, Utc, Weekday};
     use claims::assert_ok;
 
+    fn digest(frequency: DigestFrequency) -> StaffDigestSettings {
+        StaffDigestSettings {
+            frequency,
+            hour: 7,
+            weekday: Weekday::Mon,
+            renewal_lookahead_days: 14,
+        }
+    }
+
+    fn at(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time).unwrap().to_utc()
+    }
+
     #[test]
     fn get_configuration_works() {
         assert_ok!(get_configuration());
@@ -193,4 +412,48 @@ mod tests {
 
         assert_ok!(get_configuration());
     }
+
+    #[test]
+    fn emails_are_never_captured_in_production() {
+        let mut settings = get_configuration().unwrap().email_client;
+        settings.capture_emails = true;
+
+        assert!(settings.captures_emails(&Environment::Local));
+        assert!(!settings.captures_emails(&Environment::Production));
+        settings.capture_emails = false;
+        assert!(!settings.captures_emails(&Environment::Local));
+    }
+
+    #[test]
+    fn daily_digests_are_due_at_the_hour() {
+        let daily = digest(DigestFrequency::Daily);
+
+        assert_eq!(
+            at("2026-10-15T07:00:00Z"),
+            daily.last_due(at("2026-10-15T07:00:00Z"))
+        );
+        assert_eq!(
+            at("2026-10-14T07:00:00Z"),
+            daily.last_due(at("2026-10-15T06:59:59Z"))
+        );
+    }
+
+    #[test]
+    fn weekly_digests_are_due_on_the_weekday() {
+        let weekly = digest(DigestFrequency::Weekly);
+
+        // 2026-10-19 is a Monday.
+        assert_eq!(
+            at("2026-10-12T07:00:00Z"),
+            weekly.last_due(at("2026-10-18T23:00:00Z"))
+        );
+        assert_eq!(
+            at("2026-10-19T07:00:00Z"),
+            weekly.last_due(at("2026-10-19T08:00:00Z"))
+        );
+        assert_eq!(
+            at("2026-10-12T07:00:00Z"),
+            weekly.last_due(at("2026-10-19T06:00:00Z"))
+        );
+    }
 }
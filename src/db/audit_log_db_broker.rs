@@ -0,0 +1,123 @@
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::audit_models::{AuditEvent, AuditLogEntry, AuditLogQuery};
+
+#[tracing::instrument(
+    name = "Saving an audit log entry",
+    skip(event, ip_address, user_agent, pool),
+    fields(action = %event.action.as_str())
+)]
+pub async fn insert_audit_log_entry(
+    event: &AuditEvent,
+    ip_address: Option<&str>,
+    user_agent: Option<&str>,
+    pool: &PgPool,
+) -> Result<Uuid, sqlx::Error> {
+    let id = Uuid::new_v4();
+    sqlx::query!(
+        r#"INSERT INTO audit_log (
+            id,
+            occurred_at,
+            actor_user_id,
+            actor,
+            action,
+            target_type,
+            target_id,
+            ip_address,
+            user_agent,
+            payload
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)"#,
+        id,
+        Utc::now(),
+        event.actor_user_id,
+        event.actor,
+        event.action.as_str(),
+        event.target_type,
+        event.target_id,
+        ip_address,
+        user_agent,
+        event.payload
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(id)
+}
+
+#[tracing::instrument(name = "Search the audit log", skip(query, pool))]
+pub async fn search_audit_log(
+    query: &AuditLogQuery,
+    pool: &PgPool,
+) -> Result<(Vec<AuditLogEntry>, i64), sqlx::Error> {
+    let total = sqlx::query!(
+        r#"SELECT COUNT(*) AS "count!"
+            FROM audit_log
+            WHERE ($1::uuid IS NULL OR actor_user_id = $1)
+            AND ($2::text IS NULL OR action = $2)
+            AND ($3::text IS NULL OR target_id = $3)
+            AND ($4::timestamptz IS NULL OR occurred_at >= $4)
+            AND ($5::timestamptz IS NULL OR occurred_at < $5)"#,
+        query.actor_user_id,
+        query.action,
+        query.target_id,
+        query.from,
+        query.to
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?
+    .count;
+
+    let rows = sqlx::query!(
+        r#"SELECT id, occurred_at, actor_user_id, actor, action, target_type, target_id,
+            ip_address, user_agent, payload
+            FROM audit_log
+            WHERE ($1::uuid IS NULL OR actor_user_id = $1)
+            AND ($2::text IS NULL OR action = $2)
+            AND ($3::text IS NULL OR target_id = $3)
+            AND ($4::timestamptz IS NULL OR occurred_at >= $4)
+            AND ($5::timestamptz IS NULL OR occurred_at < $5)
+            ORDER BY occurred_at DESC, id
+            LIMIT $6 OFFSET $7"#,
+        query.actor_user_id,
+        query.action,
+        query.target_id,
+        query.from,
+        query.to,
+        query.page_size(),
+        query.offset()
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    let entries = rows
+        .into_iter()
+        .map(|row| AuditLogEntry {
+            id: row.id,
+            occurred_at: row.occurred_at,
+            actor_user_id: row.actor_user_id,
+            actor: row.actor,
+            action: row.action,
+            target_type: row.target_type,
+            target_id: row.target_id,
+            ip_address: row.ip_address,
+            user_agent: row.user_agent,
+            payload: row.payload,
+        })
+        .collect();
+
+    Ok((entries, total))
+}
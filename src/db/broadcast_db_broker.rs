@@ -0,0 +1,370 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::broadcast_models::{
+    BroadcastProgress, BroadcastRecipient, BroadcastStatus, IssueBroadcast, RecipientStatus,
+    BROADCAST_CLAIM_TIMEOUT_SECONDS,
+};
+
+#[tracing::instrument(
+    name = "Saving an issue broadcast in the database",
+    skip(broadcast, recipients, pool),
+    fields(broadcast_id = %broadcast.id, recipients = recipients.len())
+)]
+pub async fn insert_broadcast(
+    broadcast: &IssueBroadcast,
+    recipients: &[BroadcastRecipient],
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        r#"INSERT INTO issue_broadcasts (
+            id, issue_id, test_send, status, created_at, created_by, completed_at, recipient_count
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+        broadcast.id,
+        broadcast.issue_id,
+        broadcast.test_send,
+        broadcast.status.as_str(),
+        broadcast.created_at,
+        broadcast.created_by,
+        broadcast.completed_at,
+        broadcast.recipient_count,
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    for recipient in recipients {
+        sqlx::query!(
+            r#"INSERT INTO issue_broadcast_recipients (
+                id, broadcast_id, subscription_id, email_address, status
+                ) VALUES ($1, $2, $3, $4, $5)"#,
+            recipient.id,
+            recipient.broadcast_id,
+            recipient.subscription_id,
+            recipient.email_address,
+            recipient.status.as_str(),
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to execute query: {:?}", e);
+            e
+        })?;
+    }
+
+    transaction.commit().await
+}
+
+#[tracing::instrument(name = "Get an issue broadcast", skip(pool))]
+pub async fn get_broadcast(id: Uuid, pool: &PgPool) -> Result<IssueBroadcast, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"SELECT
+            id, issue_id, test_send, status, created_at, created_by, completed_at, recipient_count
+            FROM issue_broadcasts
+            WHERE id = $1"#,
+        id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(IssueBroadcast {
+        id: row.id,
+        issue_id: row.issue_id,
+        test_send: row.test_send,
+        status: BroadcastStatus::from_str(&row.status).unwrap_or(BroadcastStatus::Sending),
+        created_at: row.created_at,
+        created_by: row.created_by,
+        completed_at: row.completed_at,
+        recipient_count: row.recipient_count,
+    })
+}
+
+/// Newest first, with recipient counts by status.
+#[tracing::instrument(name = "Get the broadcasts of an issue", skip(pool))]
+pub async fn get_broadcasts_by_issue_id(
+    issue_id: Uuid,
+    pool: &PgPool,
+) -> Result<Vec<BroadcastProgress>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT
+            b.id,
+            b.issue_id,
+            b.test_send,
+            b.status,
+            b.created_at,
+            b.created_by,
+            b.completed_at,
+            b.recipient_count,
+            COUNT(r.id) FILTER (WHERE r.status IN ('Pending', 'Sending')) AS "pending_count!",
+            COUNT(r.id) FILTER (WHERE r.status = 'Sent') AS "sent_count!",
+            COUNT(r.id) FILTER (WHERE r.status = 'Failed') AS "failed_count!"
+            FROM issue_broadcasts b
+            LEFT JOIN issue_broadcast_recipients r ON r.broadcast_id = b.id
+            WHERE b.issue_id = $1
+            GROUP BY b.id
+            ORDER BY b.created_at DESC"#,
+        issue_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| BroadcastProgress {
+            broadcast: IssueBroadcast {
+                id: row.id,
+                issue_id: row.issue_id,
+                test_send: row.test_send,
+                status: BroadcastStatus::from_str(&row.status).unwrap_or(BroadcastStatus::Sending),
+                created_at: row.created_at,
+                created_by: row.created_by,
+                completed_at: row.completed_at,
+                recipient_count: row.recipient_count,
+            },
+            pending_count: row.pending_count,
+            sent_count: row.sent_count,
+            failed_count: row.failed_count,
+        })
+        .collect())
+}
+
+#[tracing::instrument(name = "Get the recipients of a broadcast", skip(pool))]
+pub async fn get_broadcast_recipients(
+    broadcast_id: Uuid,
+    pool: &PgPool,
+) -> Result<Vec<BroadcastRecipient>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT
+            id, broadcast_id, subscription_id, email_address, status, attempts, last_error, sent_at
+            FROM issue_broadcast_recipients
+            WHERE broadcast_id = $1
+            ORDER BY email_address"#,
+        broadcast_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| BroadcastRecipient {
+            id: row.id,
+            broadcast_id: row.broadcast_id,
+            subscription_id: row.subscription_id,
+            email_address: row.email_address,
+            status: RecipientStatus::from_str(&row.status).unwrap_or(RecipientStatus::Pending),
+            attempts: row.attempts,
+            last_error: row.last_error,
+            sent_at: row.sent_at,
+        })
+        .collect())
+}
+
+/// Marks up to `limit` pending recipients as `Sending` and returns them,
+/// including ones whose previous claim lapsed because the worker holding them
+/// died. Rows locked by another worker are skipped, so a recipient is only
+/// claimed once at a time.
+#[tracing::instrument(name = "Claim a batch of broadcast recipients", skip(pool))]
+pub async fn claim_broadcast_recipients(
+    broadcast_id: Uuid,
+    limit: i64,
+    pool: &PgPool,
+) -> Result<Vec<BroadcastRecipient>, sqlx::Error> {
+    let now = Utc::now();
+    let rows = sqlx::query!(
+        r#"UPDATE issue_broadcast_recipients
+            SET status = 'Sending', attempts = attempts + 1, claimed_until = $3
+            WHERE id IN (
+                SELECT id FROM issue_broadcast_recipients
+                WHERE broadcast_id = $1
+                    AND (status = 'Pending' OR (status = 'Sending' AND claimed_until <= $4))
+                ORDER BY email_address
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING
+                id, broadcast_id, subscription_id, email_address, status, attempts, last_error, sent_at"#,
+        broadcast_id,
+        limit,
+        now + Duration::seconds(BROADCAST_CLAIM_TIMEOUT_SECONDS),
+        now
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    let mut recipients: Vec<BroadcastRecipient> = rows
+        .into_iter()
+        .map(|row| BroadcastRecipient {
+            id: row.id,
+            broadcast_id: row.broadcast_id,
+            subscription_id: row.subscription_id,
+            email_address: row.email_address,
+            status: RecipientStatus::from_str(&row.status).unwrap_or(RecipientStatus::Sending),
+            attempts: row.attempts,
+            last_error: row.last_error,
+            sent_at: row.sent_at,
+        })
+        .collect();
+    recipients.sort_by(|a, b| a.email_address.cmp(&b.email_address));
+    Ok(recipients)
+}
+
+#[tracing::instrument(name = "Record a broadcast delivery", skip(last_error, pool))]
+pub async fn set_recipient_status(
+    id: Uuid,
+    status: RecipientStatus,
+    last_error: Option<String>,
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    let sent_at = (status == RecipientStatus::Sent).then(Utc::now);
+    sqlx::query!(
+        r#"UPDATE issue_broadcast_recipients
+            SET status = $1, last_error = $2, sent_at = $3
+            WHERE id = $4"#,
+        status.as_str(),
+        last_error,
+        sent_at,
+        id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+/// Completes the broadcast once no recipient is left to send to. Returns
+/// whether it did.
+#[tracing::instrument(name = "Complete a broadcast", skip(pool))]
+pub async fn complete_broadcast_if_done(
+    broadcast_id: Uuid,
+    pool: &PgPool,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"UPDATE issue_broadcasts
+            SET status = 'Completed', completed_at = $1
+            WHERE id = $2
+            AND status = 'Sending'
+            AND NOT EXISTS (
+                SELECT 1 FROM issue_broadcast_recipients
+                WHERE broadcast_id = $2 AND status IN ('Pending', 'Sending')
+            )"#,
+        Utc::now(),
+        broadcast_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Puts failed recipients back in the queue and reopens the broadcast.
+/// Returns how many were requeued.
+#[tracing::instrument(name = "Requeue failed broadcast recipients", skip(pool))]
+pub async fn requeue_failed_recipients(
+    broadcast_id: Uuid,
+    pool: &PgPool,
+) -> Result<u64, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let result = sqlx::query!(
+        r#"UPDATE issue_broadcast_recipients
+            SET status = 'Pending'
+            WHERE broadcast_id = $1 AND status = 'Failed'"#,
+        broadcast_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    if result.rows_affected() > 0 {
+        sqlx::query!(
+            r#"UPDATE issue_broadcasts
+                SET status = 'Sending', completed_at = NULL
+                WHERE id = $1"#,
+            broadcast_id
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to execute query: {:?}", e);
+            e
+        })?;
+    }
+
+    transaction.commit().await?;
+    Ok(result.rows_affected())
+}
+
+/// When the earliest claim on the broadcast's recipients runs out, if any are
+/// claimed. Those recipients are being sent to elsewhere, or can be claimed
+/// again at that point.
+#[tracing::instrument(name = "Get the next broadcast claim expiry", skip(pool))]
+pub async fn get_next_claim_expiry(
+    broadcast_id: Uuid,
+    pool: &PgPool,
+) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"SELECT MIN(claimed_until) AS claimed_until
+            FROM issue_broadcast_recipients
+            WHERE broadcast_id = $1 AND status = 'Sending'"#,
+        broadcast_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(row.claimed_until)
+}
+
+/// Broadcasts that were still going when the server stopped. Their claimed
+/// recipients are left to the claims' expiry, as another server may still be
+/// sending to them.
+#[tracing::instrument(name = "Get unfinished broadcasts", skip(pool))]
+pub async fn get_unfinished_broadcasts(pool: &PgPool) -> Result<Vec<Uuid>, sqlx::Error> {
+    let rows = sqlx::query!(r#"SELECT id FROM issue_broadcasts WHERE status = 'Sending'"#)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to execute query: {:?}", e);
+            e
+        })?;
+
+    Ok(rows.into_iter().map(|row| row.id).collect())
+}
@@ -0,0 +1,81 @@
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::captured_email_models::{find_links, CapturedEmail};
+use crate::email_client::EmailMessage;
+
+#[tracing::instrument(
+    name = "Capture an email",
+    skip(message, pool),
+    fields(subject = %message.subject)
+)]
+pub async fn insert_captured_email(
+    message: &EmailMessage<'_>,
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    let recipients: Vec<String> = message
+        .recipients
+        .iter()
+        .map(|recipient| recipient.to_string())
+        .collect();
+    sqlx::query!(
+        r#"INSERT INTO captured_emails (id, recipients, subject, html_content, text_content,
+            unsubscribe_url, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+        Uuid::new_v4(),
+        &recipients,
+        message.subject,
+        message.html_content,
+        message.text_content,
+        message.unsubscribe_url,
+        Utc::now()
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+/// Captured emails, newest first, optionally only those to `recipient`.
+#[tracing::instrument(name = "Get captured emails", skip(pool))]
+pub async fn get_captured_emails(
+    recipient: Option<&str>,
+    limit: i64,
+    pool: &PgPool,
+) -> Result<Vec<CapturedEmail>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT id, recipients, subject, html_content, text_content, unsubscribe_url, created_at
+            FROM captured_emails
+            WHERE $1::TEXT IS NULL
+                OR LOWER($1) = ANY(SELECT LOWER(recipient) FROM UNNEST(recipients) AS recipient)
+            ORDER BY created_at DESC
+            LIMIT $2"#,
+        recipient,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| CapturedEmail {
+            id: row.id,
+            links: find_links(&row.text_content, row.unsubscribe_url.as_deref()),
+            recipients: row.recipients,
+            subject: row.subject,
+            html_content: row.html_content,
+            text_content: row.text_content,
+            unsubscribe_url: row.unsubscribe_url,
+            created_at: row.created_at,
+        })
+        .collect())
+}
@@ -0,0 +1,399 @@
+use std::str::FromStr;
+
+use chrono::{Duration, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::domain::email_outbox_models::{
+    OutboxEmail, OutboxStatus, EMAIL_CLAIM_TIMEOUT_SECONDS, MAX_EMAIL_ATTEMPTS,
+};
+
+/// Queues the email in the caller's transaction, so it only goes out if the
+/// change it reports on is committed.
+#[tracing::instrument(
+    name = "Queue an email",
+    skip(email, transaction),
+    fields(email_id = %email.id, subject = %email.subject)
+)]
+pub async fn queue_email(
+    email: &OutboxEmail,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO email_outbox (
+            id,
+            recipients,
+            subject,
+            html_content,
+            text_content,
+            status,
+            attempts,
+            next_attempt_at,
+            created_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"#,
+        email.id,
+        &email.recipients,
+        email.subject,
+        email.html_content,
+        email.text_content,
+        email.status.as_str(),
+        email.attempts,
+        email.next_attempt_at,
+        email.created_at,
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+/// Queues the email under its id unless it is already queued, so a job that
+/// is retried queues its email once.
+#[tracing::instrument(
+    name = "Queue an email once",
+    skip(email, transaction),
+    fields(email_id = %email.id, subject = %email.subject)
+)]
+pub async fn queue_email_once(
+    email: &OutboxEmail,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO email_outbox (
+            id,
+            recipients,
+            subject,
+            html_content,
+            text_content,
+            status,
+            attempts,
+            next_attempt_at,
+            created_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (id) DO NOTHING"#,
+        email.id,
+        &email.recipients,
+        email.subject,
+        email.html_content,
+        email.text_content,
+        email.status.as_str(),
+        email.attempts,
+        email.next_attempt_at,
+        email.created_at,
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+/// Claims up to `limit` emails that are due, including ones whose previous
+/// claim lapsed because the worker holding it died. A claim that lapsed on the
+/// last attempt is dead-lettered instead, so an email that brings the worker
+/// down is not tried forever.
+#[tracing::instrument(name = "Claim due emails", skip(pool))]
+pub async fn claim_due_emails(limit: i64, pool: &PgPool) -> Result<Vec<OutboxEmail>, sqlx::Error> {
+    let now = Utc::now();
+    sqlx::query!(
+        r#"UPDATE email_outbox
+            SET status = 'Dead', last_error = 'The last attempt never finished'
+            WHERE status = 'Sending' AND next_attempt_at <= $1 AND attempts >= $2"#,
+        now,
+        MAX_EMAIL_ATTEMPTS
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    let rows = sqlx::query!(
+        r#"UPDATE email_outbox
+            SET status = 'Sending', attempts = attempts + 1, next_attempt_at = $1
+            WHERE id IN (
+                SELECT id FROM email_outbox
+                WHERE status IN ('Pending', 'Sending') AND next_attempt_at <= $2
+                    AND attempts < $4
+                ORDER BY next_attempt_at
+                LIMIT $3
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING
+                id,
+                recipients,
+                subject,
+                html_content,
+                text_content,
+                status,
+                attempts,
+                last_error,
+                next_attempt_at,
+                created_at,
+                sent_at"#,
+        now + Duration::seconds(EMAIL_CLAIM_TIMEOUT_SECONDS),
+        now,
+        limit,
+        MAX_EMAIL_ATTEMPTS
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| OutboxEmail {
+            id: row.id,
+            recipients: row.recipients,
+            subject: row.subject,
+            html_content: row.html_content,
+            text_content: row.text_content,
+            status: OutboxStatus::from_str(&row.status).unwrap_or(OutboxStatus::Sending),
+            attempts: row.attempts,
+            last_error: row.last_error,
+            next_attempt_at: row.next_attempt_at,
+            created_at: row.created_at,
+            sent_at: row.sent_at,
+        })
+        .collect())
+}
+
+/// Claims one queued email, if it is still pending. Used to send right after
+/// the transaction that queued it commits.
+#[tracing::instrument(name = "Claim a queued email", skip(pool))]
+pub async fn claim_email(id: Uuid, pool: &PgPool) -> Result<Option<OutboxEmail>, sqlx::Error> {
+    let now = Utc::now();
+    let row = sqlx::query!(
+        r#"UPDATE email_outbox
+            SET status = 'Sending', attempts = attempts + 1, next_attempt_at = $1
+            WHERE id = $2 AND status = 'Pending' AND next_attempt_at <= $3
+            RETURNING
+                id,
+                recipients,
+                subject,
+                html_content,
+                text_content,
+                status,
+                attempts,
+                last_error,
+                next_attempt_at,
+                created_at,
+                sent_at"#,
+        now + Duration::seconds(EMAIL_CLAIM_TIMEOUT_SECONDS),
+        id,
+        now
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(row.map(|row| OutboxEmail {
+        id: row.id,
+        recipients: row.recipients,
+        subject: row.subject,
+        html_content: row.html_content,
+        text_content: row.text_content,
+        status: OutboxStatus::from_str(&row.status).unwrap_or(OutboxStatus::Sending),
+        attempts: row.attempts,
+        last_error: row.last_error,
+        next_attempt_at: row.next_attempt_at,
+        created_at: row.created_at,
+        sent_at: row.sent_at,
+    }))
+}
+
+/// `note` is kept in `last_error`, for what the send left out. Only the claim
+/// that made the attempt can settle it: once the claim lapsed and another
+/// worker took the email over, the update is skipped.
+#[tracing::instrument(name = "Mark an email sent", skip(email, pool), fields(email_id = %email.id))]
+pub async fn mark_email_sent(
+    email: &OutboxEmail,
+    note: Option<&str>,
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    let result = sqlx::query!(
+        r#"UPDATE email_outbox
+            SET status = 'Sent', sent_at = $1, last_error = $2
+            WHERE id = $3 AND status = 'Sending' AND attempts = $4"#,
+        Utc::now(),
+        note,
+        email.id,
+        email.attempts
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+    warn_if_taken_over(email, result.rows_affected());
+
+    Ok(())
+}
+
+/// Schedules the next attempt, or dead-letters the email once it is out of attempts.
+#[tracing::instrument(name = "Mark an email failed", skip(email, error, pool), fields(email_id = %email.id))]
+pub async fn mark_email_failed(
+    email: &OutboxEmail,
+    error: &str,
+    pool: &PgPool,
+) -> Result<OutboxStatus, sqlx::Error> {
+    let (status, next_attempt_at) = email.after_failure(Utc::now());
+    let result = sqlx::query!(
+        r#"UPDATE email_outbox
+            SET status = $1, next_attempt_at = $2, last_error = $3
+            WHERE id = $4 AND status = 'Sending' AND attempts = $5"#,
+        status.as_str(),
+        next_attempt_at,
+        error,
+        email.id,
+        email.attempts
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+    warn_if_taken_over(email, result.rows_affected());
+
+    Ok(status)
+}
+
+fn warn_if_taken_over(email: &OutboxEmail, rows_affected: u64) {
+    if rows_affected == 0 {
+        tracing::warn!(
+            "Attempt {} at email {} finished after its claim lapsed; keeping the newer state",
+            email.attempts,
+            email.id
+        );
+    }
+}
+
+/// Newest first, at most `limit`.
+#[tracing::instrument(name = "Get outbox emails", skip(pool))]
+pub async fn get_outbox_emails(
+    status: Option<OutboxStatus>,
+    limit: i64,
+    pool: &PgPool,
+) -> Result<Vec<OutboxEmail>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT
+            id,
+            recipients,
+            subject,
+            html_content,
+            text_content,
+            status,
+            attempts,
+            last_error,
+            next_attempt_at,
+            created_at,
+            sent_at
+            FROM email_outbox
+            WHERE ($1::TEXT IS NULL OR status = $1)
+            ORDER BY created_at DESC
+            LIMIT $2"#,
+        status.map(|status| status.as_str()),
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| OutboxEmail {
+            id: row.id,
+            recipients: row.recipients,
+            subject: row.subject,
+            html_content: row.html_content,
+            text_content: row.text_content,
+            status: OutboxStatus::from_str(&row.status).unwrap_or(OutboxStatus::Pending),
+            attempts: row.attempts,
+            last_error: row.last_error,
+            next_attempt_at: row.next_attempt_at,
+            created_at: row.created_at,
+            sent_at: row.sent_at,
+        })
+        .collect())
+}
+
+#[tracing::instrument(name = "Get an outbox email", skip(pool))]
+pub async fn get_outbox_email(id: Uuid, pool: &PgPool) -> Result<OutboxEmail, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"SELECT
+            id,
+            recipients,
+            subject,
+            html_content,
+            text_content,
+            status,
+            attempts,
+            last_error,
+            next_attempt_at,
+            created_at,
+            sent_at
+            FROM email_outbox
+            WHERE id = $1"#,
+        id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(OutboxEmail {
+        id: row.id,
+        recipients: row.recipients,
+        subject: row.subject,
+        html_content: row.html_content,
+        text_content: row.text_content,
+        status: OutboxStatus::from_str(&row.status).unwrap_or(OutboxStatus::Pending),
+        attempts: row.attempts,
+        last_error: row.last_error,
+        next_attempt_at: row.next_attempt_at,
+        created_at: row.created_at,
+        sent_at: row.sent_at,
+    })
+}
+
+/// Gives a dead email a fresh set of attempts, starting now. Returns false
+/// unless the email was dead.
+#[tracing::instrument(name = "Requeue a dead email", skip(pool))]
+pub async fn requeue_dead_email(id: Uuid, pool: &PgPool) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"UPDATE email_outbox
+            SET status = 'Pending', attempts = 0, next_attempt_at = $1
+            WHERE id = $2 AND status = 'Dead'"#,
+        Utc::now(),
+        id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
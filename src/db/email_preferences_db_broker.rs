@@ -0,0 +1,86 @@
+use std::collections::HashSet;
+
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::email_preference_models::{EmailCategory, EmailPreferences};
+
+/// The subscriber's preferences, or the defaults if they never set any.
+#[tracing::instrument(name = "Get a subscriber's email preferences", skip(pool))]
+pub async fn get_email_preferences(
+    subscriber_id: Uuid,
+    pool: &PgPool,
+) -> Result<EmailPreferences, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"SELECT issues, renewal_reminders, promotions
+            FROM email_preferences
+            WHERE subscriber_id = $1"#,
+        subscriber_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(row
+        .map(|row| EmailPreferences {
+            issues: row.issues,
+            renewal_reminders: row.renewal_reminders,
+            promotions: row.promotions,
+        })
+        .unwrap_or_default())
+}
+
+#[tracing::instrument(name = "Store a subscriber's email preferences", skip(pool))]
+pub async fn store_email_preferences(
+    subscriber_id: Uuid,
+    preferences: &EmailPreferences,
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO email_preferences (subscriber_id, issues, renewal_reminders, promotions, updated_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (subscriber_id) DO UPDATE
+            SET issues = $2, renewal_reminders = $3, promotions = $4, updated_at = $5"#,
+        subscriber_id,
+        preferences.issues,
+        preferences.renewal_reminders,
+        preferences.promotions,
+        Utc::now()
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+/// Subscribers who have turned the category off.
+#[tracing::instrument(name = "Get the subscribers who opted out of a category", skip(pool))]
+pub async fn get_opted_out_subscriber_ids(
+    category: EmailCategory,
+    pool: &PgPool,
+) -> Result<HashSet<Uuid>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT subscriber_id
+            FROM email_preferences
+            WHERE ($1 = 'issues' AND NOT issues)
+                OR ($1 = 'renewal_reminders' AND NOT renewal_reminders)
+                OR ($1 = 'promotions' AND NOT promotions)"#,
+        category.as_str()
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows.into_iter().map(|row| row.subscriber_id).collect())
+}
@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use chrono::Utc;
+use sqlx::PgPool;
+
+use crate::domain::email_suppression_models::{DeliveryEvent, EmailSuppression, SuppressionReason};
+
+/// Suppresses the event's address, or updates why it is suppressed. `true`
+/// if the address was not suppressed before.
+#[tracing::instrument(name = "Store an email suppression", skip(event, pool))]
+pub async fn store_email_suppression(
+    event: &DeliveryEvent,
+    pool: &PgPool,
+) -> Result<bool, sqlx::Error> {
+    let now = Utc::now();
+    let row = sqlx::query!(
+        r#"INSERT INTO email_suppressions (email_address, reason, detail, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $4)
+            ON CONFLICT (email_address) DO UPDATE
+            SET reason = $2, detail = $3, updated_at = $4
+            RETURNING (xmax = 0) AS "inserted!""#,
+        event.email_address,
+        event.reason.as_str(),
+        event.detail,
+        now
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(row.inserted)
+}
+
+/// Which of the addresses are suppressed, compared without case.
+#[tracing::instrument(name = "Find suppressed addresses", skip(email_addresses, pool))]
+pub async fn get_suppressed_addresses(
+    email_addresses: &[String],
+    pool: &PgPool,
+) -> Result<HashSet<String>, sqlx::Error> {
+    let lowercase: Vec<String> = email_addresses
+        .iter()
+        .map(|email_address| email_address.to_lowercase())
+        .collect();
+    let rows = sqlx::query!(
+        r#"SELECT email_address FROM email_suppressions WHERE email_address = ANY($1)"#,
+        &lowercase
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows.into_iter().map(|row| row.email_address).collect())
+}
+
+/// Newest first.
+#[tracing::instrument(name = "Get email suppressions", skip(pool))]
+pub async fn get_email_suppressions(
+    reason: Option<SuppressionReason>,
+    limit: i64,
+    pool: &PgPool,
+) -> Result<Vec<EmailSuppression>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT email_address, reason, detail, created_at, updated_at
+            FROM email_suppressions
+            WHERE ($1::text IS NULL OR reason = $1)
+            ORDER BY updated_at DESC
+            LIMIT $2"#,
+        reason.map(|reason| reason.as_str()),
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| EmailSuppression {
+            email_address: row.email_address,
+            reason: SuppressionReason::from_str(&row.reason)
+                .unwrap_or(SuppressionReason::HardBounce),
+            detail: row.detail,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+        .collect())
+}
+
+/// `false` if the address was not suppressed.
+#[tracing::instrument(name = "Clear an email suppression", skip(pool))]
+pub async fn delete_email_suppression(
+    email_address: &str,
+    pool: &PgPool,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"DELETE FROM email_suppressions WHERE email_address = $1"#,
+        email_address.to_lowercase()
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,173 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::fulfillment_models::{FulfillmentEntry, FulfillmentRun};
+
+#[tracing::instrument(
+    name = "Saving a fulfillment run in the database",
+    skip(run, entries, pool),
+    fields(run_id = %run.id, entries = entries.len())
+)]
+pub async fn insert_fulfillment_run(
+    run: &FulfillmentRun,
+    entries: &[FulfillmentEntry],
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        r#"INSERT INTO fulfillment_runs (
+            id, issue_date, cutoff, created_at, created_by, recipient_count, duplicate_count
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+        run.id,
+        run.issue_date,
+        run.cutoff,
+        run.created_at,
+        run.created_by,
+        run.recipient_count,
+        run.duplicate_count,
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    for entry in entries {
+        sqlx::query!(
+            r#"INSERT INTO fulfillment_run_entries (
+                run_id,
+                sequence,
+                subscription_id,
+                subscriber_id,
+                name,
+                address_line_1,
+                address_line_2,
+                city,
+                state,
+                postal_code,
+                merged_subscription_ids
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)"#,
+            run.id,
+            entry.sequence,
+            entry.subscription_id,
+            entry.subscriber_id,
+            entry.name,
+            entry.address_line_1,
+            entry.address_line_2,
+            entry.city,
+            entry.state,
+            entry.postal_code,
+            &entry.merged_subscription_ids,
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to execute query: {:?}", e);
+            e
+        })?;
+    }
+
+    transaction.commit().await
+}
+
+#[tracing::instrument(name = "Get fulfillment runs", skip(pool))]
+pub async fn get_fulfillment_runs(pool: &PgPool) -> Result<Vec<FulfillmentRun>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT id, issue_date, cutoff, created_at, created_by, recipient_count, duplicate_count
+            FROM fulfillment_runs
+            ORDER BY created_at DESC"#
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| FulfillmentRun {
+            id: row.id,
+            issue_date: row.issue_date,
+            cutoff: row.cutoff,
+            created_at: row.created_at,
+            created_by: row.created_by,
+            recipient_count: row.recipient_count,
+            duplicate_count: row.duplicate_count,
+        })
+        .collect())
+}
+
+#[tracing::instrument(name = "Get a fulfillment run", skip(pool))]
+pub async fn get_fulfillment_run(id: Uuid, pool: &PgPool) -> Result<FulfillmentRun, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"SELECT id, issue_date, cutoff, created_at, created_by, recipient_count, duplicate_count
+            FROM fulfillment_runs
+            WHERE id = $1"#,
+        id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(FulfillmentRun {
+        id: row.id,
+        issue_date: row.issue_date,
+        cutoff: row.cutoff,
+        created_at: row.created_at,
+        created_by: row.created_by,
+        recipient_count: row.recipient_count,
+        duplicate_count: row.duplicate_count,
+    })
+}
+
+#[tracing::instrument(name = "Get the entries of a fulfillment run", skip(pool))]
+pub async fn get_fulfillment_run_entries(
+    run_id: Uuid,
+    pool: &PgPool,
+) -> Result<Vec<FulfillmentEntry>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT
+            sequence,
+            subscription_id,
+            subscriber_id,
+            name,
+            address_line_1,
+            address_line_2,
+            city,
+            state,
+            postal_code,
+            merged_subscription_ids
+            FROM fulfillment_run_entries
+            WHERE run_id = $1
+            ORDER BY sequence"#,
+        run_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| FulfillmentEntry {
+            sequence: row.sequence,
+            subscription_id: row.subscription_id,
+            subscriber_id: row.subscriber_id,
+            name: row.name,
+            address_line_1: row.address_line_1,
+            address_line_2: row.address_line_2,
+            city: row.city,
+            state: row.state,
+            postal_code: row.postal_code,
+            merged_subscription_ids: row.merged_subscription_ids,
+        })
+        .collect())
+}
@@ -0,0 +1,155 @@
+use sqlx::{Error, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::domain::invitation_models::Invitation;
+use crate::domain::user_models::from_str_to_user_group;
+
+#[tracing::instrument(
+    name = "Saving a new invitation",
+    skip(invitation, token_hash, transaction)
+)]
+pub async fn insert_invitation(
+    invitation: &Invitation,
+    token_hash: &str,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), Error> {
+    sqlx::query!(
+        r#"INSERT INTO invitations (
+            id,
+            email_address,
+            user_group,
+            token_hash,
+            invited_by,
+            created_at,
+            expires_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+        invitation.id,
+        invitation.email_address,
+        invitation.user_group.as_str(),
+        token_hash,
+        invitation.invited_by,
+        invitation.created_at,
+        invitation.expires_at
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("{:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "Get pending invitations", skip(pool))]
+pub async fn get_pending_invitations(pool: &PgPool) -> Result<Vec<Invitation>, Error> {
+    let rows = sqlx::query!(
+        r#"SELECT id, email_address, user_group, invited_by, created_at, expires_at,
+            accepted_at, accepted_user_id, revoked_at
+            FROM invitations
+            WHERE accepted_at IS NULL AND revoked_at IS NULL AND expires_at > now()
+            ORDER BY created_at DESC"#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("{:?}", e);
+        e
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| Invitation {
+            id: row.id,
+            email_address: row.email_address,
+            user_group: from_str_to_user_group(row.user_group),
+            invited_by: row.invited_by,
+            created_at: row.created_at,
+            expires_at: row.expires_at,
+            accepted_at: row.accepted_at,
+            accepted_user_id: row.accepted_user_id,
+            revoked_at: row.revoked_at,
+        })
+        .collect())
+}
+
+/// Returns false when there is no pending invitation with this id.
+#[tracing::instrument(name = "Revoke an invitation", skip(id, pool))]
+pub async fn revoke_invitation(id: Uuid, pool: &PgPool) -> Result<bool, Error> {
+    let result = sqlx::query!(
+        r#"UPDATE invitations
+            SET revoked_at = now()
+            WHERE id = $1 AND accepted_at IS NULL AND revoked_at IS NULL"#,
+        id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("{:?}", e);
+        e
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Marks a pending, unexpired invitation as accepted and returns it. The update is what
+/// makes the link single-use: a second attempt finds no matching row.
+#[tracing::instrument(name = "Consume an invitation", skip(token_hash, transaction))]
+pub async fn consume_invitation(
+    token_hash: &str,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<Invitation, Error> {
+    let row = sqlx::query!(
+        r#"UPDATE invitations
+            SET accepted_at = now()
+            WHERE token_hash = $1
+            AND accepted_at IS NULL AND revoked_at IS NULL AND expires_at > now()
+            RETURNING id, email_address, user_group, invited_by, created_at, expires_at,
+            accepted_at, accepted_user_id, revoked_at"#,
+        token_hash
+    )
+    .fetch_one(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("{:?}", e);
+        e
+    })?;
+
+    Ok(Invitation {
+        id: row.id,
+        email_address: row.email_address,
+        user_group: from_str_to_user_group(row.user_group),
+        invited_by: row.invited_by,
+        created_at: row.created_at,
+        expires_at: row.expires_at,
+        accepted_at: row.accepted_at,
+        accepted_user_id: row.accepted_user_id,
+        revoked_at: row.revoked_at,
+    })
+}
+
+#[tracing::instrument(
+    name = "Link an invitation to its new user",
+    skip(id, user_id, transaction)
+)]
+pub async fn set_invitation_accepted_user(
+    id: Uuid,
+    user_id: Uuid,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), Error> {
+    sqlx::query!(
+        r#"UPDATE invitations
+            SET accepted_user_id = $1
+            WHERE id = $2"#,
+        user_id,
+        id
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("{:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
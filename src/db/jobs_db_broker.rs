@@ -0,0 +1,256 @@
+use std::str::FromStr;
+
+use chrono::{Duration, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::domain::job_models::{Job, JobStatus, JOB_CLAIM_TIMEOUT_SECONDS, MAX_JOB_ATTEMPTS};
+
+/// Queues the job in the caller's transaction, so it only runs if the change
+/// it follows up on is committed.
+#[tracing::instrument(
+    name = "Queue a job",
+    skip(job, transaction),
+    fields(job_id = %job.id, kind = %job.kind)
+)]
+pub async fn queue_job(
+    job: &Job,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO jobs (id, kind, payload, status, attempts, run_at, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+        job.id,
+        job.kind,
+        job.payload,
+        job.status.as_str(),
+        job.attempts,
+        job.run_at,
+        job.created_at,
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+/// Claims the job that has been due longest, including one whose previous
+/// claim lapsed because the worker holding it died. Workers skip the rows
+/// others are claiming, so they never take the same job. A claim that lapsed
+/// on the last attempt is dead-lettered instead, so a job that brings the
+/// worker down is not retried forever.
+#[tracing::instrument(name = "Claim a due job", skip(pool))]
+pub async fn claim_due_job(pool: &PgPool) -> Result<Option<Job>, sqlx::Error> {
+    let now = Utc::now();
+    sqlx::query!(
+        r#"UPDATE jobs
+            SET status = 'Dead', last_error = 'The last attempt never finished',
+                finished_at = $1
+            WHERE status = 'Running' AND run_at <= $1 AND attempts >= $2"#,
+        now,
+        MAX_JOB_ATTEMPTS
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    let row = sqlx::query!(
+        r#"UPDATE jobs
+            SET status = 'Running', attempts = attempts + 1, run_at = $1
+            WHERE id = (
+                SELECT id FROM jobs
+                WHERE status IN ('Pending', 'Running') AND run_at <= $2 AND attempts < $3
+                ORDER BY run_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, kind, payload, status, attempts, last_error, run_at, created_at,
+                finished_at"#,
+        now + Duration::seconds(JOB_CLAIM_TIMEOUT_SECONDS),
+        now,
+        MAX_JOB_ATTEMPTS
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(row.map(|row| Job {
+        id: row.id,
+        kind: row.kind,
+        payload: row.payload,
+        status: JobStatus::from_str(&row.status).unwrap_or(JobStatus::Running),
+        attempts: row.attempts,
+        last_error: row.last_error,
+        run_at: row.run_at,
+        created_at: row.created_at,
+        finished_at: row.finished_at,
+    }))
+}
+
+/// Only the claim that made the attempt can settle it: once the claim lapsed
+/// and another worker took the job over, the update is skipped.
+#[tracing::instrument(name = "Mark a job done", skip(job, pool), fields(job_id = %job.id))]
+pub async fn mark_job_done(job: &Job, pool: &PgPool) -> Result<(), sqlx::Error> {
+    let result = sqlx::query!(
+        r#"UPDATE jobs
+            SET status = 'Done', finished_at = $1
+            WHERE id = $2 AND status = 'Running' AND attempts = $3"#,
+        Utc::now(),
+        job.id,
+        job.attempts
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+    warn_if_taken_over(job, result.rows_affected());
+
+    Ok(())
+}
+
+/// Schedules the next attempt, or dead-letters the job once it is out of attempts.
+#[tracing::instrument(name = "Mark a job failed", skip(job, error, pool), fields(job_id = %job.id))]
+pub async fn mark_job_failed(
+    job: &Job,
+    error: &str,
+    pool: &PgPool,
+) -> Result<JobStatus, sqlx::Error> {
+    let now = Utc::now();
+    let (status, run_at) = job.after_failure(now);
+    let finished_at = (status == JobStatus::Dead).then_some(now);
+    let result = sqlx::query!(
+        r#"UPDATE jobs
+            SET status = $1, run_at = $2, last_error = $3, finished_at = $4
+            WHERE id = $5 AND status = 'Running' AND attempts = $6"#,
+        status.as_str(),
+        run_at,
+        error,
+        finished_at,
+        job.id,
+        job.attempts
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+    warn_if_taken_over(job, result.rows_affected());
+
+    Ok(status)
+}
+
+fn warn_if_taken_over(job: &Job, rows_affected: u64) {
+    if rows_affected == 0 {
+        tracing::warn!(
+            "Attempt {} at job {} finished after its claim lapsed; keeping the newer state",
+            job.attempts,
+            job.id
+        );
+    }
+}
+
+/// Newest first, at most `limit`.
+#[tracing::instrument(name = "Get jobs", skip(pool))]
+pub async fn get_jobs(
+    status: Option<JobStatus>,
+    kind: Option<&str>,
+    limit: i64,
+    pool: &PgPool,
+) -> Result<Vec<Job>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT id, kind, payload, status, attempts, last_error, run_at, created_at,
+            finished_at
+            FROM jobs
+            WHERE ($1::TEXT IS NULL OR status = $1)
+                AND ($2::TEXT IS NULL OR kind = $2)
+            ORDER BY created_at DESC
+            LIMIT $3"#,
+        status.map(|status| status.as_str()),
+        kind,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| Job {
+            id: row.id,
+            kind: row.kind,
+            payload: row.payload,
+            status: JobStatus::from_str(&row.status).unwrap_or(JobStatus::Pending),
+            attempts: row.attempts,
+            last_error: row.last_error,
+            run_at: row.run_at,
+            created_at: row.created_at,
+            finished_at: row.finished_at,
+        })
+        .collect())
+}
+
+#[tracing::instrument(name = "Get a job", skip(pool))]
+pub async fn get_job(id: Uuid, pool: &PgPool) -> Result<Job, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"SELECT id, kind, payload, status, attempts, last_error, run_at, created_at,
+            finished_at
+            FROM jobs
+            WHERE id = $1"#,
+        id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(Job {
+        id: row.id,
+        kind: row.kind,
+        payload: row.payload,
+        status: JobStatus::from_str(&row.status).unwrap_or(JobStatus::Pending),
+        attempts: row.attempts,
+        last_error: row.last_error,
+        run_at: row.run_at,
+        created_at: row.created_at,
+        finished_at: row.finished_at,
+    })
+}
+
+/// Gives a dead or waiting job a fresh set of attempts, starting now. Returns
+/// false unless the job was dead or waiting.
+#[tracing::instrument(name = "Retry a job", skip(pool))]
+pub async fn retry_job(id: Uuid, pool: &PgPool) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"UPDATE jobs
+            SET status = 'Pending', attempts = 0, run_at = $1, finished_at = NULL
+            WHERE id = $2 AND status IN ('Pending', 'Dead')"#,
+        Utc::now(),
+        id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
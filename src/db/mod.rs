@@ -1,7 +1,25 @@
+pub mod audit_log_db_broker;
+pub mod broadcast_db_broker;
+pub mod captured_emails_db_broker;
 pub mod checkout_session_db_broker;
+pub mod email_outbox_db_broker;
+pub mod email_preferences_db_broker;
+pub mod email_suppressions_db_broker;
+pub mod fulfillment_db_broker;
+pub mod invitation_db_broker;
+pub mod jobs_db_broker;
+pub mod newsletter_db_broker;
+pub mod notification_channels_db_broker;
+pub mod oidc_db_broker;
 pub mod otp_db_broker;
+pub mod publications_db_broker;
+pub mod schema_migrations;
+pub mod seed_db_broker;
+pub mod session_db_broker;
+pub mod staff_digest_db_broker;
 pub mod subscribers_db_broker;
 pub mod subscription_history_db_broker;
 pub mod subscriptions_db_broker;
 pub mod users;
+pub mod webhook_endpoints_db_broker;
 pub mod webhook_event_db_broker;
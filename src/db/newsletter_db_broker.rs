@@ -0,0 +1,97 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use sqlx::{Error, PgPool, Postgres, Transaction};
+
+use crate::domain::newsletter_models::{NewsletterMember, NewsletterMemberStatus};
+
+/// Stores a sign-up waiting for confirmation. Signing up again while pending
+/// replaces the name, consent and link. `false` if the address is already a
+/// confirmed member, in which case nothing changes.
+#[tracing::instrument(
+    name = "Store a pending newsletter sign-up",
+    skip(member, token_hash, transaction)
+)]
+pub async fn upsert_pending_newsletter_member(
+    member: &NewsletterMember,
+    token_hash: &str,
+    token_expires_at: DateTime<Utc>,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<bool, Error> {
+    let row = sqlx::query!(
+        r#"INSERT INTO newsletter_members (id, email_address, name, status, token_hash,
+            token_expires_at, consent_text, consent_ip_address, consent_user_agent, consented_at,
+            created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ON CONFLICT (email_address) DO UPDATE
+            SET name = $3, token_hash = $5, token_expires_at = $6, consent_text = $7,
+                consent_ip_address = $8, consent_user_agent = $9, consented_at = $10
+            WHERE newsletter_members.status = $4
+            RETURNING id"#,
+        member.id,
+        member.email_address,
+        member.name,
+        NewsletterMemberStatus::Pending.as_str(),
+        token_hash,
+        token_expires_at,
+        member.consent_text,
+        member.consent_ip_address,
+        member.consent_user_agent,
+        member.consented_at,
+        member.created_at
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(row.is_some())
+}
+
+/// Confirms the member whose link this is. `RowNotFound` if the link is
+/// unknown, used or expired.
+#[tracing::instrument(
+    name = "Confirm a newsletter sign-up",
+    skip(token_hash, confirmed_ip_address, pool)
+)]
+pub async fn confirm_newsletter_member(
+    token_hash: &str,
+    confirmed_ip_address: Option<&str>,
+    pool: &PgPool,
+) -> Result<NewsletterMember, Error> {
+    let row = sqlx::query!(
+        r#"UPDATE newsletter_members
+            SET status = $1, confirmed_at = now(), confirmed_ip_address = $2,
+                token_hash = NULL, token_expires_at = NULL
+            WHERE token_hash = $3 AND status = $4 AND token_expires_at > now()
+            RETURNING id, email_address, name, status, consent_text, consent_ip_address,
+                consent_user_agent, consented_at, confirmed_at, confirmed_ip_address, created_at"#,
+        NewsletterMemberStatus::Confirmed.as_str(),
+        confirmed_ip_address,
+        token_hash,
+        NewsletterMemberStatus::Pending.as_str()
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(NewsletterMember {
+        id: row.id,
+        email_address: row.email_address,
+        name: row.name,
+        status: NewsletterMemberStatus::from_str(&row.status)
+            .unwrap_or(NewsletterMemberStatus::Confirmed),
+        consent_text: row.consent_text,
+        consent_ip_address: row.consent_ip_address,
+        consent_user_agent: row.consent_user_agent,
+        consented_at: row.consented_at,
+        confirmed_at: row.confirmed_at,
+        confirmed_ip_address: row.confirmed_ip_address,
+        created_at: row.created_at,
+    })
+}
@@ -0,0 +1,221 @@
+use std::str::FromStr;
+
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::notification_channel_models::{
+    NotificationChannel, NotificationChannelKind, NotificationChannelRequest, StaffEventType,
+};
+
+#[tracing::instrument(
+    name = "Saving a notification channel in the database",
+    skip(channel, pool),
+    fields(channel_id = %channel.id)
+)]
+pub async fn insert_notification_channel(
+    channel: &NotificationChannel,
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO notification_channels (id, name, kind, target, event_types, enabled,
+            created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+        channel.id,
+        channel.name,
+        channel.kind.as_str(),
+        channel.target,
+        &event_type_strings(&channel.event_types),
+        channel.enabled,
+        channel.created_at,
+        channel.updated_at
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "Get notification channels", skip(pool))]
+pub async fn get_notification_channels(
+    pool: &PgPool,
+) -> Result<Vec<NotificationChannel>, sqlx::Error> {
+    select_notification_channels(None, None, pool).await
+}
+
+/// The enabled channels subscribed to the event type.
+#[tracing::instrument(name = "Get the notification channels for an event", skip(pool))]
+pub async fn get_notification_channels_for_event(
+    event_type: StaffEventType,
+    pool: &PgPool,
+) -> Result<Vec<NotificationChannel>, sqlx::Error> {
+    select_notification_channels(Some(event_type), None, pool).await
+}
+
+#[tracing::instrument(name = "Get a notification channel", skip(pool))]
+pub async fn get_notification_channel(
+    id: Uuid,
+    pool: &PgPool,
+) -> Result<NotificationChannel, sqlx::Error> {
+    select_notification_channels(None, Some(id), pool)
+        .await?
+        .pop()
+        .ok_or(sqlx::Error::RowNotFound)
+}
+
+/// `RowNotFound` if there is no such channel.
+#[tracing::instrument(name = "Update a notification channel", skip(request, pool))]
+pub async fn update_notification_channel(
+    id: Uuid,
+    request: &NotificationChannelRequest,
+    pool: &PgPool,
+) -> Result<NotificationChannel, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"UPDATE notification_channels
+            SET name = $1, kind = $2, target = $3, event_types = $4, enabled = $5,
+                updated_at = $6
+            WHERE id = $7"#,
+        request.name.trim(),
+        request.kind.as_str(),
+        request.target.trim(),
+        &event_type_strings(&request.event_types),
+        request.enabled,
+        Utc::now(),
+        id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+    get_notification_channel(id, pool).await
+}
+
+#[tracing::instrument(name = "Delete a notification channel", skip(pool))]
+pub async fn delete_notification_channel(id: Uuid, pool: &PgPool) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(r#"DELETE FROM notification_channels WHERE id = $1"#, id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to execute query: {:?}", e);
+            e
+        })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Records how the last notice to the channel went.
+#[tracing::instrument(name = "Record a notification channel delivery", skip(pool))]
+pub async fn record_notification_channel_delivery(
+    id: Uuid,
+    error: Option<&str>,
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"UPDATE notification_channels
+            SET last_notified_at = $1, last_error = $2
+            WHERE id = $3"#,
+        Utc::now(),
+        error,
+        id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+/// Makes an email channel, subscribed to everything, for each address, but
+/// only while there are no channels at all. Returns how many were made.
+#[tracing::instrument(name = "Import notification addresses", skip(pool))]
+pub async fn import_notification_addresses(
+    email_addresses: &[String],
+    pool: &PgPool,
+) -> Result<u64, sqlx::Error> {
+    let ids: Vec<Uuid> = email_addresses.iter().map(|_| Uuid::new_v4()).collect();
+    let result = sqlx::query!(
+        r#"INSERT INTO notification_channels (id, name, kind, target, event_types, enabled,
+            created_at, updated_at)
+            SELECT address.id, address.email_address, $3, address.email_address, $4, true, $5, $5
+            FROM UNNEST($1::uuid[], $2::text[]) AS address(id, email_address)
+            WHERE NOT EXISTS (SELECT 1 FROM notification_channels)"#,
+        &ids,
+        email_addresses,
+        NotificationChannelKind::Email.as_str(),
+        &event_type_strings(&StaffEventType::ALL),
+        Utc::now()
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.rows_affected())
+}
+
+fn event_type_strings(event_types: &[StaffEventType]) -> Vec<String> {
+    event_types
+        .iter()
+        .map(|event_type| event_type.as_str().to_string())
+        .collect()
+}
+
+/// Oldest first. Only enabled channels subscribed to `event_type`, if given.
+async fn select_notification_channels(
+    event_type: Option<StaffEventType>,
+    id: Option<Uuid>,
+    pool: &PgPool,
+) -> Result<Vec<NotificationChannel>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT id, name, kind, target, event_types, enabled, last_notified_at, last_error,
+            created_at, updated_at
+            FROM notification_channels
+            WHERE ($1::text IS NULL OR (enabled AND $1 = ANY(event_types)))
+            AND ($2::uuid IS NULL OR id = $2)
+            ORDER BY created_at"#,
+        event_type.map(|event_type| event_type.as_str()),
+        id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| NotificationChannel {
+            id: row.id,
+            name: row.name,
+            kind: NotificationChannelKind::from_str(&row.kind)
+                .unwrap_or(NotificationChannelKind::Email),
+            target: row.target,
+            event_types: row
+                .event_types
+                .iter()
+                .filter_map(|event_type| StaffEventType::from_str(event_type).ok())
+                .collect(),
+            enabled: row.enabled,
+            last_notified_at: row.last_notified_at,
+            last_error: row.last_error,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+        .collect())
+}
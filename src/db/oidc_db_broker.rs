@@ -0,0 +1,126 @@
+use chrono::Utc;
+use sqlx::{Error, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::domain::oidc_models::{OidcLoginState, UserIdentity};
+
+#[tracing::instrument(name = "Saving an OIDC login state", skip(login_state, pool))]
+pub async fn insert_oidc_login_state(
+    login_state: &OidcLoginState,
+    pool: &PgPool,
+) -> Result<(), Error> {
+    sqlx::query!(
+        r#"INSERT
+            INTO oidc_login_states (state, code_verifier, nonce, issued_on, expires_on, used)
+            VALUES ($1, $2, $3, $4, $5, $6)"#,
+        login_state.state,
+        login_state.code_verifier,
+        login_state.nonce,
+        login_state.issued_on,
+        login_state.expires_on,
+        login_state.used,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e: Error| {
+        tracing::error!("{:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+/// Marks the login state as used and returns it, but only if it has not been used and has not
+/// expired. A state can therefore be redeemed exactly once.
+#[tracing::instrument(name = "Consume an OIDC login state", skip(state, pool))]
+pub async fn consume_oidc_login_state(state: &str, pool: &PgPool) -> Result<OidcLoginState, Error> {
+    let result = sqlx::query!(
+        r#"UPDATE oidc_login_states
+            SET used = true
+            WHERE state = $1 AND used = false AND expires_on > $2
+            RETURNING state, code_verifier, nonce, issued_on, expires_on, used"#,
+        state,
+        Utc::now(),
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e: Error| {
+        tracing::error!("{:?}", e);
+        e
+    })?;
+
+    Ok(OidcLoginState {
+        state: result.state,
+        code_verifier: result.code_verifier,
+        nonce: result.nonce,
+        issued_on: result.issued_on,
+        expires_on: result.expires_on,
+        used: result.used,
+    })
+}
+
+#[tracing::instrument(
+    name = "Get user identity by provider and subject",
+    skip(provider, subject, pool)
+)]
+pub async fn get_user_identity_by_provider_and_subject(
+    provider: &str,
+    subject: &str,
+    pool: &PgPool,
+) -> Result<UserIdentity, Error> {
+    let result = sqlx::query!(
+        r#"SELECT id, user_id, provider, subject, email_address, created_at
+            FROM user_identities
+            WHERE provider = $1 AND subject = $2"#,
+        provider,
+        subject,
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e: Error| {
+        tracing::error!("{:?}", e);
+        e
+    })?;
+
+    Ok(UserIdentity {
+        id: result.id,
+        user_id: result.user_id,
+        provider: result.provider,
+        subject: result.subject,
+        email_address: result.email_address,
+        created_at: result.created_at,
+    })
+}
+
+#[tracing::instrument(
+    name = "Saving a user identity",
+    skip(user_id, provider, subject, email_address, transaction)
+)]
+pub async fn insert_user_identity(
+    user_id: Uuid,
+    provider: &str,
+    subject: &str,
+    email_address: &str,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<Uuid, Error> {
+    let id = Uuid::new_v4();
+    sqlx::query!(
+        r#"INSERT
+            INTO user_identities (id, user_id, provider, subject, email_address, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)"#,
+        id,
+        user_id,
+        provider,
+        subject,
+        email_address,
+        Utc::now(),
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e: Error| {
+        tracing::error!("{:?}", e);
+        e
+    })?;
+
+    Ok(id)
+}
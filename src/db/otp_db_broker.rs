@@ -1,9 +1,15 @@
-use sqlx::{Error, PgPool};
+use sqlx::{Error, PgPool, Postgres, Transaction};
 
 use crate::domain::otp_models::OneTimePasscode;
 
-#[tracing::instrument(name = "Saving otp in the database", skip(one_time_passcode, pool))]
-pub async fn insert_otp(one_time_passcode: OneTimePasscode, pool: &PgPool) -> Result<(), Error> {
+#[tracing::instrument(
+    name = "Saving otp in the database",
+    skip(one_time_passcode, transaction)
+)]
+pub async fn insert_otp(
+    one_time_passcode: OneTimePasscode,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), Error> {
     sqlx::query!(
         r#"INSERT 
             INTO otp (id, user_id, one_time_passcode, issued_on, expires_on, used) 
@@ -15,7 +21,7 @@ pub async fn insert_otp(one_time_passcode: OneTimePasscode, pool: &PgPool) -> Re
         one_time_passcode.expires_on,
         one_time_passcode.used,
     )
-    .execute(pool)
+    .execute(&mut **transaction)
     .await
     .map_err(|e: sqlx::Error| {
         tracing::error!("{:?}", e);
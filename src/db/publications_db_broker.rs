@@ -0,0 +1,302 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::publication_models::{Issue, IssueRequest, Publication, PublicationRequest};
+
+#[tracing::instrument(
+    name = "Saving a publication in the database",
+    skip(publication, pool),
+    fields(publication_id = %publication.id)
+)]
+pub async fn insert_publication(
+    publication: &Publication,
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO publications (id, title, description, created_at)
+            VALUES ($1, $2, $3, $4)"#,
+        publication.id,
+        publication.title,
+        publication.description,
+        publication.created_at,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "Get publications", skip(pool))]
+pub async fn get_publications(pool: &PgPool) -> Result<Vec<Publication>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT id, title, description, created_at
+            FROM publications
+            ORDER BY title, created_at"#
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| Publication {
+            id: row.id,
+            title: row.title,
+            description: row.description,
+            created_at: row.created_at,
+        })
+        .collect())
+}
+
+#[tracing::instrument(name = "Get a publication", skip(pool))]
+pub async fn get_publication(id: Uuid, pool: &PgPool) -> Result<Publication, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"SELECT id, title, description, created_at
+            FROM publications
+            WHERE id = $1"#,
+        id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(Publication {
+        id: row.id,
+        title: row.title,
+        description: row.description,
+        created_at: row.created_at,
+    })
+}
+
+/// Fails with `RowNotFound` when there is no such publication.
+#[tracing::instrument(name = "Update a publication", skip(publication, pool))]
+pub async fn update_publication(
+    id: Uuid,
+    publication: &PublicationRequest,
+    pool: &PgPool,
+) -> Result<Publication, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"UPDATE publications
+            SET title = $1, description = $2
+            WHERE id = $3
+            RETURNING id, title, description, created_at"#,
+        publication.title,
+        publication.description,
+        id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(Publication {
+        id: row.id,
+        title: row.title,
+        description: row.description,
+        created_at: row.created_at,
+    })
+}
+
+/// Deletes the publication and its issues. Fails with a foreign key violation
+/// while subscriptions still link to it.
+#[tracing::instrument(name = "Delete a publication", skip(pool))]
+pub async fn delete_publication(id: Uuid, pool: &PgPool) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(r#"DELETE FROM publications WHERE id = $1"#, id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to execute query: {:?}", e);
+            e
+        })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+#[tracing::instrument(
+    name = "Saving an issue in the database",
+    skip(issue, pool),
+    fields(issue_id = %issue.id)
+)]
+pub async fn insert_issue(issue: &Issue, pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO issues (
+            id,
+            publication_id,
+            issue_number,
+            title,
+            publish_date,
+            cutoff_date,
+            digital_asset_reference,
+            created_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+        issue.id,
+        issue.publication_id,
+        issue.issue_number,
+        issue.title,
+        issue.publish_date,
+        issue.cutoff_date,
+        issue.digital_asset_reference,
+        issue.created_at,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "Get the issues of a publication", skip(pool))]
+pub async fn get_issues_by_publication_id(
+    publication_id: Uuid,
+    pool: &PgPool,
+) -> Result<Vec<Issue>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT
+            id,
+            publication_id,
+            issue_number,
+            title,
+            publish_date,
+            cutoff_date,
+            digital_asset_reference,
+            created_at
+            FROM issues
+            WHERE publication_id = $1
+            ORDER BY issue_number"#,
+        publication_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| Issue {
+            id: row.id,
+            publication_id: row.publication_id,
+            issue_number: row.issue_number,
+            title: row.title,
+            publish_date: row.publish_date,
+            cutoff_date: row.cutoff_date,
+            digital_asset_reference: row.digital_asset_reference,
+            created_at: row.created_at,
+        })
+        .collect())
+}
+
+#[tracing::instrument(name = "Get an issue", skip(pool))]
+pub async fn get_issue(id: Uuid, pool: &PgPool) -> Result<Issue, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"SELECT
+            id,
+            publication_id,
+            issue_number,
+            title,
+            publish_date,
+            cutoff_date,
+            digital_asset_reference,
+            created_at
+            FROM issues
+            WHERE id = $1"#,
+        id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(Issue {
+        id: row.id,
+        publication_id: row.publication_id,
+        issue_number: row.issue_number,
+        title: row.title,
+        publish_date: row.publish_date,
+        cutoff_date: row.cutoff_date,
+        digital_asset_reference: row.digital_asset_reference,
+        created_at: row.created_at,
+    })
+}
+
+/// Fails with `RowNotFound` when there is no such issue.
+#[tracing::instrument(name = "Update an issue", skip(issue, pool))]
+pub async fn update_issue(
+    id: Uuid,
+    issue: &IssueRequest,
+    pool: &PgPool,
+) -> Result<Issue, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"UPDATE issues
+            SET
+                issue_number = $1,
+                title = $2,
+                publish_date = $3,
+                cutoff_date = $4,
+                digital_asset_reference = $5
+            WHERE id = $6
+            RETURNING
+                id,
+                publication_id,
+                issue_number,
+                title,
+                publish_date,
+                cutoff_date,
+                digital_asset_reference,
+                created_at"#,
+        issue.issue_number,
+        issue.title,
+        issue.publish_date,
+        issue.cutoff_date(),
+        issue.digital_asset_reference,
+        id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(Issue {
+        id: row.id,
+        publication_id: row.publication_id,
+        issue_number: row.issue_number,
+        title: row.title,
+        publish_date: row.publish_date,
+        cutoff_date: row.cutoff_date,
+        digital_asset_reference: row.digital_asset_reference,
+        created_at: row.created_at,
+    })
+}
+
+#[tracing::instrument(name = "Delete an issue", skip(pool))]
+pub async fn delete_issue(id: Uuid, pool: &PgPool) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(r#"DELETE FROM issues WHERE id = $1"#, id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to execute query: {:?}", e);
+            e
+        })?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use serde::Serialize;
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+use sqlx::PgPool;
+
+/// The migrations compiled into this build; the schema version the code expects.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but the file has changed since.
+    Modified,
+    /// Applied, but not part of this build (the database is ahead of the code).
+    Unknown,
+    /// Started but never finished.
+    Failed,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+}
+
+#[derive(Debug)]
+pub enum SchemaVersionError {
+    Database(sqlx::Error),
+    Migrate(MigrateError),
+    Unexpected(Vec<MigrationStatus>),
+}
+
+impl Display for SchemaVersionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaVersionError::Database(e) => {
+                write!(f, "Could not read the schema version. {:?}", e)
+            }
+            SchemaVersionError::Migrate(e) => write!(f, "Could not migrate the database. {}", e),
+            SchemaVersionError::Unexpected(mismatches) => {
+                let versions: Vec<String> = mismatches
+                    .iter()
+                    .map(|m| format!("{} ({:?})", m.version, m.state))
+                    .collect();
+                write!(
+                    f,
+                    "The database schema does not match this build: {}",
+                    versions.join(", ")
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchemaVersionError {}
+
+impl From<sqlx::Error> for SchemaVersionError {
+    fn from(e: sqlx::Error) -> Self {
+        SchemaVersionError::Database(e)
+    }
+}
+
+impl From<MigrateError> for SchemaVersionError {
+    fn from(e: MigrateError) -> Self {
+        SchemaVersionError::Migrate(e)
+    }
+}
+
+/// Compares the migrations recorded in the database with the ones in this build,
+/// ordered by version. Only reads, so it works with a read-only role.
+#[tracing::instrument(name = "Get migration status", skip(pool))]
+pub async fn get_migration_status(
+    pool: &PgPool,
+) -> Result<Vec<MigrationStatus>, SchemaVersionError> {
+    let table = sqlx::query!(r#"SELECT to_regclass('_sqlx_migrations') IS NOT NULL AS "exists!""#)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("{:?}", e);
+            e
+        })?;
+
+    let mut applied = HashMap::new();
+    let mut failed = None;
+    if table.exists {
+        let mut connection = pool.acquire().await?;
+        failed = connection.dirty_version().await?;
+        for migration in connection.list_applied_migrations().await? {
+            applied.insert(migration.version, migration.checksum);
+        }
+    }
+
+    let mut statuses: Vec<MigrationStatus> = MIGRATOR
+        .iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .map(|migration| {
+            let state = match applied.remove(&migration.version) {
+                _ if failed == Some(migration.version) => MigrationState::Failed,
+                Some(checksum) if checksum == migration.checksum => MigrationState::Applied,
+                Some(_) => MigrationState::Modified,
+                None => MigrationState::Pending,
+            };
+            MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                state,
+            }
+        })
+        .collect();
+    statuses.extend(applied.into_keys().map(|version| MigrationStatus {
+        version,
+        description: String::new(),
+        state: if failed == Some(version) {
+            MigrationState::Failed
+        } else {
+            MigrationState::Unknown
+        },
+    }));
+    statuses.sort_by_key(|status| status.version);
+
+    Ok(statuses)
+}
+
+/// Refuses to continue unless every migration in this build, and nothing else, has
+/// been applied unchanged.
+#[tracing::instrument(name = "Check the schema version", skip(pool))]
+pub async fn ensure_expected_schema_version(pool: &PgPool) -> Result<(), SchemaVersionError> {
+    let mismatches: Vec<MigrationStatus> = get_migration_status(pool)
+        .await?
+        .into_iter()
+        .filter(|status| status.state != MigrationState::Applied)
+        .collect();
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(SchemaVersionError::Unexpected(mismatches))
+    }
+}
+
+/// Applies pending migrations. Concurrent callers are serialised by the migrator's
+/// advisory lock.
+#[tracing::instrument(name = "Run pending migrations", skip(pool))]
+pub async fn run_pending_migrations(pool: &PgPool) -> Result<(), SchemaVersionError> {
+    MIGRATOR.run(pool).await.map_err(|e| {
+        tracing::error!("{:?}", e);
+        SchemaVersionError::from(e)
+    })
+}
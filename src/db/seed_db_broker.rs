@@ -0,0 +1,162 @@
+use sqlx::{PgPool, Postgres, Transaction};
+
+use crate::seed::SeedData;
+
+/// Stores generated data in one transaction. Rows that already exist are left
+/// alone, so seeding twice with the same seed changes nothing.
+#[tracing::instrument(name = "Store seed data", skip(data, hashed_password, pool))]
+pub async fn store_seed_data(
+    data: &SeedData,
+    hashed_password: &str,
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    insert_seed_rows(data, hashed_password, &mut transaction)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to execute query: {:?}", e);
+            e
+        })?;
+    transaction.commit().await
+}
+
+async fn insert_seed_rows(
+    data: &SeedData,
+    hashed_password: &str,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), sqlx::Error> {
+    for user in &data.users {
+        sqlx::query!(
+            r#"INSERT INTO users (user_id, email_address, password, user_group)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT DO NOTHING"#,
+            user.user_id,
+            user.email_address,
+            hashed_password,
+            user.user_group.as_str(),
+        )
+        .execute(&mut **transaction)
+        .await?;
+    }
+
+    for subscriber in &data.subscribers {
+        sqlx::query!(
+            r#"INSERT INTO subscribers (id, email_address, name, user_id, stripe_customer_id)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT DO NOTHING"#,
+            subscriber.id,
+            subscriber.email_address,
+            subscriber.name,
+            subscriber.user_id,
+            subscriber.stripe_customer_id,
+        )
+        .execute(&mut **transaction)
+        .await?;
+    }
+
+    for subscription in &data.subscriptions {
+        let stripe_subscription_id = Some(subscription.stripe_subscription_id.as_str())
+            .filter(|stripe_subscription_id| !stripe_subscription_id.is_empty());
+        sqlx::query!(
+            r#"INSERT INTO subscriptions (
+                id,
+                subscriber_id,
+                subscription_name,
+                subscription_mailing_address_line_1,
+                subscription_mailing_address_line_2,
+                subscription_city,
+                subscription_state,
+                subscription_postal_code,
+                subscription_email_address,
+                subscription_creation_date,
+                active,
+                subscription_type,
+                stripe_subscription_id,
+                subscription_cancelled_on_date,
+                subscription_anniversary_day,
+                subscription_anniversary_month,
+                billing_source
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+                ON CONFLICT DO NOTHING"#,
+            subscription.id,
+            subscription.subscriber_id,
+            subscription.subscription_name,
+            subscription.subscription_mailing_address_line_1,
+            subscription.subscription_mailing_address_line_2,
+            subscription.subscription_city,
+            subscription.subscription_state,
+            subscription.subscription_postal_code,
+            subscription.subscription_email_address,
+            subscription.subscription_creation_date,
+            subscription.active,
+            subscription.subscription_type.as_str(),
+            stripe_subscription_id,
+            subscription.subscription_cancelled_on_date,
+            subscription.subscription_anniversary_day as i32,
+            subscription.subscription_anniversary_month as i32,
+            subscription.billing_source.as_str(),
+        )
+        .execute(&mut **transaction)
+        .await?;
+    }
+
+    for event in &data.history_events {
+        sqlx::query!(
+            r#"INSERT INTO subscription_event_history (
+                id,
+                subscription_id,
+                subscription_change_event_type,
+                subscription_change_event_date,
+                subscription
+                ) VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT DO NOTHING"#,
+            event.id,
+            event.subscription_id,
+            event.subscription_change_event_type.as_str(),
+            event.subscription_change_event_date,
+            event.subscription,
+        )
+        .execute(&mut **transaction)
+        .await?;
+    }
+
+    for checkout_session in &data.checkout_sessions {
+        sqlx::query!(
+            r#"INSERT INTO checkout_session (
+                id,
+                user_id,
+                session_state,
+                created_at,
+                price_lookup_key,
+                stripe_session_id,
+                subscription
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT DO NOTHING"#,
+            checkout_session.id,
+            checkout_session.user_id,
+            checkout_session.session_state.as_str(),
+            checkout_session.created_at,
+            checkout_session.price_lookup_key,
+            checkout_session.stripe_session_id,
+            checkout_session.subscription,
+        )
+        .execute(&mut **transaction)
+        .await?;
+    }
+
+    for webhook_event in &data.webhook_events {
+        sqlx::query!(
+            r#"INSERT INTO webhook_events (id, event_text, sent_on, processed)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT DO NOTHING"#,
+            webhook_event.id,
+            webhook_event.event_text,
+            webhook_event.sent_on,
+            webhook_event.processed,
+        )
+        .execute(&mut **transaction)
+        .await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,224 @@
+use std::str::FromStr;
+
+use chrono::Utc;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::domain::session_models::{LoginAttempt, LoginFamiliarity, LoginMethod, UserSession};
+
+#[tracing::instrument(name = "Saving a new user session", skip(session, transaction))]
+pub async fn insert_user_session(
+    session: &UserSession,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO user_sessions (
+            id,
+            user_id,
+            login_method,
+            ip_address,
+            user_agent,
+            created_at,
+            expires_at,
+            revoked_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+        session.id,
+        session.user_id,
+        session.login_method.as_str(),
+        session.ip_address,
+        session.user_agent,
+        session.created_at,
+        session.expires_at,
+        session.revoked_at
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "Get active sessions by user id", skip(user_id, pool))]
+pub async fn get_active_sessions_by_user_id(
+    user_id: Uuid,
+    pool: &PgPool,
+) -> Result<Vec<UserSession>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT id, user_id, login_method, ip_address, user_agent, created_at, expires_at, revoked_at
+            FROM user_sessions
+            WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > now()
+            ORDER BY created_at DESC"#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            Some(UserSession {
+                id: row.id,
+                user_id: row.user_id,
+                login_method: LoginMethod::from_str(&row.login_method).ok()?,
+                ip_address: row.ip_address,
+                user_agent: row.user_agent,
+                created_at: row.created_at,
+                expires_at: row.expires_at,
+                revoked_at: row.revoked_at,
+            })
+        })
+        .collect())
+}
+
+/// Returns false when the session does not belong to the user or was already revoked.
+#[tracing::instrument(name = "Revoke a user session", skip(user_id, session_id, pool))]
+pub async fn revoke_user_session(
+    user_id: Uuid,
+    session_id: Uuid,
+    pool: &PgPool,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"UPDATE user_sessions
+            SET revoked_at = $1
+            WHERE id = $2 AND user_id = $3 AND revoked_at IS NULL"#,
+        Utc::now(),
+        session_id,
+        user_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Revokes every active session of the user, e.g. when their password is no longer trusted.
+#[tracing::instrument(name = "Revoke all user sessions", skip(user_id, transaction))]
+pub async fn revoke_all_user_sessions(
+    user_id: Uuid,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"UPDATE user_sessions
+            SET revoked_at = $1
+            WHERE user_id = $2 AND revoked_at IS NULL"#,
+        Utc::now(),
+        user_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.rows_affected())
+}
+
+#[tracing::instrument(name = "Check if a session is active", skip(session_id, pool))]
+pub async fn is_session_active(session_id: Uuid, pool: &PgPool) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"SELECT revoked_at
+            FROM user_sessions
+            WHERE id = $1"#,
+        session_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(matches!(result, Some(row) if row.revoked_at.is_none()))
+}
+
+#[tracing::instrument(
+    name = "Saving a login attempt",
+    skip(attempt, ip_address, user_agent, pool),
+    fields(login_method = %attempt.login_method.as_str())
+)]
+pub async fn insert_login_attempt(
+    attempt: &LoginAttempt,
+    ip_address: Option<&str>,
+    user_agent: Option<&str>,
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO login_history (
+            id,
+            user_id,
+            email_address,
+            login_method,
+            succeeded,
+            failure_reason,
+            ip_address,
+            user_agent,
+            session_id,
+            occurred_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)"#,
+        Uuid::new_v4(),
+        attempt.user_id,
+        attempt.email_address,
+        attempt.login_method.as_str(),
+        attempt.succeeded(),
+        attempt.failure_reason.map(|reason| reason.as_str()),
+        ip_address,
+        user_agent,
+        attempt.session_id,
+        Utc::now()
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+#[tracing::instrument(
+    name = "Compare a login with previous logins",
+    skip(user_id, ip_address, user_agent, pool)
+)]
+pub async fn get_login_familiarity(
+    user_id: Uuid,
+    ip_address: Option<&str>,
+    user_agent: Option<&str>,
+    pool: &PgPool,
+) -> Result<LoginFamiliarity, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"SELECT
+            COUNT(*) > 0 AS "has_previous_logins!",
+            COALESCE(BOOL_OR(user_agent IS NOT DISTINCT FROM $2), FALSE) AS "known_device!",
+            COALESCE(BOOL_OR(ip_address IS NOT DISTINCT FROM $3), FALSE) AS "known_network!"
+            FROM login_history
+            WHERE user_id = $1 AND succeeded = TRUE"#,
+        user_id,
+        user_agent,
+        ip_address
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(LoginFamiliarity {
+        has_previous_logins: result.has_previous_logins,
+        known_device: result.known_device,
+        known_network: result.known_network,
+    })
+}
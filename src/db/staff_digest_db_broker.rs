@@ -0,0 +1,229 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::staff_digest_models::{
+    parse_failed_payment, FailedPayment, PendingAddressChange, StaffDigest, SubscriptionTypeCount,
+};
+use crate::domain::subscription_history_models::HistoryEventType;
+
+/// History events of the type in `[start, end)`, by subscription type.
+#[tracing::instrument(name = "Count subscription history events by type", skip(pool))]
+pub async fn count_history_events_by_subscription_type(
+    event_type: HistoryEventType,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    pool: &PgPool,
+) -> Result<Vec<SubscriptionTypeCount>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT subscription->>'subscription_type' AS "subscription_type!", COUNT(*) AS "count!"
+            FROM subscription_event_history
+            WHERE subscription_change_event_type = $1
+            AND subscription_change_event_date >= $2 AND subscription_change_event_date < $3
+            GROUP BY 1
+            ORDER BY 1"#,
+        event_type.as_str(),
+        start,
+        end
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| SubscriptionTypeCount {
+            subscription_type: row.subscription_type,
+            count: row.count,
+        })
+        .collect())
+}
+
+/// Failed invoice payments among the Stripe events stored in `[start, end)`.
+#[tracing::instrument(name = "Get failed payments", skip(pool))]
+pub async fn get_failed_payments(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    pool: &PgPool,
+) -> Result<Vec<FailedPayment>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT event_text, sent_on FROM webhook_events
+            WHERE sent_on >= $1 AND sent_on < $2
+            ORDER BY sent_on"#,
+        start,
+        end
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| parse_failed_payment(&row.event_text, row.sent_on))
+        .collect())
+}
+
+/// The id, name and anniversary month and day of every active Paper subscription.
+#[tracing::instrument(name = "Get paper subscription anniversaries", skip(pool))]
+pub async fn get_paper_anniversaries(
+    pool: &PgPool,
+) -> Result<Vec<(Uuid, String, u32, u32)>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT id, subscription_name, subscription_anniversary_month,
+            subscription_anniversary_day
+            FROM subscriptions
+            WHERE active AND subscription_type = 'Paper'"#
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            (
+                row.id,
+                row.subscription_name,
+                row.subscription_anniversary_month as u32,
+                row.subscription_anniversary_day as u32,
+            )
+        })
+        .collect())
+}
+
+#[tracing::instrument(name = "Get the next print cutoff", skip(pool))]
+pub async fn get_next_print_cutoff(
+    today: NaiveDate,
+    pool: &PgPool,
+) -> Result<Option<NaiveDate>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"SELECT MIN(cutoff_date) AS cutoff_date FROM issues WHERE cutoff_date >= $1"#,
+        today
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(row.cutoff_date)
+}
+
+/// Active Paper subscriptions whose history shows a new mailing address since
+/// the last fulfillment run, latest change first. A change is an update whose
+/// address differs from the subscription's previous history entry.
+#[tracing::instrument(name = "Get pending address changes", skip(pool))]
+pub async fn get_pending_address_changes(
+    pool: &PgPool,
+) -> Result<Vec<PendingAddressChange>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT DISTINCT ON (s.id)
+            s.id,
+            s.subscription_name,
+            s.subscription_mailing_address_line_1,
+            s.subscription_mailing_address_line_2,
+            s.subscription_city,
+            s.subscription_state,
+            s.subscription_postal_code,
+            changes.subscription_change_event_date AS "changed_at!"
+            FROM (
+                SELECT subscription_id, subscription_change_event_type,
+                    subscription_change_event_date, subscription,
+                    LAG(subscription) OVER (
+                        PARTITION BY subscription_id ORDER BY subscription_change_event_date
+                    ) AS previous
+                FROM subscription_event_history
+            ) AS changes
+            JOIN subscriptions s ON s.id = changes.subscription_id
+            WHERE changes.subscription_change_event_type = $1
+            AND changes.subscription_change_event_date >
+                COALESCE((SELECT MAX(created_at) FROM fulfillment_runs), '-infinity')
+            AND changes.previous IS NOT NULL
+            AND (
+                changes.subscription->>'subscription_mailing_address_line_1',
+                changes.subscription->>'subscription_mailing_address_line_2',
+                changes.subscription->>'subscription_city',
+                changes.subscription->>'subscription_state',
+                changes.subscription->>'subscription_postal_code'
+            ) IS DISTINCT FROM (
+                changes.previous->>'subscription_mailing_address_line_1',
+                changes.previous->>'subscription_mailing_address_line_2',
+                changes.previous->>'subscription_city',
+                changes.previous->>'subscription_state',
+                changes.previous->>'subscription_postal_code'
+            )
+            AND s.active AND s.subscription_type = 'Paper'
+            ORDER BY s.id, changes.subscription_change_event_date DESC"#,
+        HistoryEventType::UpdatedSubscriptionInformation.as_str()
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    let mut changes: Vec<PendingAddressChange> = rows
+        .into_iter()
+        .map(|row| PendingAddressChange {
+            subscription_id: row.id,
+            subscription_name: row.subscription_name,
+            mailing_address: [
+                row.subscription_mailing_address_line_1,
+                row.subscription_mailing_address_line_2,
+                format!(
+                    "{}, {} {}",
+                    row.subscription_city, row.subscription_state, row.subscription_postal_code
+                ),
+            ]
+            .iter()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<&str>>()
+            .join(", "),
+            changed_at: row.changed_at,
+        })
+        .collect();
+    changes.sort_by_key(|change| std::cmp::Reverse(change.changed_at));
+    Ok(changes)
+}
+
+/// Records the digest for its period. `false` if one was already recorded,
+/// in which case it must not be sent again.
+#[tracing::instrument(
+    name = "Claim a staff digest",
+    skip(digest, pool),
+    fields(period_end = %digest.period_end)
+)]
+pub async fn claim_staff_digest(digest: &StaffDigest, pool: &PgPool) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"INSERT INTO staff_digests (id, frequency, period_start, period_end, report, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (frequency, period_end) DO NOTHING"#,
+        Uuid::new_v4(),
+        digest.frequency.as_str(),
+        digest.period_start,
+        digest.period_end,
+        json!(digest),
+        Utc::now()
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
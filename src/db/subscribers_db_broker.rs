@@ -30,6 +30,28 @@ pub async fn insert_subscriber(
     Ok(())
 }
 
+#[tracing::instrument(
+    name = "Retrieving a subscriber id by email address within a transaction",
+    skip(email_address, transaction)
+)]
+pub async fn retrieve_subscriber_id_by_email(
+    email_address: &str,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"SELECT id FROM subscribers WHERE email_address = $1"#,
+        email_address
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.map(|row| row.id))
+}
+
 #[tracing::instrument(name = "Set Stripe Customer ID", skip(id, stripe_customer_id, pool))]
 pub async fn set_stripe_customer_id(
     id: &Uuid,
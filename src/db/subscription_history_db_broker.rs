@@ -1,8 +1,8 @@
 use std::str::FromStr;
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use serde_json::json;
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
 use crate::domain::subscription_history_models::{HistoryEventType, SubscriptionHistoryEvent};
@@ -49,6 +49,44 @@ pub async fn insert_subscription_history_event(
     Ok(())
 }
 
+/// Saves the event under `id` unless it is already stored, so a job that is
+/// retried records the change once.
+#[tracing::instrument(
+    name = "Saving a subscription history event once",
+    skip(subscription, subscription_change_event_type, transaction)
+)]
+pub async fn insert_subscription_history_event_once(
+    id: Uuid,
+    subscription: &OverTheWireSubscription,
+    subscription_change_event_type: HistoryEventType,
+    occurred_at: DateTime<Utc>,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO subscription_event_history (
+            id,
+            subscription_id,
+            subscription_change_event_type,
+            subscription_change_event_date,
+            subscription
+            ) VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (id) DO NOTHING"#,
+        id,
+        subscription.id,
+        subscription_change_event_type.as_str(),
+        occurred_at,
+        json!(subscription)
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
 #[tracing::instrument(
     name = "Get all subscription events by subscription id",
     skip(id, pool)
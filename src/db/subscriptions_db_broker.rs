@@ -6,7 +6,7 @@ use tracing::log::error;
 use uuid::Uuid;
 
 use crate::domain::subscription_models::{
-    NewSubscription, OverTheWireSubscription, SubscriptionType,
+    BillingSource, NewSubscription, OverTheWireSubscription, SubscriptionType,
 };
 use crate::util::NaiveDateExt;
 
@@ -19,6 +19,36 @@ pub async fn insert_subscription(
     stripe_subscription_id: String,
     transaction: &mut Transaction<'_, Postgres>,
 ) -> Result<OverTheWireSubscription, sqlx::Error> {
+    save_subscription(
+        subscription,
+        Some(stripe_subscription_id),
+        BillingSource::Stripe,
+        transaction,
+    )
+    .await
+}
+
+/// Stores a subscription that is paid for outside of Stripe, or not at all.
+#[tracing::instrument(
+    name = "Saving a subscription billed outside of Stripe",
+    skip(subscription, transaction),
+    fields(billing_source = %billing_source.as_str())
+)]
+pub async fn insert_externally_billed_subscription(
+    subscription: NewSubscription,
+    billing_source: BillingSource,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<OverTheWireSubscription, sqlx::Error> {
+    save_subscription(subscription, None, billing_source, transaction).await
+}
+
+async fn save_subscription(
+    subscription: NewSubscription,
+    stripe_subscription_id: Option<String>,
+    billing_source: BillingSource,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<OverTheWireSubscription, sqlx::Error> {
+    let creation_date = Utc::now();
     let subscription_to_be_saved = OverTheWireSubscription {
         id: Uuid::new_v4(),
         subscriber_id: Uuid::from_str(&subscription.subscriber_id)
@@ -32,14 +62,21 @@ pub async fn insert_subscription(
         subscription_state: subscription.subscription_state,
         subscription_postal_code: subscription.subscription_postal_code,
         subscription_email_address: String::from(subscription.subscription_email_address.as_ref()),
-        subscription_creation_date: Utc::now(),
+        subscription_creation_date: creation_date,
         subscription_cancelled_on_date: None,
         subscription_anniversary_day: subscription.subscription_anniversary_day,
         subscription_anniversary_month: subscription.subscription_anniversary_month,
-        subscription_renewal_date: "".to_string(),
+        subscription_renewal_date: calculate_subscription_renewal_date(
+            subscription.subscription_anniversary_month,
+            subscription.subscription_anniversary_day,
+            creation_date,
+        )
+        .await,
         active: true,
         subscription_type: subscription.subscription_type,
-        stripe_subscription_id,
+        stripe_subscription_id: stripe_subscription_id.clone().unwrap_or_default(),
+        billing_source,
+        publication_id: subscription.publication_id,
     };
 
     sqlx::query!(
@@ -59,8 +96,10 @@ pub async fn insert_subscription(
             stripe_subscription_id,
             subscription_cancelled_on_date,
             subscription_anniversary_day,
-            subscription_anniversary_month
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)"#,
+            subscription_anniversary_month,
+            billing_source,
+            publication_id
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)"#,
         subscription_to_be_saved.id,
         subscription_to_be_saved.subscriber_id,
         subscription_to_be_saved.subscription_name,
@@ -73,10 +112,12 @@ pub async fn insert_subscription(
         subscription_to_be_saved.subscription_creation_date,
         subscription_to_be_saved.active,
         subscription_to_be_saved.subscription_type.as_str(),
-        subscription_to_be_saved.stripe_subscription_id,
+        stripe_subscription_id,
         subscription_to_be_saved.subscription_cancelled_on_date,
         subscription_to_be_saved.subscription_anniversary_day as i32,
-        subscription_to_be_saved.subscription_anniversary_month as i32
+        subscription_to_be_saved.subscription_anniversary_month as i32,
+        subscription_to_be_saved.billing_source.as_str(),
+        subscription_to_be_saved.publication_id
     )
     .execute(&mut **transaction)
     .await
@@ -88,14 +129,45 @@ pub async fn insert_subscription(
     Ok(subscription_to_be_saved)
 }
 
+/// Active subscriptions of `subscription_type` held by the subscriber with this
+/// email address or addressed to it.
+#[tracing::instrument(
+    name = "Count active subscriptions by email address and type",
+    skip(email_address, subscription_type, pool)
+)]
+pub async fn count_active_subscriptions_by_email_and_type(
+    email_address: &str,
+    subscription_type: &SubscriptionType,
+    pool: &PgPool,
+) -> Result<i64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"SELECT COUNT(subscriptions.id) AS "count!"
+            FROM subscriptions
+            LEFT JOIN subscribers ON subscribers.id = subscriptions.subscriber_id
+            WHERE subscriptions.active
+            AND subscriptions.subscription_type = $2
+            AND (subscribers.email_address = $1 OR subscriptions.subscription_email_address = $1)"#,
+        email_address,
+        subscription_type.as_str(),
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.count)
+}
+
 #[tracing::instrument(
     name = "Update a subscription by subscription id",
-    skip(id, subscription, pool)
+    skip(id, subscription, transaction)
 )]
 pub async fn update_subscription_by_subscription_id(
     id: Uuid,
     subscription: OverTheWireSubscription,
-    pool: &PgPool,
+    transaction: &mut Transaction<'_, Postgres>,
 ) -> Result<(), sqlx::Error> {
     sqlx::query!(
         r#"UPDATE subscriptions
@@ -117,7 +189,7 @@ pub async fn update_subscription_by_subscription_id(
         subscription.subscription_email_address,
         id,
     )
-    .execute(pool)
+    .execute(&mut **transaction)
     .await
     .map_err(|e| {
         tracing::error!("Failed to execute query: {:?}", e);
@@ -127,6 +199,27 @@ pub async fn update_subscription_by_subscription_id(
     Ok(())
 }
 
+#[tracing::instrument(name = "Link a subscription to a publication", skip(pool))]
+pub async fn set_subscription_publication(
+    id: Uuid,
+    publication_id: Option<Uuid>,
+    pool: &PgPool,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"UPDATE subscriptions SET publication_id = $1 WHERE id = $2"#,
+        publication_id,
+        id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
 #[tracing::instrument(name = "Get all subscriptions by subscriber id", skip(id, pool))]
 pub async fn retrieve_subscriptions_by_subscriber_id(
     id: Uuid,
@@ -149,7 +242,9 @@ pub async fn retrieve_subscriptions_by_subscriber_id(
             active,
             subscription_type,
             stripe_subscription_id,
-            subscription_anniversary_month
+            subscription_anniversary_month,
+            billing_source,
+            publication_id
             FROM subscriptions WHERE subscriber_id = $1"#,
         id
     )
@@ -178,7 +273,79 @@ pub async fn retrieve_subscriptions_by_subscriber_id(
             subscription_anniversary_day: row.subscription_anniversary_day as u32,
             subscription_type: from_str_to_subscription_type(row.subscription_type),
             active: row.active,
-            stripe_subscription_id: row.stripe_subscription_id,
+            stripe_subscription_id: row.stripe_subscription_id.unwrap_or_default(),
+            billing_source: BillingSource::from_str(&row.billing_source).unwrap_or_default(),
+            publication_id: row.publication_id,
+            subscription_anniversary_month: row.subscription_anniversary_month as u32,
+            subscription_renewal_date: calculate_subscription_renewal_date(
+                row.subscription_anniversary_month as u32,
+                row.subscription_anniversary_day as u32,
+                row.subscription_creation_date,
+            )
+            .await,
+        })
+    }
+    Ok(subscriptions)
+}
+
+/// Active subscriptions that send email to the address, compared without case.
+#[tracing::instrument(name = "Get active subscriptions by email address", skip(pool))]
+pub async fn retrieve_active_subscriptions_by_email(
+    email_address: &str,
+    pool: &PgPool,
+) -> Result<Vec<OverTheWireSubscription>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT
+            id,
+            subscriber_id,
+            subscription_name,
+            subscription_mailing_address_line_1,
+            subscription_mailing_address_line_2,
+            subscription_city,
+            subscription_state,
+            subscription_postal_code,
+            subscription_email_address,
+            subscription_creation_date,
+            subscription_cancelled_on_date,
+            subscription_anniversary_day,
+            active,
+            subscription_type,
+            stripe_subscription_id,
+            subscription_anniversary_month,
+            billing_source,
+            publication_id
+            FROM subscriptions
+            WHERE lower(subscription_email_address) = lower($1) AND active"#,
+        email_address
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    let mut subscriptions: Vec<OverTheWireSubscription> = Vec::new();
+
+    for row in rows {
+        subscriptions.push(OverTheWireSubscription {
+            id: row.id,
+            subscriber_id: row.subscriber_id,
+            subscription_name: row.subscription_name,
+            subscription_email_address: row.subscription_email_address,
+            subscription_mailing_address_line_1: row.subscription_mailing_address_line_1,
+            subscription_mailing_address_line_2: row.subscription_mailing_address_line_2,
+            subscription_city: row.subscription_city,
+            subscription_state: row.subscription_state,
+            subscription_postal_code: row.subscription_postal_code,
+            subscription_creation_date: row.subscription_creation_date,
+            subscription_cancelled_on_date: row.subscription_cancelled_on_date,
+            subscription_anniversary_day: row.subscription_anniversary_day as u32,
+            subscription_type: from_str_to_subscription_type(row.subscription_type),
+            active: row.active,
+            stripe_subscription_id: row.stripe_subscription_id.unwrap_or_default(),
+            billing_source: BillingSource::from_str(&row.billing_source).unwrap_or_default(),
+            publication_id: row.publication_id,
             subscription_anniversary_month: row.subscription_anniversary_month as u32,
             subscription_renewal_date: calculate_subscription_renewal_date(
                 row.subscription_anniversary_month as u32,
@@ -213,7 +380,9 @@ pub async fn retrieve_subscription_by_subscription_id(
             active,
             subscription_type,
             stripe_subscription_id,
-            subscription_anniversary_month
+            subscription_anniversary_month,
+            billing_source,
+            publication_id
             FROM subscriptions WHERE id = $1"#,
         id
     )
@@ -239,7 +408,9 @@ pub async fn retrieve_subscription_by_subscription_id(
         subscription_anniversary_day: result.subscription_anniversary_day as u32,
         subscription_type: from_str_to_subscription_type(result.subscription_type),
         active: result.active,
-        stripe_subscription_id: result.stripe_subscription_id,
+        stripe_subscription_id: result.stripe_subscription_id.unwrap_or_default(),
+        billing_source: BillingSource::from_str(&result.billing_source).unwrap_or_default(),
+        publication_id: result.publication_id,
         subscription_anniversary_month: result.subscription_anniversary_month as u32,
         subscription_renewal_date: calculate_subscription_renewal_date(
             result.subscription_anniversary_month as u32,
@@ -250,6 +421,7 @@ pub async fn retrieve_subscription_by_subscription_id(
     })
 }
 
+/// Returns the date the subscription is cancelled on.
 #[tracing::instrument(
     name = "Cancel a subscription by subscription id",
     skip(id, transaction)
@@ -257,7 +429,7 @@ pub async fn retrieve_subscription_by_subscription_id(
 pub async fn cancel_subscription_by_subscription_id(
     id: Uuid,
     transaction: &mut Transaction<'_, Postgres>,
-) -> Result<(), sqlx::Error> {
+) -> Result<DateTime<Utc>, sqlx::Error> {
     let cancelled_date = Utc::now();
     sqlx::query!(
         r#"UPDATE subscriptions
@@ -274,7 +446,7 @@ pub async fn cancel_subscription_by_subscription_id(
         e
     })?;
 
-    Ok(())
+    Ok(cancelled_date)
 }
 
 #[tracing::instrument(name = "Get all subscriptions", skip(pool))]
@@ -298,7 +470,9 @@ pub async fn retrieve_all_subscriptions(
             active,
             subscription_type,
             stripe_subscription_id,
-            subscription_anniversary_month
+            subscription_anniversary_month,
+            billing_source,
+            publication_id
             FROM subscriptions"#
     )
     .fetch_all(pool)
@@ -326,7 +500,90 @@ pub async fn retrieve_all_subscriptions(
             subscription_anniversary_day: row.subscription_anniversary_day as u32,
             subscription_type: from_str_to_subscription_type(row.subscription_type),
             active: row.active,
-            stripe_subscription_id: row.stripe_subscription_id,
+            stripe_subscription_id: row.stripe_subscription_id.unwrap_or_default(),
+            billing_source: BillingSource::from_str(&row.billing_source).unwrap_or_default(),
+            publication_id: row.publication_id,
+            subscription_anniversary_month: row.subscription_anniversary_month as u32,
+            subscription_renewal_date: calculate_subscription_renewal_date(
+                row.subscription_anniversary_month as u32,
+                row.subscription_anniversary_day as u32,
+                row.subscription_creation_date,
+            )
+            .await,
+        })
+    }
+    Ok(subscriptions)
+}
+
+/// Subscriptions of `subscription_type` that existed at `at` and had not been
+/// cancelled by then.
+#[tracing::instrument(
+    name = "Get subscriptions active at a point in time",
+    skip(subscription_type, pool)
+)]
+pub async fn retrieve_subscriptions_active_at(
+    subscription_type: &SubscriptionType,
+    at: DateTime<Utc>,
+    pool: &PgPool,
+) -> Result<Vec<OverTheWireSubscription>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT
+            id,
+            subscriber_id,
+            subscription_name,
+            subscription_mailing_address_line_1,
+            subscription_mailing_address_line_2,
+            subscription_city,
+            subscription_state,
+            subscription_postal_code,
+            subscription_email_address,
+            subscription_creation_date,
+            subscription_cancelled_on_date,
+            subscription_anniversary_day,
+            active,
+            subscription_type,
+            stripe_subscription_id,
+            subscription_anniversary_month,
+            billing_source,
+            publication_id
+            FROM subscriptions
+            WHERE subscription_type = $1
+            AND subscription_creation_date <= $2
+            AND (
+                subscription_cancelled_on_date > $2
+                OR (subscription_cancelled_on_date IS NULL AND active)
+            )"#,
+        subscription_type.as_str(),
+        at,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    let mut subscriptions: Vec<OverTheWireSubscription> = Vec::new();
+
+    for row in rows {
+        subscriptions.push(OverTheWireSubscription {
+            id: row.id,
+            subscriber_id: row.subscriber_id,
+            subscription_name: row.subscription_name,
+            subscription_email_address: row.subscription_email_address,
+            subscription_mailing_address_line_1: row.subscription_mailing_address_line_1,
+            subscription_mailing_address_line_2: row.subscription_mailing_address_line_2,
+            subscription_city: row.subscription_city,
+            subscription_state: row.subscription_state,
+            subscription_postal_code: row.subscription_postal_code,
+            subscription_creation_date: row.subscription_creation_date,
+            subscription_cancelled_on_date: row.subscription_cancelled_on_date,
+            subscription_anniversary_day: row.subscription_anniversary_day as u32,
+            subscription_type: from_str_to_subscription_type(row.subscription_type),
+            active: row.active,
+            stripe_subscription_id: row.stripe_subscription_id.unwrap_or_default(),
+            billing_source: BillingSource::from_str(&row.billing_source).unwrap_or_default(),
+            publication_id: row.publication_id,
             subscription_anniversary_month: row.subscription_anniversary_month as u32,
             subscription_renewal_date: calculate_subscription_renewal_date(
                 row.subscription_anniversary_month as u32,
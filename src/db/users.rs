@@ -2,7 +2,7 @@ use sqlx::{Error, PgPool, Postgres, Transaction};
 use std::str::FromStr;
 use uuid::Uuid;
 
-use crate::domain::user_models::{from_str_to_user_group, User, UserGroup};
+use crate::domain::user_models::{from_str_to_user_group, User, UserAccess, UserGroup};
 
 #[tracing::instrument(name = "Count users with a given username", skip(email_address, pool))]
 pub async fn count_users_with_email_address(
@@ -29,7 +29,7 @@ pub async fn count_users_with_email_address(
 #[tracing::instrument(name = "Get user by email address", skip(email_address, pool))]
 pub async fn get_user_by_email_address(email_address: &str, pool: &PgPool) -> Result<User, Error> {
     let result = sqlx::query!(
-        r#"SELECT user_id, email_address, password, user_group
+        r#"SELECT user_id, email_address, password, user_group, disabled, password_reset_required
             FROM users 
             WHERE email_address = $1"#,
         email_address,
@@ -46,14 +46,38 @@ pub async fn get_user_by_email_address(email_address: &str, pool: &PgPool) -> Re
         email_address: result.email_address,
         password: result.password,
         user_group: from_str_to_user_group(result.user_group),
+        disabled: result.disabled,
+        password_reset_required: result.password_reset_required,
     })
 }
 
+#[tracing::instrument(
+    name = "Get user id by email address within a transaction",
+    skip(email_address, transaction)
+)]
+pub async fn get_user_id_by_email_address(
+    email_address: &str,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<Option<Uuid>, Error> {
+    let result = sqlx::query!(
+        r#"SELECT user_id FROM users WHERE email_address = $1"#,
+        email_address,
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("{:?}", e);
+        e
+    })?;
+
+    Ok(result.map(|row| row.user_id))
+}
+
 #[tracing::instrument(name = "Get user by user_id", skip(user_id, pool))]
 pub async fn get_user_by_user_id(user_id: &str, pool: &PgPool) -> Result<User, Error> {
     let id = Uuid::from_str(user_id).unwrap();
     let result = sqlx::query!(
-        r#"SELECT user_id, email_address, password, user_group
+        r#"SELECT user_id, email_address, password, user_group, disabled, password_reset_required
             FROM users 
             WHERE user_id = $1"#,
         id,
@@ -70,13 +94,15 @@ pub async fn get_user_by_user_id(user_id: &str, pool: &PgPool) -> Result<User, E
         email_address: result.email_address,
         password: result.password,
         user_group: from_str_to_user_group(result.user_group),
+        disabled: result.disabled,
+        password_reset_required: result.password_reset_required,
     })
 }
 
 #[tracing::instrument(name = "Get all users from the database", skip(pool))]
 pub async fn get_all_users(pool: &PgPool) -> Result<Vec<User>, Error> {
     let rows = sqlx::query!(
-        r#"SELECT user_id, email_address, password, user_group
+        r#"SELECT user_id, email_address, password, user_group, disabled, password_reset_required
             FROM users"#,
     )
     .fetch_all(pool)
@@ -93,6 +119,8 @@ pub async fn get_all_users(pool: &PgPool) -> Result<Vec<User>, Error> {
             email_address: row.email_address,
             password: row.password,
             user_group: from_str_to_user_group(row.user_group),
+            disabled: row.disabled,
+            password_reset_required: row.password_reset_required,
         })
         .collect())
 }
@@ -129,21 +157,21 @@ pub async fn insert_user(
 
 #[tracing::instrument(
     name = "Update the password",
-    skip(email_address, hashed_password, pool)
+    skip(email_address, hashed_password, transaction)
 )]
 pub async fn update_password(
     email_address: &str,
     hashed_password: &str,
-    pool: &PgPool,
+    transaction: &mut Transaction<'_, Postgres>,
 ) -> Result<(), Error> {
     sqlx::query!(
         r#"UPDATE users
-            SET password = $1
+            SET password = $1, password_reset_required = FALSE
             WHERE email_address = $2"#,
         hashed_password,
         email_address,
     )
-    .execute(pool)
+    .execute(&mut **transaction)
     .await
     .map_err(|e: Error| {
         tracing::error!("{:?}", e);
@@ -153,8 +181,11 @@ pub async fn update_password(
     Ok(())
 }
 
-#[tracing::instrument(name = "Demote admin to user", skip(user_id, pool))]
-pub async fn demote_admin_to_user(user_id: Uuid, pool: &PgPool) -> Result<(), Error> {
+#[tracing::instrument(name = "Demote admin to user", skip(user_id, transaction))]
+pub async fn demote_admin_to_user(
+    user_id: Uuid,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), Error> {
     sqlx::query!(
         r#"UPDATE users
             SET user_group = $1
@@ -162,7 +193,7 @@ pub async fn demote_admin_to_user(user_id: Uuid, pool: &PgPool) -> Result<(), Er
         UserGroup::USER.as_str(),
         user_id,
     )
-    .execute(pool)
+    .execute(&mut **transaction)
     .await
     .map_err(|e: Error| {
         tracing::error!("{:?}", e);
@@ -190,3 +221,95 @@ pub async fn promote_user_to_admin(user_id: Uuid, pool: &PgPool) -> Result<(), E
 
     Ok(())
 }
+
+/// Locks every enabled admin row until the transaction ends, so two admins cannot
+/// concurrently demote or disable each other and leave nobody in charge.
+#[tracing::instrument(name = "Lock enabled admins", skip(transaction))]
+pub async fn lock_enabled_admin_ids(
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<Vec<Uuid>, Error> {
+    let rows = sqlx::query!(
+        r#"SELECT user_id
+            FROM users
+            WHERE user_group = $1 AND disabled = FALSE
+            FOR UPDATE"#,
+        UserGroup::ADMIN.as_str(),
+    )
+    .fetch_all(&mut **transaction)
+    .await
+    .map_err(|e: Error| {
+        tracing::error!("{:?}", e);
+        e
+    })?;
+
+    Ok(rows.into_iter().map(|row| row.user_id).collect())
+}
+
+#[tracing::instrument(name = "Set user disabled", skip(user_id, transaction))]
+pub async fn set_user_disabled(
+    user_id: Uuid,
+    disabled: bool,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), Error> {
+    sqlx::query!(
+        r#"UPDATE users
+            SET disabled = $1,
+                disabled_on = CASE WHEN $1 THEN now() ELSE NULL END
+            WHERE user_id = $2"#,
+        disabled,
+        user_id,
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e: Error| {
+        tracing::error!("{:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "Require a password reset", skip(user_id, transaction))]
+pub async fn set_password_reset_required(
+    user_id: Uuid,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), Error> {
+    sqlx::query!(
+        r#"UPDATE users
+            SET password_reset_required = TRUE
+            WHERE user_id = $1"#,
+        user_id,
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e: Error| {
+        tracing::error!("{:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+/// Unknown users are not restricted; only explicit flags on an existing row are.
+#[tracing::instrument(name = "Get a user's access flags", skip(user_id, pool))]
+pub async fn get_user_access(user_id: Uuid, pool: &PgPool) -> Result<UserAccess, Error> {
+    let result = sqlx::query!(
+        r#"SELECT disabled, password_reset_required
+            FROM users
+            WHERE user_id = $1"#,
+        user_id,
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e: Error| {
+        tracing::error!("{:?}", e);
+        e
+    })?;
+
+    Ok(result
+        .map(|row| UserAccess {
+            disabled: row.disabled,
+            password_reset_required: row.password_reset_required,
+        })
+        .unwrap_or_default())
+}
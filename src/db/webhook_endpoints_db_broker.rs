@@ -0,0 +1,530 @@
+use std::str::FromStr;
+
+use chrono::{Duration, Utc};
+use serde_json::Value;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::domain::webhook_endpoint_models::{
+    SubscriptionEventType, WebhookDelivery, WebhookDeliveryStatus, WebhookEndpoint,
+    WebhookEndpointRequest, MAX_WEBHOOK_ATTEMPTS, WEBHOOK_CLAIM_TIMEOUT_SECONDS,
+};
+
+#[tracing::instrument(
+    name = "Saving a webhook endpoint in the database",
+    skip(endpoint, pool),
+    fields(endpoint_id = %endpoint.id)
+)]
+pub async fn insert_webhook_endpoint(
+    endpoint: &WebhookEndpoint,
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO webhook_endpoints (id, url, description, secret, event_types, enabled,
+            created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+        endpoint.id,
+        endpoint.url,
+        endpoint.description,
+        endpoint.secret,
+        &event_type_strings(&endpoint.event_types),
+        endpoint.enabled,
+        endpoint.created_at,
+        endpoint.updated_at
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+/// Oldest first.
+#[tracing::instrument(name = "Get webhook endpoints", skip(pool))]
+pub async fn get_webhook_endpoints(pool: &PgPool) -> Result<Vec<WebhookEndpoint>, sqlx::Error> {
+    select_webhook_endpoints(None, pool).await
+}
+
+#[tracing::instrument(name = "Get a webhook endpoint", skip(pool))]
+pub async fn get_webhook_endpoint(id: Uuid, pool: &PgPool) -> Result<WebhookEndpoint, sqlx::Error> {
+    select_webhook_endpoints(Some(id), pool)
+        .await?
+        .pop()
+        .ok_or(sqlx::Error::RowNotFound)
+}
+
+/// Keeps the secret. `RowNotFound` if there is no such endpoint.
+#[tracing::instrument(name = "Update a webhook endpoint", skip(request, pool))]
+pub async fn update_webhook_endpoint(
+    id: Uuid,
+    request: &WebhookEndpointRequest,
+    pool: &PgPool,
+) -> Result<WebhookEndpoint, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"UPDATE webhook_endpoints
+            SET url = $1, description = $2, event_types = $3, enabled = $4, updated_at = $5
+            WHERE id = $6"#,
+        request.url.trim(),
+        request.description.trim(),
+        &event_type_strings(&request.event_types),
+        request.enabled,
+        Utc::now(),
+        id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+    get_webhook_endpoint(id, pool).await
+}
+
+/// Deliveries still waiting for the endpoint die with it; the rest stay in
+/// the log.
+#[tracing::instrument(name = "Delete a webhook endpoint", skip(pool))]
+pub async fn delete_webhook_endpoint(id: Uuid, pool: &PgPool) -> Result<bool, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    let result = sqlx::query!(r#"DELETE FROM webhook_endpoints WHERE id = $1"#, id)
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to execute query: {:?}", e);
+            e
+        })?;
+    sqlx::query!(
+        r#"UPDATE webhook_deliveries
+            SET status = 'Dead', last_error = 'The endpoint was deleted'
+            WHERE endpoint_id = $1 AND status IN ('Pending', 'Delivering')"#,
+        id
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+    transaction.commit().await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Queues a delivery of the event to every enabled endpoint subscribed to it,
+/// skipping endpoints that already have the event, so a retried job does not
+/// send it twice. Returns the new deliveries' ids.
+#[tracing::instrument(name = "Queue webhook deliveries", skip(payload, transaction))]
+pub async fn queue_webhook_deliveries(
+    event_id: Uuid,
+    event_type: SubscriptionEventType,
+    payload: &Value,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<Vec<Uuid>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"INSERT INTO webhook_deliveries (id, endpoint_id, event_id, event_type, payload,
+            status, next_attempt_at, created_at)
+            SELECT gen_random_uuid(), e.id, $1, $2, $3, 'Pending', $4, $4
+            FROM webhook_endpoints e
+            WHERE e.enabled AND $2 = ANY(e.event_types)
+                AND NOT EXISTS (
+                    SELECT 1 FROM webhook_deliveries d
+                    WHERE d.endpoint_id = e.id AND d.event_id = $1
+                )
+            RETURNING id"#,
+        event_id,
+        event_type.as_str(),
+        payload,
+        Utc::now()
+    )
+    .fetch_all(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows.into_iter().map(|row| row.id).collect())
+}
+
+/// Claims up to `limit` deliveries that are due, including ones whose
+/// previous claim lapsed because the worker holding it died. A claim that
+/// lapsed on the last attempt is dead-lettered instead, so a delivery that
+/// brings the worker down is not tried forever.
+#[tracing::instrument(name = "Claim due webhook deliveries", skip(pool))]
+pub async fn claim_due_webhook_deliveries(
+    limit: i64,
+    pool: &PgPool,
+) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+    let now = Utc::now();
+    sqlx::query!(
+        r#"UPDATE webhook_deliveries
+            SET status = 'Dead', last_error = 'The last attempt never finished'
+            WHERE status = 'Delivering' AND next_attempt_at <= $1 AND attempts >= $2"#,
+        now,
+        MAX_WEBHOOK_ATTEMPTS
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    let rows = sqlx::query!(
+        r#"UPDATE webhook_deliveries
+            SET status = 'Delivering', attempts = attempts + 1, next_attempt_at = $1
+            WHERE id IN (
+                SELECT id FROM webhook_deliveries
+                WHERE status IN ('Pending', 'Delivering') AND next_attempt_at <= $2
+                    AND attempts < $4
+                ORDER BY next_attempt_at
+                LIMIT $3
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, endpoint_id, event_id, event_type, payload, status, attempts,
+                last_response_status, last_error, next_attempt_at, created_at, delivered_at"#,
+        now + Duration::seconds(WEBHOOK_CLAIM_TIMEOUT_SECONDS),
+        now,
+        limit,
+        MAX_WEBHOOK_ATTEMPTS
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    let mut deliveries = Vec::new();
+    for row in rows {
+        let event_type = match SubscriptionEventType::from_str(&row.event_type) {
+            Ok(event_type) => event_type,
+            Err(_) => {
+                mark_unreadable_delivery_dead(row.id, &row.event_type, pool).await?;
+                continue;
+            }
+        };
+        deliveries.push(WebhookDelivery {
+            id: row.id,
+            endpoint_id: row.endpoint_id,
+            event_id: row.event_id,
+            event_type,
+            payload: row.payload,
+            status: WebhookDeliveryStatus::from_str(&row.status)
+                .unwrap_or(WebhookDeliveryStatus::Delivering),
+            attempts: row.attempts,
+            last_response_status: row.last_response_status,
+            last_error: row.last_error,
+            next_attempt_at: row.next_attempt_at,
+            created_at: row.created_at,
+            delivered_at: row.delivered_at,
+        });
+    }
+    Ok(deliveries)
+}
+
+/// Claims one queued delivery, if it is still pending. Used to deliver right
+/// after the event.
+#[tracing::instrument(name = "Claim a webhook delivery", skip(pool))]
+pub async fn claim_webhook_delivery(
+    id: Uuid,
+    pool: &PgPool,
+) -> Result<Option<WebhookDelivery>, sqlx::Error> {
+    let now = Utc::now();
+    let row = sqlx::query!(
+        r#"UPDATE webhook_deliveries
+            SET status = 'Delivering', attempts = attempts + 1, next_attempt_at = $1
+            WHERE id = $2 AND status = 'Pending' AND next_attempt_at <= $3
+            RETURNING id, endpoint_id, event_id, event_type, payload, status, attempts,
+                last_response_status, last_error, next_attempt_at, created_at, delivered_at"#,
+        now + Duration::seconds(WEBHOOK_CLAIM_TIMEOUT_SECONDS),
+        id,
+        now
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    let row = match row {
+        Some(row) => row,
+        None => return Ok(None),
+    };
+    let event_type = match SubscriptionEventType::from_str(&row.event_type) {
+        Ok(event_type) => event_type,
+        Err(_) => {
+            mark_unreadable_delivery_dead(row.id, &row.event_type, pool).await?;
+            return Ok(None);
+        }
+    };
+    Ok(Some(WebhookDelivery {
+        id: row.id,
+        endpoint_id: row.endpoint_id,
+        event_id: row.event_id,
+        event_type,
+        payload: row.payload,
+        status: WebhookDeliveryStatus::from_str(&row.status)
+            .unwrap_or(WebhookDeliveryStatus::Delivering),
+        attempts: row.attempts,
+        last_response_status: row.last_response_status,
+        last_error: row.last_error,
+        next_attempt_at: row.next_attempt_at,
+        created_at: row.created_at,
+        delivered_at: row.delivered_at,
+    }))
+}
+
+/// A claimed delivery whose event type this version does not know cannot be
+/// sent, so it is left dead rather than claimed again once the claim lapses.
+async fn mark_unreadable_delivery_dead(
+    id: Uuid,
+    event_type: &str,
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    tracing::error!(
+        "Webhook delivery {} has the unknown event type {}",
+        id,
+        event_type
+    );
+    sqlx::query!(
+        r#"UPDATE webhook_deliveries
+            SET status = 'Dead', last_error = $1
+            WHERE id = $2"#,
+        format!("Unknown event type {}", event_type),
+        id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+/// Only the claim that made the attempt can settle it: once the claim lapsed
+/// and another worker took the delivery over, the update is skipped.
+#[tracing::instrument(
+    name = "Mark a webhook delivered",
+    skip(delivery, pool),
+    fields(delivery_id = %delivery.id)
+)]
+pub async fn mark_webhook_delivered(
+    delivery: &WebhookDelivery,
+    response_status: i32,
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    let result = sqlx::query!(
+        r#"UPDATE webhook_deliveries
+            SET status = 'Delivered', delivered_at = $1, last_response_status = $2,
+                last_error = NULL
+            WHERE id = $3 AND status = 'Delivering' AND attempts = $4"#,
+        Utc::now(),
+        response_status,
+        delivery.id,
+        delivery.attempts
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+    warn_if_taken_over(delivery, result.rows_affected());
+
+    Ok(())
+}
+
+/// Schedules the next attempt, or leaves the delivery dead once it is out of
+/// attempts.
+#[tracing::instrument(
+    name = "Mark a webhook delivery failed",
+    skip(delivery, error, pool),
+    fields(delivery_id = %delivery.id)
+)]
+pub async fn mark_webhook_delivery_failed(
+    delivery: &WebhookDelivery,
+    response_status: Option<i32>,
+    error: &str,
+    pool: &PgPool,
+) -> Result<WebhookDeliveryStatus, sqlx::Error> {
+    let (status, next_attempt_at) = delivery.after_failure(Utc::now());
+    let result = sqlx::query!(
+        r#"UPDATE webhook_deliveries
+            SET status = $1, next_attempt_at = $2, last_response_status = $3, last_error = $4
+            WHERE id = $5 AND status = 'Delivering' AND attempts = $6"#,
+        status.as_str(),
+        next_attempt_at,
+        response_status,
+        error,
+        delivery.id,
+        delivery.attempts
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+    warn_if_taken_over(delivery, result.rows_affected());
+
+    Ok(status)
+}
+
+fn warn_if_taken_over(delivery: &WebhookDelivery, rows_affected: u64) {
+    if rows_affected == 0 {
+        tracing::warn!(
+            "Attempt {} at webhook delivery {} finished after its claim lapsed; keeping the newer state",
+            delivery.attempts,
+            delivery.id
+        );
+    }
+}
+
+/// Newest first, at most `limit`.
+#[tracing::instrument(name = "Get webhook deliveries", skip(pool))]
+pub async fn get_webhook_deliveries(
+    endpoint_id: Option<Uuid>,
+    status: Option<WebhookDeliveryStatus>,
+    limit: i64,
+    pool: &PgPool,
+) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+    select_webhook_deliveries(None, endpoint_id, status, limit, pool).await
+}
+
+#[tracing::instrument(name = "Get a webhook delivery", skip(pool))]
+pub async fn get_webhook_delivery(id: Uuid, pool: &PgPool) -> Result<WebhookDelivery, sqlx::Error> {
+    select_webhook_deliveries(Some(id), None, None, 1, pool)
+        .await?
+        .pop()
+        .ok_or(sqlx::Error::RowNotFound)
+}
+
+/// Queues the delivery's event to its endpoint again, as a new delivery with
+/// fresh attempts. `RowNotFound` if the delivery or its endpoint is gone.
+#[tracing::instrument(name = "Replay a webhook delivery", skip(pool))]
+pub async fn replay_webhook_delivery(id: Uuid, pool: &PgPool) -> Result<Uuid, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"INSERT INTO webhook_deliveries (id, endpoint_id, event_id, event_type, payload,
+            status, next_attempt_at, created_at)
+            SELECT $1, d.endpoint_id, d.event_id, d.event_type, d.payload, 'Pending', $2, $2
+            FROM webhook_deliveries d
+            JOIN webhook_endpoints e ON e.id = d.endpoint_id
+            WHERE d.id = $3
+            RETURNING id"#,
+        Uuid::new_v4(),
+        Utc::now(),
+        id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(row.id)
+}
+
+fn event_type_strings(event_types: &[SubscriptionEventType]) -> Vec<String> {
+    event_types
+        .iter()
+        .map(|event_type| event_type.as_str().to_string())
+        .collect()
+}
+
+async fn select_webhook_endpoints(
+    id: Option<Uuid>,
+    pool: &PgPool,
+) -> Result<Vec<WebhookEndpoint>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT id, url, description, secret, event_types, enabled, created_at, updated_at
+            FROM webhook_endpoints
+            WHERE ($1::uuid IS NULL OR id = $1)
+            ORDER BY created_at"#,
+        id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| WebhookEndpoint {
+            id: row.id,
+            url: row.url,
+            description: row.description,
+            secret: row.secret,
+            event_types: row
+                .event_types
+                .iter()
+                .filter_map(|event_type| SubscriptionEventType::from_str(event_type).ok())
+                .collect(),
+            enabled: row.enabled,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+        .collect())
+}
+
+async fn select_webhook_deliveries(
+    id: Option<Uuid>,
+    endpoint_id: Option<Uuid>,
+    status: Option<WebhookDeliveryStatus>,
+    limit: i64,
+    pool: &PgPool,
+) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT id, endpoint_id, event_id, event_type, payload, status, attempts,
+            last_response_status, last_error, next_attempt_at, created_at, delivered_at
+            FROM webhook_deliveries
+            WHERE ($1::uuid IS NULL OR id = $1)
+            AND ($2::uuid IS NULL OR endpoint_id = $2)
+            AND ($3::text IS NULL OR status = $3)
+            ORDER BY created_at DESC
+            LIMIT $4"#,
+        id,
+        endpoint_id,
+        status.map(|status| status.as_str()),
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            Some(WebhookDelivery {
+                id: row.id,
+                endpoint_id: row.endpoint_id,
+                event_id: row.event_id,
+                event_type: SubscriptionEventType::from_str(&row.event_type).ok()?,
+                payload: row.payload,
+                status: WebhookDeliveryStatus::from_str(&row.status)
+                    .unwrap_or(WebhookDeliveryStatus::Pending),
+                attempts: row.attempts,
+                last_response_status: row.last_response_status,
+                last_error: row.last_error,
+                next_attempt_at: row.next_attempt_at,
+                created_at: row.created_at,
+                delivered_at: row.delivered_at,
+            })
+        })
+        .collect())
+}
@@ -1,4 +1,5 @@
 use crate::domain::webhook_event::WebhookEvent;
+use chrono::{DateTime, Utc};
 use sqlx::{Error, PgPool};
 use uuid::Uuid;
 
@@ -72,3 +73,29 @@ pub async fn get_unprocessed_webhook_events(pool: &PgPool) -> Result<Vec<Webhook
 
     Ok(events)
 }
+
+/// Flags the given events, and every event sent on or after `since`, for processing
+/// again. Returns the ids that were flagged.
+#[tracing::instrument(name = "Set webhook events to unprocessed", skip(ids, since, pool))]
+pub async fn set_webhook_events_to_unprocessed(
+    ids: &[Uuid],
+    since: Option<DateTime<Utc>>,
+    pool: &PgPool,
+) -> Result<Vec<Uuid>, Error> {
+    let results = sqlx::query!(
+        r#"UPDATE webhook_events
+            SET processed = false
+            WHERE id = ANY($1) OR ($2::timestamptz IS NOT NULL AND sent_on >= $2)
+            RETURNING id"#,
+        ids,
+        since
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e: Error| {
+        tracing::error!("{:?}", e);
+        e
+    })?;
+
+    Ok(results.into_iter().map(|row| row.id).collect())
+}
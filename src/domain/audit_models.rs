@@ -0,0 +1,330 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const DEFAULT_AUDIT_PAGE_SIZE: i64 = 50;
+pub const MAX_AUDIT_PAGE_SIZE: i64 = 200;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum AuditAction {
+    CreateAdmin,
+    PromoteUser,
+    DemoteUser,
+    DisableUser,
+    EnableUser,
+    ForcePasswordReset,
+    ListUsers,
+    ListSubscribers,
+    ListSubscriptions,
+    ListAuditLog,
+    ResetPassword,
+    ResetPasswordFromForgotPassword,
+    ForgotPasswordLogin,
+    CancelSubscription,
+    CreateInvitation,
+    RevokeInvitation,
+    AcceptInvitation,
+    ImportSubscriptions,
+    CreateFulfillmentRun,
+    CreatePublication,
+    UpdatePublication,
+    DeletePublication,
+    CreateIssue,
+    UpdateIssue,
+    DeleteIssue,
+    SetSubscriptionPublication,
+    CreateBroadcast,
+    ResumeBroadcast,
+    RequeueEmail,
+    ClearEmailSuppression,
+    CreateNotificationChannel,
+    UpdateNotificationChannel,
+    DeleteNotificationChannel,
+    CreateWebhookEndpoint,
+    UpdateWebhookEndpoint,
+    DeleteWebhookEndpoint,
+    ReplayWebhookDelivery,
+    RetryJob,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::CreateAdmin => "CreateAdmin",
+            AuditAction::PromoteUser => "PromoteUser",
+            AuditAction::DemoteUser => "DemoteUser",
+            AuditAction::DisableUser => "DisableUser",
+            AuditAction::EnableUser => "EnableUser",
+            AuditAction::ForcePasswordReset => "ForcePasswordReset",
+            AuditAction::ListUsers => "ListUsers",
+            AuditAction::ListSubscribers => "ListSubscribers",
+            AuditAction::ListSubscriptions => "ListSubscriptions",
+            AuditAction::ListAuditLog => "ListAuditLog",
+            AuditAction::ResetPassword => "ResetPassword",
+            AuditAction::ResetPasswordFromForgotPassword => "ResetPasswordFromForgotPassword",
+            AuditAction::ForgotPasswordLogin => "ForgotPasswordLogin",
+            AuditAction::CancelSubscription => "CancelSubscription",
+            AuditAction::CreateInvitation => "CreateInvitation",
+            AuditAction::RevokeInvitation => "RevokeInvitation",
+            AuditAction::AcceptInvitation => "AcceptInvitation",
+            AuditAction::ImportSubscriptions => "ImportSubscriptions",
+            AuditAction::CreateFulfillmentRun => "CreateFulfillmentRun",
+            AuditAction::CreatePublication => "CreatePublication",
+            AuditAction::UpdatePublication => "UpdatePublication",
+            AuditAction::DeletePublication => "DeletePublication",
+            AuditAction::CreateIssue => "CreateIssue",
+            AuditAction::UpdateIssue => "UpdateIssue",
+            AuditAction::DeleteIssue => "DeleteIssue",
+            AuditAction::SetSubscriptionPublication => "SetSubscriptionPublication",
+            AuditAction::CreateBroadcast => "CreateBroadcast",
+            AuditAction::ResumeBroadcast => "ResumeBroadcast",
+            AuditAction::RequeueEmail => "RequeueEmail",
+            AuditAction::ClearEmailSuppression => "ClearEmailSuppression",
+            AuditAction::CreateNotificationChannel => "CreateNotificationChannel",
+            AuditAction::UpdateNotificationChannel => "UpdateNotificationChannel",
+            AuditAction::DeleteNotificationChannel => "DeleteNotificationChannel",
+            AuditAction::CreateWebhookEndpoint => "CreateWebhookEndpoint",
+            AuditAction::UpdateWebhookEndpoint => "UpdateWebhookEndpoint",
+            AuditAction::DeleteWebhookEndpoint => "DeleteWebhookEndpoint",
+            AuditAction::ReplayWebhookDelivery => "ReplayWebhookDelivery",
+            AuditAction::RetryJob => "RetryJob",
+        }
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = ();
+
+    fn from_str(val: &str) -> Result<AuditAction, ()> {
+        match val {
+            "CreateAdmin" => Ok(AuditAction::CreateAdmin),
+            "PromoteUser" => Ok(AuditAction::PromoteUser),
+            "DemoteUser" => Ok(AuditAction::DemoteUser),
+            "DisableUser" => Ok(AuditAction::DisableUser),
+            "EnableUser" => Ok(AuditAction::EnableUser),
+            "ForcePasswordReset" => Ok(AuditAction::ForcePasswordReset),
+            "ListUsers" => Ok(AuditAction::ListUsers),
+            "ListSubscribers" => Ok(AuditAction::ListSubscribers),
+            "ListSubscriptions" => Ok(AuditAction::ListSubscriptions),
+            "ListAuditLog" => Ok(AuditAction::ListAuditLog),
+            "ResetPassword" => Ok(AuditAction::ResetPassword),
+            "ResetPasswordFromForgotPassword" => Ok(AuditAction::ResetPasswordFromForgotPassword),
+            "ForgotPasswordLogin" => Ok(AuditAction::ForgotPasswordLogin),
+            "CancelSubscription" => Ok(AuditAction::CancelSubscription),
+            "CreateInvitation" => Ok(AuditAction::CreateInvitation),
+            "RevokeInvitation" => Ok(AuditAction::RevokeInvitation),
+            "AcceptInvitation" => Ok(AuditAction::AcceptInvitation),
+            "ImportSubscriptions" => Ok(AuditAction::ImportSubscriptions),
+            "CreateFulfillmentRun" => Ok(AuditAction::CreateFulfillmentRun),
+            "CreatePublication" => Ok(AuditAction::CreatePublication),
+            "UpdatePublication" => Ok(AuditAction::UpdatePublication),
+            "DeletePublication" => Ok(AuditAction::DeletePublication),
+            "CreateIssue" => Ok(AuditAction::CreateIssue),
+            "UpdateIssue" => Ok(AuditAction::UpdateIssue),
+            "DeleteIssue" => Ok(AuditAction::DeleteIssue),
+            "SetSubscriptionPublication" => Ok(AuditAction::SetSubscriptionPublication),
+            "CreateBroadcast" => Ok(AuditAction::CreateBroadcast),
+            "ResumeBroadcast" => Ok(AuditAction::ResumeBroadcast),
+            "RequeueEmail" => Ok(AuditAction::RequeueEmail),
+            "ClearEmailSuppression" => Ok(AuditAction::ClearEmailSuppression),
+            "CreateNotificationChannel" => Ok(AuditAction::CreateNotificationChannel),
+            "UpdateNotificationChannel" => Ok(AuditAction::UpdateNotificationChannel),
+            "DeleteNotificationChannel" => Ok(AuditAction::DeleteNotificationChannel),
+            "CreateWebhookEndpoint" => Ok(AuditAction::CreateWebhookEndpoint),
+            "UpdateWebhookEndpoint" => Ok(AuditAction::UpdateWebhookEndpoint),
+            "DeleteWebhookEndpoint" => Ok(AuditAction::DeleteWebhookEndpoint),
+            "ReplayWebhookDelivery" => Ok(AuditAction::ReplayWebhookDelivery),
+            "RetryJob" => Ok(AuditAction::RetryJob),
+            _ => {
+                tracing::error!("Could not map string: {} to the enum AuditAction", val);
+                Err(())
+            }
+        }
+    }
+}
+
+/// Who did what to which record. The request-specific details (IP address,
+/// user agent) are added when the event is recorded.
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    pub actor_user_id: Option<Uuid>,
+    /// Who acted when it was not a user, e.g. `cli:<os user>`.
+    pub actor: Option<String>,
+    pub action: AuditAction,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub payload: serde_json::Value,
+}
+
+impl AuditEvent {
+    /// `actor` is the acting user's id, or a name such as `cli:<os user>` for
+    /// changes made outside of a user's session.
+    pub fn new(actor: &str, action: AuditAction) -> Self {
+        let actor_user_id = Uuid::from_str(actor).ok();
+        Self {
+            actor: (actor_user_id.is_none() && !actor.is_empty()).then(|| actor.to_string()),
+            actor_user_id,
+            action,
+            target_type: None,
+            target_id: None,
+            payload: serde_json::json!({}),
+        }
+    }
+
+    pub fn with_target(mut self, target_type: &str, target_id: impl ToString) -> Self {
+        self.target_type = Some(target_type.to_string());
+        self.target_id = Some(target_id.to_string());
+        self
+    }
+
+    pub fn with_payload(mut self, payload: serde_json::Value) -> Self {
+        self.payload = payload;
+        self
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AuditLogEntry {
+    pub id: Uuid,
+    pub occurred_at: DateTime<Utc>,
+    pub actor_user_id: Option<Uuid>,
+    pub actor: Option<String>,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub payload: serde_json::Value,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct AuditLogQuery {
+    pub actor_user_id: Option<Uuid>,
+    pub action: Option<String>,
+    pub target_id: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub page: Option<i64>,
+    pub page_size: Option<i64>,
+}
+
+impl AuditLogQuery {
+    /// One-based page number, never below 1.
+    pub fn page(&self) -> i64 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    pub fn page_size(&self) -> i64 {
+        self.page_size
+            .unwrap_or(DEFAULT_AUDIT_PAGE_SIZE)
+            .clamp(1, MAX_AUDIT_PAGE_SIZE)
+    }
+
+    pub fn offset(&self) -> i64 {
+        (self.page() - 1) * self.page_size()
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuditLogPage {
+    pub entries: Vec<AuditLogEntry>,
+    pub page: i64,
+    pub page_size: i64,
+    pub total: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use claims::{assert_err, assert_ok};
+
+    use uuid::Uuid;
+
+    use crate::domain::audit_models::{
+        AuditAction, AuditEvent, AuditLogQuery, MAX_AUDIT_PAGE_SIZE,
+    };
+
+    #[test]
+    fn audit_event_names_actors_that_are_not_users() {
+        let user_id = Uuid::new_v4();
+        let event = AuditEvent::new(&user_id.to_string(), AuditAction::ListUsers);
+        assert_eq!(Some(user_id), event.actor_user_id);
+        assert_eq!(None, event.actor);
+
+        let event = AuditEvent::new("cli:operator", AuditAction::CreateAdmin);
+        assert_eq!(None, event.actor_user_id);
+        assert_eq!(Some("cli:operator".to_string()), event.actor);
+
+        assert_eq!(None, AuditEvent::new("", AuditAction::ResetPassword).actor);
+    }
+
+    #[test]
+    fn audit_action_round_trips_through_strings() {
+        for action in [
+            AuditAction::CreateAdmin,
+            AuditAction::PromoteUser,
+            AuditAction::DemoteUser,
+            AuditAction::DisableUser,
+            AuditAction::EnableUser,
+            AuditAction::ForcePasswordReset,
+            AuditAction::ListUsers,
+            AuditAction::ListSubscribers,
+            AuditAction::ListSubscriptions,
+            AuditAction::ListAuditLog,
+            AuditAction::ResetPassword,
+            AuditAction::ResetPasswordFromForgotPassword,
+            AuditAction::ForgotPasswordLogin,
+            AuditAction::CancelSubscription,
+            AuditAction::CreateInvitation,
+            AuditAction::RevokeInvitation,
+            AuditAction::AcceptInvitation,
+            AuditAction::ImportSubscriptions,
+            AuditAction::CreateFulfillmentRun,
+            AuditAction::CreatePublication,
+            AuditAction::UpdatePublication,
+            AuditAction::DeletePublication,
+            AuditAction::CreateIssue,
+            AuditAction::UpdateIssue,
+            AuditAction::DeleteIssue,
+            AuditAction::SetSubscriptionPublication,
+            AuditAction::CreateBroadcast,
+            AuditAction::ResumeBroadcast,
+            AuditAction::RequeueEmail,
+            AuditAction::ClearEmailSuppression,
+            AuditAction::CreateNotificationChannel,
+            AuditAction::UpdateNotificationChannel,
+            AuditAction::DeleteNotificationChannel,
+            AuditAction::CreateWebhookEndpoint,
+            AuditAction::UpdateWebhookEndpoint,
+            AuditAction::DeleteWebhookEndpoint,
+            AuditAction::ReplayWebhookDelivery,
+            AuditAction::RetryJob,
+        ] {
+            assert_eq!(action, AuditAction::from_str(action.as_str()).unwrap());
+        }
+        assert_ok!(AuditAction::from_str("PromoteUser"));
+        assert_err!(AuditAction::from_str("promote_user"));
+    }
+
+    #[test]
+    fn audit_log_query_paging_is_clamped() {
+        let query = AuditLogQuery {
+            page: Some(0),
+            page_size: Some(10_000),
+            ..Default::default()
+        };
+        assert_eq!(1, query.page());
+        assert_eq!(MAX_AUDIT_PAGE_SIZE, query.page_size());
+        assert_eq!(0, query.offset());
+
+        let query = AuditLogQuery {
+            page: Some(3),
+            page_size: Some(20),
+            ..Default::default()
+        };
+        assert_eq!(40, query.offset());
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::domain::publication_models::{Issue, Publication};
+use crate::domain::subscription_models::OverTheWireSubscription;
+use crate::email_client::templates::{render_email, EmailTemplate, RenderedEmail, DEFAULT_LOCALE};
+use crate::util::standardize_email;
+
+/// How long a worker may hold a claimed batch of recipients before another may
+/// take them. Long enough to send a whole batch.
+pub const BROADCAST_CLAIM_TIMEOUT_SECONDS: i64 = 15 * 60;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BroadcastStatus {
+    Sending,
+    Completed,
+}
+
+impl BroadcastStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BroadcastStatus::Sending => "Sending",
+            BroadcastStatus::Completed => "Completed",
+        }
+    }
+}
+
+impl FromStr for BroadcastStatus {
+    type Err = ();
+
+    fn from_str(val: &str) -> Result<BroadcastStatus, ()> {
+        match val {
+            "Sending" => Ok(BroadcastStatus::Sending),
+            "Completed" => Ok(BroadcastStatus::Completed),
+            _ => {
+                tracing::error!("Could not map string: {} to the enum BroadcastStatus", val);
+                Err(())
+            }
+        }
+    }
+}
+
+/// `Sending` means a worker has claimed the recipient and not yet recorded the outcome.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecipientStatus {
+    Pending,
+    Sending,
+    Sent,
+    Failed,
+}
+
+impl RecipientStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecipientStatus::Pending => "Pending",
+            RecipientStatus::Sending => "Sending",
+            RecipientStatus::Sent => "Sent",
+            RecipientStatus::Failed => "Failed",
+        }
+    }
+}
+
+impl FromStr for RecipientStatus {
+    type Err = ();
+
+    fn from_str(val: &str) -> Result<RecipientStatus, ()> {
+        match val {
+            "Pending" => Ok(RecipientStatus::Pending),
+            "Sending" => Ok(RecipientStatus::Sending),
+            "Sent" => Ok(RecipientStatus::Sent),
+            "Failed" => Ok(RecipientStatus::Failed),
+            _ => {
+                tracing::error!("Could not map string: {} to the enum RecipientStatus", val);
+                Err(())
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct IssueBroadcast {
+    pub id: Uuid,
+    pub issue_id: Uuid,
+    /// Sent only to the admin who asked for it, never to subscribers.
+    pub test_send: bool,
+    pub status: BroadcastStatus,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<Uuid>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub recipient_count: i32,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct BroadcastRecipient {
+    pub id: Uuid,
+    pub broadcast_id: Uuid,
+    pub subscription_id: Option<Uuid>,
+    pub email_address: String,
+    pub status: RecipientStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+/// A broadcast with how far it has got.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct BroadcastProgress {
+    #[serde(flatten)]
+    pub broadcast: IssueBroadcast,
+    pub pending_count: i64,
+    pub sent_count: i64,
+    pub failed_count: i64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct CreateBroadcast {
+    #[serde(default)]
+    pub test_send: bool,
+}
+
+impl CreateBroadcast {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Was not able to serialize.")
+    }
+}
+
+/// One recipient per email address among the publication's subscriptions.
+/// Subscriptions not linked to any publication predate publications and get
+/// every issue.
+pub fn build_recipients(
+    broadcast_id: Uuid,
+    subscriptions: Vec<OverTheWireSubscription>,
+    publication_id: Uuid,
+) -> Vec<BroadcastRecipient> {
+    let mut seen = HashSet::new();
+    subscriptions
+        .into_iter()
+        .filter(|subscription| {
+            subscription
+                .publication_id
+                .is_none_or(|id| id == publication_id)
+        })
+        .filter(|subscription| {
+            seen.insert(standardize_email(
+                subscription.subscription_email_address.trim(),
+            ))
+        })
+        .map(|subscription| BroadcastRecipient {
+            id: Uuid::new_v4(),
+            broadcast_id,
+            subscription_id: Some(subscription.id),
+            email_address: subscription.subscription_email_address.trim().to_string(),
+            status: RecipientStatus::Pending,
+            attempts: 0,
+            last_error: None,
+            sent_at: None,
+        })
+        .collect()
+}
+
+/// The email announcing an issue. Subscribers get an `unsubscribe_link`;
+/// test sends to admins do not.
+pub fn issue_email(
+    publication: &Publication,
+    issue: &Issue,
+    test_send: bool,
+    unsubscribe_link: Option<&str>,
+) -> RenderedEmail {
+    let issue_title = issue
+        .title
+        .clone()
+        .unwrap_or_else(|| format!("Issue {}", issue.issue_number));
+
+    render_email(
+        EmailTemplate::IssueAvailable,
+        DEFAULT_LOCALE,
+        json!({
+            "publication_title": publication.title,
+            "issue_title": issue_title,
+            "publish_date": issue.publish_date,
+            "link": issue.digital_asset_reference.clone().unwrap_or_default(),
+            "test_send": test_send,
+            "unsubscribe_link": unsubscribe_link,
+        }),
+    )
+    .expect("Email templates render")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, Utc};
+    use uuid::Uuid;
+
+    use crate::domain::broadcast_models::{build_recipients, issue_email};
+    use crate::domain::publication_models::{Issue, Publication};
+    use crate::domain::subscription_models::{
+        BillingSource, OverTheWireSubscription, SubscriptionType,
+    };
+
+    fn subscription(email: &str, publication_id: Option<Uuid>) -> OverTheWireSubscription {
+        OverTheWireSubscription {
+            id: Uuid::new_v4(),
+            subscriber_id: Uuid::new_v4(),
+            subscription_name: "Reader".to_string(),
+            subscription_mailing_address_line_1: "".to_string(),
+            subscription_mailing_address_line_2: "".to_string(),
+            subscription_city: "".to_string(),
+            subscription_state: "".to_string(),
+            subscription_postal_code: "".to_string(),
+            subscription_email_address: email.to_string(),
+            subscription_creation_date: Utc::now(),
+            subscription_cancelled_on_date: None,
+            subscription_anniversary_day: 1,
+            subscription_anniversary_month: 1,
+            subscription_renewal_date: "".to_string(),
+            active: true,
+            subscription_type: SubscriptionType::Digital,
+            stripe_subscription_id: "".to_string(),
+            billing_source: BillingSource::Stripe,
+            publication_id,
+        }
+    }
+
+    #[test]
+    fn recipients_are_deduplicated_and_limited_to_the_publication() {
+        let publication_id = Uuid::new_v4();
+        let subscriptions = vec![
+            subscription("reader@example.com", Some(publication_id)),
+            subscription("Reader@Example.com ", None),
+            subscription("other@example.com", Some(Uuid::new_v4())),
+            subscription("legacy@example.com", None),
+        ];
+
+        let recipients = build_recipients(Uuid::new_v4(), subscriptions, publication_id);
+
+        let addresses: Vec<&str> = recipients
+            .iter()
+            .map(|r| r.email_address.as_str())
+            .collect();
+        assert_eq!(vec!["reader@example.com", "legacy@example.com"], addresses);
+    }
+
+    #[test]
+    fn the_issue_email_links_to_the_digital_edition() {
+        let publication = Publication {
+            id: Uuid::new_v4(),
+            title: "The <Weekly>".to_string(),
+            description: None,
+            created_at: Utc::now(),
+        };
+        let issue = Issue {
+            id: Uuid::new_v4(),
+            publication_id: publication.id,
+            issue_number: 12,
+            title: None,
+            publish_date: NaiveDate::from_ymd_opt(2026, 11, 1).unwrap(),
+            cutoff_date: NaiveDate::from_ymd_opt(2026, 11, 1).unwrap(),
+            digital_asset_reference: Some("https://example.com/12.pdf".to_string()),
+            created_at: Utc::now(),
+        };
+
+        let email = issue_email(
+            &publication,
+            &issue,
+            false,
+            Some("https://example.com/unsubscribe?token=abc"),
+        );
+        assert_eq!("The <Weekly>: Issue 12", email.subject);
+        assert!(email.html_content.contains("The &lt;Weekly&gt;"));
+        assert!(email.text_content.contains("https://example.com/12.pdf"));
+        assert!(email
+            .text_content
+            .contains("Unsubscribe from issue emails: https://example.com/unsubscribe?token=abc"));
+        let test_send = issue_email(&publication, &issue, true, None);
+        assert!(test_send.subject.starts_with("[TEST] "));
+        assert!(!test_send.text_content.contains("Unsubscribe"));
+    }
+}
@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+use linkify::{LinkFinder, LinkKind};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// An email as the server would have sent it, kept in the local environment.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CapturedEmail {
+    pub id: Uuid,
+    pub recipients: Vec<String>,
+    pub subject: String,
+    pub html_content: String,
+    pub text_content: String,
+    pub unsubscribe_url: Option<String>,
+    /// Every URL in the text body, then the unsubscribe URL, without repeats.
+    pub links: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CapturedEmailQuery {
+    /// Only emails to this address, compared without case.
+    pub recipient: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// The URLs in the text body and the unsubscribe URL, in order of appearance.
+/// The text body is used because its links are not HTML-escaped.
+pub fn find_links(text_content: &str, unsubscribe_url: Option<&str>) -> Vec<String> {
+    let mut links: Vec<String> = vec![];
+    let mut finder = LinkFinder::new();
+    finder.kinds(&[LinkKind::Url]);
+    for link in finder
+        .links(text_content)
+        .map(|link| link.as_str())
+        .chain(unsubscribe_url)
+    {
+        if !links.iter().any(|known| known == link) {
+            links.push(link.to_string());
+        }
+    }
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::captured_email_models::find_links;
+
+    #[test]
+    fn links_are_found_once_in_order() {
+        let text = "Reset your password at http://localhost:3000/reset?otp=abc123.\n\
+            Or sign in at https://example.com/login and then http://localhost:3000/reset?otp=abc123 again.\n\
+            Write to help@example.com.";
+
+        assert_eq!(
+            vec![
+                "http://localhost:3000/reset?otp=abc123".to_string(),
+                "https://example.com/login".to_string(),
+                "http://localhost:8000/unsubscribe/token".to_string(),
+            ],
+            find_links(text, Some("http://localhost:8000/unsubscribe/token"))
+        );
+        assert!(find_links("No links here.", None).is_empty());
+    }
+}
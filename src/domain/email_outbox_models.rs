@@ -0,0 +1,174 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::valid_email::ValidEmail;
+use crate::email_client::templates::RenderedEmail;
+
+/// After this many failed attempts an email is left `Dead` until an admin requeues it.
+pub const MAX_EMAIL_ATTEMPTS: i32 = 6;
+/// How long a worker may hold a claimed email before another may take it.
+pub const EMAIL_CLAIM_TIMEOUT_SECONDS: i64 = 300;
+const FIRST_RETRY_DELAY_SECONDS: i64 = 30;
+const MAX_RETRY_DELAY_SECONDS: i64 = 60 * 60;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutboxStatus {
+    Pending,
+    Sending,
+    Sent,
+    Dead,
+}
+
+impl OutboxStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutboxStatus::Pending => "Pending",
+            OutboxStatus::Sending => "Sending",
+            OutboxStatus::Sent => "Sent",
+            OutboxStatus::Dead => "Dead",
+        }
+    }
+}
+
+impl FromStr for OutboxStatus {
+    type Err = ();
+
+    fn from_str(val: &str) -> Result<OutboxStatus, ()> {
+        match val {
+            "Pending" => Ok(OutboxStatus::Pending),
+            "Sending" => Ok(OutboxStatus::Sending),
+            "Sent" => Ok(OutboxStatus::Sent),
+            "Dead" => Ok(OutboxStatus::Dead),
+            _ => {
+                tracing::error!("Could not map string: {} to the enum OutboxStatus", val);
+                Err(())
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct OutboxEmail {
+    pub id: Uuid,
+    pub recipients: Vec<String>,
+    pub subject: String,
+    /// Bodies can hold one-time links, so they are never sent over the API.
+    #[serde(skip)]
+    pub html_content: String,
+    #[serde(skip)]
+    pub text_content: String,
+    pub status: OutboxStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+impl OutboxEmail {
+    pub fn new(
+        recipients: Vec<ValidEmail>,
+        subject: &str,
+        html_content: &str,
+        text_content: &str,
+    ) -> Self {
+        let now = Utc::now();
+        OutboxEmail {
+            id: Uuid::new_v4(),
+            recipients: recipients.iter().map(|r| r.to_string()).collect(),
+            subject: subject.to_string(),
+            html_content: html_content.to_string(),
+            text_content: text_content.to_string(),
+            status: OutboxStatus::Pending,
+            attempts: 0,
+            last_error: None,
+            next_attempt_at: now,
+            created_at: now,
+            sent_at: None,
+        }
+    }
+
+    pub fn from_template(recipients: Vec<ValidEmail>, email: RenderedEmail) -> Self {
+        OutboxEmail::new(
+            recipients,
+            &email.subject,
+            &email.html_content,
+            &email.text_content,
+        )
+    }
+
+    /// Where a failed attempt leaves the email: retried later, or dead once
+    /// it has used up its attempts.
+    pub fn after_failure(&self, now: DateTime<Utc>) -> (OutboxStatus, DateTime<Utc>) {
+        if self.attempts >= MAX_EMAIL_ATTEMPTS {
+            (OutboxStatus::Dead, now)
+        } else {
+            (OutboxStatus::Pending, now + retry_delay(self.attempts))
+        }
+    }
+}
+
+/// Doubles from thirty seconds after the first attempt, up to an hour.
+pub fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.clamp(1, 16) as u32 - 1;
+    Duration::seconds((FIRST_RETRY_DELAY_SECONDS << exponent).min(MAX_RETRY_DELAY_SECONDS))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OutboxQuery {
+    pub status: Option<OutboxStatus>,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use crate::domain::email_outbox_models::{
+        retry_delay, OutboxEmail, OutboxStatus, MAX_EMAIL_ATTEMPTS,
+    };
+    use crate::domain::valid_email::ValidEmail;
+
+    #[test]
+    fn retries_back_off_exponentially_up_to_an_hour() {
+        assert_eq!(Duration::seconds(30), retry_delay(1));
+        assert_eq!(Duration::seconds(60), retry_delay(2));
+        assert_eq!(Duration::seconds(120), retry_delay(3));
+        assert_eq!(Duration::hours(1), retry_delay(10));
+        assert_eq!(Duration::hours(1), retry_delay(i32::MAX));
+    }
+
+    #[test]
+    fn emails_die_after_the_last_attempt() {
+        let now = Utc::now();
+        let mut email = OutboxEmail::new(
+            vec![ValidEmail::parse("someone@example.com".to_string()).unwrap()],
+            "Subject",
+            "<p>Body</p>",
+            "Body",
+        );
+
+        email.attempts = 1;
+        assert_eq!(
+            (OutboxStatus::Pending, now + Duration::seconds(30)),
+            email.after_failure(now)
+        );
+        email.attempts = MAX_EMAIL_ATTEMPTS;
+        assert_eq!((OutboxStatus::Dead, now), email.after_failure(now));
+    }
+
+    #[test]
+    fn bodies_are_not_serialized() {
+        let email = OutboxEmail::new(
+            vec![ValidEmail::parse("someone@example.com".to_string()).unwrap()],
+            "Subject",
+            "<a href=\"https://example.com/otp=secret\">link</a>",
+            "https://example.com/otp=secret",
+        );
+        let json = serde_json::to_string(&email).unwrap();
+        assert!(json.contains("someone@example.com"));
+        assert!(!json.contains("secret"));
+    }
+}
@@ -0,0 +1,184 @@
+use std::str::FromStr;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::configuration::get_configuration;
+
+/// The kinds of email a subscriber can turn off. Transactional email (password
+/// resets, receipts, account notices) always goes out.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailCategory {
+    Issues,
+    RenewalReminders,
+    Promotions,
+}
+
+impl EmailCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmailCategory::Issues => "issues",
+            EmailCategory::RenewalReminders => "renewal_reminders",
+            EmailCategory::Promotions => "promotions",
+        }
+    }
+}
+
+impl FromStr for EmailCategory {
+    type Err = ();
+
+    fn from_str(val: &str) -> Result<EmailCategory, ()> {
+        match val {
+            "issues" => Ok(EmailCategory::Issues),
+            "renewal_reminders" => Ok(EmailCategory::RenewalReminders),
+            "promotions" => Ok(EmailCategory::Promotions),
+            _ => {
+                tracing::error!("Could not map string: {} to the enum EmailCategory", val);
+                Err(())
+            }
+        }
+    }
+}
+
+/// A subscriber's choices. Promotions are opt-in.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EmailPreferences {
+    pub issues: bool,
+    pub renewal_reminders: bool,
+    pub promotions: bool,
+}
+
+impl Default for EmailPreferences {
+    fn default() -> Self {
+        EmailPreferences {
+            issues: true,
+            renewal_reminders: true,
+            promotions: false,
+        }
+    }
+}
+
+impl EmailPreferences {
+    pub fn allows(&self, category: EmailCategory) -> bool {
+        match category {
+            EmailCategory::Issues => self.issues,
+            EmailCategory::RenewalReminders => self.renewal_reminders,
+            EmailCategory::Promotions => self.promotions,
+        }
+    }
+
+    pub fn without(mut self, category: EmailCategory) -> Self {
+        match category {
+            EmailCategory::Issues => self.issues = false,
+            EmailCategory::RenewalReminders => self.renewal_reminders = false,
+            EmailCategory::Promotions => self.promotions = false,
+        }
+        self
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Was not able to serialize.")
+    }
+}
+
+/// `<subscriber id>.<category>.<signature>`. Signed with the unsubscribe
+/// signing key so the link works without logging in, and it does not expire: an old email
+/// must still be able to unsubscribe.
+pub fn unsubscribe_token(subscriber_id: Uuid, category: EmailCategory) -> String {
+    let signature = unsubscribe_mac(subscriber_id, category)
+        .finalize()
+        .into_bytes();
+    format!(
+        "{}.{}.{}",
+        subscriber_id,
+        category.as_str(),
+        URL_SAFE_NO_PAD.encode(signature)
+    )
+}
+
+/// The subscriber and category a token was issued for, if its signature holds.
+pub fn verify_unsubscribe_token(token: &str) -> Option<(Uuid, EmailCategory)> {
+    let mut parts = token.splitn(3, '.');
+    let subscriber_id = Uuid::parse_str(parts.next()?).ok()?;
+    let category = EmailCategory::from_str(parts.next()?).ok()?;
+    let signature = URL_SAFE_NO_PAD.decode(parts.next()?).ok()?;
+    unsubscribe_mac(subscriber_id, category)
+        .verify_slice(&signature)
+        .ok()?;
+    Some((subscriber_id, category))
+}
+
+/// The link for the email body, to a web app page that confirms the choice.
+pub fn unsubscribe_link(subscriber_id: Uuid, category: EmailCategory) -> String {
+    format!(
+        "{}/unsubscribe?token={}",
+        get_configuration().unwrap().application.web_app_host,
+        unsubscribe_token(subscriber_id, category)
+    )
+}
+
+/// The RFC 8058 one-click URL for the `List-Unsubscribe` header, which mail
+/// clients POST to directly.
+pub fn one_click_unsubscribe_url(subscriber_id: Uuid, category: EmailCategory) -> String {
+    format!(
+        "{}/unsubscribe/{}",
+        get_configuration().unwrap().application.external_hostname,
+        unsubscribe_token(subscriber_id, category)
+    )
+}
+
+fn unsubscribe_mac(subscriber_id: Uuid, category: EmailCategory) -> Hmac<Sha256> {
+    let signing_key = get_configuration()
+        .unwrap()
+        .auth_config
+        .unsubscribe_signing_key;
+    let mut mac = Hmac::<Sha256>::new_from_slice(signing_key.expose_secret().as_bytes())
+        .expect("HMAC takes keys of any length");
+    mac.update(format!("unsubscribe:{}:{}", subscriber_id, category.as_str()).as_bytes());
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use crate::domain::email_preference_models::{
+        unsubscribe_token, verify_unsubscribe_token, EmailCategory, EmailPreferences,
+    };
+
+    #[test]
+    fn a_token_verifies_for_what_it_was_issued() {
+        let subscriber_id = Uuid::new_v4();
+        let token = unsubscribe_token(subscriber_id, EmailCategory::Promotions);
+        assert_eq!(
+            Some((subscriber_id, EmailCategory::Promotions)),
+            verify_unsubscribe_token(&token)
+        );
+    }
+
+    #[test]
+    fn a_tampered_token_is_rejected() {
+        let subscriber_id = Uuid::new_v4();
+        let token = unsubscribe_token(subscriber_id, EmailCategory::Promotions);
+        let other_category = token.replace(".promotions.", ".issues.");
+        let other_subscriber =
+            token.replace(&subscriber_id.to_string(), &Uuid::new_v4().to_string());
+        assert_eq!(None, verify_unsubscribe_token(&other_category));
+        assert_eq!(None, verify_unsubscribe_token(&other_subscriber));
+        assert_eq!(None, verify_unsubscribe_token("not-a-token"));
+    }
+
+    #[test]
+    fn unsubscribing_turns_off_only_that_category() {
+        let preferences = EmailPreferences::default().without(EmailCategory::Issues);
+        assert!(!preferences.allows(EmailCategory::Issues));
+        assert!(preferences.allows(EmailCategory::RenewalReminders));
+        assert!(!preferences.allows(EmailCategory::Promotions));
+    }
+}
@@ -0,0 +1,174 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::util::standardize_email;
+
+/// Why nothing more is sent to an address.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SuppressionReason {
+    /// The mailbox does not exist or refuses all mail. Soft bounces, such as
+    /// a full mailbox, are left for the provider to retry.
+    HardBounce,
+    /// The recipient marked an email as spam.
+    Complaint,
+    /// The recipient unsubscribed through the provider rather than through us.
+    Unsubscribe,
+}
+
+impl SuppressionReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SuppressionReason::HardBounce => "HardBounce",
+            SuppressionReason::Complaint => "Complaint",
+            SuppressionReason::Unsubscribe => "Unsubscribe",
+        }
+    }
+}
+
+impl FromStr for SuppressionReason {
+    type Err = ();
+
+    fn from_str(val: &str) -> Result<SuppressionReason, ()> {
+        match val {
+            "HardBounce" => Ok(SuppressionReason::HardBounce),
+            "Complaint" => Ok(SuppressionReason::Complaint),
+            "Unsubscribe" => Ok(SuppressionReason::Unsubscribe),
+            _ => {
+                tracing::error!(
+                    "Could not map string: {} to the enum SuppressionReason",
+                    val
+                );
+                Err(())
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct EmailSuppression {
+    pub email_address: String,
+    pub reason: SuppressionReason,
+    /// What the provider said, such as the receiving server's response.
+    pub detail: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SuppressionQuery {
+    pub reason: Option<SuppressionReason>,
+}
+
+/// One event from the provider's webhook that should stop email to an address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeliveryEvent {
+    pub email_address: String,
+    pub reason: SuppressionReason,
+    pub detail: Option<String>,
+}
+
+/// The events in a delivery webhook that suppress an address. Reads both
+/// SendGrid's event array and Mailtrap's `{ "events": [...] }`; anything else,
+/// such as deliveries, opens and soft bounces, is skipped.
+pub fn parse_delivery_events(body: &Value) -> Vec<DeliveryEvent> {
+    let events = match body {
+        Value::Array(events) => events,
+        Value::Object(body) => match body.get("events") {
+            Some(Value::Array(events)) => events,
+            _ => return vec![],
+        },
+        _ => return vec![],
+    };
+
+    events.iter().filter_map(parse_delivery_event).collect()
+}
+
+fn parse_delivery_event(event: &Value) -> Option<DeliveryEvent> {
+    let email_address = event.get("email")?.as_str()?;
+    let reason = match event.get("event")?.as_str()? {
+        // SendGrid reports blocks, its name for temporary failures, as
+        // bounces of type `blocked`.
+        "bounce" if event.get("type").and_then(Value::as_str) != Some("blocked") => {
+            SuppressionReason::HardBounce
+        }
+        "spamreport" | "spam" => SuppressionReason::Complaint,
+        "unsubscribe" | "group_unsubscribe" => SuppressionReason::Unsubscribe,
+        _ => return None,
+    };
+    let detail = ["reason", "response"]
+        .iter()
+        .find_map(|key| event.get(*key).and_then(Value::as_str))
+        .map(str::to_string);
+
+    Some(DeliveryEvent {
+        email_address: standardize_email(email_address.trim()),
+        reason,
+        detail,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::domain::email_suppression_models::{
+        parse_delivery_events, DeliveryEvent, SuppressionReason,
+    };
+
+    #[test]
+    fn sendgrid_events_are_read() {
+        let body = json!([
+            { "email": "Gone@Example.com", "event": "bounce", "type": "bounce", "reason": "550 5.1.1 User unknown" },
+            { "email": "busy@example.com", "event": "bounce", "type": "blocked", "reason": "421 Try again later" },
+            { "email": "angry@example.com", "event": "spamreport" },
+            { "email": "done@example.com", "event": "group_unsubscribe" },
+            { "email": "happy@example.com", "event": "delivered" },
+        ]);
+
+        assert_eq!(
+            vec![
+                DeliveryEvent {
+                    email_address: "gone@example.com".to_string(),
+                    reason: SuppressionReason::HardBounce,
+                    detail: Some("550 5.1.1 User unknown".to_string()),
+                },
+                DeliveryEvent {
+                    email_address: "angry@example.com".to_string(),
+                    reason: SuppressionReason::Complaint,
+                    detail: None,
+                },
+                DeliveryEvent {
+                    email_address: "done@example.com".to_string(),
+                    reason: SuppressionReason::Unsubscribe,
+                    detail: None,
+                },
+            ],
+            parse_delivery_events(&body)
+        );
+    }
+
+    #[test]
+    fn mailtrap_events_are_read() {
+        let body = json!({ "events": [
+            { "email": "gone@example.com", "event": "bounce", "response": "550 No such user" },
+            { "email": "full@example.com", "event": "soft bounce" },
+            { "email": "angry@example.com", "event": "spam" },
+        ]});
+
+        let events = parse_delivery_events(&body);
+        assert_eq!(2, events.len());
+        assert_eq!(SuppressionReason::HardBounce, events[0].reason);
+        assert_eq!(Some("550 No such user".to_string()), events[0].detail);
+        assert_eq!(SuppressionReason::Complaint, events[1].reason);
+    }
+
+    #[test]
+    fn unknown_bodies_have_no_events() {
+        assert!(parse_delivery_events(&json!({ "hello": "world" })).is_empty());
+        assert!(parse_delivery_events(&json!("bounce")).is_empty());
+        assert!(parse_delivery_events(&json!([{ "event": "bounce" }])).is_empty());
+    }
+}
@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::subscription_models::OverTheWireSubscription;
+
+/// Written out in full by the print house; everything else is left as entered.
+const ADDRESS_ABBREVIATIONS: [(&str, &str); 24] = [
+    ("APARTMENT", "APT"),
+    ("AVENUE", "AVE"),
+    ("BOULEVARD", "BLVD"),
+    ("BUILDING", "BLDG"),
+    ("CIRCLE", "CIR"),
+    ("COURT", "CT"),
+    ("DRIVE", "DR"),
+    ("EAST", "E"),
+    ("FLOOR", "FL"),
+    ("HIGHWAY", "HWY"),
+    ("LANE", "LN"),
+    ("NORTH", "N"),
+    ("NORTHEAST", "NE"),
+    ("NORTHWEST", "NW"),
+    ("PARKWAY", "PKWY"),
+    ("PLACE", "PL"),
+    ("ROAD", "RD"),
+    ("SOUTH", "S"),
+    ("SOUTHEAST", "SE"),
+    ("SOUTHWEST", "SW"),
+    ("STREET", "ST"),
+    ("SUITE", "STE"),
+    ("TERRACE", "TER"),
+    ("WEST", "W"),
+];
+
+/// Column names and widths, in characters, of the print vendor's fixed-width file.
+pub const FIXED_WIDTH_COLUMNS: [(&str, usize); 7] = [
+    ("sequence", 6),
+    ("name", 40),
+    ("address_line_1", 40),
+    ("address_line_2", 40),
+    ("city", 28),
+    ("state", 2),
+    ("postal_code", 10),
+];
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CreateFulfillmentRun {
+    pub issue_date: NaiveDate,
+    /// Subscriptions cancelled before this instant are left out. Defaults to the
+    /// start of `issue_date` (UTC).
+    pub cutoff: Option<DateTime<Utc>>,
+}
+
+impl CreateFulfillmentRun {
+    pub fn cutoff(&self) -> DateTime<Utc> {
+        self.cutoff
+            .unwrap_or_else(|| self.issue_date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct FulfillmentRun {
+    pub id: Uuid,
+    pub issue_date: NaiveDate,
+    pub cutoff: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<Uuid>,
+    pub recipient_count: i32,
+    /// Subscriptions folded into another entry because they share its address.
+    pub duplicate_count: i32,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FulfillmentEntry {
+    pub sequence: i32,
+    pub subscription_id: Uuid,
+    pub subscriber_id: Uuid,
+    pub name: String,
+    pub address_line_1: String,
+    pub address_line_2: Option<String>,
+    pub city: String,
+    pub state: String,
+    pub postal_code: String,
+    pub merged_subscription_ids: Vec<Uuid>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+    FixedWidth,
+}
+
+/// One entry per normalised address, sorted by postal code. The oldest
+/// subscription at an address is kept; the others are listed on it as merged.
+pub fn build_mailing_list(
+    mut subscriptions: Vec<OverTheWireSubscription>,
+) -> Vec<FulfillmentEntry> {
+    subscriptions.sort_by_key(|s| (s.subscription_creation_date, s.id));
+
+    let mut entries: Vec<FulfillmentEntry> = Vec::new();
+    let mut by_address: HashMap<(String, String, String, String, String), usize> = HashMap::new();
+    for subscription in subscriptions {
+        let address_line_2 = Some(normalize_address_line(
+            &subscription.subscription_mailing_address_line_2,
+        ))
+        .filter(|line| !line.is_empty());
+        let entry = FulfillmentEntry {
+            sequence: 0,
+            subscription_id: subscription.id,
+            subscriber_id: subscription.subscriber_id,
+            name: collapse_whitespace(&subscription.subscription_name).to_uppercase(),
+            address_line_1: normalize_address_line(
+                &subscription.subscription_mailing_address_line_1,
+            ),
+            address_line_2,
+            city: normalize_address_line(&subscription.subscription_city),
+            state: subscription.subscription_state.trim().to_uppercase(),
+            postal_code: normalize_postal_code(&subscription.subscription_postal_code),
+            merged_subscription_ids: vec![],
+        };
+        let key = (
+            entry.address_line_1.clone(),
+            entry.address_line_2.clone().unwrap_or_default(),
+            entry.city.clone(),
+            entry.state.clone(),
+            entry.postal_code.chars().take(5).collect(),
+        );
+
+        match by_address.get(&key) {
+            Some(&index) => entries[index]
+                .merged_subscription_ids
+                .push(entry.subscription_id),
+            None => {
+                by_address.insert(key, entries.len());
+                entries.push(entry);
+            }
+        }
+    }
+
+    entries.sort_by(|a, b| {
+        (&a.postal_code, &a.address_line_1, &a.name).cmp(&(
+            &b.postal_code,
+            &b.address_line_1,
+            &b.name,
+        ))
+    });
+    for (index, entry) in entries.iter_mut().enumerate() {
+        entry.sequence = index as i32 + 1;
+    }
+    entries
+}
+
+/// Upper case, no periods or commas, single spaces and USPS abbreviations.
+pub fn normalize_address_line(line: &str) -> String {
+    line.to_uppercase()
+        .replace(['.', ','], " ")
+        .split_whitespace()
+        .map(|word| {
+            ADDRESS_ABBREVIATIONS
+                .iter()
+                .find(|(full, _)| *full == word)
+                .map_or(word, |(_, abbreviation)| abbreviation)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `123456789` becomes `12345-6789`; anything else is only trimmed.
+pub fn normalize_postal_code(postal_code: &str) -> String {
+    let postal_code: String = postal_code.split_whitespace().collect();
+    if postal_code.len() == 9 && postal_code.chars().all(|c| c.is_ascii_digit()) {
+        format!("{}-{}", &postal_code[..5], &postal_code[5..])
+    } else {
+        postal_code
+    }
+}
+
+fn collapse_whitespace(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+pub fn render_csv(entries: &[FulfillmentEntry]) -> String {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer
+        .write_record([
+            "sequence",
+            "name",
+            "address_line_1",
+            "address_line_2",
+            "city",
+            "state",
+            "postal_code",
+            "subscription_id",
+            "subscriber_id",
+        ])
+        .expect("Was not able to write CSV.");
+    for entry in entries {
+        writer
+            .write_record([
+                entry.sequence.to_string(),
+                entry.name.clone(),
+                entry.address_line_1.clone(),
+                entry.address_line_2.clone().unwrap_or_default(),
+                entry.city.clone(),
+                entry.state.clone(),
+                entry.postal_code.clone(),
+                entry.subscription_id.to_string(),
+                entry.subscriber_id.to_string(),
+            ])
+            .expect("Was not able to write CSV.");
+    }
+    String::from_utf8(writer.into_inner().expect("Was not able to write CSV."))
+        .expect("CSV is always UTF-8.")
+}
+
+/// One line per entry, every field padded or cut to its width in
+/// `FIXED_WIDTH_COLUMNS`; the sequence number is zero-padded.
+pub fn render_fixed_width(entries: &[FulfillmentEntry]) -> String {
+    let mut output = String::new();
+    for entry in entries {
+        let fields = [
+            format!("{:0>6}", entry.sequence),
+            entry.name.clone(),
+            entry.address_line_1.clone(),
+            entry.address_line_2.clone().unwrap_or_default(),
+            entry.city.clone(),
+            entry.state.clone(),
+            entry.postal_code.clone(),
+        ];
+        for (field, (_, width)) in fields.iter().zip(FIXED_WIDTH_COLUMNS) {
+            let field: String = field.chars().take(width).collect();
+            output.push_str(&format!("{:<width$}", field, width = width));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use uuid::Uuid;
+
+    use crate::domain::fulfillment_models::{
+        build_mailing_list, normalize_address_line, normalize_postal_code, render_csv,
+        render_fixed_width, FIXED_WIDTH_COLUMNS,
+    };
+    use crate::domain::subscription_models::{
+        BillingSource, OverTheWireSubscription, SubscriptionType,
+    };
+
+    fn subscription(name: &str, line_1: &str, postal_code: &str) -> OverTheWireSubscription {
+        OverTheWireSubscription {
+            id: Uuid::new_v4(),
+            subscriber_id: Uuid::new_v4(),
+            subscription_name: name.to_string(),
+            subscription_mailing_address_line_1: line_1.to_string(),
+            subscription_mailing_address_line_2: "".to_string(),
+            subscription_city: "Springfield".to_string(),
+            subscription_state: "il".to_string(),
+            subscription_postal_code: postal_code.to_string(),
+            subscription_email_address: "reader@example.com".to_string(),
+            subscription_creation_date: Utc::now(),
+            subscription_cancelled_on_date: None,
+            subscription_anniversary_day: 1,
+            subscription_anniversary_month: 1,
+            subscription_renewal_date: "".to_string(),
+            active: true,
+            subscription_type: SubscriptionType::Paper,
+            stripe_subscription_id: "".to_string(),
+            billing_source: BillingSource::Stripe,
+            publication_id: None,
+        }
+    }
+
+    #[test]
+    fn addresses_are_normalised() {
+        assert_eq!(
+            "12 N MAIN ST APT 4",
+            normalize_address_line(" 12 North  Main Street, Apartment 4. ")
+        );
+        assert_eq!("62701-1234", normalize_postal_code("627011234"));
+        assert_eq!("62701", normalize_postal_code(" 62701 "));
+    }
+
+    #[test]
+    fn the_same_address_is_listed_once() {
+        let mut older = subscription("First Reader", "1 Main Street", "62701");
+        older.subscription_creation_date = Utc::now() - Duration::days(30);
+        let newer = subscription("Second Reader", "1 main st.", "62701-0001");
+        let elsewhere = subscription("Other Reader", "9 Elm St", "10001");
+
+        let entries = build_mailing_list(vec![newer.clone(), elsewhere.clone(), older.clone()]);
+
+        assert_eq!(2, entries.len());
+        assert_eq!(elsewhere.id, entries[0].subscription_id);
+        assert_eq!(1, entries[0].sequence);
+        assert_eq!(older.id, entries[1].subscription_id);
+        assert_eq!(vec![newer.id], entries[1].merged_subscription_ids);
+        assert_eq!("IL", entries[1].state);
+    }
+
+    #[test]
+    fn fixed_width_lines_have_a_constant_length() {
+        let entries = build_mailing_list(vec![
+            subscription(
+                "A Reader With A Name Much Longer Than Forty Characters",
+                "1 Main St",
+                "62701",
+            ),
+            subscription("B", "2 Main St", "62701"),
+        ]);
+        let width: usize = FIXED_WIDTH_COLUMNS.iter().map(|(_, width)| width).sum();
+
+        let output = render_fixed_width(&entries);
+
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(2, lines.len());
+        assert!(lines.iter().all(|line| line.chars().count() == width));
+        assert!(lines[0].starts_with("000001A READER"));
+    }
+
+    #[test]
+    fn csv_has_a_header_and_a_line_per_entry() {
+        let entries = build_mailing_list(vec![subscription("Reader, Jr.", "1 Main St", "62701")]);
+        let output = render_csv(&entries);
+        assert_eq!(2, output.lines().count());
+        assert!(output.contains("\"READER, JR.\""));
+    }
+}
@@ -0,0 +1,81 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::user_models::UserGroup;
+
+pub const DEFAULT_INVITATION_LIFETIME_HOURS: i64 = 72;
+pub const MAX_INVITATION_LIFETIME_HOURS: i64 = 24 * 30;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Invitation {
+    pub id: Uuid,
+    pub email_address: String,
+    pub user_group: UserGroup,
+    pub invited_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub accepted_at: Option<DateTime<Utc>>,
+    pub accepted_user_id: Option<Uuid>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CreateInvitation {
+    pub email_address: String,
+    pub user_group: UserGroup,
+    pub expires_in_hours: Option<i64>,
+}
+
+impl CreateInvitation {
+    /// Hours until the link stops working, defaulting to three days and capped at thirty.
+    pub fn lifetime_hours(&self) -> i64 {
+        self.expires_in_hours
+            .unwrap_or(DEFAULT_INVITATION_LIFETIME_HOURS)
+            .clamp(1, MAX_INVITATION_LIFETIME_HOURS)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Was not able to serialize.")
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AcceptInvitation {
+    pub token: String,
+    pub password: String,
+    pub name: String,
+}
+
+impl AcceptInvitation {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Was not able to serialize.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::invitation_models::{
+        CreateInvitation, DEFAULT_INVITATION_LIFETIME_HOURS, MAX_INVITATION_LIFETIME_HOURS,
+    };
+    use crate::domain::user_models::UserGroup;
+
+    #[test]
+    fn invitation_lifetime_is_defaulted_and_clamped() {
+        let invitation = |expires_in_hours| CreateInvitation {
+            email_address: "someone@example.com".to_string(),
+            user_group: UserGroup::ADMIN,
+            expires_in_hours,
+        };
+
+        assert_eq!(
+            DEFAULT_INVITATION_LIFETIME_HOURS,
+            invitation(None).lifetime_hours()
+        );
+        assert_eq!(1, invitation(Some(-5)).lifetime_hours());
+        assert_eq!(
+            MAX_INVITATION_LIFETIME_HOURS,
+            invitation(Some(100_000)).lifetime_hours()
+        );
+    }
+}
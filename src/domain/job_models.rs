@@ -0,0 +1,231 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::domain::email_outbox_models::retry_delay;
+use crate::domain::notification_channel_models::StaffEventType;
+use crate::domain::subscription_history_models::HistoryEventType;
+use crate::domain::subscription_models::OverTheWireSubscription;
+
+/// After this many failed attempts a job is left `Dead` until an admin retries it.
+pub const MAX_JOB_ATTEMPTS: i32 = 10;
+/// How long a worker may hold a claimed job before another may take it.
+pub const JOB_CLAIM_TIMEOUT_SECONDS: i64 = 300;
+
+/// What a job does, with everything it needs. Stored as JSON tagged with its
+/// `kind`, so jobs queued by an older release must still read: add variants
+/// and optional fields rather than changing existing ones.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind")]
+pub enum JobPayload {
+    /// Records the subscription, as it was when it changed, in its history
+    /// and tells the webhook endpoints subscribed to the change.
+    StoreSubscriptionHistory {
+        subscription: Box<OverTheWireSubscription>,
+        event_type: HistoryEventType,
+        occurred_at: DateTime<Utc>,
+    },
+    /// Tells staff about a new subscription.
+    NotifyNewSubscription { subscription_id: Uuid },
+    /// Tells staff about a change to a subscription, as the change left it.
+    NotifyStaff {
+        event_type: StaffEventType,
+        subscription: Box<OverTheWireSubscription>,
+    },
+}
+
+impl JobPayload {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            JobPayload::StoreSubscriptionHistory { .. } => "StoreSubscriptionHistory",
+            JobPayload::NotifyNewSubscription { .. } => "NotifyNewSubscription",
+            JobPayload::NotifyStaff { .. } => "NotifyStaff",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Done,
+    Dead,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Pending => "Pending",
+            JobStatus::Running => "Running",
+            JobStatus::Done => "Done",
+            JobStatus::Dead => "Dead",
+        }
+    }
+}
+
+impl FromStr for JobStatus {
+    type Err = ();
+
+    fn from_str(val: &str) -> Result<JobStatus, ()> {
+        match val {
+            "Pending" => Ok(JobStatus::Pending),
+            "Running" => Ok(JobStatus::Running),
+            "Done" => Ok(JobStatus::Done),
+            "Dead" => Ok(JobStatus::Dead),
+            _ => {
+                tracing::error!("Could not map string: {} to the enum JobStatus", val);
+                Err(())
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Job {
+    pub id: Uuid,
+    pub kind: String,
+    /// The `JobPayload`, as stored.
+    pub payload: Value,
+    pub status: JobStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    /// When a pending job is next due, or when the claim on a running one lapses.
+    pub run_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl Job {
+    pub fn new(payload: &JobPayload, run_at: DateTime<Utc>) -> Self {
+        Job {
+            id: Uuid::new_v4(),
+            kind: payload.kind().to_string(),
+            payload: serde_json::to_value(payload).expect("Was not able to serialize."),
+            status: JobStatus::Pending,
+            attempts: 0,
+            last_error: None,
+            run_at,
+            created_at: Utc::now(),
+            finished_at: None,
+        }
+    }
+
+    /// Fails for payloads this release cannot read, which then fail like any
+    /// other attempt.
+    pub fn payload(&self) -> Result<JobPayload, String> {
+        serde_json::from_value(self.payload.clone())
+            .map_err(|e| format!("Cannot read the {} payload: {}", self.kind, e))
+    }
+
+    /// Where a failed attempt leaves the job: retried later, or dead once it
+    /// has used up its attempts.
+    pub fn after_failure(&self, now: DateTime<Utc>) -> (JobStatus, DateTime<Utc>) {
+        if self.attempts >= MAX_JOB_ATTEMPTS {
+            (JobStatus::Dead, now)
+        } else {
+            (JobStatus::Pending, now + retry_delay(self.attempts))
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct JobQuery {
+    pub status: Option<JobStatus>,
+    pub kind: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use crate::domain::job_models::{Job, JobPayload, JobStatus, MAX_JOB_ATTEMPTS};
+    use crate::domain::subscription_history_models::HistoryEventType;
+    use crate::domain::subscription_models::{
+        BillingSource, OverTheWireSubscription, SubscriptionType,
+    };
+
+    fn subscription() -> OverTheWireSubscription {
+        OverTheWireSubscription {
+            id: Uuid::new_v4(),
+            subscriber_id: Uuid::new_v4(),
+            subscription_name: "Joe Smith".to_string(),
+            subscription_mailing_address_line_1: "123 Main".to_string(),
+            subscription_mailing_address_line_2: "".to_string(),
+            subscription_city: "Kansas City".to_string(),
+            subscription_state: "MO".to_string(),
+            subscription_postal_code: "64105".to_string(),
+            subscription_email_address: "someone@gmail.com".to_string(),
+            subscription_creation_date: Utc::now(),
+            subscription_cancelled_on_date: Some(Utc::now()),
+            subscription_anniversary_day: 1,
+            subscription_anniversary_month: 1,
+            subscription_renewal_date: "".to_string(),
+            active: false,
+            subscription_type: SubscriptionType::Digital,
+            stripe_subscription_id: "".to_string(),
+            billing_source: BillingSource::Complimentary,
+            publication_id: None,
+        }
+    }
+
+    #[test]
+    fn payloads_are_stored_tagged_with_their_kind() {
+        let subscription = subscription();
+        let occurred_at = Utc::now();
+        let payload = JobPayload::StoreSubscriptionHistory {
+            subscription: Box::new(subscription.clone()),
+            event_type: HistoryEventType::Cancelled,
+            occurred_at,
+        };
+        let job = Job::new(&payload, Utc::now());
+
+        assert_eq!("StoreSubscriptionHistory", job.kind);
+        assert_eq!(
+            json!({
+                "kind": "StoreSubscriptionHistory",
+                "subscription": subscription,
+                "event_type": "Cancelled",
+                "occurred_at": occurred_at,
+            }),
+            job.payload
+        );
+        assert_eq!(Ok(payload), job.payload());
+    }
+
+    #[test]
+    fn unknown_payloads_cannot_be_read() {
+        let mut job = Job::new(
+            &JobPayload::NotifyNewSubscription {
+                subscription_id: Uuid::new_v4(),
+            },
+            Utc::now(),
+        );
+        job.payload = json!({ "kind": "SomethingNewer" });
+
+        assert!(job.payload().is_err());
+    }
+
+    #[test]
+    fn jobs_die_after_the_last_attempt() {
+        let now = Utc::now();
+        let mut job = Job::new(
+            &JobPayload::NotifyNewSubscription {
+                subscription_id: Uuid::new_v4(),
+            },
+            now,
+        );
+
+        job.attempts = 1;
+        assert_eq!(
+            (JobStatus::Pending, now + Duration::seconds(30)),
+            job.after_failure(now)
+        );
+        job.attempts = MAX_JOB_ATTEMPTS;
+        assert_eq!((JobStatus::Dead, now), job.after_failure(now));
+    }
+}
@@ -0,0 +1,200 @@
+use printpdf::{BuiltinFont, Mm, PdfDocument};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::fulfillment_models::FulfillmentEntry;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LabelLayout {
+    /// US Letter sheets of 30 labels, 2 5/8" x 1", three across.
+    #[default]
+    #[serde(rename = "avery_5160")]
+    Avery5160,
+    /// One 4" x 6" label per page, for thermal label printers.
+    #[serde(rename = "thermal_4x6")]
+    Thermal4x6,
+}
+
+/// Where the labels sit on a page, in millimetres from the top-left corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LabelSheet {
+    pub page_width: f32,
+    pub page_height: f32,
+    pub columns: usize,
+    pub rows: usize,
+    pub left_margin: f32,
+    pub top_margin: f32,
+    pub column_pitch: f32,
+    pub row_pitch: f32,
+    pub padding: f32,
+    /// Points.
+    pub font_size: f32,
+    pub line_height: f32,
+    /// Longer lines are cut so they stay on the label.
+    pub max_chars: usize,
+}
+
+impl LabelLayout {
+    pub fn sheet(&self) -> LabelSheet {
+        match self {
+            LabelLayout::Avery5160 => LabelSheet {
+                page_width: 215.9,
+                page_height: 279.4,
+                columns: 3,
+                rows: 10,
+                left_margin: 4.8,
+                top_margin: 12.7,
+                column_pitch: 69.85,
+                row_pitch: 25.4,
+                padding: 3.0,
+                font_size: 7.0,
+                line_height: 3.3,
+                max_chars: 36,
+            },
+            LabelLayout::Thermal4x6 => LabelSheet {
+                page_width: 101.6,
+                page_height: 152.4,
+                columns: 1,
+                rows: 1,
+                left_margin: 0.0,
+                top_margin: 0.0,
+                column_pitch: 101.6,
+                row_pitch: 152.4,
+                padding: 8.0,
+                font_size: 12.0,
+                line_height: 6.0,
+                max_chars: 30,
+            },
+        }
+    }
+
+    pub fn labels_per_page(&self) -> usize {
+        let sheet = self.sheet();
+        sheet.columns * sheet.rows
+    }
+
+    pub fn page_count(&self, labels: usize) -> usize {
+        labels.div_ceil(self.labels_per_page()).max(1)
+    }
+}
+
+/// The presort line (sequence number and ZIP), then the address block.
+pub fn label_lines(entry: &FulfillmentEntry) -> Vec<String> {
+    let mut lines = vec![
+        format!("#{:0>6}  {}", entry.sequence, entry.postal_code),
+        entry.name.clone(),
+        entry.address_line_1.clone(),
+    ];
+    if let Some(address_line_2) = &entry.address_line_2 {
+        lines.push(address_line_2.clone());
+    }
+    lines.push(format!(
+        "{} {}  {}",
+        entry.city, entry.state, entry.postal_code
+    ));
+    lines
+}
+
+/// Lays the labels out in postal code order, filling each page left to right
+/// and top to bottom, so sheets come off the printer already presorted.
+pub fn render_labels(
+    entries: &[FulfillmentEntry],
+    layout: LabelLayout,
+    title: &str,
+) -> Result<Vec<u8>, printpdf::Error> {
+    let sheet = layout.sheet();
+    let mut entries: Vec<&FulfillmentEntry> = entries.iter().collect();
+    entries.sort_by(|a, b| (&a.postal_code, a.sequence).cmp(&(&b.postal_code, b.sequence)));
+
+    let (document, page, layer) =
+        PdfDocument::new(title, Mm(sheet.page_width), Mm(sheet.page_height), "Labels");
+    let font = document.add_builtin_font(BuiltinFont::Helvetica)?;
+    let mut current_layer = document.get_page(page).get_layer(layer);
+
+    for (index, entry) in entries.into_iter().enumerate() {
+        let slot = index % layout.labels_per_page();
+        if index > 0 && slot == 0 {
+            let (page, layer) =
+                document.add_page(Mm(sheet.page_width), Mm(sheet.page_height), "Labels");
+            current_layer = document.get_page(page).get_layer(layer);
+        }
+        let column = (slot % sheet.columns) as f32;
+        let row = (slot / sheet.columns) as f32;
+        let x = sheet.left_margin + column * sheet.column_pitch + sheet.padding;
+        let top = sheet.page_height - sheet.top_margin - row * sheet.row_pitch - sheet.padding;
+
+        for (line_index, line) in label_lines(entry).iter().enumerate() {
+            // The presort line is smaller so the address block stands out.
+            let font_size = if line_index == 0 {
+                sheet.font_size * 0.75
+            } else {
+                sheet.font_size
+            };
+            let line: String = line.chars().take(sheet.max_chars).collect();
+            let y = top - sheet.line_height * (line_index + 1) as f32;
+            current_layer.use_text(line, font_size, Mm(x), Mm(y), &font);
+        }
+    }
+
+    document.save_to_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use crate::domain::fulfillment_models::FulfillmentEntry;
+    use crate::domain::mailing_label_models::{label_lines, render_labels, LabelLayout};
+
+    fn entry(sequence: i32, postal_code: &str, address_line_2: Option<&str>) -> FulfillmentEntry {
+        FulfillmentEntry {
+            sequence,
+            subscription_id: Uuid::new_v4(),
+            subscriber_id: Uuid::new_v4(),
+            name: "A READER".to_string(),
+            address_line_1: format!("{} MAIN ST", sequence),
+            address_line_2: address_line_2.map(|line| line.to_string()),
+            city: "SPRINGFIELD".to_string(),
+            state: "IL".to_string(),
+            postal_code: postal_code.to_string(),
+            merged_subscription_ids: vec![],
+        }
+    }
+
+    #[test]
+    fn a_label_has_a_presort_line_and_the_address_block() {
+        assert_eq!(
+            vec![
+                "#000007  62701",
+                "A READER",
+                "7 MAIN ST",
+                "APT 2",
+                "SPRINGFIELD IL  62701"
+            ],
+            label_lines(&entry(7, "62701", Some("APT 2")))
+        );
+        assert_eq!(4, label_lines(&entry(7, "62701", None)).len());
+    }
+
+    #[test]
+    fn pages_are_added_when_a_sheet_is_full() {
+        assert_eq!(30, LabelLayout::Avery5160.labels_per_page());
+        assert_eq!(1, LabelLayout::Avery5160.page_count(0));
+        assert_eq!(1, LabelLayout::Avery5160.page_count(30));
+        assert_eq!(2, LabelLayout::Avery5160.page_count(31));
+        assert_eq!(31, LabelLayout::Thermal4x6.page_count(31));
+    }
+
+    #[test]
+    fn labels_render_as_a_pdf() {
+        let entries: Vec<FulfillmentEntry> = (1..=31)
+            .map(|sequence| entry(sequence, "62701", None))
+            .collect();
+
+        for layout in [LabelLayout::Avery5160, LabelLayout::Thermal4x6] {
+            let pdf = render_labels(&entries, layout, "Labels").unwrap();
+            assert!(pdf.starts_with(b"%PDF-"));
+            let pages = pdf.windows(11).filter(|w| w == b"/Type/Page/").count();
+            assert_eq!(layout.page_count(entries.len()), pages);
+        }
+    }
+}
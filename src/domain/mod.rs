@@ -1,10 +1,29 @@
+pub mod audit_models;
+pub mod broadcast_models;
+pub mod captured_email_models;
 pub mod checkout_models;
+pub mod email_outbox_models;
+pub mod email_preference_models;
+pub mod email_suppression_models;
+pub mod fulfillment_models;
+pub mod invitation_models;
+pub mod job_models;
+pub mod mailing_label_models;
+pub mod newsletter_models;
+pub mod notification_channel_models;
+pub mod oidc_models;
 pub mod otp_models;
+pub mod publication_models;
+pub mod session_models;
+pub mod staff_digest_models;
 pub mod subscriber_models;
 pub mod subscription_history_models;
+pub mod subscription_import_models;
 pub mod subscription_models;
 pub mod user_models;
+pub mod valid_address;
 pub mod valid_email;
 pub mod valid_name;
 pub mod valid_string;
+pub mod webhook_endpoint_models;
 pub mod webhook_event;
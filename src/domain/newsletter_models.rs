@@ -0,0 +1,84 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// What the sign-up form shows next to its button. A sign-up must echo it
+/// back word for word, and it is stored with the member as their consent.
+pub const NEWSLETTER_CONSENT_TEXT: &str =
+    "Yes, email me the newsletter. I can unsubscribe at any time using the link in every email.";
+pub const NEWSLETTER_CONFIRMATION_LIFETIME_HOURS: i64 = 48;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NewsletterMemberStatus {
+    /// Waiting for the emailed link to be followed; not on the list yet.
+    Pending,
+    Confirmed,
+}
+
+impl NewsletterMemberStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NewsletterMemberStatus::Pending => "Pending",
+            NewsletterMemberStatus::Confirmed => "Confirmed",
+        }
+    }
+}
+
+impl FromStr for NewsletterMemberStatus {
+    type Err = ();
+
+    fn from_str(val: &str) -> Result<NewsletterMemberStatus, ()> {
+        match val {
+            "Pending" => Ok(NewsletterMemberStatus::Pending),
+            "Confirmed" => Ok(NewsletterMemberStatus::Confirmed),
+            _ => {
+                tracing::error!(
+                    "Could not map string: {} to the enum NewsletterMemberStatus",
+                    val
+                );
+                Err(())
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct NewsletterMember {
+    pub id: Uuid,
+    pub email_address: String,
+    pub name: String,
+    pub status: NewsletterMemberStatus,
+    pub consent_text: String,
+    pub consent_ip_address: Option<String>,
+    pub consent_user_agent: Option<String>,
+    pub consented_at: DateTime<Utc>,
+    pub confirmed_at: Option<DateTime<Utc>>,
+    pub confirmed_ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct NewsletterSignup {
+    pub name: String,
+    pub email_address: String,
+    pub consent_text: String,
+}
+
+impl NewsletterSignup {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Was not able to serialize.")
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ConfirmNewsletterSignup {
+    pub token: String,
+}
+
+impl ConfirmNewsletterSignup {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Was not able to serialize.")
+    }
+}
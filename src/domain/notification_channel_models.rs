@@ -0,0 +1,219 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::valid_email::ValidEmail;
+
+pub const MAX_CHANNEL_NAME_LENGTH: usize = 200;
+
+/// How a channel is told about an event.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NotificationChannelKind {
+    /// `target` is an email address.
+    Email,
+    /// `target` is a Slack-compatible incoming webhook URL, posted `{ "text": ... }`.
+    Slack,
+    /// `target` is a URL posted the event as JSON.
+    Webhook,
+}
+
+impl NotificationChannelKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationChannelKind::Email => "Email",
+            NotificationChannelKind::Slack => "Slack",
+            NotificationChannelKind::Webhook => "Webhook",
+        }
+    }
+}
+
+impl FromStr for NotificationChannelKind {
+    type Err = ();
+
+    fn from_str(val: &str) -> Result<NotificationChannelKind, ()> {
+        match val {
+            "Email" => Ok(NotificationChannelKind::Email),
+            "Slack" => Ok(NotificationChannelKind::Slack),
+            "Webhook" => Ok(NotificationChannelKind::Webhook),
+            _ => {
+                tracing::error!(
+                    "Could not map string: {} to the enum NotificationChannelKind",
+                    val
+                );
+                Err(())
+            }
+        }
+    }
+}
+
+/// What staff can be told about.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StaffEventType {
+    NewSubscription,
+    Cancellation,
+    PaymentFailure,
+    AddressChange,
+    /// Email to an active subscription's address hard-bounced.
+    EmailBounced,
+    /// The periodic staff digest.
+    Digest,
+}
+
+impl StaffEventType {
+    pub const ALL: [StaffEventType; 6] = [
+        StaffEventType::NewSubscription,
+        StaffEventType::Cancellation,
+        StaffEventType::PaymentFailure,
+        StaffEventType::AddressChange,
+        StaffEventType::EmailBounced,
+        StaffEventType::Digest,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StaffEventType::NewSubscription => "NewSubscription",
+            StaffEventType::Cancellation => "Cancellation",
+            StaffEventType::PaymentFailure => "PaymentFailure",
+            StaffEventType::AddressChange => "AddressChange",
+            StaffEventType::EmailBounced => "EmailBounced",
+            StaffEventType::Digest => "Digest",
+        }
+    }
+}
+
+impl FromStr for StaffEventType {
+    type Err = ();
+
+    fn from_str(val: &str) -> Result<StaffEventType, ()> {
+        StaffEventType::ALL
+            .into_iter()
+            .find(|event_type| event_type.as_str() == val)
+            .ok_or_else(|| {
+                tracing::error!("Could not map string: {} to the enum StaffEventType", val);
+            })
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct NotificationChannel {
+    pub id: Uuid,
+    pub name: String,
+    pub kind: NotificationChannelKind,
+    pub target: String,
+    pub event_types: Vec<StaffEventType>,
+    pub enabled: bool,
+    pub last_notified_at: Option<DateTime<Utc>>,
+    /// Why the last notice could not be delivered, cleared by the next one
+    /// that is.
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl NotificationChannel {
+    pub fn new(request: &NotificationChannelRequest) -> Self {
+        let now = Utc::now();
+        NotificationChannel {
+            id: Uuid::new_v4(),
+            name: request.name.trim().to_string(),
+            kind: request.kind,
+            target: request.target.trim().to_string(),
+            event_types: request.event_types.clone(),
+            enabled: request.enabled,
+            last_notified_at: None,
+            last_error: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// Body of the create and update notification channel endpoints.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct NotificationChannelRequest {
+    pub name: String,
+    pub kind: NotificationChannelKind,
+    pub target: String,
+    pub event_types: Vec<StaffEventType>,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl NotificationChannelRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        let name = self.name.trim();
+        if name.is_empty() || name.chars().count() > MAX_CHANNEL_NAME_LENGTH {
+            return Err(format!(
+                "A channel name must have 1 to {} characters",
+                MAX_CHANNEL_NAME_LENGTH
+            ));
+        }
+        if self.event_types.is_empty() {
+            return Err("A channel must subscribe to at least one event type".to_string());
+        }
+
+        let target = self.target.trim();
+        match self.kind {
+            NotificationChannelKind::Email => ValidEmail::parse(target.to_string()).map(|_| ()),
+            NotificationChannelKind::Slack | NotificationChannelKind::Webhook => {
+                match reqwest::Url::parse(target) {
+                    Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(()),
+                    _ => Err(format!("{} is not an http(s) URL", target)),
+                }
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Was not able to serialize.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_err, assert_ok};
+
+    use crate::domain::notification_channel_models::{
+        NotificationChannelKind, NotificationChannelRequest, StaffEventType,
+    };
+
+    fn request(kind: NotificationChannelKind, target: &str) -> NotificationChannelRequest {
+        NotificationChannelRequest {
+            name: "Office".to_string(),
+            kind,
+            target: target.to_string(),
+            event_types: vec![StaffEventType::NewSubscription],
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn targets_must_suit_the_kind() {
+        assert_ok!(request(NotificationChannelKind::Email, "office@example.com").validate());
+        assert_err!(request(NotificationChannelKind::Email, "https://example.com").validate());
+        assert_ok!(request(
+            NotificationChannelKind::Slack,
+            "https://hooks.slack.com/services/T0/B0/x"
+        )
+        .validate());
+        assert_err!(request(NotificationChannelKind::Webhook, "office@example.com").validate());
+        assert_err!(request(NotificationChannelKind::Webhook, "ftp://example.com").validate());
+    }
+
+    #[test]
+    fn channels_need_a_name_and_an_event_type() {
+        let mut unnamed = request(NotificationChannelKind::Email, "office@example.com");
+        unnamed.name = "  ".to_string();
+        assert_err!(unnamed.validate());
+
+        let mut deaf = request(NotificationChannelKind::Email, "office@example.com");
+        deaf.event_types.clear();
+        assert_err!(deaf.validate());
+    }
+}
@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct OidcLoginState {
+    pub state: String,
+    pub code_verifier: String,
+    pub nonce: String,
+    pub issued_on: DateTime<Utc>,
+    pub expires_on: DateTime<Utc>,
+    pub used: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct UserIdentity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub provider: String,
+    pub subject: String,
+    pub email_address: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct OidcCallback {
+    pub code: String,
+    pub state: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct OidcLoginRedirect {
+    pub location: String,
+}
+
+impl OidcLoginRedirect {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Was not able to serialize.")
+    }
+}
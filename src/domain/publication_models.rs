@@ -0,0 +1,278 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::subscription_models::OverTheWireSubscription;
+
+pub const MAX_TITLE_LENGTH: usize = 200;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Publication {
+    pub id: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Body of the create and update publication endpoints.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PublicationRequest {
+    pub title: String,
+    pub description: Option<String>,
+}
+
+impl PublicationRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_title(&self.title)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Was not able to serialize.")
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub id: Uuid,
+    pub publication_id: Uuid,
+    pub issue_number: i32,
+    pub title: Option<String>,
+    pub publish_date: NaiveDate,
+    /// Subscriptions active at the start of this day (UTC) get the issue.
+    pub cutoff_date: NaiveDate,
+    /// Where the digital edition lives, e.g. a URL or storage key.
+    pub digital_asset_reference: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Issue {
+    pub fn cutoff(&self) -> DateTime<Utc> {
+        self.cutoff_date.and_hms_opt(0, 0, 0).unwrap().and_utc()
+    }
+
+    /// Whether the subscription was, or if nothing changes will be, active at
+    /// the issue's cutoff.
+    pub fn is_delivered_to(&self, subscription: &OverTheWireSubscription) -> bool {
+        let cutoff = self.cutoff();
+        subscription.subscription_creation_date <= cutoff
+            && subscription
+                .subscription_cancelled_on_date
+                .map_or(subscription.active, |cancelled_on| cancelled_on > cutoff)
+    }
+}
+
+/// Body of the create and update issue endpoints.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct IssueRequest {
+    pub issue_number: i32,
+    pub title: Option<String>,
+    pub publish_date: NaiveDate,
+    /// Defaults to `publish_date`.
+    pub cutoff_date: Option<NaiveDate>,
+    pub digital_asset_reference: Option<String>,
+}
+
+impl IssueRequest {
+    pub fn cutoff_date(&self) -> NaiveDate {
+        self.cutoff_date.unwrap_or(self.publish_date)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.issue_number < 1 {
+            return Err("issue_number must be 1 or more.".to_string());
+        }
+        if self.cutoff_date() > self.publish_date {
+            return Err("cutoff_date cannot be after publish_date.".to_string());
+        }
+        match &self.title {
+            Some(title) => validate_title(title),
+            None => Ok(()),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Was not able to serialize.")
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SetSubscriptionPublication {
+    pub publication_id: Option<Uuid>,
+}
+
+/// What a subscription has been sent and what is still due in its current term.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SubscriptionIssues {
+    pub subscription_id: Uuid,
+    pub publication: Option<Publication>,
+    pub received: Vec<Issue>,
+    pub upcoming: Vec<Issue>,
+    pub remaining_count: usize,
+    /// The next renewal date; issues with a cutoff on or after it belong to the next term.
+    pub term_end: Option<NaiveDate>,
+}
+
+/// Splits a publication's issues into those already published for the
+/// subscription and those still to come before it renews.
+pub fn subscription_issues(
+    subscription: &OverTheWireSubscription,
+    publication: Option<Publication>,
+    mut issues: Vec<Issue>,
+    today: NaiveDate,
+) -> SubscriptionIssues {
+    issues.sort_by_key(|issue| (issue.publish_date, issue.issue_number));
+    let term_end =
+        NaiveDate::parse_from_str(&subscription.subscription_renewal_date, "%m/%d/%Y").ok();
+
+    let (received, upcoming): (Vec<Issue>, Vec<Issue>) = issues
+        .into_iter()
+        .filter(|issue| issue.is_delivered_to(subscription))
+        .partition(|issue| issue.publish_date <= today);
+    let upcoming: Vec<Issue> = upcoming
+        .into_iter()
+        .filter(|issue| term_end.is_none_or(|term_end| issue.cutoff_date < term_end))
+        .collect();
+
+    SubscriptionIssues {
+        subscription_id: subscription.id,
+        publication,
+        received,
+        remaining_count: upcoming.len(),
+        upcoming,
+        term_end,
+    }
+}
+
+fn validate_title(title: &str) -> Result<(), String> {
+    if title.trim().is_empty() {
+        return Err("title cannot be empty.".to_string());
+    }
+    if title.chars().count() > MAX_TITLE_LENGTH {
+        return Err(format!(
+            "title cannot be longer than {} characters.",
+            MAX_TITLE_LENGTH
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate, Utc};
+    use uuid::Uuid;
+
+    use crate::domain::publication_models::{
+        subscription_issues, Issue, IssueRequest, PublicationRequest,
+    };
+    use crate::domain::subscription_models::{
+        BillingSource, OverTheWireSubscription, SubscriptionType,
+    };
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn issue(issue_number: i32, publish_date: &str) -> Issue {
+        Issue {
+            id: Uuid::new_v4(),
+            publication_id: Uuid::new_v4(),
+            issue_number,
+            title: None,
+            publish_date: date(publish_date),
+            cutoff_date: date(publish_date) - Duration::days(7),
+            digital_asset_reference: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn subscription(created: &str, renewal_date: &str) -> OverTheWireSubscription {
+        OverTheWireSubscription {
+            id: Uuid::new_v4(),
+            subscriber_id: Uuid::new_v4(),
+            subscription_name: "Reader".to_string(),
+            subscription_mailing_address_line_1: "1 Main St".to_string(),
+            subscription_mailing_address_line_2: "".to_string(),
+            subscription_city: "Springfield".to_string(),
+            subscription_state: "IL".to_string(),
+            subscription_postal_code: "62701".to_string(),
+            subscription_email_address: "reader@example.com".to_string(),
+            subscription_creation_date: date(created).and_hms_opt(12, 0, 0).unwrap().and_utc(),
+            subscription_cancelled_on_date: None,
+            subscription_anniversary_day: 1,
+            subscription_anniversary_month: 1,
+            subscription_renewal_date: renewal_date.to_string(),
+            active: true,
+            subscription_type: SubscriptionType::Paper,
+            stripe_subscription_id: "".to_string(),
+            billing_source: BillingSource::Stripe,
+            publication_id: None,
+        }
+    }
+
+    #[test]
+    fn issues_are_split_into_received_and_upcoming() {
+        let subscription = subscription("2026-02-01", "2/1/2027");
+        let issues = vec![
+            issue(1, "2026-01-15"),
+            issue(2, "2026-03-15"),
+            issue(3, "2026-06-15"),
+            issue(4, "2026-12-15"),
+            issue(5, "2027-03-15"),
+        ];
+
+        let result = subscription_issues(&subscription, None, issues, date("2026-07-01"));
+
+        let numbers = |issues: &[Issue]| issues.iter().map(|i| i.issue_number).collect::<Vec<_>>();
+        assert_eq!(vec![2, 3], numbers(&result.received));
+        assert_eq!(vec![4], numbers(&result.upcoming));
+        assert_eq!(1, result.remaining_count);
+        assert_eq!(Some(date("2027-02-01")), result.term_end);
+    }
+
+    #[test]
+    fn cancelled_subscriptions_have_nothing_remaining() {
+        let mut subscription = subscription("2026-02-01", "2/1/2027");
+        subscription.active = false;
+        subscription.subscription_cancelled_on_date =
+            Some(date("2026-05-01").and_hms_opt(0, 0, 0).unwrap().and_utc());
+        let issues = vec![issue(2, "2026-03-15"), issue(3, "2026-06-15")];
+
+        let result = subscription_issues(&subscription, None, issues, date("2026-04-01"));
+
+        assert_eq!(1, result.received.len());
+        assert_eq!(0, result.remaining_count);
+    }
+
+    #[test]
+    fn issue_requests_are_validated() {
+        let valid = IssueRequest {
+            issue_number: 1,
+            title: None,
+            publish_date: date("2026-11-01"),
+            cutoff_date: None,
+            digital_asset_reference: None,
+        };
+        assert!(valid.validate().is_ok());
+        assert_eq!(date("2026-11-01"), valid.cutoff_date());
+
+        let late_cutoff = IssueRequest {
+            cutoff_date: Some(date("2026-11-02")),
+            ..valid.clone()
+        };
+        assert!(late_cutoff.validate().is_err());
+        let zero = IssueRequest {
+            issue_number: 0,
+            ..valid
+        };
+        assert!(zero.validate().is_err());
+    }
+
+    #[test]
+    fn publication_titles_cannot_be_blank() {
+        let blank = PublicationRequest {
+            title: " ".to_string(),
+            description: None,
+        };
+        assert!(blank.validate().is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginMethod {
+    SignUp,
+    Password,
+    Otp,
+    Oidc,
+    Invitation,
+}
+
+impl LoginMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoginMethod::SignUp => "SignUp",
+            LoginMethod::Password => "Password",
+            LoginMethod::Otp => "Otp",
+            LoginMethod::Oidc => "Oidc",
+            LoginMethod::Invitation => "Invitation",
+        }
+    }
+}
+
+impl FromStr for LoginMethod {
+    type Err = ();
+
+    fn from_str(val: &str) -> Result<LoginMethod, ()> {
+        match val {
+            "SignUp" => Ok(LoginMethod::SignUp),
+            "Password" => Ok(LoginMethod::Password),
+            "Otp" => Ok(LoginMethod::Otp),
+            "Oidc" => Ok(LoginMethod::Oidc),
+            "Invitation" => Ok(LoginMethod::Invitation),
+            _ => {
+                tracing::error!("Could not map string: {} to the enum LoginMethod", val);
+                Err(())
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginFailureReason {
+    UnknownUser,
+    WrongPassword,
+    InvalidPasscode,
+    Disabled,
+    PasswordResetRequired,
+}
+
+impl LoginFailureReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoginFailureReason::UnknownUser => "UnknownUser",
+            LoginFailureReason::WrongPassword => "WrongPassword",
+            LoginFailureReason::InvalidPasscode => "InvalidPasscode",
+            LoginFailureReason::Disabled => "Disabled",
+            LoginFailureReason::PasswordResetRequired => "PasswordResetRequired",
+        }
+    }
+}
+
+/// One row of `login_history`. Failed attempts against an unknown email have no `user_id`.
+#[derive(Debug, Clone)]
+pub struct LoginAttempt {
+    pub user_id: Option<Uuid>,
+    pub email_address: Option<String>,
+    pub login_method: LoginMethod,
+    pub failure_reason: Option<LoginFailureReason>,
+    pub session_id: Option<Uuid>,
+}
+
+impl LoginAttempt {
+    pub fn succeeded(&self) -> bool {
+        self.failure_reason.is_none()
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct UserSession {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub login_method: LoginMethod,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct OverTheWireUserSession {
+    pub id: Uuid,
+    pub login_method: LoginMethod,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// True for the session whose token made this request.
+    pub current: bool,
+}
+
+impl OverTheWireUserSession {
+    pub fn from_session(session: UserSession, current_session_id: Option<&str>) -> Self {
+        Self {
+            current: current_session_id == Some(session.id.to_string().as_str()),
+            id: session.id,
+            login_method: session.login_method,
+            ip_address: session.ip_address,
+            user_agent: session.user_agent,
+            created_at: session.created_at,
+            expires_at: session.expires_at,
+        }
+    }
+}
+
+/// Whether a successful login matches anything the user has signed in from before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoginFamiliarity {
+    pub has_previous_logins: bool,
+    pub known_device: bool,
+    pub known_network: bool,
+}
+
+impl LoginFamiliarity {
+    /// The very first login has nothing to compare against, so it never counts as new.
+    pub fn is_new_device_or_network(&self) -> bool {
+        self.has_previous_logins && !(self.known_device && self.known_network)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use claims::assert_err;
+
+    use crate::domain::session_models::{LoginFamiliarity, LoginMethod};
+
+    #[test]
+    fn login_method_round_trips_through_strings() {
+        for method in [
+            LoginMethod::SignUp,
+            LoginMethod::Password,
+            LoginMethod::Otp,
+            LoginMethod::Oidc,
+            LoginMethod::Invitation,
+        ] {
+            assert_eq!(method, LoginMethod::from_str(method.as_str()).unwrap());
+        }
+        assert_err!(LoginMethod::from_str("password"));
+    }
+
+    #[test]
+    fn only_unfamiliar_logins_after_the_first_are_new() {
+        let familiarity = |has_previous_logins, known_device, known_network| LoginFamiliarity {
+            has_previous_logins,
+            known_device,
+            known_network,
+        };
+
+        assert!(!familiarity(false, false, false).is_new_device_or_network());
+        assert!(!familiarity(true, true, true).is_new_device_or_network());
+        assert!(familiarity(true, false, true).is_new_device_or_network());
+        assert!(familiarity(true, true, false).is_new_device_or_network());
+    }
+}
@@ -0,0 +1,152 @@
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::configuration::DigestFrequency;
+
+/// What happened to subscriptions over one digest period, and what is coming up.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct StaffDigest {
+    pub frequency: DigestFrequency,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub new_subscriptions: Vec<SubscriptionTypeCount>,
+    pub cancelled_subscriptions: Vec<SubscriptionTypeCount>,
+    /// New less cancelled subscriptions.
+    pub net_change: i64,
+    pub failed_payments: Vec<FailedPayment>,
+    /// Active Paper subscriptions whose anniversary is coming up.
+    pub upcoming_renewals: Vec<UpcomingRenewal>,
+    /// The earliest issue cutoff from today on.
+    pub next_print_cutoff: Option<NaiveDate>,
+    /// Paper mailing addresses changed since the last fulfillment run, which
+    /// the next one will be the first to use.
+    pub pending_address_changes: Vec<PendingAddressChange>,
+}
+
+impl StaffDigest {
+    pub fn total(counts: &[SubscriptionTypeCount]) -> i64 {
+        counts.iter().map(|count| count.count).sum()
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionTypeCount {
+    pub subscription_type: String,
+    pub count: i64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FailedPayment {
+    pub occurred_at: DateTime<Utc>,
+    pub stripe_subscription_id: Option<String>,
+    pub customer_email: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct UpcomingRenewal {
+    pub subscription_id: Uuid,
+    pub subscription_name: String,
+    pub renewal_date: NaiveDate,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PendingAddressChange {
+    pub subscription_id: Uuid,
+    pub subscription_name: String,
+    /// The address now, on one line.
+    pub mailing_address: String,
+    pub changed_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct StaffDigestQuery {
+    pub frequency: Option<DigestFrequency>,
+}
+
+/// The first anniversary on or after `today`. February 29th anniversaries
+/// fall on the 28th outside leap years.
+pub fn next_anniversary(month: u32, day: u32, today: NaiveDate) -> Option<NaiveDate> {
+    [today.year(), today.year() + 1]
+        .into_iter()
+        .filter_map(|year| {
+            NaiveDate::from_ymd_opt(year, month, day).or_else(|| {
+                (month == 2 && day == 29)
+                    .then(|| NaiveDate::from_ymd_opt(year, 2, 28))
+                    .flatten()
+            })
+        })
+        .find(|anniversary| *anniversary >= today)
+}
+
+/// A stored Stripe event, if it reports a failed invoice payment.
+pub fn parse_failed_payment(event_text: &str, sent_on: DateTime<Utc>) -> Option<FailedPayment> {
+    let event: Value = serde_json::from_str(event_text).ok()?;
+    if event.get("type")?.as_str()? != "invoice.payment_failed" {
+        return None;
+    }
+    let invoice = &event["data"]["object"];
+    // Newer API versions moved the subscription under `parent`.
+    let stripe_subscription_id = [
+        &invoice["subscription"],
+        &invoice["parent"]["subscription_details"]["subscription"],
+    ]
+    .into_iter()
+    .find_map(Value::as_str)
+    .map(str::to_string);
+
+    Some(FailedPayment {
+        occurred_at: sent_on,
+        stripe_subscription_id,
+        customer_email: invoice["customer_email"].as_str().map(str::to_string),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, Utc};
+    use serde_json::json;
+
+    use crate::domain::staff_digest_models::{next_anniversary, parse_failed_payment};
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn anniversaries_come_this_year_or_next() {
+        let today = date(2026, 10, 19);
+
+        assert_eq!(Some(today), next_anniversary(10, 19, today));
+        assert_eq!(Some(date(2026, 11, 1)), next_anniversary(11, 1, today));
+        assert_eq!(Some(date(2027, 10, 18)), next_anniversary(10, 18, today));
+        assert_eq!(Some(date(2027, 2, 28)), next_anniversary(2, 29, today));
+        assert_eq!(
+            Some(date(2028, 2, 29)),
+            next_anniversary(2, 29, date(2028, 1, 1))
+        );
+    }
+
+    #[test]
+    fn only_failed_invoice_payments_are_read() {
+        let failed = json!({
+            "type": "invoice.payment_failed",
+            "data": { "object": { "subscription": "sub_1", "customer_email": "joe@example.com" } },
+        });
+        let moved = json!({
+            "type": "invoice.payment_failed",
+            "data": { "object": { "parent": { "subscription_details": { "subscription": "sub_2" } } } },
+        });
+        let created = json!({ "type": "customer.subscription.created", "data": { "object": {} } });
+
+        let payment = parse_failed_payment(&failed.to_string(), Utc::now()).unwrap();
+        assert_eq!(Some("sub_1".to_string()), payment.stripe_subscription_id);
+        assert_eq!(Some("joe@example.com".to_string()), payment.customer_email);
+        let payment = parse_failed_payment(&moved.to_string(), Utc::now()).unwrap();
+        assert_eq!(Some("sub_2".to_string()), payment.stripe_subscription_id);
+        assert_eq!(None, payment.customer_email);
+        assert!(parse_failed_payment(&created.to_string(), Utc::now()).is_none());
+        assert!(parse_failed_payment("not json", Utc::now()).is_none());
+    }
+}
@@ -14,7 +14,7 @@ pub struct SubscriptionHistoryEvent {
     pub subscription: serde_json::Value,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 pub enum HistoryEventType {
     Created,
     ChangedPaymentMethod,
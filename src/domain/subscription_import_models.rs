@@ -0,0 +1,287 @@
+use chrono::{Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::subscription_models::{BillingSource, NewSubscription, SubscriptionType};
+use crate::domain::valid_address::ValidPostalAddress;
+use crate::domain::valid_email::ValidEmail;
+use crate::domain::valid_name::ValidName;
+use crate::util::standardize_email;
+
+/// Columns every import file must have; `address_line_2` and `billing_source` are optional.
+pub const REQUIRED_IMPORT_COLUMNS: [&str; 7] = [
+    "name",
+    "email_address",
+    "address_line_1",
+    "city",
+    "state",
+    "postal_code",
+    "subscription_type",
+];
+
+/// One line of an import file as written in the spreadsheet.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SubscriptionImportRow {
+    pub name: String,
+    pub email_address: String,
+    pub address_line_1: String,
+    #[serde(default)]
+    pub address_line_2: Option<String>,
+    pub city: String,
+    pub state: String,
+    pub postal_code: String,
+    pub subscription_type: String,
+    #[serde(default)]
+    pub billing_source: Option<String>,
+}
+
+/// A row that passed validation and can be stored.
+#[derive(Debug, Clone)]
+pub struct ValidatedImportRow {
+    pub name: ValidName,
+    pub email_address: ValidEmail,
+    pub address: ValidPostalAddress,
+    pub subscription_type: SubscriptionType,
+    pub billing_source: BillingSource,
+}
+
+impl SubscriptionImportRow {
+    /// Validates every field and reports all problems at once. Rows without a
+    /// `billing_source` use `default_billing_source`.
+    pub fn validate(
+        self,
+        default_billing_source: BillingSource,
+    ) -> Result<ValidatedImportRow, Vec<String>> {
+        let mut errors = Vec::new();
+
+        let name = ValidName::parse(self.name.trim().to_string()).map_err(|e| errors.push(e));
+        let email_address = ValidEmail::parse(standardize_email(self.email_address.trim()))
+            .map_err(|e| errors.push(e));
+        let address = ValidPostalAddress::parse(
+            self.address_line_1,
+            self.address_line_2,
+            self.city,
+            self.state,
+            self.postal_code,
+        )
+        .map_err(|e| errors.extend(e));
+        let subscription_type = match self.subscription_type.trim().to_lowercase().as_str() {
+            "paper" => Ok(SubscriptionType::Paper),
+            "digital" => Ok(SubscriptionType::Digital),
+            other => {
+                errors.push(format!("{} is not a valid subscription type.", other));
+                Err(())
+            }
+        };
+        let billing_source = match self
+            .billing_source
+            .as_deref()
+            .map(|s| s.trim().to_lowercase())
+            .as_deref()
+        {
+            None | Some("") => Ok(default_billing_source),
+            Some("complimentary") => Ok(BillingSource::Complimentary),
+            Some("external") => Ok(BillingSource::External),
+            Some(other) => {
+                errors.push(format!(
+                    "{} is not a valid billing source; use Complimentary or External.",
+                    other
+                ));
+                Err(())
+            }
+        };
+
+        match (
+            name,
+            email_address,
+            address,
+            subscription_type,
+            billing_source,
+        ) {
+            (
+                Ok(name),
+                Ok(email_address),
+                Ok(address),
+                Ok(subscription_type),
+                Ok(billing_source),
+            ) if errors.is_empty() => Ok(ValidatedImportRow {
+                name,
+                email_address,
+                address,
+                subscription_type,
+                billing_source,
+            }),
+            _ => Err(errors),
+        }
+    }
+}
+
+impl ValidatedImportRow {
+    pub fn to_new_subscription(&self, subscriber_id: Uuid) -> NewSubscription {
+        let now = Utc::now();
+        NewSubscription {
+            subscriber_id: subscriber_id.to_string(),
+            subscription_name: self.name.clone(),
+            subscription_mailing_address_line_1: self.address.line_1.clone(),
+            subscription_mailing_address_line_2: self.address.line_2.clone(),
+            subscription_city: self.address.city.clone(),
+            subscription_state: self.address.state.clone(),
+            subscription_postal_code: self.address.postal_code.clone(),
+            subscription_email_address: self.email_address.clone(),
+            subscription_creation_date: now,
+            subscription_anniversary_day: now.day(),
+            subscription_anniversary_month: now.month(),
+            active: true,
+            subscription_type: self.subscription_type.clone(),
+            publication_id: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportRowStatus {
+    Created,
+    /// Dry run only: the row is valid and would be created.
+    WouldCreate,
+    /// The email address already has an active subscription of this type.
+    Skipped,
+    Invalid,
+    Failed,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ImportRowResult {
+    /// Line in the file, counting the header as line 1.
+    pub line: u64,
+    pub email_address: String,
+    pub status: ImportRowStatus,
+    pub subscription_id: Option<Uuid>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub created: usize,
+    pub skipped: usize,
+    pub invalid: usize,
+    pub failed: usize,
+    pub rows: Vec<ImportRowResult>,
+}
+
+impl ImportReport {
+    pub fn new(dry_run: bool, rows: Vec<ImportRowResult>) -> Self {
+        let count = |status: ImportRowStatus| rows.iter().filter(|r| r.status == status).count();
+        Self {
+            dry_run,
+            created: count(ImportRowStatus::Created) + count(ImportRowStatus::WouldCreate),
+            skipped: count(ImportRowStatus::Skipped),
+            invalid: count(ImportRowStatus::Invalid),
+            failed: count(ImportRowStatus::Failed),
+            rows,
+        }
+    }
+}
+
+/// A line number and either the row or why it could not be read.
+pub type ParsedImportRow = (u64, Result<SubscriptionImportRow, String>);
+
+/// Reads an import file. Fails only if the header is unusable; a malformed line
+/// becomes an `Err` entry carrying its line number.
+pub fn parse_import_csv(data: &[u8]) -> Result<Vec<ParsedImportRow>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::Headers)
+        .flexible(true)
+        .from_reader(data);
+
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("Could not read the header line: {}", e))?
+        .clone();
+    let missing: Vec<&str> = REQUIRED_IMPORT_COLUMNS
+        .iter()
+        .copied()
+        .filter(|column| !headers.iter().any(|header| header == *column))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Missing columns: {}", missing.join(", ")));
+    }
+
+    let mut rows = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let fallback_line = index as u64 + 2;
+        match record {
+            Ok(record) => {
+                let line = record.position().map_or(fallback_line, |p| p.line());
+                let row = record
+                    .deserialize::<SubscriptionImportRow>(Some(&headers))
+                    .map_err(|e| e.to_string());
+                rows.push((line, row));
+            }
+            Err(e) => {
+                let line = e.position().map_or(fallback_line, |p| p.line());
+                rows.push((line, Err(e.to_string())));
+            }
+        }
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_err, assert_ok};
+
+    use crate::domain::subscription_import_models::{parse_import_csv, SubscriptionImportRow};
+    use crate::domain::subscription_models::BillingSource;
+
+    fn row() -> SubscriptionImportRow {
+        SubscriptionImportRow {
+            name: "Jane Reader".to_string(),
+            email_address: "Jane@Example.com".to_string(),
+            address_line_1: "1 Main St".to_string(),
+            address_line_2: None,
+            city: "Springfield".to_string(),
+            state: "IL".to_string(),
+            postal_code: "62701".to_string(),
+            subscription_type: "paper".to_string(),
+            billing_source: None,
+        }
+    }
+
+    #[test]
+    fn a_valid_row_uses_the_default_billing_source() {
+        let validated = row().validate(BillingSource::External).unwrap();
+        assert_eq!(BillingSource::External, validated.billing_source);
+        assert_eq!("jane@example.com", validated.email_address.as_ref());
+    }
+
+    #[test]
+    fn all_row_errors_are_reported() {
+        let errors = SubscriptionImportRow {
+            email_address: "not an email".to_string(),
+            postal_code: "1".to_string(),
+            subscription_type: "carrier pigeon".to_string(),
+            billing_source: Some("Stripe".to_string()),
+            ..row()
+        }
+        .validate(BillingSource::Complimentary)
+        .unwrap_err();
+        assert_eq!(4, errors.len());
+    }
+
+    #[test]
+    fn the_header_must_name_the_required_columns() {
+        assert_err!(parse_import_csv(
+            b"name,email_address\nJane,jane@example.com\n"
+        ));
+
+        let rows = parse_import_csv(
+            b"name,email_address,address_line_1,city,state,postal_code,subscription_type\n\
+              Jane,jane@example.com,1 Main St,Springfield,IL,62701,Paper\n",
+        )
+        .unwrap();
+        assert_eq!(1, rows.len());
+        assert_eq!(2, rows[0].0);
+        assert_ok!(&rows[0].1);
+    }
+}
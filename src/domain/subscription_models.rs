@@ -1,3 +1,5 @@
+use std::str::FromStr;
+
 use chrono::{DateTime, Datelike, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -16,9 +18,10 @@ pub struct OverTheWireCreateSubscription {
     pub subscription_postal_code: String,
     pub subscription_email_address: String,
     pub subscription_type: SubscriptionType,
+    pub publication_id: Option<Uuid>,
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub struct OverTheWireSubscription {
     pub id: Uuid,
     pub subscriber_id: Uuid,
@@ -36,7 +39,12 @@ pub struct OverTheWireSubscription {
     pub subscription_renewal_date: String,
     pub active: bool,
     pub subscription_type: SubscriptionType,
+    /// Empty unless the subscription is billed through Stripe.
     pub stripe_subscription_id: String,
+    #[serde(default)]
+    pub billing_source: BillingSource,
+    /// What the subscription is for; older subscriptions may not be linked yet.
+    pub publication_id: Option<Uuid>,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -54,9 +62,10 @@ pub struct NewSubscription {
     pub subscription_anniversary_month: u32,
     pub active: bool,
     pub subscription_type: SubscriptionType,
+    pub publication_id: Option<Uuid>,
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub enum SubscriptionType {
     Digital,
     Paper,
@@ -71,6 +80,42 @@ impl SubscriptionType {
     }
 }
 
+/// Who collects payment for a subscription. Only `Stripe` subscriptions are
+/// cancelled or managed through Stripe.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BillingSource {
+    #[default]
+    Stripe,
+    Complimentary,
+    External,
+}
+
+impl BillingSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BillingSource::Stripe => "Stripe",
+            BillingSource::Complimentary => "Complimentary",
+            BillingSource::External => "External",
+        }
+    }
+}
+
+impl FromStr for BillingSource {
+    type Err = ();
+
+    fn from_str(val: &str) -> Result<BillingSource, ()> {
+        match val {
+            "Stripe" => Ok(BillingSource::Stripe),
+            "Complimentary" => Ok(BillingSource::Complimentary),
+            "External" => Ok(BillingSource::External),
+            _ => {
+                tracing::error!("Could not map string: {} to the enum BillingSource", val);
+                Err(())
+            }
+        }
+    }
+}
+
 impl OverTheWireCreateSubscription {
     pub fn to_json(&self) -> String {
         serde_json::to_string(self).expect("Was not able to serialize.")
@@ -113,14 +158,20 @@ impl TryFrom<OverTheWireCreateSubscription> for NewSubscription {
             subscription_anniversary_day,
             subscription_anniversary_month,
             active: true,
+            publication_id: subscription.publication_id,
         })
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
+
+    use claims::assert_err;
+
     use crate::domain::subscription_models::{
-        NewSubscription, OverTheWireCreateSubscription, OverTheWireSubscription, SubscriptionType,
+        BillingSource, NewSubscription, OverTheWireCreateSubscription, OverTheWireSubscription,
+        SubscriptionType,
     };
     use crate::domain::valid_email::ValidEmail;
     use crate::domain::valid_name::ValidName;
@@ -132,6 +183,18 @@ mod tests {
         assert_eq!("Paper", SubscriptionType::Paper.as_str());
     }
 
+    #[test]
+    fn billing_source_round_trips_through_strings() {
+        for source in [
+            BillingSource::Stripe,
+            BillingSource::Complimentary,
+            BillingSource::External,
+        ] {
+            assert_eq!(source, BillingSource::from_str(source.as_str()).unwrap());
+        }
+        assert_err!(BillingSource::from_str("stripe"));
+    }
+
     #[test]
     fn over_the_wire_create_subscription_to_json_works() {
         let over_the_wire_create_subscription = OverTheWireCreateSubscription {
@@ -144,6 +207,7 @@ mod tests {
             subscription_postal_code: "".to_string(),
             subscription_email_address: "".to_string(),
             subscription_type: SubscriptionType::Digital,
+            publication_id: None,
         };
         let _json = over_the_wire_create_subscription.to_json();
     }
@@ -164,6 +228,7 @@ mod tests {
             subscription_anniversary_month: 0,
             active: false,
             subscription_type: SubscriptionType::Paper,
+            publication_id: None,
         };
         let _json = new_subscription.to_json();
     }
@@ -188,6 +253,8 @@ mod tests {
             active: false,
             subscription_type: SubscriptionType::Digital,
             stripe_subscription_id: "".to_string(),
+            billing_source: BillingSource::Stripe,
+            publication_id: None,
         };
         let _json = over_the_wire_subscription.to_json();
     }
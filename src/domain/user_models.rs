@@ -7,6 +7,15 @@ pub struct User {
     pub email_address: String,
     pub password: String,
     pub user_group: UserGroup,
+    pub disabled: bool,
+    pub password_reset_required: bool,
+}
+
+/// The account flags every authenticated request is checked against.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct UserAccess {
+    pub disabled: bool,
+    pub password_reset_required: bool,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -14,6 +23,12 @@ pub struct OverTheWireUser {
     pub user_id: Uuid,
     pub email_address: String,
     pub user_group: String,
+    #[serde(default)]
+    pub disabled: bool,
+    /// Set when an admin forces a password reset. Deliberately not named after the
+    /// password so the listing never looks like it carries credentials.
+    #[serde(default)]
+    pub reset_required: bool,
 }
 
 impl From<User> for OverTheWireUser {
@@ -22,6 +37,8 @@ impl From<User> for OverTheWireUser {
             user_id: user.user_id,
             email_address: user.email_address,
             user_group: user.user_group.as_str().to_string(),
+            disabled: user.disabled,
+            reset_required: user.password_reset_required,
         }
     }
 }
@@ -61,6 +78,8 @@ pub struct ForgotPassword {
 pub enum UserGroup {
     USER,
     ADMIN,
+    /// Staff who print and mail the paper edition.
+    FULFILLMENT,
 }
 
 impl UserGroup {
@@ -68,6 +87,7 @@ impl UserGroup {
         match self {
             UserGroup::USER => "USER",
             UserGroup::ADMIN => "ADMIN",
+            UserGroup::FULFILLMENT => "FULFILLMENT",
         }
     }
 }
@@ -111,6 +131,10 @@ pub fn from_str_to_user_group(val: String) -> UserGroup {
         return UserGroup::ADMIN;
     }
 
+    if val.eq("FULFILLMENT") {
+        return UserGroup::FULFILLMENT;
+    }
+
     tracing::error!("Could not map string: {} to the enum UserGroup", val);
     UserGroup::USER
 }
@@ -123,9 +147,11 @@ mod tests {
     fn user_group_as_str() {
         let user = UserGroup::USER.as_str();
         let admin = UserGroup::ADMIN.as_str();
+        let fulfillment = UserGroup::FULFILLMENT.as_str();
 
         assert_eq!("USER", user);
         assert_eq!("ADMIN", admin);
+        assert_eq!("FULFILLMENT", fulfillment);
     }
 
     #[test]
@@ -138,6 +164,10 @@ mod tests {
             UserGroup::ADMIN,
             from_str_to_user_group(String::from(UserGroup::ADMIN.as_str()))
         );
+        assert_eq!(
+            UserGroup::FULFILLMENT,
+            from_str_to_user_group(String::from(UserGroup::FULFILLMENT.as_str()))
+        );
         assert_eq!(
             UserGroup::USER,
             from_str_to_user_group(String::from("not a user group"))
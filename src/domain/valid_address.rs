@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+const US_STATE_CODES: [&str; 54] = [
+    "AL", "AK", "AZ", "AR", "CA", "CO", "CT", "DE", "DC", "FL", "GA", "HI", "ID", "IL", "IN", "IA",
+    "KS", "KY", "LA", "ME", "MD", "MA", "MI", "MN", "MS", "MO", "MT", "NE", "NV", "NH", "NJ", "NM",
+    "NY", "NC", "ND", "OH", "OK", "OR", "PA", "RI", "SC", "SD", "TN", "TX", "UT", "VT", "VA", "WA",
+    "WV", "WI", "WY", "PR", "VI", "GU",
+];
+
+/// A US mailing address that a paper issue can be sent to.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ValidPostalAddress {
+    pub line_1: String,
+    pub line_2: Option<String>,
+    pub city: String,
+    pub state: String,
+    pub postal_code: String,
+}
+
+impl ValidPostalAddress {
+    /// Collects every problem with the address rather than stopping at the first one.
+    pub fn parse(
+        line_1: String,
+        line_2: Option<String>,
+        city: String,
+        state: String,
+        postal_code: String,
+    ) -> Result<ValidPostalAddress, Vec<String>> {
+        let mut errors = Vec::new();
+        let line_1 = line_1.trim().to_string();
+        let line_2 = line_2
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty());
+        let city = city.trim().to_string();
+        let state = state.trim().to_uppercase();
+        let postal_code = postal_code.trim().to_string();
+
+        if !is_valid_line(&line_1) {
+            errors.push(format!("{} is not a valid address line.", line_1));
+        }
+        if let Some(line) = line_2.as_ref().filter(|line| !is_valid_line(line)) {
+            errors.push(format!("{} is not a valid address line.", line));
+        }
+        if !is_valid_line(&city) {
+            errors.push(format!("{} is not a valid city.", city));
+        }
+        if !US_STATE_CODES.contains(&state.as_str()) {
+            errors.push(format!("{} is not a valid state code.", state));
+        }
+        if !is_valid_zip_code(&postal_code) {
+            errors.push(format!("{} is not a valid ZIP code.", postal_code));
+        }
+
+        if errors.is_empty() {
+            Ok(Self {
+                line_1,
+                line_2,
+                city,
+                state,
+                postal_code,
+            })
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+fn is_valid_line(line: &str) -> bool {
+    let forbidden_characters = ['<', '>', '\\', '{', '}', '"'];
+    !line.is_empty()
+        && line.graphemes(true).count() <= 256
+        && !line.chars().any(|c| forbidden_characters.contains(&c))
+}
+
+/// `12345` or `12345-6789`.
+fn is_valid_zip_code(postal_code: &str) -> bool {
+    let (zip, plus_four) = match postal_code.split_once('-') {
+        Some((zip, plus_four)) => (zip, Some(plus_four)),
+        None => (postal_code, None),
+    };
+    let all_digits = |s: &str, len: usize| s.len() == len && s.chars().all(|c| c.is_ascii_digit());
+
+    all_digits(zip, 5) && plus_four.is_none_or(|plus_four| all_digits(plus_four, 4))
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_err, assert_ok};
+
+    use crate::domain::valid_address::ValidPostalAddress;
+
+    fn parse(
+        line_1: &str,
+        city: &str,
+        state: &str,
+        postal_code: &str,
+    ) -> Result<ValidPostalAddress, Vec<String>> {
+        ValidPostalAddress::parse(
+            line_1.to_string(),
+            None,
+            city.to_string(),
+            state.to_string(),
+            postal_code.to_string(),
+        )
+    }
+
+    #[test]
+    fn a_normal_address_is_valid_and_normalised() {
+        let address = parse(" 1 Main St ", "Springfield", "il", "62701-1234").unwrap();
+        assert_eq!("1 Main St", address.line_1);
+        assert_eq!("IL", address.state);
+        assert_eq!(None, address.line_2);
+    }
+
+    #[test]
+    fn zip_codes_must_be_five_or_nine_digits() {
+        assert_ok!(parse("1 Main St", "Springfield", "IL", "62701"));
+        assert_err!(parse("1 Main St", "Springfield", "IL", "6270"));
+        assert_err!(parse("1 Main St", "Springfield", "IL", "62701-12"));
+        assert_err!(parse("1 Main St", "Springfield", "IL", "ABCDE"));
+    }
+
+    #[test]
+    fn unknown_states_are_rejected() {
+        assert_err!(parse("1 Main St", "Springfield", "XX", "62701"));
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let errors = parse("", "", "XX", "1").unwrap_err();
+        assert_eq!(4, errors.len());
+    }
+}
@@ -0,0 +1,351 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::domain::email_outbox_models::retry_delay;
+use crate::domain::subscription_history_models::HistoryEventType;
+use crate::domain::subscription_models::OverTheWireSubscription;
+use crate::util::generate_random_token;
+
+pub const MAX_ENDPOINT_DESCRIPTION_LENGTH: usize = 200;
+/// After this many failed attempts a delivery is left `Dead` until it is replayed.
+pub const MAX_WEBHOOK_ATTEMPTS: i32 = 8;
+/// How long a worker may hold a claimed delivery before another may take it.
+pub const WEBHOOK_CLAIM_TIMEOUT_SECONDS: i64 = 120;
+/// The header carrying `t=<unix seconds>,v1=<hex HMAC-SHA256>`.
+pub const SIGNATURE_HEADER: &str = "Webhook-Signature";
+/// The header carrying the event id, the same for every delivery of an event.
+pub const EVENT_ID_HEADER: &str = "Webhook-Id";
+
+/// What an endpoint can subscribe to.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubscriptionEventType {
+    #[serde(rename = "subscription.created")]
+    Created,
+    #[serde(rename = "subscription.cancelled")]
+    Cancelled,
+    #[serde(rename = "subscription.updated")]
+    Updated,
+    /// Subscriptions cannot be paused yet, so this is never sent.
+    #[serde(rename = "subscription.paused")]
+    Paused,
+}
+
+impl SubscriptionEventType {
+    pub const ALL: [SubscriptionEventType; 4] = [
+        SubscriptionEventType::Created,
+        SubscriptionEventType::Cancelled,
+        SubscriptionEventType::Updated,
+        SubscriptionEventType::Paused,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubscriptionEventType::Created => "subscription.created",
+            SubscriptionEventType::Cancelled => "subscription.cancelled",
+            SubscriptionEventType::Updated => "subscription.updated",
+            SubscriptionEventType::Paused => "subscription.paused",
+        }
+    }
+
+    /// The event announcing a change recorded in the subscription history.
+    pub fn from_history(event_type: &HistoryEventType) -> Self {
+        match event_type {
+            HistoryEventType::Created => SubscriptionEventType::Created,
+            HistoryEventType::Cancelled => SubscriptionEventType::Cancelled,
+            HistoryEventType::ChangedPaymentMethod
+            | HistoryEventType::UpdatedSubscriptionInformation => SubscriptionEventType::Updated,
+        }
+    }
+}
+
+impl FromStr for SubscriptionEventType {
+    type Err = ();
+
+    fn from_str(val: &str) -> Result<SubscriptionEventType, ()> {
+        SubscriptionEventType::ALL
+            .into_iter()
+            .find(|event_type| event_type.as_str() == val)
+            .ok_or_else(|| {
+                tracing::error!(
+                    "Could not map string: {} to the enum SubscriptionEventType",
+                    val
+                );
+            })
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct WebhookEndpoint {
+    pub id: Uuid,
+    pub url: String,
+    pub description: String,
+    /// Signs every delivery. Generated by the server.
+    pub secret: String,
+    pub event_types: Vec<SubscriptionEventType>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl WebhookEndpoint {
+    pub fn new(request: &WebhookEndpointRequest) -> Self {
+        let now = Utc::now();
+        WebhookEndpoint {
+            id: Uuid::new_v4(),
+            url: request.url.trim().to_string(),
+            description: request.description.trim().to_string(),
+            secret: format!("whsec_{}", generate_random_token()),
+            event_types: request.event_types.clone(),
+            enabled: request.enabled,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// Body of the create and update webhook endpoint endpoints.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct WebhookEndpointRequest {
+    pub url: String,
+    #[serde(default)]
+    pub description: String,
+    pub event_types: Vec<SubscriptionEventType>,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl WebhookEndpointRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        let url = self.url.trim();
+        match reqwest::Url::parse(url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+            _ => return Err(format!("{} is not an http(s) URL", url)),
+        }
+        if self.description.trim().chars().count() > MAX_ENDPOINT_DESCRIPTION_LENGTH {
+            return Err(format!(
+                "A description must have at most {} characters",
+                MAX_ENDPOINT_DESCRIPTION_LENGTH
+            ));
+        }
+        if self.event_types.is_empty() {
+            return Err("An endpoint must subscribe to at least one event type".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Was not able to serialize.")
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WebhookDeliveryStatus {
+    Pending,
+    Delivering,
+    Delivered,
+    Dead,
+}
+
+impl WebhookDeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookDeliveryStatus::Pending => "Pending",
+            WebhookDeliveryStatus::Delivering => "Delivering",
+            WebhookDeliveryStatus::Delivered => "Delivered",
+            WebhookDeliveryStatus::Dead => "Dead",
+        }
+    }
+}
+
+impl FromStr for WebhookDeliveryStatus {
+    type Err = ();
+
+    fn from_str(val: &str) -> Result<WebhookDeliveryStatus, ()> {
+        match val {
+            "Pending" => Ok(WebhookDeliveryStatus::Pending),
+            "Delivering" => Ok(WebhookDeliveryStatus::Delivering),
+            "Delivered" => Ok(WebhookDeliveryStatus::Delivered),
+            "Dead" => Ok(WebhookDeliveryStatus::Dead),
+            _ => {
+                tracing::error!(
+                    "Could not map string: {} to the enum WebhookDeliveryStatus",
+                    val
+                );
+                Err(())
+            }
+        }
+    }
+}
+
+/// One event sent, or to be sent, to one endpoint.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub endpoint_id: Uuid,
+    pub event_id: Uuid,
+    pub event_type: SubscriptionEventType,
+    /// The exact JSON body posted.
+    pub payload: Value,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    pub last_response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl WebhookDelivery {
+    /// Where a failed attempt leaves the delivery: retried later, or dead
+    /// once it has used up its attempts.
+    pub fn after_failure(&self, now: DateTime<Utc>) -> (WebhookDeliveryStatus, DateTime<Utc>) {
+        if self.attempts >= MAX_WEBHOOK_ATTEMPTS {
+            (WebhookDeliveryStatus::Dead, now)
+        } else {
+            (
+                WebhookDeliveryStatus::Pending,
+                now + retry_delay(self.attempts),
+            )
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WebhookDeliveryQuery {
+    pub endpoint_id: Option<Uuid>,
+    pub status: Option<WebhookDeliveryStatus>,
+}
+
+/// The body posted for an event. `id` stays the same across retries and
+/// replays, so receivers can ignore repeats.
+pub fn subscription_event_payload(
+    event_id: Uuid,
+    event_type: SubscriptionEventType,
+    occurred_at: DateTime<Utc>,
+    subscription: &OverTheWireSubscription,
+) -> Value {
+    json!({
+        "id": event_id,
+        "type": event_type,
+        "created_at": occurred_at,
+        "data": { "subscription": subscription },
+    })
+}
+
+/// The `Webhook-Signature` value: an HMAC-SHA256 of `<timestamp>.<body>`
+/// keyed with the endpoint's secret. Receivers recompute it and reject old
+/// timestamps, so a captured delivery cannot be replayed against them later.
+pub fn webhook_signature(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!("t={},v1={:x}", timestamp, mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use claims::{assert_err, assert_ok};
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use crate::domain::webhook_endpoint_models::{
+        webhook_signature, SubscriptionEventType, WebhookDelivery, WebhookDeliveryStatus,
+        WebhookEndpointRequest, MAX_WEBHOOK_ATTEMPTS,
+    };
+
+    fn request(url: &str, event_types: Vec<SubscriptionEventType>) -> WebhookEndpointRequest {
+        WebhookEndpointRequest {
+            url: url.to_string(),
+            description: "CRM".to_string(),
+            event_types,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn endpoints_need_an_http_url_and_an_event_type() {
+        let created = vec![SubscriptionEventType::Created];
+
+        assert_ok!(request("https://crm.example.com/hooks", created.clone()).validate());
+        assert_err!(request("ftp://crm.example.com/hooks", created.clone()).validate());
+        assert_err!(request("crm.example.com", created).validate());
+        assert_err!(request("https://crm.example.com/hooks", vec![]).validate());
+    }
+
+    #[test]
+    fn event_types_use_dotted_names() {
+        assert_eq!(
+            json!(["subscription.created", "subscription.paused"]),
+            json!([
+                SubscriptionEventType::Created,
+                SubscriptionEventType::Paused
+            ])
+        );
+        for event_type in SubscriptionEventType::ALL {
+            assert_eq!(
+                Ok(event_type),
+                event_type.as_str().parse::<SubscriptionEventType>()
+            );
+        }
+    }
+
+    #[test]
+    fn signatures_cover_the_timestamp_and_the_body() {
+        let signature = webhook_signature("whsec_test", 1_700_000_000, r#"{"id":1}"#);
+
+        assert!(signature.starts_with("t=1700000000,v1="));
+        assert_eq!(64, signature.split("v1=").nth(1).unwrap().len());
+        assert_eq!(
+            signature,
+            webhook_signature("whsec_test", 1_700_000_000, r#"{"id":1}"#)
+        );
+        assert_ne!(
+            signature,
+            webhook_signature("whsec_test", 1_700_000_001, r#"{"id":1}"#)
+        );
+        assert_ne!(
+            signature,
+            webhook_signature("whsec_other", 1_700_000_000, r#"{"id":1}"#)
+        );
+    }
+
+    #[test]
+    fn deliveries_die_after_the_last_attempt() {
+        let now = Utc::now();
+        let mut delivery = WebhookDelivery {
+            id: Uuid::new_v4(),
+            endpoint_id: Uuid::new_v4(),
+            event_id: Uuid::new_v4(),
+            event_type: SubscriptionEventType::Created,
+            payload: json!({}),
+            status: WebhookDeliveryStatus::Delivering,
+            attempts: 1,
+            last_response_status: None,
+            last_error: None,
+            next_attempt_at: now,
+            created_at: now,
+            delivered_at: None,
+        };
+
+        assert_eq!(
+            (WebhookDeliveryStatus::Pending, now + Duration::seconds(30)),
+            delivery.after_failure(now)
+        );
+        delivery.attempts = MAX_WEBHOOK_ATTEMPTS;
+        assert_eq!(
+            (WebhookDeliveryStatus::Dead, now),
+            delivery.after_failure(now)
+        );
+    }
+}
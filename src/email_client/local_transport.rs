@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::email_client::smtp_transport::mime_message;
+use crate::email_client::{EmailMessage, EmailSender, EmailTransport};
+
+/// Writes each email to a directory as an `.eml` file that any mail client can
+/// open. For local development without a provider account.
+pub struct FileTransport {
+    directory: PathBuf,
+    sender: EmailSender,
+}
+
+impl FileTransport {
+    pub fn new(directory: impl Into<PathBuf>, sender: EmailSender) -> Self {
+        Self {
+            directory: directory.into(),
+            sender,
+        }
+    }
+}
+
+#[async_trait]
+impl EmailTransport for FileTransport {
+    async fn send(&self, message: &EmailMessage<'_>) -> Result<(), anyhow::Error> {
+        let mime_message = mime_message(&self.sender, message)?;
+        tokio::fs::create_dir_all(&self.directory).await?;
+        let path = self.directory.join(format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%dT%H%M%S%.3f"),
+            Uuid::new_v4()
+        ));
+        tokio::fs::write(&path, mime_message.formatted()).await?;
+        tracing::info!("Wrote email \"{}\" to {}", message.subject, path.display());
+        Ok(())
+    }
+}
+
+/// Logs each email instead of sending it.
+pub struct ConsoleTransport {
+    sender: EmailSender,
+}
+
+impl ConsoleTransport {
+    pub fn new(sender: EmailSender) -> Self {
+        Self { sender }
+    }
+}
+
+#[async_trait]
+impl EmailTransport for ConsoleTransport {
+    async fn send(&self, message: &EmailMessage<'_>) -> Result<(), anyhow::Error> {
+        let recipients: Vec<&str> = message.recipients.iter().map(|r| r.as_ref()).collect();
+        tracing::info!(
+            "Email from {} to {}\nSubject: {}\n\n{}",
+            self.sender.email,
+            recipients.join(", "),
+            message.subject,
+            message.text_content
+        );
+        Ok(())
+    }
+}
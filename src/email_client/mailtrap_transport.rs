@@ -0,0 +1,78 @@
+use async_trait::async_trait;
+use mailtrap_rs::{
+    client::MailtrapClient,
+    types::email::{Body, EmailAddress},
+};
+use secrecy::ExposeSecret;
+
+use crate::configuration::EmailClientSettings;
+use crate::email_client::{EmailMessage, EmailTransport};
+
+pub struct MailtrapTransport {
+    mailtrap_client: MailtrapClient,
+    from_email: EmailAddress,
+    reply_to_email: EmailAddress,
+}
+
+impl MailtrapTransport {
+    pub fn new(email_settings: &EmailClientSettings) -> Self {
+        Self {
+            mailtrap_client: MailtrapClient::new(
+                &email_settings.base_url,
+                email_settings.api_key.expose_secret().to_string(),
+                email_settings.timeout(),
+            )
+            .expect("Invalid Mailtrap client configuration"),
+            from_email: EmailAddress::new(
+                email_settings.sender_email.clone(),
+                Some(email_settings.sender_name.clone()),
+            )
+            .expect("Invalid from email address"),
+            reply_to_email: EmailAddress::new(
+                email_settings.reply_to_email.clone(),
+                Some(email_settings.reply_to_name.clone()),
+            )
+            .expect("Invalid reply to email address"),
+        }
+    }
+}
+
+#[async_trait]
+impl EmailTransport for MailtrapTransport {
+    async fn send(&self, message: &EmailMessage<'_>) -> Result<(), anyhow::Error> {
+        let to_addresses: Vec<EmailAddress> = message
+            .recipients
+            .iter()
+            .map(|r| {
+                EmailAddress::new(r.to_string(), None)
+                    .expect("ValidEmail should produce valid email")
+            })
+            .collect();
+
+        let mailtrap_message = mailtrap_rs::types::email::Message::new(
+            self.from_email.clone(),
+            message.subject.to_string(),
+            Body::TextAndHtml {
+                text: message.text_content.to_string(),
+                html: message.html_content.to_string(),
+            },
+        )
+        .reply_to(self.reply_to_email.clone());
+
+        let mailtrap_message = to_addresses
+            .into_iter()
+            .fold(mailtrap_message, |msg, addr| msg.to(addr));
+        let mailtrap_message = message
+            .headers()
+            .into_iter()
+            .fold(mailtrap_message, |msg, (name, value)| {
+                msg.header(name, value)
+            });
+
+        self.mailtrap_client
+            .send_email(mailtrap_message)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,436 @@
+pub mod local_transport;
+pub mod mailtrap_transport;
+pub mod sendgrid_transport;
+pub mod smtp_transport;
+pub mod templates;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::configuration::{EmailClientSettings, EmailTransportKind};
+use crate::db::captured_emails_db_broker::insert_captured_email;
+use crate::db::email_suppressions_db_broker::get_suppressed_addresses;
+use crate::domain::valid_email::ValidEmail;
+use crate::email_client::local_transport::{ConsoleTransport, FileTransport};
+use crate::email_client::mailtrap_transport::MailtrapTransport;
+use crate::email_client::sendgrid_transport::SendGridTransport;
+use crate::email_client::smtp_transport::SmtpTransport;
+
+/// Who the email is from and where replies go.
+#[derive(Clone, Debug)]
+pub struct EmailSender {
+    pub email: String,
+    pub name: String,
+    pub reply_to_email: String,
+    pub reply_to_name: String,
+}
+
+impl EmailSender {
+    fn from_settings(email_settings: &EmailClientSettings) -> Self {
+        Self {
+            email: email_settings.sender_email.clone(),
+            name: email_settings.sender_name.clone(),
+            reply_to_email: email_settings.reply_to_email.clone(),
+            reply_to_name: email_settings.reply_to_name.clone(),
+        }
+    }
+}
+
+pub struct EmailMessage<'a> {
+    pub recipients: &'a [ValidEmail],
+    pub subject: &'a str,
+    pub html_content: &'a str,
+    pub text_content: &'a str,
+    /// Where mail clients can unsubscribe the recipient with one click. Only
+    /// set on email a subscriber can opt out of.
+    pub unsubscribe_url: Option<&'a str>,
+}
+
+impl EmailMessage<'_> {
+    /// Extra headers: the RFC 8058 one-click unsubscribe pair, when there is
+    /// an unsubscribe URL.
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        match self.unsubscribe_url {
+            Some(url) => vec![
+                ("List-Unsubscribe", format!("<{}>", url)),
+                (
+                    "List-Unsubscribe-Post",
+                    "List-Unsubscribe=One-Click".to_string(),
+                ),
+            ],
+            None => vec![],
+        }
+    }
+}
+
+/// Something that can deliver an email: a provider's API, an SMTP server or a
+/// local sink for development.
+#[async_trait]
+pub trait EmailTransport: Send + Sync {
+    async fn send(&self, message: &EmailMessage<'_>) -> Result<(), anyhow::Error>;
+}
+
+/// What became of an email that was handed to the transport, or skipped.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SendReport {
+    /// Recipients on the suppression list, who were left off.
+    pub suppressed: Vec<String>,
+}
+
+#[derive(Clone)]
+pub struct EmailClient {
+    transport: Arc<dyn EmailTransport>,
+    /// Where the suppression list lives. Without it every recipient is sent to.
+    suppressions: Option<PgPool>,
+    /// Where emails are captured for `GET /dev/emails`, in the local environment.
+    capture: Option<PgPool>,
+}
+
+impl EmailClient {
+    pub fn new(email_settings: EmailClientSettings) -> Self {
+        let sender = EmailSender::from_settings(&email_settings);
+        let transport: Arc<dyn EmailTransport> = match email_settings.transport {
+            EmailTransportKind::Mailtrap => Arc::new(MailtrapTransport::new(&email_settings)),
+            EmailTransportKind::SendGrid => {
+                Arc::new(SendGridTransport::new(&email_settings, sender))
+            }
+            EmailTransportKind::Smtp => Arc::new(SmtpTransport::new(&email_settings, sender)),
+            EmailTransportKind::File => Arc::new(FileTransport::new(
+                email_settings.file_directory.clone(),
+                sender,
+            )),
+            EmailTransportKind::Console => Arc::new(ConsoleTransport::new(sender)),
+        };
+        Self::with_transport(transport)
+    }
+
+    pub fn with_transport(transport: Arc<dyn EmailTransport>) -> Self {
+        Self {
+            transport,
+            suppressions: None,
+            capture: None,
+        }
+    }
+
+    /// Leaves addresses in `email_suppressions` off every email.
+    pub fn with_suppression_list(mut self, pool: PgPool) -> Self {
+        self.suppressions = Some(pool);
+        self
+    }
+
+    /// Also keeps every email in `captured_emails`. Only for development and
+    /// tests: see [`EmailClientSettings::captures_emails`].
+    pub fn with_email_capture(mut self, pool: PgPool) -> Self {
+        self.capture = Some(pool);
+        self
+    }
+
+    pub fn captures_emails(&self) -> bool {
+        self.capture.is_some()
+    }
+
+    #[tracing::instrument(
+        name = "Sending an email",
+        skip(self, recipient, subject, html_content, text_content)
+    )]
+    pub async fn send_email(
+        &self,
+        recipient: Vec<ValidEmail>,
+        subject: &str,
+        html_content: &str,
+        text_content: &str,
+    ) -> Result<SendReport, anyhow::Error> {
+        self.send(EmailMessage {
+            recipients: &recipient,
+            subject,
+            html_content,
+            text_content,
+            unsubscribe_url: None,
+        })
+        .await
+    }
+
+    /// Sends email the recipient has opted into and can opt out of, with the
+    /// one-click unsubscribe headers.
+    #[tracing::instrument(
+        name = "Sending an email with an unsubscribe link",
+        skip(self, recipient, subject, html_content, text_content, unsubscribe_url)
+    )]
+    pub async fn send_email_with_unsubscribe(
+        &self,
+        recipient: Vec<ValidEmail>,
+        subject: &str,
+        html_content: &str,
+        text_content: &str,
+        unsubscribe_url: &str,
+    ) -> Result<SendReport, anyhow::Error> {
+        self.send(EmailMessage {
+            recipients: &recipient,
+            subject,
+            html_content,
+            text_content,
+            unsubscribe_url: Some(unsubscribe_url),
+        })
+        .await
+    }
+
+    /// Sends to the recipients who are not suppressed; nothing goes out when
+    /// all of them are.
+    async fn send(&self, message: EmailMessage<'_>) -> Result<SendReport, anyhow::Error> {
+        let Some(pool) = &self.suppressions else {
+            self.deliver(&message).await?;
+            return Ok(SendReport::default());
+        };
+        let addresses: Vec<String> = message
+            .recipients
+            .iter()
+            .map(|recipient| recipient.to_string())
+            .collect();
+        let suppressed = get_suppressed_addresses(&addresses, pool).await?;
+        let (suppressed, recipients): (Vec<ValidEmail>, Vec<ValidEmail>) = message
+            .recipients
+            .iter()
+            .cloned()
+            .partition(|recipient| suppressed.contains(&recipient.as_ref().to_lowercase()));
+        let report = SendReport {
+            suppressed: suppressed
+                .iter()
+                .map(|recipient| recipient.to_string())
+                .collect(),
+        };
+        if !report.suppressed.is_empty() {
+            tracing::warn!(
+                "Not sending to {} suppressed recipient(s)",
+                report.suppressed.len()
+            );
+        }
+
+        if !recipients.is_empty() {
+            self.deliver(&EmailMessage {
+                recipients: &recipients,
+                ..message
+            })
+            .await?;
+        }
+        Ok(report)
+    }
+
+    /// Hands the email to the transport, capturing it first when capture is on.
+    /// A failed capture is logged and does not stop the email.
+    async fn deliver(&self, message: &EmailMessage<'_>) -> Result<(), anyhow::Error> {
+        if let Some(pool) = &self.capture {
+            let _ = insert_captured_email(message, pool).await;
+        }
+        self.transport.send(message).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_err, assert_ok};
+    use fake::faker::internet::en::SafeEmail;
+    use fake::faker::lorem::en::{Paragraph, Sentence};
+    use fake::{Fake, Faker};
+    use secrecy::SecretString;
+    use wiremock::matchers::{any, header_exists, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::configuration::{EmailClientSettings, EmailTransportKind};
+    use crate::domain::valid_email::ValidEmail;
+    use crate::email_client::EmailClient;
+
+    fn subject() -> String {
+        Sentence(1..2).fake()
+    }
+
+    fn content() -> String {
+        Paragraph(1..10).fake()
+    }
+
+    fn email() -> ValidEmail {
+        ValidEmail::parse(SafeEmail().fake()).unwrap()
+    }
+
+    fn email_settings(base_url: String) -> EmailClientSettings {
+        let base_url_with_slash = if base_url.ends_with('/') {
+            base_url
+        } else {
+            format!("{}/", base_url)
+        };
+        EmailClientSettings {
+            base_url: base_url_with_slash,
+            sender_email: email().to_string(),
+            sender_name: "Test".to_string(),
+            reply_to_email: email().to_string(),
+            reply_to_name: "Test".to_string(),
+            api_key: SecretString::new(Faker.fake::<String>().into_boxed_str()),
+            timeout_milliseconds: 200,
+            transport: EmailTransportKind::Mailtrap,
+            smtp: None,
+            file_directory: "target/emails".to_string(),
+            webhook_token: None,
+            capture_emails: false,
+        }
+    }
+
+    fn email_client(base_url: String) -> EmailClient {
+        EmailClient::new(email_settings(base_url))
+    }
+
+    #[tokio::test]
+    async fn send_email_fires_a_request_to_base_url() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client = email_client(mock_server.uri());
+        Mock::given(header_exists("Api-Token"))
+            .and(path("api/send"))
+            .and(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                r#"{"success":true,"message_ids":["test-id"],"errors":[]}"#,
+                "application/json",
+            ))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let outcome = email_client
+            .send_email(Vec::from([email()]), &subject(), &content(), &content())
+            .await;
+        // Assert
+        assert_ok!(outcome);
+    }
+
+    #[tokio::test]
+    async fn send_email_fails_if_the_server_returns_500() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client = email_client(mock_server.uri());
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let outcome = email_client
+            .send_email(Vec::from([email()]), &subject(), &content(), &content())
+            .await;
+        // Assert
+        assert_err!(outcome);
+    }
+
+    #[tokio::test]
+    async fn send_email_errors_if_the_server_takes_too_long() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client = email_client(mock_server.uri());
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(500).set_delay(std::time::Duration::from_secs(180)))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let outcome = email_client
+            .send_email(Vec::from([email()]), &subject(), &content(), &content())
+            .await;
+        // Assert
+        assert_err!(outcome);
+    }
+
+    #[tokio::test]
+    async fn sendgrid_sends_a_v3_request_with_both_bodies() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let mut settings = email_settings(mock_server.uri());
+        settings.transport = EmailTransportKind::SendGrid;
+        let email_client = EmailClient::new(settings);
+        Mock::given(header_exists("Authorization"))
+            .and(path("v3/mail/send"))
+            .and(method("POST"))
+            .respond_with(ResponseTemplate::new(202))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let recipient = email();
+
+        // Act
+        let outcome = email_client
+            .send_email(vec![recipient.clone()], "Subject", "<p>Html</p>", "Text")
+            .await;
+
+        // Assert
+        assert_ok!(outcome);
+        let received = mock_server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert_eq!(
+            recipient.as_ref(),
+            body["personalizations"][0]["to"][0]["email"]
+        );
+        assert_eq!("Subject", body["subject"]);
+        assert_eq!("text/plain", body["content"][0]["type"]);
+        assert_eq!("<p>Html</p>", body["content"][1]["value"]);
+    }
+
+    #[tokio::test]
+    async fn the_file_transport_writes_an_eml_file() {
+        // Arrange
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let mut settings = email_settings("http://localhost".to_string());
+        settings.transport = EmailTransportKind::File;
+        settings.file_directory = directory.to_string_lossy().to_string();
+        let email_client = EmailClient::new(settings);
+        let recipient = email();
+
+        // Act
+        let outcome = email_client
+            .send_email(
+                vec![recipient.clone()],
+                "File subject",
+                "<p>Html</p>",
+                "Text",
+            )
+            .await;
+
+        // Assert
+        assert_ok!(outcome);
+        let files: Vec<_> = std::fs::read_dir(&directory).unwrap().collect();
+        assert_eq!(1, files.len());
+        let contents = std::fs::read_to_string(files[0].as_ref().unwrap().path()).unwrap();
+        assert!(contents.contains("Subject: File subject"));
+        assert!(contents.contains(recipient.as_ref()));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn unsubscribable_email_carries_the_one_click_headers() {
+        // Arrange
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let mut settings = email_settings("http://localhost".to_string());
+        settings.transport = EmailTransportKind::File;
+        settings.file_directory = directory.to_string_lossy().to_string();
+        let email_client = EmailClient::new(settings);
+
+        // Act
+        let outcome = email_client
+            .send_email_with_unsubscribe(
+                vec![email()],
+                "Subject",
+                "<p>Html</p>",
+                "Text",
+                "https://example.com/unsubscribe/token",
+            )
+            .await;
+
+        // Assert
+        assert_ok!(outcome);
+        let files: Vec<_> = std::fs::read_dir(&directory).unwrap().collect();
+        let contents = std::fs::read_to_string(files[0].as_ref().unwrap().path()).unwrap();
+        assert!(contents.contains("List-Unsubscribe: <https://example.com/unsubscribe/token>"));
+        assert!(contents.contains("List-Unsubscribe-Post: List-Unsubscribe=One-Click"));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
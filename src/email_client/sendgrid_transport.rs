@@ -0,0 +1,131 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use secrecy::{ExposeSecret, SecretString};
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::configuration::EmailClientSettings;
+use crate::email_client::{EmailMessage, EmailSender, EmailTransport};
+
+pub const SENDGRID_SEND_PATH: &str = "/v3/mail/send";
+
+/// Sends through SendGrid's v3 mail API.
+pub struct SendGridTransport {
+    http_client: Client,
+    base_url: String,
+    api_key: SecretString,
+    sender: EmailSender,
+}
+
+impl SendGridTransport {
+    pub fn new(email_settings: &EmailClientSettings, sender: EmailSender) -> Self {
+        Self {
+            http_client: Client::builder()
+                .timeout(email_settings.timeout())
+                .build()
+                .unwrap(),
+            base_url: email_settings.base_url.trim_end_matches('/').to_string(),
+            api_key: email_settings.api_key.clone(),
+            sender,
+        }
+    }
+}
+
+#[async_trait]
+impl EmailTransport for SendGridTransport {
+    async fn send(&self, message: &EmailMessage<'_>) -> Result<(), anyhow::Error> {
+        let request = SendEmailRequest::new(&self.sender, message);
+        self.http_client
+            .post(format!("{}{}", self.base_url, SENDGRID_SEND_PATH))
+            .bearer_auth(self.api_key.expose_secret())
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SendEmailRequest {
+    pub personalizations: Vec<Personalization>,
+    pub from: SendFrom,
+    pub reply_to: SendFrom,
+    pub subject: String,
+    pub content: [EmailContent; 2],
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Personalization {
+    pub to: Vec<SendTo>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SendTo {
+    pub email: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SendFrom {
+    pub email: String,
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct EmailContent {
+    #[serde(rename(serialize = "type", deserialize = "content_type"))]
+    #[serde(alias = "content_type", alias = "type")]
+    pub content_type: String,
+    pub value: String,
+}
+
+impl SendEmailRequest {
+    /// SendGrid wants the plain text part before the HTML one.
+    pub fn new(sender: &EmailSender, message: &EmailMessage<'_>) -> Self {
+        SendEmailRequest {
+            personalizations: vec![Personalization {
+                to: message
+                    .recipients
+                    .iter()
+                    .map(|recipient| SendTo {
+                        email: recipient.to_string(),
+                        name: None,
+                    })
+                    .collect(),
+            }],
+            from: SendFrom {
+                email: sender.email.clone(),
+                name: sender.name.clone(),
+            },
+            reply_to: SendFrom {
+                email: sender.reply_to_email.clone(),
+                name: sender.reply_to_name.clone(),
+            },
+            subject: message.subject.to_string(),
+            content: [
+                EmailContent {
+                    content_type: "text/plain".to_string(),
+                    value: message.text_content.to_string(),
+                },
+                EmailContent {
+                    content_type: "text/html".to_string(),
+                    value: message.html_content.to_string(),
+                },
+            ],
+            headers: message
+                .headers()
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Was not able to serialize.")
+    }
+}
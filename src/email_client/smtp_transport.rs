@@ -0,0 +1,94 @@
+use async_trait::async_trait;
+use lettre::message::header::{HeaderName, HeaderValue};
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use secrecy::ExposeSecret;
+
+use crate::configuration::{EmailClientSettings, SmtpSecurity};
+use crate::email_client::{EmailMessage, EmailSender, EmailTransport};
+
+pub struct SmtpTransport {
+    mailer: AsyncSmtpTransport<Tokio1Executor>,
+    sender: EmailSender,
+}
+
+impl SmtpTransport {
+    pub fn new(email_settings: &EmailClientSettings, sender: EmailSender) -> Self {
+        let smtp = email_settings
+            .smtp
+            .as_ref()
+            .expect("The smtp email transport needs email_client.smtp settings");
+        let builder = match smtp.security {
+            SmtpSecurity::Tls => {
+                AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.host).expect("Invalid SMTP host")
+            }
+            SmtpSecurity::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host)
+                    .expect("Invalid SMTP host")
+            }
+            SmtpSecurity::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.host)
+            }
+        };
+        let builder = builder
+            .port(smtp.port)
+            .timeout(Some(email_settings.timeout()));
+        let builder = match (&smtp.username, &smtp.password) {
+            (Some(username), Some(password)) => builder.credentials(Credentials::new(
+                username.clone(),
+                password.expose_secret().to_string(),
+            )),
+            _ => builder,
+        };
+        Self {
+            mailer: builder.build(),
+            sender,
+        }
+    }
+}
+
+#[async_trait]
+impl EmailTransport for SmtpTransport {
+    async fn send(&self, message: &EmailMessage<'_>) -> Result<(), anyhow::Error> {
+        let mime_message = mime_message(&self.sender, message)?;
+        self.mailer.send(mime_message).await?;
+        Ok(())
+    }
+}
+
+/// The email as a multipart/alternative MIME message with both bodies.
+pub fn mime_message(
+    sender: &EmailSender,
+    message: &EmailMessage<'_>,
+) -> Result<Message, anyhow::Error> {
+    let builder = Message::builder()
+        .from(Mailbox::new(
+            Some(sender.name.clone()),
+            sender.email.parse()?,
+        ))
+        .reply_to(Mailbox::new(
+            Some(sender.reply_to_name.clone()),
+            sender.reply_to_email.parse()?,
+        ))
+        .subject(message.subject);
+    let builder = message
+        .recipients
+        .iter()
+        .try_fold(builder, |builder, recipient| {
+            Ok::<_, anyhow::Error>(builder.to(Mailbox::new(None, recipient.as_ref().parse()?)))
+        })?;
+    let builder = message
+        .headers()
+        .into_iter()
+        .fold(builder, |builder, (name, value)| {
+            builder.raw_header(HeaderValue::new(
+                HeaderName::new_from_ascii_str(name),
+                value,
+            ))
+        });
+    Ok(builder.multipart(MultiPart::alternative_plain_html(
+        message.text_content.to_string(),
+        message.html_content.to_string(),
+    ))?)
+}
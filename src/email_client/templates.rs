@@ -0,0 +1,354 @@
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+use minijinja::{context, Environment, Value};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::configuration::get_configuration;
+
+pub const DEFAULT_LOCALE: &str = "en";
+pub const LOCALES: [&str; 2] = ["en", "es"];
+
+/// Every email the service sends. Each has a subject, an HTML body and a text
+/// body per locale under `templates/email`, built on the shared layouts there.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailTemplate {
+    PasswordReset,
+    AdminPasswordReset,
+    Invitation,
+    NewSubscription,
+    NewDeviceLogin,
+    IssueAvailable,
+    Welcome,
+    SubscriptionConfirmation,
+    SubscriptionCancelled,
+    SubscriptionAddressChanged,
+    NewsletterConfirmation,
+    SubscriptionEmailBounced,
+    StaffSubscriptionEvent,
+    StaffDigest,
+}
+
+impl EmailTemplate {
+    pub const ALL: [EmailTemplate; 14] = [
+        EmailTemplate::PasswordReset,
+        EmailTemplate::AdminPasswordReset,
+        EmailTemplate::Invitation,
+        EmailTemplate::NewSubscription,
+        EmailTemplate::NewDeviceLogin,
+        EmailTemplate::IssueAvailable,
+        EmailTemplate::Welcome,
+        EmailTemplate::SubscriptionConfirmation,
+        EmailTemplate::SubscriptionCancelled,
+        EmailTemplate::SubscriptionAddressChanged,
+        EmailTemplate::NewsletterConfirmation,
+        EmailTemplate::SubscriptionEmailBounced,
+        EmailTemplate::StaffSubscriptionEvent,
+        EmailTemplate::StaffDigest,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmailTemplate::PasswordReset => "password_reset",
+            EmailTemplate::AdminPasswordReset => "admin_password_reset",
+            EmailTemplate::Invitation => "invitation",
+            EmailTemplate::NewSubscription => "new_subscription",
+            EmailTemplate::NewDeviceLogin => "new_device_login",
+            EmailTemplate::IssueAvailable => "issue_available",
+            EmailTemplate::Welcome => "welcome",
+            EmailTemplate::SubscriptionConfirmation => "subscription_confirmation",
+            EmailTemplate::SubscriptionCancelled => "subscription_cancelled",
+            EmailTemplate::SubscriptionAddressChanged => "subscription_address_changed",
+            EmailTemplate::NewsletterConfirmation => "newsletter_confirmation",
+            EmailTemplate::SubscriptionEmailBounced => "subscription_email_bounced",
+            EmailTemplate::StaffSubscriptionEvent => "staff_subscription_event",
+            EmailTemplate::StaffDigest => "staff_digest",
+        }
+    }
+
+    /// Made-up values for every variable the template uses, for previews.
+    pub fn sample_context(&self) -> serde_json::Value {
+        match self {
+            EmailTemplate::PasswordReset | EmailTemplate::AdminPasswordReset => json!({
+                "link": "https://example.com/reset-password?otp=sample",
+            }),
+            EmailTemplate::Invitation => json!({
+                "link": "https://example.com/accept-invitation?token=sample",
+                "expires_on": "2026-01-08 12:00 UTC",
+            }),
+            EmailTemplate::NewSubscription => json!({
+                "subscription": {
+                    "subscription_type": "Paper",
+                    "subscription_name": "Joe Smith",
+                    "subscription_mailing_address_line_1": "123 Main St",
+                    "subscription_mailing_address_line_2": "Apt 4",
+                    "subscription_city": "Kansas City",
+                    "subscription_state": "MO",
+                    "subscription_postal_code": "64105",
+                    "subscription_creation_date": "2026-01-01T12:00:00Z",
+                    "subscription_email_address": "joe@example.com",
+                },
+            }),
+            EmailTemplate::NewDeviceLogin => json!({
+                "signed_in_on": "2026-01-01 12:00:00 UTC",
+                "login_method": "Password",
+                "ip_address": "203.0.113.7",
+                "user_agent": "Mozilla/5.0 (Macintosh; Intel Mac OS X 14_0)",
+            }),
+            EmailTemplate::IssueAvailable => json!({
+                "publication_title": "The Weekly",
+                "issue_title": "Issue 1",
+                "publish_date": "2026-01-01",
+                "link": "https://example.com/issues/1.pdf",
+                "test_send": false,
+                "unsubscribe_link": "https://example.com/unsubscribe?token=sample",
+            }),
+            EmailTemplate::Welcome => json!({
+                "name": "Joe Smith",
+                "manage_link": "https://example.com/subscriber",
+            }),
+            EmailTemplate::SubscriptionConfirmation => json!({
+                "name": "Joe Smith",
+                "plan": "Paper",
+                "price": "25.00 USD",
+                "address_lines": ["123 Main St", "Apt 4", "Kansas City, MO 64105"],
+                "renewal_date": "1/1/2027",
+                "manage_link": "https://example.com/subscriber",
+            }),
+            EmailTemplate::SubscriptionCancelled => json!({
+                "name": "Joe Smith",
+                "plan": "Digital",
+                "cancelled_on": "2026-01-01",
+                "manage_link": "https://example.com/subscriber",
+            }),
+            EmailTemplate::SubscriptionAddressChanged => json!({
+                "name": "Joe Smith",
+                "plan": "Paper",
+                "address_lines": ["123 Main St", "Apt 4", "Kansas City, MO 64105"],
+                "manage_link": "https://example.com/subscriber",
+            }),
+            EmailTemplate::NewsletterConfirmation => json!({
+                "name": "Joe Smith",
+                "link": "https://example.com/newsletter/confirm?token=sample",
+                "expires_on": "2026-01-03 12:00 UTC",
+            }),
+            EmailTemplate::SubscriptionEmailBounced => json!({
+                "email_address": "joe@example.com",
+                "detail": "550 5.1.1 User unknown",
+                "subscriptions": [{
+                    "id": "00000000-0000-0000-0000-000000000000",
+                    "subscription_type": "Paper",
+                    "subscription_name": "Joe Smith",
+                    "subscription_mailing_address_line_1": "123 Main St",
+                    "subscription_city": "Kansas City",
+                    "subscription_state": "MO",
+                    "subscription_postal_code": "64105",
+                }],
+            }),
+            EmailTemplate::StaffSubscriptionEvent => json!({
+                "event_type": "Cancellation",
+                "subscription": {
+                    "id": "00000000-0000-0000-0000-000000000000",
+                    "subscription_type": "Paper",
+                    "subscription_name": "Joe Smith",
+                    "subscription_mailing_address_line_1": "123 Main St",
+                    "subscription_mailing_address_line_2": "Apt 4",
+                    "subscription_city": "Kansas City",
+                    "subscription_state": "MO",
+                    "subscription_postal_code": "64105",
+                    "subscription_email_address": "joe@example.com",
+                },
+            }),
+            EmailTemplate::StaffDigest => json!({
+                "period_start": "2026-10-18",
+                "period_end": "2026-10-19",
+                "new_total": 3,
+                "cancelled_total": 1,
+                "digest": {
+                    "frequency": "daily",
+                    "new_subscriptions": [
+                        { "subscription_type": "Digital", "count": 2 },
+                        { "subscription_type": "Paper", "count": 1 },
+                    ],
+                    "cancelled_subscriptions": [{ "subscription_type": "Paper", "count": 1 }],
+                    "net_change": 2,
+                    "failed_payments": [{
+                        "occurred_at": "2026-10-18T14:00:00Z",
+                        "stripe_subscription_id": "sub_123",
+                        "customer_email": "joe@example.com",
+                    }],
+                    "upcoming_renewals": [{
+                        "subscription_id": "00000000-0000-0000-0000-000000000000",
+                        "subscription_name": "Joe Smith",
+                        "renewal_date": "2026-10-25",
+                    }],
+                    "next_print_cutoff": "2026-10-28",
+                    "pending_address_changes": [{
+                        "subscription_id": "00000000-0000-0000-0000-000000000000",
+                        "subscription_name": "Joe Smith",
+                        "mailing_address": "123 Main St, Apt 4, Kansas City, MO 64105",
+                        "changed_at": "2026-10-18T09:30:00Z",
+                    }],
+                },
+            }),
+        }
+    }
+}
+
+impl FromStr for EmailTemplate {
+    type Err = ();
+
+    fn from_str(val: &str) -> Result<EmailTemplate, ()> {
+        EmailTemplate::ALL
+            .into_iter()
+            .find(|template| template.as_str() == val)
+            .ok_or(())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub html_content: String,
+    pub text_content: String,
+}
+
+/// The supported locale for a requested one such as `es-MX`, or the default.
+pub fn supported_locale(requested: &str) -> &'static str {
+    let language = requested
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    LOCALES
+        .into_iter()
+        .find(|locale| *locale == language)
+        .unwrap_or(DEFAULT_LOCALE)
+}
+
+/// Renders the template in the given locale. Values are HTML-escaped in the
+/// HTML body only.
+pub fn render_email(
+    template: EmailTemplate,
+    locale: &str,
+    values: impl Serialize,
+) -> Result<RenderedEmail, minijinja::Error> {
+    let locale = supported_locale(locale);
+    let organization = get_configuration().unwrap().email_client.sender_name;
+    let values = context! {
+        locale => locale,
+        organization => organization,
+        ..Value::from_serialize(&values)
+    };
+    let render = |part: &str, values: &Value| {
+        TEMPLATES
+            .get_template(&format!("{}/{}.{}", locale, template.as_str(), part))
+            .and_then(|t| t.render(values))
+            .map(|rendered| rendered.trim().to_string())
+    };
+
+    let subject = render("subject.txt", &values)?;
+    let values = context! { subject => subject, ..values };
+    Ok(RenderedEmail {
+        html_content: render("html", &values)?,
+        text_content: render("txt", &values)?,
+        subject,
+    })
+}
+
+macro_rules! template_sources {
+    ($($name:expr),* $(,)?) => {
+        [$(($name, include_str!(concat!("../../templates/email/", $name)))),*]
+    };
+}
+
+macro_rules! localized_template_sources {
+    ($($name:literal),* $(,)?) => {
+        template_sources!(
+            $(
+                concat!("en/", $name, ".subject.txt"),
+                concat!("en/", $name, ".html"),
+                concat!("en/", $name, ".txt"),
+                concat!("es/", $name, ".subject.txt"),
+                concat!("es/", $name, ".html"),
+                concat!("es/", $name, ".txt"),
+            )*
+        )
+    };
+}
+
+static TEMPLATES: LazyLock<Environment<'static>> = LazyLock::new(|| {
+    let mut environment = Environment::new();
+    environment.set_trim_blocks(true);
+    environment.set_lstrip_blocks(true);
+    let layouts = template_sources!("layout.html", "layout.txt");
+    let emails = localized_template_sources!(
+        "password_reset",
+        "admin_password_reset",
+        "invitation",
+        "new_subscription",
+        "new_device_login",
+        "issue_available",
+        "welcome",
+        "subscription_confirmation",
+        "subscription_cancelled",
+        "subscription_address_changed",
+        "newsletter_confirmation",
+        "subscription_email_bounced",
+        "staff_subscription_event",
+        "staff_digest",
+    );
+    for (name, source) in layouts.into_iter().chain(emails) {
+        environment
+            .add_template(name, source)
+            .expect("Email templates should parse");
+    }
+    environment
+});
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::email_client::templates::{
+        render_email, supported_locale, EmailTemplate, DEFAULT_LOCALE, LOCALES,
+    };
+
+    #[test]
+    fn every_template_renders_in_every_locale() {
+        for template in EmailTemplate::ALL {
+            for locale in LOCALES {
+                let email = render_email(template, locale, template.sample_context()).unwrap();
+                assert!(!email.subject.is_empty());
+                assert!(email
+                    .html_content
+                    .contains(&format!("<html lang=\"{}\">", locale)));
+                assert!(email.html_content.contains(&email.subject));
+                assert!(!email.text_content.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn values_are_escaped_in_html_only() {
+        let email = render_email(
+            EmailTemplate::NewDeviceLogin,
+            DEFAULT_LOCALE,
+            json!({ "user_agent": "<script>alert(1)</script>" }),
+        )
+        .unwrap();
+        assert!(email.html_content.contains("&lt;script&gt;"));
+        assert!(!email.html_content.contains("<script>"));
+        assert!(email.text_content.contains("<script>alert(1)</script>"));
+    }
+
+    #[test]
+    fn unknown_locales_fall_back_to_the_default() {
+        assert_eq!("es", supported_locale("es-MX"));
+        assert_eq!("es", supported_locale("ES"));
+        assert_eq!(DEFAULT_LOCALE, supported_locale("fr"));
+        assert_eq!(DEFAULT_LOCALE, supported_locale(""));
+    }
+}
@@ -1,10 +1,13 @@
+pub mod admin_cli;
 pub mod auth;
 pub mod background;
 pub mod configuration;
 pub mod db;
 pub mod domain;
 pub mod email_client;
+pub mod oidc_client;
 pub mod routes;
+pub mod seed;
 pub mod startup;
 pub mod stripe_client;
 pub mod telemetry;
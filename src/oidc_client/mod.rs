@@ -0,0 +1,534 @@
+pub mod oidc_provider_models;
+
+use std::str::FromStr;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use reqwest::{Client, Error};
+use secrecy::ExposeSecret;
+use sha2::{Digest, Sha256};
+use tracing::Level;
+use urlencoding::encode;
+
+use crate::configuration::OidcSettings;
+use crate::oidc_client::oidc_provider_models::{
+    IdTokenClaims, OidcDiscoveryDocument, OidcTokenResponse,
+};
+
+pub const OIDC_DISCOVERY_PATH: &str = "/.well-known/openid-configuration";
+
+#[derive(Clone)]
+pub struct OidcClient {
+    http_client: Client,
+    settings: OidcSettings,
+}
+
+#[derive(Debug)]
+pub enum OidcError {
+    Provider(Error),
+    InvalidIdToken(String),
+}
+
+impl std::fmt::Display for OidcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OidcError::Provider(err) => {
+                write!(f, "The OIDC provider could not be reached. {:?}", err)
+            }
+            OidcError::InvalidIdToken(reason) => {
+                write!(f, "The ID token was rejected. {}", reason)
+            }
+        }
+    }
+}
+
+impl From<Error> for OidcError {
+    fn from(err: Error) -> Self {
+        OidcError::Provider(err)
+    }
+}
+
+impl OidcClient {
+    pub fn new(settings: OidcSettings) -> Self {
+        Self {
+            http_client: Client::builder()
+                .timeout(settings.timeout())
+                .build()
+                .unwrap(),
+            settings,
+        }
+    }
+
+    pub fn provider_name(&self) -> &str {
+        &self.settings.provider_name
+    }
+
+    #[tracing::instrument(name = "Get OIDC discovery document", skip(self))]
+    pub async fn get_discovery_document(&self) -> Result<OidcDiscoveryDocument, Error> {
+        let address = format!(
+            "{}{}",
+            self.settings.issuer_url.trim_end_matches('/'),
+            OIDC_DISCOVERY_PATH
+        );
+
+        let response = self
+            .http_client
+            .get(address)
+            .send()
+            .await?
+            .error_for_status();
+
+        match response {
+            Ok(response) => response.json::<OidcDiscoveryDocument>().await,
+            Err(err) => {
+                tracing::event!(Level::ERROR, "Err: {:?}", err);
+                Err(err)
+            }
+        }
+    }
+
+    pub fn authorization_url(
+        &self,
+        discovery_document: &OidcDiscoveryDocument,
+        state: &str,
+        nonce: &str,
+        code_verifier: &str,
+    ) -> String {
+        format!(
+            "{}?response_type=code&client_id={}&redirect_uri={}&scope={}&state={}&nonce={}&code_challenge={}&code_challenge_method=S256",
+            discovery_document.authorization_endpoint,
+            encode(&self.settings.client_id),
+            encode(&self.settings.redirect_url),
+            encode(&self.settings.scopes),
+            encode(state),
+            encode(nonce),
+            pkce_code_challenge(code_verifier),
+        )
+    }
+
+    #[tracing::instrument(
+        name = "Exchange an OIDC authorization code",
+        skip(self, discovery_document, code, code_verifier)
+    )]
+    pub async fn exchange_code(
+        &self,
+        discovery_document: &OidcDiscoveryDocument,
+        code: &str,
+        code_verifier: &str,
+    ) -> Result<OidcTokenResponse, Error> {
+        let response = self
+            .http_client
+            .post(&discovery_document.token_endpoint)
+            .basic_auth(
+                encode(&self.settings.client_id),
+                Some(encode(self.settings.client_secret.expose_secret())),
+            )
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", self.settings.redirect_url.as_str()),
+                ("code_verifier", code_verifier),
+            ])
+            .send()
+            .await?
+            .error_for_status();
+
+        match response {
+            Ok(response) => response.json::<OidcTokenResponse>().await,
+            Err(err) => {
+                tracing::event!(Level::ERROR, "Err: {:?}", err);
+                Err(err)
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "Get OIDC signing keys", skip(self, discovery_document))]
+    pub async fn get_signing_keys(
+        &self,
+        discovery_document: &OidcDiscoveryDocument,
+    ) -> Result<JwkSet, Error> {
+        let response = self
+            .http_client
+            .get(&discovery_document.jwks_uri)
+            .send()
+            .await?
+            .error_for_status();
+
+        match response {
+            Ok(response) => response.json::<JwkSet>().await,
+            Err(err) => {
+                tracing::event!(Level::ERROR, "Err: {:?}", err);
+                Err(err)
+            }
+        }
+    }
+
+    /// Verifies the ID token signature against the provider's published keys and checks the
+    /// issuer, audience, expiry and nonce before handing back its claims.
+    #[tracing::instrument(
+        name = "Validate an OIDC ID token",
+        skip(self, discovery_document, id_token, expected_nonce)
+    )]
+    pub async fn validate_id_token(
+        &self,
+        discovery_document: &OidcDiscoveryDocument,
+        id_token: &str,
+        expected_nonce: &str,
+    ) -> Result<IdTokenClaims, OidcError> {
+        let header = decode_header(id_token)
+            .map_err(|e| OidcError::InvalidIdToken(format!("Malformed header. {:?}", e)))?;
+
+        let key_set = self.get_signing_keys(discovery_document).await?;
+        // Without a kid the token can only be matched when the provider has a single key.
+        let jwk = match &header.kid {
+            Some(kid) => key_set.find(kid),
+            None if key_set.keys.len() == 1 => key_set.keys.first(),
+            None => None,
+        }
+        .ok_or_else(|| OidcError::InvalidIdToken("No matching signing key.".to_string()))?;
+        let key = DecodingKey::from_jwk(jwk)
+            .map_err(|e| OidcError::InvalidIdToken(format!("Unusable signing key. {:?}", e)))?;
+
+        // The token header is untrusted, so the algorithm comes from the provider's key, and
+        // falls back to RS256, the one every OIDC provider must support.
+        let algorithm = match jwk.common.key_algorithm {
+            Some(key_algorithm) => Algorithm::from_str(&key_algorithm.to_string())
+                .map_err(|e| OidcError::InvalidIdToken(format!("Unusable signing key. {:?}", e)))?,
+            None => Algorithm::RS256,
+        };
+        let mut validation = Validation::new(algorithm);
+        validation.set_issuer(&[discovery_document.issuer.as_str()]);
+        validation.set_audience(&[self.settings.client_id.as_str()]);
+
+        let claims = decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map_err(|e| OidcError::InvalidIdToken(format!("{:?}", e)))?
+            .claims;
+
+        if claims.nonce.as_deref() != Some(expected_nonce) {
+            return Err(OidcError::InvalidIdToken(
+                "The nonce does not match.".to_string(),
+            ));
+        }
+
+        Ok(claims)
+    }
+}
+
+/// The S256 PKCE challenge: the base64url encoded SHA-256 digest of the verifier.
+pub fn pkce_code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use claims::{assert_err, assert_ok};
+    use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+    use secrecy::SecretString;
+    use serde_json::json;
+    use uuid::Uuid;
+    use wiremock::matchers::{body_string_contains, header_exists, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::configuration::OidcSettings;
+    use crate::oidc_client::oidc_provider_models::{OidcDiscoveryDocument, OidcTokenResponse};
+    use crate::oidc_client::{pkce_code_challenge, OidcClient, OidcError, OIDC_DISCOVERY_PATH};
+
+    const CLIENT_ID: &str = "newsletter-signup-service";
+    const SIGNING_KEY_ID: &str = "test-key";
+    const SIGNING_SECRET: &str = "a-signing-secret-that-is-only-used-in-tests";
+
+    fn oidc_client(issuer_url: String) -> OidcClient {
+        OidcClient::new(OidcSettings {
+            provider_name: "test".to_string(),
+            issuer_url,
+            client_id: CLIENT_ID.to_string(),
+            client_secret: SecretString::from(Uuid::new_v4().to_string()),
+            redirect_url: "http://localhost:3000/oidc-callback".to_string(),
+            scopes: "openid email profile".to_string(),
+            timeout_milliseconds: 200,
+        })
+    }
+
+    fn discovery_document(issuer_url: &str) -> OidcDiscoveryDocument {
+        OidcDiscoveryDocument {
+            issuer: issuer_url.to_string(),
+            authorization_endpoint: format!("{}/authorize", issuer_url),
+            token_endpoint: format!("{}/token", issuer_url),
+            jwks_uri: format!("{}/jwks", issuer_url),
+            userinfo_endpoint: None,
+        }
+    }
+
+    fn id_token(issuer: &str, audience: &str, nonce: &str) -> String {
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some(SIGNING_KEY_ID.to_string());
+        signed_id_token(&header, issuer, audience, nonce)
+    }
+
+    fn signed_id_token(header: &Header, issuer: &str, audience: &str, nonce: &str) -> String {
+        let now = jsonwebtoken::get_current_timestamp();
+        encode(
+            header,
+            &json!({
+                "iss": issuer,
+                "sub": Uuid::new_v4().to_string(),
+                "aud": audience,
+                "exp": now + 300,
+                "iat": now,
+                "nonce": nonce,
+                "email": format!("{}@gmail.com", Uuid::new_v4()),
+                "email_verified": true,
+            }),
+            &EncodingKey::from_secret(SIGNING_SECRET.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    async fn mock_jwks(mock_server: &MockServer) {
+        Mock::given(path("/jwks"))
+            .and(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "keys": [{
+                    "kty": "oct",
+                    "kid": SIGNING_KEY_ID,
+                    "alg": "HS256",
+                    "k": URL_SAFE_NO_PAD.encode(SIGNING_SECRET),
+                }]
+            })))
+            .mount(mock_server)
+            .await;
+    }
+
+    #[test]
+    fn pkce_code_challenge_matches_the_rfc_7636_example() {
+        assert_eq!(
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM",
+            pkce_code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk")
+        );
+    }
+
+    #[test]
+    fn authorization_url_carries_the_pkce_challenge_and_state() {
+        let client = oidc_client("https://accounts.example.com".to_string());
+        let document = discovery_document("https://accounts.example.com");
+
+        let url = client.authorization_url(&document, "the-state", "the-nonce", "the-verifier");
+
+        assert!(url.starts_with("https://accounts.example.com/authorize?"));
+        assert!(url.contains("state=the-state"));
+        assert!(url.contains("nonce=the-nonce"));
+        assert!(url.contains(&format!(
+            "code_challenge={}",
+            pkce_code_challenge("the-verifier")
+        )));
+        assert!(url.contains("code_challenge_method=S256"));
+    }
+
+    #[tokio::test]
+    async fn get_discovery_document_works() {
+        let mock_server = MockServer::start().await;
+        let client = oidc_client(mock_server.uri());
+
+        Mock::given(path(OIDC_DISCOVERY_PATH))
+            .and(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!(discovery_document(&mock_server.uri()))),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let outcome = client.get_discovery_document().await;
+        assert_ok!(&outcome);
+        assert_eq!(
+            format!("{}/token", mock_server.uri()),
+            outcome.unwrap().token_endpoint
+        );
+    }
+
+    #[tokio::test]
+    async fn get_discovery_document_returns_error_when_it_is_an_error() {
+        let mock_server = MockServer::start().await;
+        let client = oidc_client(mock_server.uri());
+
+        Mock::given(path(OIDC_DISCOVERY_PATH))
+            .and(method("GET"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        assert_err!(client.get_discovery_document().await);
+    }
+
+    #[tokio::test]
+    async fn exchange_code_sends_the_code_verifier() {
+        let mock_server = MockServer::start().await;
+        let client = oidc_client(mock_server.uri());
+        let document = discovery_document(&mock_server.uri());
+
+        let token_response = OidcTokenResponse {
+            access_token: Uuid::new_v4().to_string(),
+            token_type: "Bearer".to_string(),
+            id_token: id_token(&mock_server.uri(), CLIENT_ID, "nonce"),
+            expires_in: Some(3600),
+        };
+
+        Mock::given(header_exists("Authorization"))
+            .and(path("/token"))
+            .and(method("POST"))
+            .and(body_string_contains("grant_type=authorization_code"))
+            .and(body_string_contains("code=the-code"))
+            .and(body_string_contains("code_verifier=the-verifier"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!(token_response)))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        assert_ok!(
+            client
+                .exchange_code(&document, "the-code", "the-verifier")
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn exchange_code_returns_error_when_it_is_an_error() {
+        let mock_server = MockServer::start().await;
+        let client = oidc_client(mock_server.uri());
+        let document = discovery_document(&mock_server.uri());
+
+        Mock::given(path("/token"))
+            .and(method("POST"))
+            .respond_with(ResponseTemplate::new(400))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        assert_err!(
+            client
+                .exchange_code(&document, "the-code", "the-verifier")
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn validate_id_token_works() {
+        let mock_server = MockServer::start().await;
+        let client = oidc_client(mock_server.uri());
+        let document = discovery_document(&mock_server.uri());
+        mock_jwks(&mock_server).await;
+
+        let token = id_token(&mock_server.uri(), CLIENT_ID, "the-nonce");
+
+        assert_ok!(
+            client
+                .validate_id_token(&document, &token, "the-nonce")
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn validate_id_token_rejects_a_different_nonce() {
+        let mock_server = MockServer::start().await;
+        let client = oidc_client(mock_server.uri());
+        let document = discovery_document(&mock_server.uri());
+        mock_jwks(&mock_server).await;
+
+        let token = id_token(&mock_server.uri(), CLIENT_ID, "the-nonce");
+
+        let outcome = client
+            .validate_id_token(&document, &token, "another-nonce")
+            .await;
+        assert!(matches!(outcome, Err(OidcError::InvalidIdToken(_))));
+    }
+
+    #[tokio::test]
+    async fn validate_id_token_rejects_another_audience() {
+        let mock_server = MockServer::start().await;
+        let client = oidc_client(mock_server.uri());
+        let document = discovery_document(&mock_server.uri());
+        mock_jwks(&mock_server).await;
+
+        let token = id_token(&mock_server.uri(), "someone-else", "the-nonce");
+
+        let outcome = client
+            .validate_id_token(&document, &token, "the-nonce")
+            .await;
+        assert!(matches!(outcome, Err(OidcError::InvalidIdToken(_))));
+    }
+
+    #[tokio::test]
+    async fn validate_id_token_rejects_a_bad_signature() {
+        let mock_server = MockServer::start().await;
+        let client = oidc_client(mock_server.uri());
+        let document = discovery_document(&mock_server.uri());
+        mock_jwks(&mock_server).await;
+
+        let token = id_token(&mock_server.uri(), CLIENT_ID, "the-nonce");
+        let tampered = format!("{}x", token);
+
+        let outcome = client
+            .validate_id_token(&document, &tampered, "the-nonce")
+            .await;
+        assert!(matches!(outcome, Err(OidcError::InvalidIdToken(_))));
+    }
+
+    #[tokio::test]
+    async fn validate_id_token_rejects_an_algorithm_the_key_does_not_use() {
+        let mock_server = MockServer::start().await;
+        let client = oidc_client(mock_server.uri());
+        let document = discovery_document(&mock_server.uri());
+        mock_jwks(&mock_server).await;
+
+        let mut header = Header::new(Algorithm::HS384);
+        header.kid = Some(SIGNING_KEY_ID.to_string());
+        let token = signed_id_token(&header, &mock_server.uri(), CLIENT_ID, "the-nonce");
+
+        let outcome = client
+            .validate_id_token(&document, &token, "the-nonce")
+            .await;
+        assert!(matches!(outcome, Err(OidcError::InvalidIdToken(_))));
+    }
+
+    #[tokio::test]
+    async fn validate_id_token_rejects_a_token_without_kid_when_there_are_several_keys() {
+        let mock_server = MockServer::start().await;
+        let client = oidc_client(mock_server.uri());
+        let document = discovery_document(&mock_server.uri());
+        Mock::given(path("/jwks"))
+            .and(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "keys": [{
+                    "kty": "oct",
+                    "kid": SIGNING_KEY_ID,
+                    "alg": "HS256",
+                    "k": URL_SAFE_NO_PAD.encode(SIGNING_SECRET),
+                }, {
+                    "kty": "oct",
+                    "kid": "another-key",
+                    "alg": "HS256",
+                    "k": URL_SAFE_NO_PAD.encode(Uuid::new_v4().to_string()),
+                }]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let token = signed_id_token(
+            &Header::new(Algorithm::HS256),
+            &mock_server.uri(),
+            CLIENT_ID,
+            "the-nonce",
+        );
+
+        let outcome = client
+            .validate_id_token(&document, &token, "the-nonce")
+            .await;
+        assert!(matches!(outcome, Err(OidcError::InvalidIdToken(_))));
+    }
+}
@@ -0,0 +1,65 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::auth::authorization::is_authorized_admin_only;
+use crate::auth::request_metadata::RequestMetadata;
+use crate::auth::token::Claims;
+use crate::db::audit_log_db_broker::{insert_audit_log_entry, search_audit_log};
+use crate::domain::audit_models::{AuditAction, AuditEvent, AuditLogPage, AuditLogQuery};
+
+/// Appends an event to the audit log. A failed write is logged rather than
+/// surfaced so the action that has already happened still gets its response.
+pub async fn record_audit_event(event: AuditEvent, metadata: &RequestMetadata, pool: &PgPool) {
+    if let Err(e) = insert_audit_log_entry(
+        &event,
+        metadata.ip_address.as_deref(),
+        metadata.user_agent.as_deref(),
+        pool,
+    )
+    .await
+    {
+        tracing::error!(
+            "Failed to record audit event {}: {:?}",
+            event.action.as_str(),
+            e
+        );
+    }
+}
+
+#[tracing::instrument(
+    name = "Getting the audit log (admin only)",
+    skip(admin_user_id, query, pool, user, metadata),
+    fields(admin_user_id = %admin_user_id)
+)]
+pub async fn get_audit_log_admin(
+    admin_user_id: web::Path<String>,
+    query: web::Query<AuditLogQuery>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let admin_user_id = admin_user_id.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    match search_audit_log(&query, &pool).await {
+        Ok((entries, total)) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::ListAuditLog)
+                    .with_payload(json!(query.0)),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(AuditLogPage {
+                entries,
+                page: query.page(),
+                page_size: query.page_size(),
+                total,
+            })
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
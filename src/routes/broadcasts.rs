@@ -0,0 +1,226 @@
+use actix_web::{web, HttpResponse, Responder};
+use chrono::Utc;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::authorization::is_authorized_admin_only;
+use crate::auth::request_metadata::RequestMetadata;
+use crate::auth::token::Claims;
+use crate::background::issue_broadcaster::start_broadcast;
+use crate::db::broadcast_db_broker::{
+    get_broadcast, get_broadcast_recipients, get_broadcasts_by_issue_id, insert_broadcast,
+    requeue_failed_recipients,
+};
+use crate::db::email_preferences_db_broker::get_opted_out_subscriber_ids;
+use crate::db::publications_db_broker::get_issue;
+use crate::db::subscriptions_db_broker::retrieve_subscriptions_active_at;
+use crate::db::users::get_user_by_user_id;
+use crate::domain::audit_models::{AuditAction, AuditEvent};
+use crate::domain::broadcast_models::{
+    build_recipients, BroadcastRecipient, BroadcastStatus, CreateBroadcast, IssueBroadcast,
+    RecipientStatus,
+};
+use crate::domain::email_preference_models::EmailCategory;
+use crate::domain::subscription_models::SubscriptionType;
+use crate::email_client::EmailClient;
+use crate::routes::audit::record_audit_event;
+
+#[tracing::instrument(
+    name = "Broadcast an issue (admin only)",
+    skip(path, create_broadcast, pool, email_client, user, metadata),
+    fields(test_send = %create_broadcast.test_send)
+)]
+pub async fn create_broadcast_admin(
+    path: web::Path<(String, String)>,
+    create_broadcast: web::Json<CreateBroadcast>,
+    pool: web::Data<PgPool>,
+    email_client: web::Data<EmailClient>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, issue_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let issue_id = match Uuid::parse_str(&issue_id) {
+        Ok(issue_id) => issue_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+
+    let issue = match get_issue(issue_id, &pool).await {
+        Ok(issue) => issue,
+        Err(sqlx::Error::RowNotFound) => return HttpResponse::NotFound().finish(),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+    // There is nothing to send until the digital edition exists.
+    if issue.digital_asset_reference.is_none() {
+        return HttpResponse::BadRequest().finish();
+    }
+
+    let broadcast_id = Uuid::new_v4();
+    let recipients = if create_broadcast.test_send {
+        match get_user_by_user_id(&admin_user_id, &pool).await {
+            Ok(admin) => vec![BroadcastRecipient {
+                id: Uuid::new_v4(),
+                broadcast_id,
+                subscription_id: None,
+                email_address: admin.email_address,
+                status: RecipientStatus::Pending,
+                attempts: 0,
+                last_error: None,
+                sent_at: None,
+            }],
+            Err(_) => return HttpResponse::InternalServerError().finish(),
+        }
+    } else {
+        let opted_out = match get_opted_out_subscriber_ids(EmailCategory::Issues, &pool).await {
+            Ok(opted_out) => opted_out,
+            Err(_) => return HttpResponse::InternalServerError().finish(),
+        };
+        match retrieve_subscriptions_active_at(&SubscriptionType::Digital, issue.cutoff(), &pool)
+            .await
+        {
+            Ok(subscriptions) => build_recipients(
+                broadcast_id,
+                subscriptions
+                    .into_iter()
+                    .filter(|subscription| !opted_out.contains(&subscription.subscriber_id))
+                    .collect(),
+                issue.publication_id,
+            ),
+            Err(_) => return HttpResponse::InternalServerError().finish(),
+        }
+    };
+
+    let broadcast = IssueBroadcast {
+        id: broadcast_id,
+        issue_id,
+        test_send: create_broadcast.test_send,
+        status: BroadcastStatus::Sending,
+        created_at: Utc::now(),
+        created_by: Uuid::parse_str(&admin_user_id).ok(),
+        completed_at: None,
+        recipient_count: recipients.len() as i32,
+    };
+    match insert_broadcast(&broadcast, &recipients, &pool).await {
+        Ok(_) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::CreateBroadcast)
+                    .with_target("issue_broadcast", broadcast.id)
+                    .with_payload(json!({
+                        "issue_id": broadcast.issue_id,
+                        "test_send": broadcast.test_send,
+                        "recipient_count": broadcast.recipient_count,
+                    })),
+                &metadata,
+                &pool,
+            )
+            .await;
+            start_broadcast(broadcast.id, email_client.get_ref().clone(), &pool);
+            HttpResponse::Ok().json(broadcast)
+        }
+        // The issue has already gone out to subscribers.
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            HttpResponse::Conflict().finish()
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Get the broadcasts of an issue (admin only)",
+    skip(path, pool, user)
+)]
+pub async fn get_broadcasts_admin(
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    let (admin_user_id, issue_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id, user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let issue_id = match Uuid::parse_str(&issue_id) {
+        Ok(issue_id) => issue_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+
+    match get_broadcasts_by_issue_id(issue_id, &pool).await {
+        Ok(broadcasts) => HttpResponse::Ok().json(broadcasts),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Get the recipients of a broadcast (admin only)",
+    skip(path, pool, user)
+)]
+pub async fn get_broadcast_recipients_admin(
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    let (admin_user_id, broadcast_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id, user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let broadcast_id = match Uuid::parse_str(&broadcast_id) {
+        Ok(broadcast_id) => broadcast_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+
+    match get_broadcast(broadcast_id, &pool).await {
+        Ok(_) => {}
+        Err(sqlx::Error::RowNotFound) => return HttpResponse::NotFound().finish(),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    }
+    match get_broadcast_recipients(broadcast_id, &pool).await {
+        Ok(recipients) => HttpResponse::Ok().json(recipients),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Resume a broadcast (admin only)",
+    skip(path, pool, email_client, user, metadata)
+)]
+pub async fn resume_broadcast_admin(
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    email_client: web::Data<EmailClient>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, broadcast_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let broadcast_id = match Uuid::parse_str(&broadcast_id) {
+        Ok(broadcast_id) => broadcast_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+
+    match get_broadcast(broadcast_id, &pool).await {
+        Ok(_) => {}
+        Err(sqlx::Error::RowNotFound) => return HttpResponse::NotFound().finish(),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    }
+    match requeue_failed_recipients(broadcast_id, &pool).await {
+        Ok(requeued) => {
+            if requeued > 0 {
+                record_audit_event(
+                    AuditEvent::new(&admin_user_id, AuditAction::ResumeBroadcast)
+                        .with_target("issue_broadcast", broadcast_id)
+                        .with_payload(json!({ "requeued": requeued })),
+                    &metadata,
+                    &pool,
+                )
+                .await;
+                start_broadcast(broadcast_id, email_client.get_ref().clone(), &pool);
+            }
+            HttpResponse::Ok().json(json!({ "requeued": requeued }))
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
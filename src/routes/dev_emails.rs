@@ -0,0 +1,33 @@
+use actix_web::{web, HttpResponse, Responder};
+use sqlx::PgPool;
+
+use crate::configuration::{current_environment, Environment};
+use crate::db::captured_emails_db_broker::get_captured_emails;
+use crate::domain::captured_email_models::CapturedEmailQuery;
+use crate::email_client::EmailClient;
+
+const DEFAULT_CAPTURED_EMAILS: i64 = 50;
+const MAX_CAPTURED_EMAILS: i64 = 500;
+
+/// The emails captured in the local environment, with their links, so that
+/// developers can follow reset and confirmation links without a mailbox. Not
+/// found unless capture is on, and never in production.
+#[tracing::instrument(name = "Get captured emails", skip(query, pool, email_client))]
+pub async fn get_dev_emails(
+    query: web::Query<CapturedEmailQuery>,
+    pool: web::Data<PgPool>,
+    email_client: web::Data<EmailClient>,
+) -> impl Responder {
+    if !email_client.captures_emails() || matches!(current_environment(), Environment::Production) {
+        return HttpResponse::NotFound().finish();
+    }
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_CAPTURED_EMAILS)
+        .clamp(1, MAX_CAPTURED_EMAILS);
+    match get_captured_emails(query.recipient.as_deref(), limit, &pool).await {
+        Ok(emails) => HttpResponse::Ok().json(emails),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
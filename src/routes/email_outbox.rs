@@ -0,0 +1,74 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::authorization::is_authorized_admin_only;
+use crate::auth::request_metadata::RequestMetadata;
+use crate::auth::token::Claims;
+use crate::db::email_outbox_db_broker::{get_outbox_email, get_outbox_emails, requeue_dead_email};
+use crate::domain::audit_models::{AuditAction, AuditEvent};
+use crate::domain::email_outbox_models::OutboxQuery;
+use crate::routes::audit::record_audit_event;
+
+const MAX_OUTBOX_EMAILS: i64 = 200;
+
+#[tracing::instrument(
+    name = "Get outbox emails (admin only)",
+    skip(admin_user_id, query, pool, user)
+)]
+pub async fn get_outbox_emails_admin(
+    admin_user_id: web::Path<String>,
+    query: web::Query<OutboxQuery>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    if !is_authorized_admin_only(admin_user_id.into_inner(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    match get_outbox_emails(query.status, MAX_OUTBOX_EMAILS, &pool).await {
+        Ok(emails) => HttpResponse::Ok().json(emails),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Requeue a dead email (admin only)",
+    skip(path, pool, user, metadata)
+)]
+pub async fn requeue_outbox_email_admin(
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, email_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let email_id = match Uuid::parse_str(&email_id) {
+        Ok(email_id) => email_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+
+    match requeue_dead_email(email_id, &pool).await {
+        Ok(true) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::RequeueEmail)
+                    .with_target("email", email_id),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(json!({}))
+        }
+        // Either there is no such email, or it is not dead.
+        Ok(false) => match get_outbox_email(email_id, &pool).await {
+            Ok(_) => HttpResponse::Conflict().finish(),
+            Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().finish(),
+            Err(_) => HttpResponse::InternalServerError().finish(),
+        },
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
@@ -0,0 +1,112 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::token::Claims;
+use crate::db::email_preferences_db_broker::{get_email_preferences, store_email_preferences};
+use crate::db::subscribers_db_broker::retrieve_subscriber_by_id;
+use crate::domain::email_preference_models::{verify_unsubscribe_token, EmailPreferences};
+use crate::util::from_path_to_uuid;
+
+#[tracing::instrument(name = "Get email preferences", skip(id, pool, user))]
+pub async fn get_email_preferences_by_subscriber_id(
+    id: web::Path<String>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    let subscriber_id = match owned_subscriber_id(&id, &user, &pool).await {
+        Ok(subscriber_id) => subscriber_id,
+        Err(response) => return response,
+    };
+
+    match get_email_preferences(subscriber_id, &pool).await {
+        Ok(preferences) => HttpResponse::Ok().json(preferences),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(name = "Update email preferences", skip(id, preferences, pool, user))]
+pub async fn update_email_preferences(
+    id: web::Path<String>,
+    preferences: web::Json<EmailPreferences>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    let subscriber_id = match owned_subscriber_id(&id, &user, &pool).await {
+        Ok(subscriber_id) => subscriber_id,
+        Err(response) => return response,
+    };
+
+    match store_email_preferences(subscriber_id, &preferences, &pool).await {
+        Ok(_) => HttpResponse::Ok().json(preferences.0),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// What an unsubscribe link would turn off, so that the web app can ask before
+/// doing it. Changes nothing, since link scanners follow links in email.
+#[tracing::instrument(name = "Look up an unsubscribe link", skip(token, pool))]
+pub async fn get_unsubscribe(token: web::Path<String>, pool: web::Data<PgPool>) -> impl Responder {
+    let (subscriber_id, category) = match verify_unsubscribe_token(&token) {
+        Some(verified) => verified,
+        None => return HttpResponse::BadRequest().finish(),
+    };
+
+    match get_email_preferences(subscriber_id, &pool).await {
+        Ok(preferences) => HttpResponse::Ok().json(json!({
+            "category": category,
+            "preferences": preferences,
+        })),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// Unsubscribes without logging in. Also the RFC 8058 one-click target, which
+/// mail clients POST `List-Unsubscribe=One-Click` to; the body is ignored.
+#[tracing::instrument(name = "Unsubscribe", skip(token, pool))]
+pub async fn post_unsubscribe(token: web::Path<String>, pool: web::Data<PgPool>) -> impl Responder {
+    let (subscriber_id, category) = match verify_unsubscribe_token(&token) {
+        Some(verified) => verified,
+        None => return HttpResponse::BadRequest().finish(),
+    };
+    match retrieve_subscriber_by_id(subscriber_id, &pool).await {
+        Ok(_) => {}
+        Err(sqlx::Error::RowNotFound) => return HttpResponse::NotFound().finish(),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    }
+
+    let preferences = match get_email_preferences(subscriber_id, &pool).await {
+        Ok(preferences) => preferences.without(category),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+    match store_email_preferences(subscriber_id, &preferences, &pool).await {
+        Ok(_) => HttpResponse::Ok().json(json!({
+            "category": category,
+            "preferences": preferences,
+        })),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// The subscriber id in the path, if it belongs to the caller.
+async fn owned_subscriber_id(
+    id: &web::Path<String>,
+    user: &Claims,
+    pool: &PgPool,
+) -> Result<Uuid, HttpResponse> {
+    let subscriber_id = from_path_to_uuid(id)?;
+    match retrieve_subscriber_by_id(subscriber_id, pool).await {
+        Ok(subscriber) if subscriber.user_id == user.user_id => Ok(subscriber_id),
+        Ok(subscriber) => {
+            tracing::error!(
+                "A user with id: {} does not have access to a user with id {}",
+                user.user_id,
+                subscriber.user_id
+            );
+            Err(HttpResponse::Unauthorized().finish())
+        }
+        Err(sqlx::Error::RowNotFound) => Err(HttpResponse::NotFound().finish()),
+        Err(_) => Err(HttpResponse::InternalServerError().finish()),
+    }
+}
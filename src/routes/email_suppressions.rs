@@ -0,0 +1,110 @@
+use actix_web::{web, HttpResponse, Responder};
+use secrecy::ExposeSecret;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use crate::auth::authorization::is_authorized_admin_only;
+use crate::auth::request_metadata::RequestMetadata;
+use crate::auth::token::Claims;
+use crate::background::bounced_email_notifier::notify_of_bounced_subscription_email;
+use crate::configuration::get_configuration;
+use crate::db::email_suppressions_db_broker::{
+    delete_email_suppression, get_email_suppressions, store_email_suppression,
+};
+use crate::domain::audit_models::{AuditAction, AuditEvent};
+use crate::domain::email_suppression_models::{
+    parse_delivery_events, SuppressionQuery, SuppressionReason,
+};
+use crate::email_client::EmailClient;
+use crate::routes::audit::record_audit_event;
+use crate::util::hash_token;
+
+const MAX_EMAIL_SUPPRESSIONS: i64 = 200;
+
+/// Delivery events from the email provider. Bounces, complaints and
+/// unsubscribes put the address on the suppression list; the first hard
+/// bounce of an active subscription's address is flagged to staff.
+#[tracing::instrument(
+    name = "Receive email delivery events",
+    skip(token, body, pool, email_client)
+)]
+pub async fn email_delivery_events(
+    token: web::Path<String>,
+    body: web::Json<Value>,
+    pool: web::Data<PgPool>,
+    email_client: web::Data<EmailClient>,
+) -> impl Responder {
+    let Some(webhook_token) = get_configuration().unwrap().email_client.webhook_token else {
+        return HttpResponse::NotFound().finish();
+    };
+    if hash_token(&token) != hash_token(webhook_token.expose_secret()) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    let events = parse_delivery_events(&body);
+    for event in &events {
+        match store_email_suppression(event, &pool).await {
+            Ok(true) if event.reason == SuppressionReason::HardBounce => {
+                notify_of_bounced_subscription_email(event, &email_client, &pool).await
+            }
+            Ok(_) => {}
+            // The provider sends the events again.
+            Err(_) => return HttpResponse::InternalServerError().finish(),
+        }
+    }
+
+    HttpResponse::Ok().json(json!({ "suppressed": events.len() }))
+}
+
+#[tracing::instrument(
+    name = "Get email suppressions (admin only)",
+    skip(admin_user_id, query, pool, user)
+)]
+pub async fn get_email_suppressions_admin(
+    admin_user_id: web::Path<String>,
+    query: web::Query<SuppressionQuery>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    if !is_authorized_admin_only(admin_user_id.into_inner(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    match get_email_suppressions(query.reason, MAX_EMAIL_SUPPRESSIONS, &pool).await {
+        Ok(suppressions) => HttpResponse::Ok().json(suppressions),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// Lets email go to the address again, for example once a subscriber says
+/// their mailbox works.
+#[tracing::instrument(
+    name = "Clear an email suppression (admin only)",
+    skip(path, pool, user, metadata)
+)]
+pub async fn clear_email_suppression_admin(
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, email_address) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    match delete_email_suppression(&email_address, &pool).await {
+        Ok(true) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::ClearEmailSuppression)
+                    .with_target("email_address", email_address.to_lowercase()),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(json!({}))
+        }
+        Ok(false) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
@@ -0,0 +1,75 @@
+use std::str::FromStr;
+
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::auth::authorization::is_authorized_admin_only;
+use crate::auth::token::Claims;
+use crate::email_client::templates::{
+    render_email, supported_locale, EmailTemplate, DEFAULT_LOCALE, LOCALES,
+};
+
+#[derive(Deserialize, Debug)]
+pub struct TemplatePreviewQuery {
+    pub locale: Option<String>,
+    /// `html` returns only the HTML body, to open in a browser.
+    pub format: Option<String>,
+}
+
+#[tracing::instrument(name = "Get email templates (admin only)", skip(admin_user_id, user))]
+pub async fn get_email_templates_admin(
+    admin_user_id: web::Path<String>,
+    user: Claims,
+) -> impl Responder {
+    if !is_authorized_admin_only(admin_user_id.into_inner(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    HttpResponse::Ok().json(json!({
+        "templates": EmailTemplate::ALL,
+        "locales": LOCALES,
+        "default_locale": DEFAULT_LOCALE,
+    }))
+}
+
+#[tracing::instrument(
+    name = "Preview an email template (admin only)",
+    skip(path, query, user)
+)]
+pub async fn preview_email_template_admin(
+    path: web::Path<(String, String)>,
+    query: web::Query<TemplatePreviewQuery>,
+    user: Claims,
+) -> impl Responder {
+    let (admin_user_id, template) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id, user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let template = match EmailTemplate::from_str(&template) {
+        Ok(template) => template,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+    let locale = supported_locale(query.locale.as_deref().unwrap_or(DEFAULT_LOCALE));
+
+    let email = match render_email(template, locale, template.sample_context()) {
+        Ok(email) => email,
+        Err(e) => {
+            tracing::error!("Could not render {}: {:?}", template.as_str(), e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+    match query.format.as_deref() {
+        Some("html") => HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .body(email.html_content),
+        Some("json") | None => HttpResponse::Ok().json(json!({
+            "template": template,
+            "locale": locale,
+            "subject": email.subject,
+            "html_content": email.html_content,
+            "text_content": email.text_content,
+        })),
+        Some(_) => HttpResponse::BadRequest().finish(),
+    }
+}
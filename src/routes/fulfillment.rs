@@ -0,0 +1,213 @@
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{web, HttpResponse, HttpResponseBuilder, Responder};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::authorization::{is_authorized_admin_only, is_authorized_fulfillment_or_admin};
+use crate::auth::request_metadata::RequestMetadata;
+use crate::auth::token::Claims;
+use crate::db::fulfillment_db_broker::{
+    get_fulfillment_run, get_fulfillment_run_entries, get_fulfillment_runs, insert_fulfillment_run,
+};
+use crate::db::subscriptions_db_broker::retrieve_subscriptions_active_at;
+use crate::domain::audit_models::{AuditAction, AuditEvent};
+use crate::domain::fulfillment_models::{
+    build_mailing_list, render_csv, render_fixed_width, CreateFulfillmentRun, ExportFormat,
+    FulfillmentEntry, FulfillmentRun,
+};
+use crate::domain::mailing_label_models::{render_labels, LabelLayout};
+use crate::domain::subscription_models::SubscriptionType;
+use crate::routes::audit::record_audit_event;
+
+#[derive(Deserialize, Debug)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LabelsQuery {
+    #[serde(default)]
+    pub layout: LabelLayout,
+}
+
+#[tracing::instrument(
+    name = "Create a fulfillment run (admin only)",
+    skip(admin_user_id, create_fulfillment_run, pool, user, metadata),
+    fields(issue_date = %create_fulfillment_run.issue_date)
+)]
+pub async fn create_fulfillment_run_admin(
+    admin_user_id: web::Path<String>,
+    create_fulfillment_run: web::Json<CreateFulfillmentRun>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let admin_user_id = admin_user_id.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    match store_fulfillment_run(&create_fulfillment_run, &admin_user_id, &metadata, &pool).await {
+        Ok((run, _)) => HttpResponse::Ok().json(run),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Get fulfillment runs (admin or fulfillment)",
+    skip(admin_user_id, pool, user)
+)]
+pub async fn get_fulfillment_runs_admin(
+    admin_user_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    if !is_authorized_fulfillment_or_admin(admin_user_id.into_inner(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    match get_fulfillment_runs(&pool).await {
+        Ok(runs) => HttpResponse::Ok().json(runs),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Export a fulfillment run (admin or fulfillment)",
+    skip(path, pool, user)
+)]
+pub async fn export_fulfillment_run_admin(
+    path: web::Path<(String, String)>,
+    query: web::Query<ExportQuery>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    let (admin_user_id, run_id) = path.into_inner();
+    if !is_authorized_fulfillment_or_admin(admin_user_id, user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let (run, entries) = match load_fulfillment_run(&run_id, &pool).await {
+        Ok(run_and_entries) => run_and_entries,
+        Err(response) => return response,
+    };
+
+    let (content_type, extension, body) = match query.format {
+        ExportFormat::Json => {
+            return HttpResponse::Ok().json(json!({ "run": run, "entries": entries }))
+        }
+        ExportFormat::Csv => ("text/csv; charset=utf-8", "csv", render_csv(&entries)),
+        ExportFormat::FixedWidth => (
+            "text/plain; charset=utf-8",
+            "txt",
+            render_fixed_width(&entries),
+        ),
+    };
+    attachment(
+        content_type,
+        format!("mailing-list-{}.{}", run.issue_date, extension),
+    )
+    .body(body)
+}
+
+#[tracing::instrument(
+    name = "Print mailing labels for a fulfillment run (admin or fulfillment)",
+    skip(path, pool, user)
+)]
+pub async fn get_fulfillment_run_labels(
+    path: web::Path<(String, String)>,
+    query: web::Query<LabelsQuery>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    let (user_id, run_id) = path.into_inner();
+    if !is_authorized_fulfillment_or_admin(user_id, user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let (run, entries) = match load_fulfillment_run(&run_id, &pool).await {
+        Ok(run_and_entries) => run_and_entries,
+        Err(response) => return response,
+    };
+
+    let title = format!("Mailing labels {}", run.issue_date);
+    match render_labels(&entries, query.layout, &title) {
+        Ok(pdf) => attachment(
+            "application/pdf",
+            format!("mailing-labels-{}.pdf", run.issue_date),
+        )
+        .body(pdf),
+        Err(e) => {
+            tracing::error!("Failed to render mailing labels: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+async fn load_fulfillment_run(
+    run_id: &str,
+    pool: &PgPool,
+) -> Result<(FulfillmentRun, Vec<FulfillmentEntry>), HttpResponse> {
+    let run_id = Uuid::parse_str(run_id).map_err(|_| HttpResponse::BadRequest().finish())?;
+    let run = match get_fulfillment_run(run_id, pool).await {
+        Ok(run) => run,
+        Err(sqlx::Error::RowNotFound) => return Err(HttpResponse::NotFound().finish()),
+        Err(_) => return Err(HttpResponse::InternalServerError().finish()),
+    };
+    let entries = get_fulfillment_run_entries(run_id, pool)
+        .await
+        .map_err(|_| HttpResponse::InternalServerError().finish())?;
+    Ok((run, entries))
+}
+
+fn attachment(content_type: &str, filename: String) -> HttpResponseBuilder {
+    let mut response = HttpResponse::Ok();
+    response
+        .content_type(content_type)
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(filename)],
+        });
+    response
+}
+
+/// Builds and stores the mailing list of Paper subscriptions that were active at
+/// the run's cutoff.
+pub async fn store_fulfillment_run(
+    create_fulfillment_run: &CreateFulfillmentRun,
+    actor: &str,
+    metadata: &RequestMetadata,
+    pool: &PgPool,
+) -> Result<(FulfillmentRun, Vec<FulfillmentEntry>), sqlx::Error> {
+    let cutoff = create_fulfillment_run.cutoff();
+    let subscriptions =
+        retrieve_subscriptions_active_at(&SubscriptionType::Paper, cutoff, pool).await?;
+    let subscription_count = subscriptions.len();
+    let entries = build_mailing_list(subscriptions);
+
+    let run = FulfillmentRun {
+        id: Uuid::new_v4(),
+        issue_date: create_fulfillment_run.issue_date,
+        cutoff,
+        created_at: Utc::now(),
+        created_by: Uuid::parse_str(actor).ok(),
+        recipient_count: entries.len() as i32,
+        duplicate_count: (subscription_count - entries.len()) as i32,
+    };
+    insert_fulfillment_run(&run, &entries, pool).await?;
+
+    record_audit_event(
+        AuditEvent::new(actor, AuditAction::CreateFulfillmentRun)
+            .with_target("fulfillment_run", run.id)
+            .with_payload(json!({
+                "issue_date": run.issue_date,
+                "recipient_count": run.recipient_count,
+            })),
+        metadata,
+        pool,
+    )
+    .await;
+    Ok((run, entries))
+}
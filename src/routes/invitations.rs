@@ -0,0 +1,300 @@
+use actix_web::{web, HttpResponse, Responder};
+use chrono::{Duration, Utc};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::authorization::is_authorized_admin_only;
+use crate::auth::password_hashing::hash_password;
+use crate::auth::request_metadata::RequestMetadata;
+use crate::auth::token::Claims;
+use crate::background::email_outbox_worker::send_queued_email;
+use crate::configuration::get_configuration;
+use crate::db::email_outbox_db_broker::queue_email;
+use crate::db::invitation_db_broker::{
+    consume_invitation, get_pending_invitations, insert_invitation, revoke_invitation,
+    set_invitation_accepted_user,
+};
+use crate::db::subscribers_db_broker::insert_subscriber;
+use crate::db::users::{count_users_with_email_address, insert_user};
+use crate::domain::audit_models::{AuditAction, AuditEvent};
+use crate::domain::email_outbox_models::OutboxEmail;
+use crate::domain::invitation_models::{AcceptInvitation, CreateInvitation, Invitation};
+use crate::domain::session_models::LoginMethod;
+use crate::domain::subscriber_models::NewSubscriber;
+use crate::domain::valid_email::ValidEmail;
+use crate::domain::valid_name::ValidName;
+use crate::email_client::templates::{render_email, EmailTemplate, DEFAULT_LOCALE};
+use crate::email_client::EmailClient;
+use crate::routes::audit::record_audit_event;
+use crate::routes::sessions::start_session;
+use crate::util::{generate_random_token, hash_token, standardize_email};
+
+#[tracing::instrument(
+    name = "Invite a new user (admin only)",
+    skip(admin_user_id, create_invitation, pool, user, email_client, metadata),
+    fields(
+        user_username = %create_invitation.email_address,
+    )
+)]
+pub async fn create_invitation(
+    admin_user_id: web::Path<String>,
+    create_invitation: web::Json<CreateInvitation>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    email_client: web::Data<EmailClient>,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let admin_user_id = admin_user_id.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let email_address = match ValidEmail::parse(standardize_email(&create_invitation.email_address))
+    {
+        Ok(email_address) => email_address,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+    match count_users_with_email_address(email_address.as_ref(), &pool).await {
+        Ok(0) => {}
+        Ok(_) => return HttpResponse::Conflict().finish(),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    }
+
+    let now = Utc::now();
+    let invitation = Invitation {
+        id: Uuid::new_v4(),
+        email_address: email_address.as_ref().to_string(),
+        user_group: create_invitation.user_group.clone(),
+        invited_by: Uuid::parse_str(&admin_user_id).ok(),
+        created_at: now,
+        expires_at: now + Duration::hours(create_invitation.lifetime_hours()),
+        accepted_at: None,
+        accepted_user_id: None,
+        revoked_at: None,
+    };
+    let token = generate_random_token();
+
+    let mut transaction = match pool.begin().await {
+        Ok(transaction) => transaction,
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+    if insert_invitation(&invitation, &hash_token(&token), &mut transaction)
+        .await
+        .is_err()
+    {
+        transaction.rollback().await.unwrap();
+        return HttpResponse::InternalServerError().finish();
+    }
+    let email = match invitation_email(&invitation, &token) {
+        Some(email) => email,
+        None => {
+            transaction.rollback().await.unwrap();
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+    if queue_email(&email, &mut transaction).await.is_err() {
+        transaction.rollback().await.unwrap();
+        return HttpResponse::InternalServerError().finish();
+    }
+    if transaction.commit().await.is_err() {
+        return HttpResponse::InternalServerError().finish();
+    }
+    send_queued_email(email.id, &email_client, &pool).await;
+
+    record_audit_event(
+        AuditEvent::new(&admin_user_id, AuditAction::CreateInvitation)
+            .with_target("invitation", invitation.id)
+            .with_payload(json!({
+                "email_address": invitation.email_address,
+                "user_group": invitation.user_group.as_str(),
+                "expires_at": invitation.expires_at,
+            })),
+        &metadata,
+        &pool,
+    )
+    .await;
+    HttpResponse::Ok().json(invitation)
+}
+
+#[tracing::instrument(
+    name = "Get pending invitations (admin only)",
+    skip(admin_user_id, pool, user)
+)]
+pub async fn get_pending_invitations_admin(
+    admin_user_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    if !is_authorized_admin_only(admin_user_id.into_inner(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    match get_pending_invitations(&pool).await {
+        Ok(invitations) => HttpResponse::Ok().json(invitations),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Revoke an invitation (admin only)",
+    skip(path, pool, user, metadata)
+)]
+pub async fn revoke_invitation_admin(
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, invitation_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let invitation_id = match Uuid::parse_str(&invitation_id) {
+        Ok(invitation_id) => invitation_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+
+    match revoke_invitation(invitation_id, &pool).await {
+        Ok(true) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::RevokeInvitation)
+                    .with_target("invitation", invitation_id),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(json!({}))
+        }
+        Ok(false) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Accept an invitation",
+    skip(accept_invitation, pool, email_client, metadata)
+)]
+pub async fn accept_invitation(
+    accept_invitation: web::Json<AcceptInvitation>,
+    pool: web::Data<PgPool>,
+    email_client: web::Data<EmailClient>,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let accept_invitation = accept_invitation.into_inner();
+    let name = match ValidName::parse(accept_invitation.name) {
+        Ok(name) => name,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+
+    let mut transaction = match pool.begin().await {
+        Ok(transaction) => transaction,
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+    let invitation =
+        match consume_invitation(&hash_token(&accept_invitation.token), &mut transaction).await {
+            Ok(invitation) => invitation,
+            Err(sqlx::Error::RowNotFound) => {
+                transaction.rollback().await.unwrap();
+                return HttpResponse::BadRequest().finish();
+            }
+            Err(_) => {
+                transaction.rollback().await.unwrap();
+                return HttpResponse::InternalServerError().finish();
+            }
+        };
+
+    let hashed_password = hash_password(accept_invitation.password).await;
+    let user_id = match insert_user(
+        &invitation.email_address,
+        &hashed_password,
+        invitation.user_group.clone(),
+        &mut transaction,
+    )
+    .await
+    {
+        Ok(user_id) => user_id,
+        // The address may have signed up on its own since the invitation was sent.
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            transaction.rollback().await.unwrap();
+            return HttpResponse::Conflict().finish();
+        }
+        Err(_) => {
+            transaction.rollback().await.unwrap();
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+    let user_uuid = Uuid::parse_str(&user_id).unwrap();
+
+    let new_subscriber = NewSubscriber {
+        name,
+        email_address: ValidEmail::parse(invitation.email_address.clone()).unwrap(),
+        user_id: user_id.clone(),
+    };
+    if insert_subscriber(&new_subscriber, &mut transaction)
+        .await
+        .is_err()
+        || set_invitation_accepted_user(invitation.id, user_uuid, &mut transaction)
+            .await
+            .is_err()
+    {
+        transaction.rollback().await.unwrap();
+        return HttpResponse::InternalServerError().finish();
+    }
+    if transaction.commit().await.is_err() {
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    record_audit_event(
+        AuditEvent::new(&user_id, AuditAction::AcceptInvitation)
+            .with_target("invitation", invitation.id)
+            .with_payload(json!({ "user_group": invitation.user_group.as_str() })),
+        &metadata,
+        &pool,
+    )
+    .await;
+
+    match start_session(
+        user_uuid,
+        invitation.user_group,
+        &invitation.email_address,
+        LoginMethod::Invitation,
+        &metadata,
+        &pool,
+        &email_client,
+    )
+    .await
+    {
+        Ok(login_response) => HttpResponse::Ok().json(&login_response),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// None when the invitation cannot be addressed or the link cannot be built.
+pub fn invitation_email(invitation: &Invitation, token: &str) -> Option<OutboxEmail> {
+    let recipient = match ValidEmail::parse(invitation.email_address.clone()) {
+        Ok(recipient) => recipient,
+        Err(e) => {
+            tracing::error!("Cannot email invitation {}: {}", invitation.id, e);
+            return None;
+        }
+    };
+    let web_app_hostname = match get_configuration() {
+        Ok(configuration) => configuration.application.web_app_host,
+        Err(e) => {
+            tracing::error!("Cannot build the invitation link: {:?}", e);
+            return None;
+        }
+    };
+    let link = format!("{}/accept-invitation?token={}", web_app_hostname, token);
+    let email = render_email(
+        EmailTemplate::Invitation,
+        DEFAULT_LOCALE,
+        json!({
+            "link": link,
+            "expires_on": invitation.expires_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+        }),
+    )
+    .expect("Email templates render");
+
+    Some(OutboxEmail::from_template(vec![recipient], email))
+}
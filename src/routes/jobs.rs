@@ -0,0 +1,67 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::authorization::is_authorized_admin_only;
+use crate::auth::request_metadata::RequestMetadata;
+use crate::auth::token::Claims;
+use crate::db::jobs_db_broker::{get_job, get_jobs, retry_job};
+use crate::domain::audit_models::{AuditAction, AuditEvent};
+use crate::domain::job_models::JobQuery;
+use crate::routes::audit::record_audit_event;
+
+const MAX_JOBS: i64 = 200;
+
+#[tracing::instrument(name = "Get jobs (admin only)", skip(admin_user_id, query, pool, user))]
+pub async fn get_jobs_admin(
+    admin_user_id: web::Path<String>,
+    query: web::Query<JobQuery>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    if !is_authorized_admin_only(admin_user_id.into_inner(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    match get_jobs(query.status, query.kind.as_deref(), MAX_JOBS, &pool).await {
+        Ok(jobs) => HttpResponse::Ok().json(jobs),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(name = "Retry a job (admin only)", skip(path, pool, user, metadata))]
+pub async fn retry_job_admin(
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, job_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let job_id = match Uuid::parse_str(&job_id) {
+        Ok(job_id) => job_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+
+    match retry_job(job_id, &pool).await {
+        Ok(true) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::RetryJob).with_target("job", job_id),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(json!({}))
+        }
+        // Either there is no such job, or it is running or done.
+        Ok(false) => match get_job(job_id, &pool).await {
+            Ok(_) => HttpResponse::Conflict().finish(),
+            Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().finish(),
+            Err(_) => HttpResponse::InternalServerError().finish(),
+        },
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
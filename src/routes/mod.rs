@@ -1,12 +1,48 @@
+pub use audit::*;
+pub use broadcasts::*;
+pub use dev_emails::*;
+pub use email_outbox::*;
+pub use email_preferences::*;
+pub use email_suppressions::*;
+pub use email_templates::*;
+pub use fulfillment::*;
 pub use health_check::*;
+pub use invitations::*;
+pub use jobs::*;
+pub use newsletter::*;
+pub use notification_channels::*;
+pub use oidc::*;
 pub use payment::*;
+pub use publications::*;
+pub use sessions::*;
+pub use staff_digest::*;
 pub use subscribers::*;
+pub use subscription_import::*;
 pub use subscriptions::*;
 pub use users::*;
+pub use webhook_endpoints::*;
 
+pub mod audit;
+pub mod broadcasts;
+pub mod dev_emails;
+pub mod email_outbox;
+pub mod email_preferences;
+pub mod email_suppressions;
+pub mod email_templates;
+pub mod fulfillment;
 pub mod health_check;
+pub mod invitations;
+pub mod jobs;
+pub mod newsletter;
+pub mod notification_channels;
+pub mod oidc;
 pub mod payment;
+pub mod publications;
+pub mod sessions;
+pub mod staff_digest;
 pub mod stripe_webhook;
 pub mod subscribers;
+pub mod subscription_import;
 pub mod subscriptions;
 pub mod users;
+pub mod webhook_endpoints;
@@ -0,0 +1,150 @@
+use actix_web::{web, HttpResponse, Responder};
+use chrono::{DateTime, Duration, Utc};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::request_metadata::RequestMetadata;
+use crate::background::email_outbox_worker::send_queued_email;
+use crate::configuration::get_configuration;
+use crate::db::email_outbox_db_broker::queue_email;
+use crate::db::newsletter_db_broker::{
+    confirm_newsletter_member, upsert_pending_newsletter_member,
+};
+use crate::domain::email_outbox_models::OutboxEmail;
+use crate::domain::newsletter_models::{
+    ConfirmNewsletterSignup, NewsletterMember, NewsletterMemberStatus, NewsletterSignup,
+    NEWSLETTER_CONFIRMATION_LIFETIME_HOURS, NEWSLETTER_CONSENT_TEXT,
+};
+use crate::domain::valid_email::ValidEmail;
+use crate::domain::valid_name::ValidName;
+use crate::email_client::templates::{render_email, EmailTemplate, DEFAULT_LOCALE};
+use crate::email_client::EmailClient;
+use crate::util::{generate_random_token, hash_token, standardize_email};
+
+#[tracing::instrument(name = "Get the newsletter consent wording")]
+pub async fn get_newsletter_consent() -> impl Responder {
+    HttpResponse::Ok().json(json!({ "consent_text": NEWSLETTER_CONSENT_TEXT }))
+}
+
+/// Emails a confirmation link; the address only joins the list once it is
+/// followed. Answers the same whether or not the address is already a member.
+#[tracing::instrument(
+    name = "Sign up to the newsletter",
+    skip(signup, pool, email_client, metadata)
+)]
+pub async fn newsletter_subscribe(
+    signup: web::Json<NewsletterSignup>,
+    pool: web::Data<PgPool>,
+    email_client: web::Data<EmailClient>,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let signup = signup.into_inner();
+    let name = match ValidName::parse(signup.name) {
+        Ok(name) => name,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+    let email_address = match ValidEmail::parse(standardize_email(signup.email_address.trim())) {
+        Ok(email_address) => email_address,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+    // Consent only counts for the wording the form actually showed.
+    if signup.consent_text != NEWSLETTER_CONSENT_TEXT {
+        return HttpResponse::BadRequest().finish();
+    }
+
+    let now = Utc::now();
+    let member = NewsletterMember {
+        id: Uuid::new_v4(),
+        email_address: email_address.as_ref().to_string(),
+        name: name.as_ref().to_string(),
+        status: NewsletterMemberStatus::Pending,
+        consent_text: signup.consent_text,
+        consent_ip_address: metadata.ip_address,
+        consent_user_agent: metadata.user_agent,
+        consented_at: now,
+        confirmed_at: None,
+        confirmed_ip_address: None,
+        created_at: now,
+    };
+    let token = generate_random_token();
+    let expires_at = now + Duration::hours(NEWSLETTER_CONFIRMATION_LIFETIME_HOURS);
+
+    let mut transaction = match pool.begin().await {
+        Ok(transaction) => transaction,
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+    let pending = match upsert_pending_newsletter_member(
+        &member,
+        &hash_token(&token),
+        expires_at,
+        &mut transaction,
+    )
+    .await
+    {
+        Ok(pending) => pending,
+        Err(_) => {
+            transaction.rollback().await.unwrap();
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+    let email =
+        pending.then(|| confirmation_email(email_address, &member.name, &token, expires_at));
+    if let Some(email) = &email {
+        if queue_email(email, &mut transaction).await.is_err() {
+            transaction.rollback().await.unwrap();
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+    if transaction.commit().await.is_err() {
+        return HttpResponse::InternalServerError().finish();
+    }
+    if let Some(email) = email {
+        send_queued_email(email.id, &email_client, &pool).await;
+    }
+
+    HttpResponse::Ok().json(json!({}))
+}
+
+#[tracing::instrument(name = "Confirm a newsletter sign-up", skip(confirm, pool, metadata))]
+pub async fn newsletter_confirm(
+    confirm: web::Json<ConfirmNewsletterSignup>,
+    pool: web::Data<PgPool>,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    match confirm_newsletter_member(
+        &hash_token(&confirm.token),
+        metadata.ip_address.as_deref(),
+        &pool,
+    )
+    .await
+    {
+        Ok(member) => HttpResponse::Ok().json(json!({
+            "email_address": member.email_address,
+            "status": member.status,
+        })),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::BadRequest().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+fn confirmation_email(
+    email_address: ValidEmail,
+    name: &str,
+    token: &str,
+    expires_at: DateTime<Utc>,
+) -> OutboxEmail {
+    let web_app_hostname = get_configuration().unwrap().application.web_app_host;
+    let email = render_email(
+        EmailTemplate::NewsletterConfirmation,
+        DEFAULT_LOCALE,
+        json!({
+            "name": name,
+            "link": format!("{}/newsletter/confirm?token={}", web_app_hostname, token),
+            "expires_on": expires_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+        }),
+    )
+    .expect("Email templates render");
+
+    OutboxEmail::from_template(vec![email_address], email)
+}
@@ -0,0 +1,155 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::authorization::is_authorized_admin_only;
+use crate::auth::request_metadata::RequestMetadata;
+use crate::auth::token::Claims;
+use crate::db::notification_channels_db_broker::{
+    delete_notification_channel, get_notification_channels, insert_notification_channel,
+    update_notification_channel,
+};
+use crate::domain::audit_models::{AuditAction, AuditEvent};
+use crate::domain::notification_channel_models::{NotificationChannel, NotificationChannelRequest};
+use crate::routes::audit::record_audit_event;
+
+#[tracing::instrument(
+    name = "Create a notification channel (admin only)",
+    skip(admin_user_id, channel, pool, user, metadata),
+    fields(name = %channel.name)
+)]
+pub async fn create_notification_channel_admin(
+    admin_user_id: web::Path<String>,
+    channel: web::Json<NotificationChannelRequest>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let admin_user_id = admin_user_id.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    if let Err(e) = channel.validate() {
+        return HttpResponse::BadRequest().json(json!({ "error": e }));
+    }
+
+    let channel = NotificationChannel::new(&channel);
+    match insert_notification_channel(&channel, &pool).await {
+        Ok(_) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::CreateNotificationChannel)
+                    .with_target("notification_channel", channel.id)
+                    .with_payload(json!({
+                        "name": channel.name,
+                        "kind": channel.kind,
+                        "event_types": channel.event_types,
+                    })),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(channel)
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Get notification channels (admin only)",
+    skip(admin_user_id, pool, user)
+)]
+pub async fn get_notification_channels_admin(
+    admin_user_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    if !is_authorized_admin_only(admin_user_id.into_inner(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    match get_notification_channels(&pool).await {
+        Ok(channels) => HttpResponse::Ok().json(channels),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Update a notification channel (admin only)",
+    skip(path, channel, pool, user, metadata)
+)]
+pub async fn update_notification_channel_admin(
+    path: web::Path<(String, String)>,
+    channel: web::Json<NotificationChannelRequest>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, channel_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let channel_id = match Uuid::parse_str(&channel_id) {
+        Ok(channel_id) => channel_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+    if let Err(e) = channel.validate() {
+        return HttpResponse::BadRequest().json(json!({ "error": e }));
+    }
+
+    match update_notification_channel(channel_id, &channel, &pool).await {
+        Ok(channel) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::UpdateNotificationChannel)
+                    .with_target("notification_channel", channel.id)
+                    .with_payload(json!({
+                        "name": channel.name,
+                        "kind": channel.kind,
+                        "event_types": channel.event_types,
+                        "enabled": channel.enabled,
+                    })),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(channel)
+        }
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Delete a notification channel (admin only)",
+    skip(path, pool, user, metadata)
+)]
+pub async fn delete_notification_channel_admin(
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, channel_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let channel_id = match Uuid::parse_str(&channel_id) {
+        Ok(channel_id) => channel_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+
+    match delete_notification_channel(channel_id, &pool).await {
+        Ok(true) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::DeleteNotificationChannel)
+                    .with_target("notification_channel", channel_id),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(json!({}))
+        }
+        Ok(false) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
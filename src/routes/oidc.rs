@@ -0,0 +1,293 @@
+use actix_web::{web, HttpResponse, Responder};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use tracing::Level;
+
+use crate::auth::password_hashing::hash_password;
+use crate::auth::request_metadata::RequestMetadata;
+use crate::db::oidc_db_broker::{
+    consume_oidc_login_state, get_user_identity_by_provider_and_subject, insert_oidc_login_state,
+    insert_user_identity,
+};
+use crate::db::subscribers_db_broker::insert_subscriber;
+use crate::db::users::{get_user_by_email_address, get_user_by_user_id, insert_user};
+use crate::domain::oidc_models::{OidcCallback, OidcLoginRedirect, OidcLoginState};
+use crate::domain::session_models::{LoginFailureReason, LoginMethod};
+use crate::domain::subscriber_models::NewSubscriber;
+use crate::domain::user_models::{User, UserGroup};
+use crate::domain::valid_email::ValidEmail;
+use crate::domain::valid_name::ValidName;
+use crate::email_client::EmailClient;
+use crate::oidc_client::oidc_provider_models::IdTokenClaims;
+use crate::oidc_client::{OidcClient, OidcError};
+use crate::routes::sessions::{record_failed_login, start_session};
+use crate::util::{generate_random_token, standardize_email};
+
+#[tracing::instrument(name = "Start an OIDC login", skip(pool, oidc_client))]
+pub async fn oidc_login(
+    pool: web::Data<PgPool>,
+    oidc_client: web::Data<Option<OidcClient>>,
+) -> impl Responder {
+    let oidc_client = match oidc_client.as_ref() {
+        Some(client) => client,
+        None => return HttpResponse::NotFound().finish(),
+    };
+
+    let discovery_document = match oidc_client.get_discovery_document().await {
+        Ok(document) => document,
+        Err(err) => {
+            tracing::event!(Level::ERROR, "Err: {:?}", err);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let login_state = OidcLoginState {
+        state: generate_random_token(),
+        code_verifier: generate_random_token(),
+        nonce: generate_random_token(),
+        issued_on: Utc::now(),
+        expires_on: Utc::now() + Duration::minutes(10),
+        used: false,
+    };
+
+    match insert_oidc_login_state(&login_state, &pool).await {
+        Ok(_) => HttpResponse::Ok().json(OidcLoginRedirect {
+            location: oidc_client.authorization_url(
+                &discovery_document,
+                &login_state.state,
+                &login_state.nonce,
+                &login_state.code_verifier,
+            ),
+        }),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Complete an OIDC login",
+    skip(callback, pool, oidc_client, email_client, metadata)
+)]
+pub async fn oidc_callback(
+    callback: web::Query<OidcCallback>,
+    pool: web::Data<PgPool>,
+    oidc_client: web::Data<Option<OidcClient>>,
+    email_client: web::Data<EmailClient>,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let oidc_client = match oidc_client.as_ref() {
+        Some(client) => client,
+        None => return HttpResponse::NotFound().finish(),
+    };
+
+    let login_state = match consume_oidc_login_state(&callback.state, &pool).await {
+        Ok(login_state) => login_state,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+
+    let discovery_document = match oidc_client.get_discovery_document().await {
+        Ok(document) => document,
+        Err(err) => {
+            tracing::event!(Level::ERROR, "Err: {:?}", err);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let token_response = match oidc_client
+        .exchange_code(
+            &discovery_document,
+            &callback.code,
+            &login_state.code_verifier,
+        )
+        .await
+    {
+        Ok(token_response) => token_response,
+        Err(err) => {
+            tracing::event!(Level::ERROR, "Err: {:?}", err);
+            return HttpResponse::BadRequest().finish();
+        }
+    };
+
+    let claims = match oidc_client
+        .validate_id_token(
+            &discovery_document,
+            &token_response.id_token,
+            &login_state.nonce,
+        )
+        .await
+    {
+        Ok(claims) => claims,
+        Err(OidcError::Provider(err)) => {
+            tracing::event!(Level::ERROR, "Err: {:?}", err);
+            return HttpResponse::InternalServerError().finish();
+        }
+        Err(err) => {
+            tracing::event!(Level::ERROR, "{}", err);
+            return HttpResponse::BadRequest().finish();
+        }
+    };
+
+    match find_or_create_user(oidc_client.provider_name(), &claims, &pool).await {
+        Ok(user) if user.disabled => {
+            record_failed_login(
+                Some(user.user_id),
+                Some(user.email_address),
+                LoginMethod::Oidc,
+                LoginFailureReason::Disabled,
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Forbidden().finish()
+        }
+        Ok(user) => match start_session(
+            user.user_id,
+            user.user_group,
+            &user.email_address,
+            LoginMethod::Oidc,
+            &metadata,
+            &pool,
+            &email_client,
+        )
+        .await
+        {
+            Ok(login_response) => HttpResponse::Ok().json(login_response),
+            Err(_) => HttpResponse::InternalServerError().finish(),
+        },
+        Err(response) => response,
+    }
+}
+
+/// Resolves the provider subject to one of our users. A known subject logs straight in, a
+/// verified email that already has a USER account gets linked to it, and anyone else gets a new
+/// account (and subscriber) just like `sign_up` would create. Admin and staff accounts are never
+/// linked this way: whoever controls the address at the provider would get their privileges.
+async fn find_or_create_user(
+    provider: &str,
+    claims: &IdTokenClaims,
+    pool: &PgPool,
+) -> Result<User, HttpResponse> {
+    if let Ok(identity) =
+        get_user_identity_by_provider_and_subject(provider, &claims.sub, pool).await
+    {
+        return get_user_by_user_id(&identity.user_id.to_string(), pool)
+            .await
+            .map_err(|_| HttpResponse::InternalServerError().finish());
+    }
+
+    let email = match (&claims.email, claims.email_verified) {
+        (Some(email), Some(true)) => standardize_email(email),
+        _ => {
+            tracing::event!(
+                Level::ERROR,
+                "The OIDC provider did not supply a verified email address"
+            );
+            return Err(HttpResponse::BadRequest().finish());
+        }
+    };
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .map_err(|_| HttpResponse::InternalServerError().finish())?;
+
+    let user =
+        match get_user_by_email_address(&email, pool).await {
+            Ok(user) if user.user_group != UserGroup::USER => {
+                tracing::event!(
+                    Level::ERROR,
+                    "Refusing to link an OIDC identity to a {} account",
+                    user.user_group.as_str()
+                );
+                return Err(HttpResponse::Conflict().finish());
+            }
+            Ok(user) => user,
+            Err(_) => {
+                let valid_email = ValidEmail::parse(email.clone())
+                    .map_err(|_| HttpResponse::BadRequest().finish())?;
+                let name = claims
+                    .name
+                    .clone()
+                    .and_then(|name| ValidName::parse(name).ok())
+                    .or_else(|| name_from_email(&email))
+                    .ok_or_else(|| HttpResponse::BadRequest().finish())?;
+
+                let hashed_password = hash_password(generate_random_token()).await;
+                let user_id =
+                    match insert_user(&email, &hashed_password, UserGroup::USER, &mut transaction)
+                        .await
+                    {
+                        Ok(user_id) => user_id,
+                        Err(_) => {
+                            transaction.rollback().await.unwrap();
+                            return Err(HttpResponse::InternalServerError().finish());
+                        }
+                    };
+
+                let new_subscriber = NewSubscriber {
+                    email_address: valid_email,
+                    name,
+                    user_id: user_id.clone(),
+                };
+                if insert_subscriber(&new_subscriber, &mut transaction)
+                    .await
+                    .is_err()
+                {
+                    transaction.rollback().await.unwrap();
+                    return Err(HttpResponse::InternalServerError().finish());
+                }
+
+                User {
+                    user_id: uuid::Uuid::parse_str(&user_id).unwrap(),
+                    email_address: email.clone(),
+                    password: hashed_password,
+                    user_group: UserGroup::USER,
+                    disabled: false,
+                    password_reset_required: false,
+                }
+            }
+        };
+
+    if insert_user_identity(
+        user.user_id,
+        provider,
+        &claims.sub,
+        &email,
+        &mut transaction,
+    )
+    .await
+    .is_err()
+    {
+        transaction.rollback().await.unwrap();
+        return Err(HttpResponse::InternalServerError().finish());
+    }
+
+    transaction
+        .commit()
+        .await
+        .map_err(|_| HttpResponse::InternalServerError().finish())?;
+
+    Ok(user)
+}
+
+/// Falls back to the local part of the email, minus anything a name may not contain, for
+/// providers that do not share a usable name.
+fn name_from_email(email: &str) -> Option<ValidName> {
+    let local_part = email.split('@').next().unwrap_or_default();
+    let name: String = local_part
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '.' | '-' | '_' | '+'))
+        .collect();
+    ValidName::parse(name).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::routes::oidc::name_from_email;
+
+    #[test]
+    fn name_from_email_drops_characters_a_name_may_not_contain() {
+        assert_eq!("ab", name_from_email("a/b@example.com").unwrap().as_ref());
+        assert_eq!("x", name_from_email("\"x\"@example.com").unwrap().as_ref());
+        assert!(name_from_email("\"/\"@example.com").is_none());
+    }
+}
@@ -5,13 +5,17 @@ use sqlx::PgPool;
 use tracing::Level;
 
 use crate::auth::token::Claims;
-use crate::background::new_subscription_notifier::notify_subscriber;
-use crate::background::subscription_history_storer::store_subscription_history_event;
+use crate::background::email_outbox_worker::send_queued_email;
+use crate::background::new_subscription_notifier::queue_new_subscription_notice;
+use crate::background::subscriber_emails::{format_price, subscription_confirmation_email};
+use crate::background::subscription_history_storer::queue_subscription_history_event;
 use crate::configuration::get_configuration;
 use crate::db::checkout_session_db_broker::{
     insert_checkout_session, retrieve_checkout_session_by_stripe_session_id,
     set_checkout_session_state_to_success_by_stripe_session_id,
 };
+use crate::db::email_outbox_db_broker::queue_email;
+use crate::db::publications_db_broker::get_publication;
 use crate::db::subscribers_db_broker::{
     retrieve_subscriber_by_id, retrieve_subscriber_by_user_id, set_stripe_customer_id,
 };
@@ -51,6 +55,14 @@ pub async fn create_checkout_session(
             Err(_) => return HttpResponse::BadRequest().finish(),
         };
 
+    if let Some(publication_id) = new_subscription.publication_id {
+        match get_publication(publication_id, &pool).await {
+            Ok(_) => {}
+            Err(sqlx::Error::RowNotFound) => return HttpResponse::BadRequest().finish(),
+            Err(_) => return HttpResponse::InternalServerError().finish(),
+        }
+    }
+
     //get the subscriber by id
     let subscriber = match retrieve_subscriber_by_id(
         from_string_to_uuid(new_subscription.subscriber_id.as_str()).unwrap(),
@@ -236,16 +248,41 @@ pub async fn complete_session(
 
             match subscription_result {
                 Ok(subscription) => {
+                    let price = format_price(
+                        stripe_session.amount_total,
+                        stripe_session.currency.as_deref(),
+                    );
+                    let email = subscription_confirmation_email(&subscription, Some(price));
+                    if let Some(email) = &email {
+                        if queue_email(email, &mut transaction).await.is_err() {
+                            transaction.rollback().await.unwrap();
+                            return HttpResponse::InternalServerError().finish();
+                        }
+                    }
+                    if queue_subscription_history_event(
+                        &subscription,
+                        HistoryEventType::Created,
+                        &mut transaction,
+                    )
+                    .await
+                    .is_err()
+                    {
+                        transaction.rollback().await.unwrap();
+                        return HttpResponse::InternalServerError().finish();
+                    }
+                    if queue_new_subscription_notice(subscription.id, &mut transaction)
+                        .await
+                        .is_err()
+                    {
+                        transaction.rollback().await.unwrap();
+                        return HttpResponse::InternalServerError().finish();
+                    }
                     if transaction.commit().await.is_err() {
                         HttpResponse::InternalServerError().finish()
                     } else {
-                        store_subscription_history_event(
-                            subscription.id,
-                            HistoryEventType::Created,
-                            &pool,
-                        );
-
-                        notify_subscriber(subscription.id, &email_client, &pool).await;
+                        if let Some(email) = email {
+                            send_queued_email(email.id, &email_client, &pool).await;
+                        }
 
                         HttpResponse::Ok().json(json!({}))
                     }
@@ -0,0 +1,329 @@
+use actix_web::{web, HttpResponse, Responder};
+use chrono::Utc;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::authorization::is_authorized_admin_only;
+use crate::auth::request_metadata::RequestMetadata;
+use crate::auth::token::Claims;
+use crate::db::publications_db_broker::{
+    delete_issue, delete_publication, get_issues_by_publication_id, get_publication,
+    get_publications, insert_issue, insert_publication, update_issue, update_publication,
+};
+use crate::domain::audit_models::{AuditAction, AuditEvent};
+use crate::domain::publication_models::{Issue, IssueRequest, Publication, PublicationRequest};
+use crate::routes::audit::record_audit_event;
+
+#[tracing::instrument(
+    name = "Create a publication (admin only)",
+    skip(admin_user_id, publication, pool, user, metadata),
+    fields(title = %publication.title)
+)]
+pub async fn create_publication_admin(
+    admin_user_id: web::Path<String>,
+    publication: web::Json<PublicationRequest>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let admin_user_id = admin_user_id.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    if publication.validate().is_err() {
+        return HttpResponse::BadRequest().finish();
+    }
+
+    let publication = Publication {
+        id: Uuid::new_v4(),
+        title: publication.title.trim().to_string(),
+        description: publication.description.clone(),
+        created_at: Utc::now(),
+    };
+    match insert_publication(&publication, &pool).await {
+        Ok(_) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::CreatePublication)
+                    .with_target("publication", publication.id)
+                    .with_payload(json!({ "title": publication.title })),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(publication)
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Get publications (admin only)",
+    skip(admin_user_id, pool, user)
+)]
+pub async fn get_publications_admin(
+    admin_user_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    if !is_authorized_admin_only(admin_user_id.into_inner(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    match get_publications(&pool).await {
+        Ok(publications) => HttpResponse::Ok().json(publications),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Update a publication (admin only)",
+    skip(path, publication, pool, user, metadata)
+)]
+pub async fn update_publication_admin(
+    path: web::Path<(String, String)>,
+    publication: web::Json<PublicationRequest>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, publication_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let publication_id = match Uuid::parse_str(&publication_id) {
+        Ok(publication_id) => publication_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+    if publication.validate().is_err() {
+        return HttpResponse::BadRequest().finish();
+    }
+
+    let request = PublicationRequest {
+        title: publication.title.trim().to_string(),
+        description: publication.description.clone(),
+    };
+    match update_publication(publication_id, &request, &pool).await {
+        Ok(publication) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::UpdatePublication)
+                    .with_target("publication", publication.id)
+                    .with_payload(json!({ "title": publication.title })),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(publication)
+        }
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Delete a publication (admin only)",
+    skip(path, pool, user, metadata)
+)]
+pub async fn delete_publication_admin(
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, publication_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let publication_id = match Uuid::parse_str(&publication_id) {
+        Ok(publication_id) => publication_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+
+    match delete_publication(publication_id, &pool).await {
+        Ok(true) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::DeletePublication)
+                    .with_target("publication", publication_id),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(json!({}))
+        }
+        Ok(false) => HttpResponse::NotFound().finish(),
+        // Subscriptions still link to it.
+        Err(e) if is_foreign_key_violation(&e) => HttpResponse::Conflict().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Create an issue (admin only)",
+    skip(path, issue, pool, user, metadata),
+    fields(issue_number = %issue.issue_number)
+)]
+pub async fn create_issue_admin(
+    path: web::Path<(String, String)>,
+    issue: web::Json<IssueRequest>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, publication_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let publication_id = match Uuid::parse_str(&publication_id) {
+        Ok(publication_id) => publication_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+    if issue.validate().is_err() {
+        return HttpResponse::BadRequest().finish();
+    }
+
+    let issue = Issue {
+        id: Uuid::new_v4(),
+        publication_id,
+        issue_number: issue.issue_number,
+        title: issue.title.clone(),
+        publish_date: issue.publish_date,
+        cutoff_date: issue.cutoff_date(),
+        digital_asset_reference: issue.digital_asset_reference.clone(),
+        created_at: Utc::now(),
+    };
+    match insert_issue(&issue, &pool).await {
+        Ok(_) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::CreateIssue)
+                    .with_target("issue", issue.id)
+                    .with_payload(json!({
+                        "publication_id": issue.publication_id,
+                        "issue_number": issue.issue_number,
+                    })),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(issue)
+        }
+        Err(e) if is_foreign_key_violation(&e) => HttpResponse::NotFound().finish(),
+        Err(e) if is_unique_violation(&e) => HttpResponse::Conflict().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Get the issues of a publication (admin only)",
+    skip(path, pool, user)
+)]
+pub async fn get_issues_admin(
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    let (admin_user_id, publication_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id, user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let publication_id = match Uuid::parse_str(&publication_id) {
+        Ok(publication_id) => publication_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+
+    match get_publication(publication_id, &pool).await {
+        Ok(_) => {}
+        Err(sqlx::Error::RowNotFound) => return HttpResponse::NotFound().finish(),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    }
+    match get_issues_by_publication_id(publication_id, &pool).await {
+        Ok(issues) => HttpResponse::Ok().json(issues),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Update an issue (admin only)",
+    skip(path, issue, pool, user, metadata)
+)]
+pub async fn update_issue_admin(
+    path: web::Path<(String, String)>,
+    issue: web::Json<IssueRequest>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, issue_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let issue_id = match Uuid::parse_str(&issue_id) {
+        Ok(issue_id) => issue_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+    if issue.validate().is_err() {
+        return HttpResponse::BadRequest().finish();
+    }
+
+    match update_issue(issue_id, &issue, &pool).await {
+        Ok(issue) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::UpdateIssue)
+                    .with_target("issue", issue.id)
+                    .with_payload(json!({
+                        "publication_id": issue.publication_id,
+                        "issue_number": issue.issue_number,
+                    })),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(issue)
+        }
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().finish(),
+        Err(e) if is_unique_violation(&e) => HttpResponse::Conflict().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Delete an issue (admin only)",
+    skip(path, pool, user, metadata)
+)]
+pub async fn delete_issue_admin(
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, issue_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let issue_id = match Uuid::parse_str(&issue_id) {
+        Ok(issue_id) => issue_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+
+    match delete_issue(issue_id, &pool).await {
+        Ok(true) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::DeleteIssue)
+                    .with_target("issue", issue_id),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(json!({}))
+        }
+        Ok(false) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+fn is_unique_violation(e: &sqlx::Error) -> bool {
+    matches!(e, sqlx::Error::Database(e) if e.is_unique_violation())
+}
+
+fn is_foreign_key_violation(e: &sqlx::Error) -> bool {
+    matches!(e, sqlx::Error::Database(e) if e.is_foreign_key_violation())
+}
@@ -0,0 +1,187 @@
+use actix_web::{web, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::request_metadata::RequestMetadata;
+use crate::auth::token::{
+    generate_session_token, get_expires_at, get_now_in_seconds, Claims, LoginResponse,
+};
+use crate::background::email_outbox_worker::send_queued_email;
+use crate::background::new_device_notifier::new_device_notice;
+use crate::db::email_outbox_db_broker::queue_email;
+use crate::db::session_db_broker::{
+    get_active_sessions_by_user_id, get_login_familiarity, insert_login_attempt,
+    insert_user_session, revoke_user_session,
+};
+use crate::domain::session_models::{
+    LoginAttempt, LoginFailureReason, LoginMethod, OverTheWireUserSession, UserSession,
+};
+use crate::domain::user_models::UserGroup;
+use crate::email_client::EmailClient;
+
+/// Stores a session for a user who has just proven who they are, records the login and
+/// hands back a token bound to that session. Logins from a device or network the user
+/// has not used before queue an email notice along with the session.
+pub async fn start_session(
+    user_id: Uuid,
+    user_group: UserGroup,
+    email_address: &str,
+    login_method: LoginMethod,
+    metadata: &RequestMetadata,
+    pool: &PgPool,
+    email_client: &EmailClient,
+) -> Result<LoginResponse, sqlx::Error> {
+    let familiarity = get_login_familiarity(
+        user_id,
+        metadata.ip_address.as_deref(),
+        metadata.user_agent.as_deref(),
+        pool,
+    )
+    .await;
+
+    let issued_at = get_now_in_seconds();
+    let expires_on = get_expires_at(Some(issued_at));
+    let session = UserSession {
+        id: Uuid::new_v4(),
+        user_id,
+        login_method,
+        ip_address: metadata.ip_address.clone(),
+        user_agent: metadata.user_agent.clone(),
+        created_at: Utc::now(),
+        expires_at: DateTime::from_timestamp(expires_on as i64, 0).unwrap(),
+        revoked_at: None,
+    };
+    let notice = match familiarity {
+        Ok(familiarity) if familiarity.is_new_device_or_network() => {
+            new_device_notice(email_address, login_method, metadata)
+        }
+        _ => None,
+    };
+    let mut transaction = pool.begin().await?;
+    insert_user_session(&session, &mut transaction).await?;
+    if let Some(notice) = &notice {
+        queue_email(notice, &mut transaction).await?;
+    }
+    transaction.commit().await?;
+
+    record_login_attempt(
+        LoginAttempt {
+            user_id: Some(user_id),
+            email_address: Some(email_address.to_string()),
+            login_method,
+            failure_reason: None,
+            session_id: Some(session.id),
+        },
+        metadata,
+        pool,
+    )
+    .await;
+
+    if let Some(notice) = notice {
+        send_queued_email(notice.id, email_client, pool).await;
+    }
+
+    Ok(LoginResponse {
+        user_id: user_id.to_string(),
+        token: generate_session_token(
+            user_id.to_string(),
+            user_group.clone(),
+            session.id,
+            issued_at,
+        ),
+        expires_on,
+        group: user_group,
+    })
+}
+
+pub async fn record_failed_login(
+    user_id: Option<Uuid>,
+    email_address: Option<String>,
+    login_method: LoginMethod,
+    failure_reason: LoginFailureReason,
+    metadata: &RequestMetadata,
+    pool: &PgPool,
+) {
+    record_login_attempt(
+        LoginAttempt {
+            user_id,
+            email_address,
+            login_method,
+            failure_reason: Some(failure_reason),
+            session_id: None,
+        },
+        metadata,
+        pool,
+    )
+    .await;
+}
+
+/// Login history is best effort; losing a row must not fail the login itself.
+async fn record_login_attempt(attempt: LoginAttempt, metadata: &RequestMetadata, pool: &PgPool) {
+    if let Err(e) = insert_login_attempt(
+        &attempt,
+        metadata.ip_address.as_deref(),
+        metadata.user_agent.as_deref(),
+        pool,
+    )
+    .await
+    {
+        tracing::error!("Failed to record a login attempt: {:?}", e);
+    }
+}
+
+#[tracing::instrument(
+    name = "Getting active sessions for a user",
+    skip(user_id, pool, user),
+    fields(user_id = %user_id)
+)]
+pub async fn get_user_sessions(
+    user_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    if *user_id != user.user_id {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let user_id = match Uuid::parse_str(&user_id) {
+        Ok(user_id) => user_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+
+    match get_active_sessions_by_user_id(user_id, &pool).await {
+        Ok(sessions) => {
+            let wire: Vec<OverTheWireUserSession> = sessions
+                .into_iter()
+                .map(|session| {
+                    OverTheWireUserSession::from_session(session, user.session_id.as_deref())
+                })
+                .collect();
+            HttpResponse::Ok().json(wire)
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(name = "Revoking a user session", skip(path, pool, user))]
+pub async fn revoke_user_session_by_id(
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    let (user_id, session_id) = path.into_inner();
+    if user_id != user.user_id {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let (user_id, session_id) = match (Uuid::parse_str(&user_id), Uuid::parse_str(&session_id)) {
+        (Ok(user_id), Ok(session_id)) => (user_id, session_id),
+        _ => return HttpResponse::BadRequest().finish(),
+    };
+
+    match revoke_user_session(user_id, session_id, &pool).await {
+        Ok(true) => HttpResponse::Ok().json(json!({})),
+        Ok(false) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
@@ -0,0 +1,43 @@
+use actix_web::{web, HttpResponse, Responder};
+use chrono::Utc;
+use sqlx::PgPool;
+
+use crate::auth::authorization::is_authorized_admin_only;
+use crate::auth::token::Claims;
+use crate::background::staff_digest::build_staff_digest;
+use crate::configuration::{get_configuration, DigestFrequency, StaffDigestSettings};
+use crate::domain::staff_digest_models::StaffDigestQuery;
+
+/// The digest for the period ending now, as the scheduler would build it.
+/// Nothing is sent or recorded.
+#[tracing::instrument(
+    name = "Preview the staff digest (admin only)",
+    skip(admin_user_id, query, pool, user)
+)]
+pub async fn preview_staff_digest_admin(
+    admin_user_id: web::Path<String>,
+    query: web::Query<StaffDigestQuery>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    if !is_authorized_admin_only(admin_user_id.into_inner(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    let configured = get_configuration()
+        .unwrap()
+        .application_feature_settings
+        .staff_digest
+        .unwrap_or_else(|| StaffDigestSettings::new(DigestFrequency::Daily));
+    let settings = match query.frequency {
+        Some(frequency) => StaffDigestSettings {
+            frequency,
+            ..configured
+        },
+        None => configured,
+    };
+    match build_staff_digest(&settings, Utc::now(), &pool).await {
+        Ok(digest) => HttpResponse::Ok().json(digest),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
@@ -6,17 +6,20 @@ use serde_json::json;
 use sqlx::PgPool;
 
 use crate::auth::authorization::is_authorized_admin_only;
+use crate::auth::request_metadata::RequestMetadata;
 use crate::auth::token::Claims;
 use crate::db::subscribers_db_broker::{
     insert_subscriber, retrieve_all_subscribers, retrieve_subscriber_by_email,
     retrieve_subscriber_by_id, retrieve_subscriber_by_user_id,
     retrieve_subscriber_by_user_id_and_email_address,
 };
+use crate::domain::audit_models::{AuditAction, AuditEvent};
 use crate::domain::subscriber_models::{
     NewSubscriber, OverTheWireCreateSubscriber, OverTheWireSubscriber,
 };
 use crate::domain::valid_email::ValidEmail;
 use crate::domain::valid_name::ValidName;
+use crate::routes::audit::record_audit_event;
 use crate::util::from_path_to_uuid;
 
 #[derive(Debug, Deserialize)]
@@ -188,20 +191,31 @@ async fn get_subscriber_by_email_and_user_id(
 
 #[tracing::instrument(
     name = "Getting all subscribers (admin only)",
-    skip(user_id, pool, user),
+    skip(user_id, pool, user, metadata),
     fields(user_id = %user_id)
 )]
 pub async fn get_all_subscribers_admin(
     user_id: web::Path<String>,
     pool: web::Data<PgPool>,
     user: Claims,
+    metadata: RequestMetadata,
 ) -> impl Responder {
-    if !is_authorized_admin_only(user_id.into_inner(), user) {
+    let user_id = user_id.into_inner();
+    if !is_authorized_admin_only(user_id.clone(), user) {
         return HttpResponse::Unauthorized().finish();
     }
 
     match retrieve_all_subscribers(&pool).await {
-        Ok(subscribers) => HttpResponse::Ok().json(subscribers),
+        Ok(subscribers) => {
+            record_audit_event(
+                AuditEvent::new(&user_id, AuditAction::ListSubscribers)
+                    .with_payload(json!({ "count": subscribers.len() })),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(subscribers)
+        }
         Err(_) => HttpResponse::InternalServerError().finish(),
     }
 }
@@ -0,0 +1,230 @@
+use std::collections::HashSet;
+
+use actix_web::{web, HttpResponse, Responder};
+use anyhow::anyhow;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::authorization::is_authorized_admin_only;
+use crate::auth::password_hashing::hash_password;
+use crate::auth::request_metadata::RequestMetadata;
+use crate::auth::token::Claims;
+use crate::background::subscription_history_storer::queue_subscription_history_event;
+use crate::db::subscribers_db_broker::{insert_subscriber, retrieve_subscriber_id_by_email};
+use crate::db::subscriptions_db_broker::{
+    count_active_subscriptions_by_email_and_type, insert_externally_billed_subscription,
+};
+use crate::db::users::{get_user_id_by_email_address, insert_user};
+use crate::domain::audit_models::{AuditAction, AuditEvent};
+use crate::domain::subscriber_models::NewSubscriber;
+use crate::domain::subscription_history_models::HistoryEventType;
+use crate::domain::subscription_import_models::{
+    parse_import_csv, ImportReport, ImportRowResult, ImportRowStatus, SubscriptionImportRow,
+    ValidatedImportRow,
+};
+use crate::domain::subscription_models::BillingSource;
+use crate::domain::user_models::UserGroup;
+use crate::routes::audit::record_audit_event;
+use crate::util::generate_random_token;
+
+/// Import files are spreadsheets of a few thousand readers at most.
+pub const MAX_IMPORT_FILE_BYTES: usize = 10 * 1024 * 1024;
+
+#[derive(Deserialize, Debug)]
+pub struct ImportQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Used for rows without a `billing_source`; defaults to Complimentary.
+    pub billing_source: Option<BillingSource>,
+}
+
+#[tracing::instrument(
+    name = "Import subscriptions from CSV (admin only)",
+    skip(admin_user_id, body, pool, user, metadata)
+)]
+pub async fn import_subscriptions_admin(
+    admin_user_id: web::Path<String>,
+    query: web::Query<ImportQuery>,
+    body: web::Bytes,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let admin_user_id = admin_user_id.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let default_billing_source = query.billing_source.unwrap_or(BillingSource::Complimentary);
+    if default_billing_source == BillingSource::Stripe {
+        return HttpResponse::BadRequest()
+            .json(json!({ "error": "Imported subscriptions cannot be billed by Stripe." }));
+    }
+
+    match import_subscriptions(
+        &body,
+        query.dry_run,
+        default_billing_source,
+        &admin_user_id,
+        &metadata,
+        &pool,
+    )
+    .await
+    {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => HttpResponse::BadRequest().json(json!({ "error": e })),
+    }
+}
+
+/// Imports every valid row of a CSV file, each in its own transaction so one bad
+/// row does not undo the others. A row is skipped when its email address already
+/// has an active subscription of the same type, which makes re-running a file safe.
+/// Fails only when the file itself cannot be read.
+pub async fn import_subscriptions(
+    data: &[u8],
+    dry_run: bool,
+    default_billing_source: BillingSource,
+    actor: &str,
+    metadata: &RequestMetadata,
+    pool: &PgPool,
+) -> Result<ImportReport, String> {
+    let rows = parse_import_csv(data)?;
+
+    let mut seen = HashSet::new();
+    let mut results = Vec::with_capacity(rows.len());
+    for (line, row) in rows {
+        let result = import_row(line, row, dry_run, default_billing_source, &mut seen, pool).await;
+        results.push(result);
+    }
+    let report = ImportReport::new(dry_run, results);
+
+    if !dry_run {
+        record_audit_event(
+            AuditEvent::new(actor, AuditAction::ImportSubscriptions).with_payload(json!({
+                "created": report.created,
+                "skipped": report.skipped,
+                "invalid": report.invalid,
+                "failed": report.failed,
+            })),
+            metadata,
+            pool,
+        )
+        .await;
+    }
+    Ok(report)
+}
+
+async fn import_row(
+    line: u64,
+    row: Result<SubscriptionImportRow, String>,
+    dry_run: bool,
+    default_billing_source: BillingSource,
+    seen: &mut HashSet<(String, &'static str)>,
+    pool: &PgPool,
+) -> ImportRowResult {
+    let result = |email_address: String, status, subscription_id, errors| ImportRowResult {
+        line,
+        email_address,
+        status,
+        subscription_id,
+        errors,
+    };
+
+    let row = match row {
+        Ok(row) => row,
+        Err(e) => return result(String::new(), ImportRowStatus::Invalid, None, vec![e]),
+    };
+    let raw_email_address = row.email_address.trim().to_string();
+    let row = match row.validate(default_billing_source) {
+        Ok(row) => row,
+        Err(errors) => return result(raw_email_address, ImportRowStatus::Invalid, None, errors),
+    };
+    let email_address = row.email_address.as_ref().to_string();
+
+    let first_in_file = seen.insert((email_address.clone(), row.subscription_type.as_str()));
+    match count_active_subscriptions_by_email_and_type(&email_address, &row.subscription_type, pool)
+        .await
+    {
+        Ok(0) if first_in_file => {}
+        Ok(_) => return result(email_address, ImportRowStatus::Skipped, None, vec![]),
+        Err(_) => {
+            let errors = vec!["Could not check for an existing subscription.".to_string()];
+            return result(email_address, ImportRowStatus::Failed, None, errors);
+        }
+    }
+    if dry_run {
+        return result(email_address, ImportRowStatus::WouldCreate, None, vec![]);
+    }
+
+    match store_imported_row(&row, pool).await {
+        Ok(subscription_id) => result(
+            email_address,
+            ImportRowStatus::Created,
+            Some(subscription_id),
+            vec![],
+        ),
+        Err(e) => {
+            tracing::error!("Could not import line {}: {:?}", line, e);
+            let errors = vec!["Could not be stored.".to_string()];
+            result(email_address, ImportRowStatus::Failed, None, errors)
+        }
+    }
+}
+
+/// Reuses the subscriber, or the user, that already has this email address.
+async fn store_imported_row(
+    row: &ValidatedImportRow,
+    pool: &PgPool,
+) -> Result<Uuid, anyhow::Error> {
+    let email_address = row.email_address.as_ref();
+    let mut transaction = pool.begin().await?;
+
+    let subscriber_id =
+        match retrieve_subscriber_id_by_email(email_address, &mut transaction).await? {
+            Some(subscriber_id) => subscriber_id,
+            None => {
+                let user_id =
+                    match get_user_id_by_email_address(email_address, &mut transaction).await? {
+                        Some(user_id) => user_id.to_string(),
+                        // Imported readers choose a password through forgot password.
+                        None => {
+                            let hashed_password = hash_password(generate_random_token()).await;
+                            insert_user(
+                                email_address,
+                                &hashed_password,
+                                UserGroup::USER,
+                                &mut transaction,
+                            )
+                            .await?
+                        }
+                    };
+                insert_subscriber(
+                    &NewSubscriber {
+                        name: row.name.clone(),
+                        email_address: row.email_address.clone(),
+                        user_id,
+                    },
+                    &mut transaction,
+                )
+                .await
+                .map_err(|e| anyhow!("{:?}", e))?;
+                retrieve_subscriber_id_by_email(email_address, &mut transaction)
+                    .await?
+                    .ok_or_else(|| anyhow!("The subscriber was not stored"))?
+            }
+        };
+
+    let subscription = insert_externally_billed_subscription(
+        row.to_new_subscription(subscriber_id),
+        row.billing_source,
+        &mut transaction,
+    )
+    .await?;
+    // The server's job worker records the history once the row is committed.
+    queue_subscription_history_event(&subscription, HistoryEventType::Created, &mut transaction)
+        .await?;
+    transaction.commit().await?;
+
+    Ok(subscription.id)
+}
@@ -1,42 +1,67 @@
-use crate::auth::authorization::is_authorized_admin_only;
+use crate::auth::authorization::{is_authorized_admin_only, is_authorized_user_only};
+use crate::auth::request_metadata::RequestMetadata;
 use crate::auth::token::Claims;
-use crate::background::subscription_history_storer::store_subscription_history_event;
+use crate::background::email_outbox_worker::send_queued_email;
+use crate::background::staff_notifier::queue_staff_notice;
+use crate::background::subscriber_emails::{
+    address_changed_email, mailing_address_changed, subscription_cancelled_email,
+};
+use crate::background::subscription_history_storer::queue_subscription_history_event;
+use crate::db::email_outbox_db_broker::queue_email;
 use crate::db::subscribers_db_broker::retrieve_subscriber_by_id;
 use actix_web::{web, HttpResponse, Responder};
+use chrono::Utc;
 use serde_json::json;
 use sqlx::PgPool;
 use tracing::Level;
 use uuid::Uuid;
 
+use crate::db::publications_db_broker::{get_issues_by_publication_id, get_publication};
 use crate::db::subscriptions_db_broker::{
     cancel_subscription_by_subscription_id, retrieve_all_subscriptions,
     retrieve_subscription_by_subscription_id, retrieve_subscriptions_by_subscriber_id,
-    update_subscription_by_subscription_id,
+    set_subscription_publication, update_subscription_by_subscription_id,
 };
+use crate::domain::audit_models::{AuditAction, AuditEvent};
+use crate::domain::notification_channel_models::StaffEventType;
+use crate::domain::publication_models::{subscription_issues, SetSubscriptionPublication};
 use crate::domain::subscription_history_models::HistoryEventType;
-use crate::domain::subscription_models::OverTheWireSubscription;
+use crate::domain::subscription_models::{BillingSource, OverTheWireSubscription};
 use crate::domain::valid_email::ValidEmail;
 use crate::domain::valid_name::ValidName;
+use crate::email_client::EmailClient;
+use crate::routes::audit::record_audit_event;
 use crate::stripe_client::StripeClient;
 
 use crate::util::from_path_to_uuid;
 
 #[tracing::instrument(
     name = "Getting all subscriptions (admin only)",
-    skip(user_id, pool, user),
+    skip(user_id, pool, user, metadata),
     fields(user_id = %user_id)
 )]
 pub async fn get_all_subscriptions_admin(
     user_id: web::Path<String>,
     pool: web::Data<PgPool>,
     user: Claims,
+    metadata: RequestMetadata,
 ) -> impl Responder {
-    if !is_authorized_admin_only(user_id.into_inner(), user) {
+    let user_id = user_id.into_inner();
+    if !is_authorized_admin_only(user_id.clone(), user) {
         return HttpResponse::Unauthorized().finish();
     }
 
     match retrieve_all_subscriptions(&pool).await {
-        Ok(subscriptions) => HttpResponse::Ok().json(subscriptions),
+        Ok(subscriptions) => {
+            record_audit_event(
+                AuditEvent::new(&user_id, AuditAction::ListSubscriptions)
+                    .with_payload(json!({ "count": subscriptions.len() })),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(subscriptions)
+        }
         Err(_) => HttpResponse::InternalServerError().finish(),
     }
 }
@@ -95,7 +120,7 @@ pub async fn get_subscription_by_id(
 
 #[tracing::instrument(
 name = "Updating subscription",
-skip(id, subscription, pool, user),
+skip(id, subscription, pool, user, email_client),
 fields(
 id = % id,
 )
@@ -105,6 +130,7 @@ pub async fn update_subscription(
     subscription: web::Json<OverTheWireSubscription>,
     pool: web::Data<PgPool>,
     user: Claims,
+    email_client: web::Data<EmailClient>,
 ) -> impl Responder {
     match retrieve_subscription_by_subscription_id(from_path_to_uuid(&id).unwrap(), &pool).await {
         Ok(stored_subscription) => {
@@ -123,16 +149,70 @@ pub async fn update_subscription(
                 return HttpResponse::BadRequest().finish();
             }
 
-            match update_subscription_by_subscription_id(
+            let address_changed = address_changed_email(&stored_subscription, &subscription);
+            let notify_staff = mailing_address_changed(&stored_subscription, &subscription);
+            let updated = OverTheWireSubscription {
+                subscription_name: subscription.subscription_name.clone(),
+                subscription_mailing_address_line_1: subscription
+                    .subscription_mailing_address_line_1
+                    .clone(),
+                subscription_mailing_address_line_2: subscription
+                    .subscription_mailing_address_line_2
+                    .clone(),
+                subscription_city: subscription.subscription_city.clone(),
+                subscription_state: subscription.subscription_state.clone(),
+                subscription_postal_code: subscription.subscription_postal_code.clone(),
+                subscription_email_address: subscription.subscription_email_address.clone(),
+                ..stored_subscription.clone()
+            };
+
+            let mut transaction = match pool.begin().await {
+                Ok(transaction) => transaction,
+                Err(_) => return HttpResponse::InternalServerError().finish(),
+            };
+            if update_subscription_by_subscription_id(
                 stored_subscription.id,
                 subscription.0,
-                &pool,
+                &mut transaction,
             )
             .await
+            .is_err()
             {
-                Ok(_) => HttpResponse::Ok().json(json!({})),
-                Err(_) => HttpResponse::InternalServerError().finish(),
+                transaction.rollback().await.unwrap();
+                return HttpResponse::InternalServerError().finish();
+            }
+            if let Some(email) = &address_changed {
+                if queue_email(email, &mut transaction).await.is_err() {
+                    transaction.rollback().await.unwrap();
+                    return HttpResponse::InternalServerError().finish();
+                }
+            }
+            if queue_subscription_history_event(
+                &updated,
+                HistoryEventType::UpdatedSubscriptionInformation,
+                &mut transaction,
+            )
+            .await
+            .is_err()
+            {
+                transaction.rollback().await.unwrap();
+                return HttpResponse::InternalServerError().finish();
+            }
+            if notify_staff
+                && queue_staff_notice(StaffEventType::AddressChange, &updated, &mut transaction)
+                    .await
+                    .is_err()
+            {
+                transaction.rollback().await.unwrap();
+                return HttpResponse::InternalServerError().finish();
+            }
+            if transaction.commit().await.is_err() {
+                return HttpResponse::InternalServerError().finish();
+            }
+            if let Some(email) = address_changed {
+                send_queued_email(email.id, &email_client, &pool).await;
             }
+            HttpResponse::Ok().json(json!({}))
         }
         Err(_) => HttpResponse::NotFound().finish(),
     }
@@ -140,7 +220,7 @@ pub async fn update_subscription(
 
 #[tracing::instrument(
 name = "Cancel subscription by subscription id",
-skip(id, pool, user, stripe_client),
+skip(id, pool, user, stripe_client, email_client, metadata),
 fields(
 id = % id,
 )
@@ -150,11 +230,15 @@ pub async fn cancel_subscription_by_id(
     pool: web::Data<PgPool>,
     user: Claims,
     stripe_client: web::Data<StripeClient>,
+    email_client: web::Data<EmailClient>,
+    metadata: RequestMetadata,
 ) -> impl Responder {
     let subscription_id = from_path_to_uuid(&id).unwrap();
     match retrieve_subscription_by_subscription_id(subscription_id, &pool).await {
         Ok(subscription) => {
-            match reject_unauthorized_user(subscription.subscriber_id, user.user_id, &pool).await {
+            match reject_unauthorized_user(subscription.subscriber_id, user.user_id.clone(), &pool)
+                .await
+            {
                 Ok(_) => {}
                 Err(response) => return response,
             };
@@ -170,29 +254,74 @@ pub async fn cancel_subscription_by_id(
             };
 
             //Set it to active = false
-            match cancel_subscription_by_subscription_id(subscription_id, &mut transaction).await {
-                Ok(_) => {}
-                Err(_) => {
+            let cancelled =
+                match cancel_subscription_by_subscription_id(subscription_id, &mut transaction)
+                    .await
+                {
+                    Ok(cancelled_on) => OverTheWireSubscription {
+                        active: false,
+                        subscription_cancelled_on_date: Some(cancelled_on),
+                        ..subscription.clone()
+                    },
+                    Err(_) => {
+                        transaction.rollback().await.unwrap();
+                        return HttpResponse::InternalServerError().finish();
+                    }
+                };
+            let email = subscription_cancelled_email(&subscription);
+            if let Some(email) = &email {
+                if queue_email(email, &mut transaction).await.is_err() {
                     transaction.rollback().await.unwrap();
                     return HttpResponse::InternalServerError().finish();
                 }
             }
-
-            //Call stripe to cancel the subscription
-            match stripe_client
-                .cancel_stripe_subscription(subscription.stripe_subscription_id)
+            if queue_subscription_history_event(
+                &cancelled,
+                HistoryEventType::Cancelled,
+                &mut transaction,
+            )
+            .await
+            .is_err()
+            {
+                transaction.rollback().await.unwrap();
+                return HttpResponse::InternalServerError().finish();
+            }
+            if queue_staff_notice(StaffEventType::Cancellation, &cancelled, &mut transaction)
                 .await
+                .is_err()
             {
+                transaction.rollback().await.unwrap();
+                return HttpResponse::InternalServerError().finish();
+            }
+
+            //Call stripe to cancel the subscription, unless it is billed elsewhere
+            let stripe_cancellation = match subscription.billing_source {
+                BillingSource::Stripe => {
+                    stripe_client
+                        .cancel_stripe_subscription(subscription.stripe_subscription_id.clone())
+                        .await
+                }
+                _ => Ok(()),
+            };
+            match stripe_cancellation {
                 Ok(_) => {
                     if transaction.commit().await.is_err() {
-                        HttpResponse::InternalServerError().finish();
+                        return HttpResponse::InternalServerError().finish();
                     }
-                    //Add a history object....
-                    store_subscription_history_event(
-                        subscription.id,
-                        HistoryEventType::Cancelled,
+                    if let Some(email) = email {
+                        send_queued_email(email.id, &email_client, &pool).await;
+                    }
+                    record_audit_event(
+                        AuditEvent::new(&user.user_id, AuditAction::CancelSubscription)
+                            .with_target("subscription", subscription.id)
+                            .with_payload(json!({
+                                "subscriber_id": subscription.subscriber_id,
+                                "stripe_subscription_id": subscription.stripe_subscription_id,
+                            })),
+                        &metadata,
                         &pool,
-                    );
+                    )
+                    .await;
                     HttpResponse::Ok().json(json!({}))
                 }
                 Err(_) => {
@@ -205,6 +334,130 @@ pub async fn cancel_subscription_by_id(
     }
 }
 
+#[tracing::instrument(
+name = "Getting the issues of a subscription",
+skip(id, pool, user),
+fields(
+id = % id,
+)
+)]
+pub async fn get_subscription_issues(
+    id: web::Path<String>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    let subscription_id = match from_path_to_uuid(&id) {
+        Ok(subscription_id) => subscription_id,
+        Err(response) => return response,
+    };
+    let subscription = match retrieve_subscription_by_subscription_id(subscription_id, &pool).await
+    {
+        Ok(subscription) => subscription,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+    let subscriber = match retrieve_subscriber_by_id(subscription.subscriber_id, &pool).await {
+        Ok(subscriber) => subscriber,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+    if !is_authorized_user_only(subscriber.user_id, user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    subscription_issues_response(&subscription, &pool).await
+}
+
+#[tracing::instrument(
+    name = "Getting the issues of a subscription (admin only)",
+    skip(path, pool, user)
+)]
+pub async fn get_subscription_issues_admin(
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    let (admin_user_id, subscription_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id, user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let subscription_id = match Uuid::parse_str(&subscription_id) {
+        Ok(subscription_id) => subscription_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+    match retrieve_subscription_by_subscription_id(subscription_id, &pool).await {
+        Ok(subscription) => subscription_issues_response(&subscription, &pool).await,
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+async fn subscription_issues_response(
+    subscription: &OverTheWireSubscription,
+    pool: &PgPool,
+) -> HttpResponse {
+    let (publication, issues) = match subscription.publication_id {
+        Some(publication_id) => {
+            let publication = get_publication(publication_id, pool).await;
+            let issues = get_issues_by_publication_id(publication_id, pool).await;
+            match (publication, issues) {
+                (Ok(publication), Ok(issues)) => (Some(publication), issues),
+                _ => return HttpResponse::InternalServerError().finish(),
+            }
+        }
+        None => (None, vec![]),
+    };
+
+    HttpResponse::Ok().json(subscription_issues(
+        subscription,
+        publication,
+        issues,
+        Utc::now().date_naive(),
+    ))
+}
+
+#[tracing::instrument(
+    name = "Link a subscription to a publication (admin only)",
+    skip(path, body, pool, user, metadata)
+)]
+pub async fn set_subscription_publication_admin(
+    path: web::Path<(String, String)>,
+    body: web::Json<SetSubscriptionPublication>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, subscription_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let subscription_id = match Uuid::parse_str(&subscription_id) {
+        Ok(subscription_id) => subscription_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+    if let Some(publication_id) = body.publication_id {
+        match get_publication(publication_id, &pool).await {
+            Ok(_) => {}
+            Err(sqlx::Error::RowNotFound) => return HttpResponse::NotFound().finish(),
+            Err(_) => return HttpResponse::InternalServerError().finish(),
+        }
+    }
+
+    match set_subscription_publication(subscription_id, body.publication_id, &pool).await {
+        Ok(true) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::SetSubscriptionPublication)
+                    .with_target("subscription", subscription_id)
+                    .with_payload(json!({ "publication_id": body.publication_id })),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(json!({}))
+        }
+        Ok(false) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
 async fn reject_unauthorized_user(
     subscriber_id: Uuid,
     user_id: String,
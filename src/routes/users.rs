@@ -1,29 +1,40 @@
 use crate::auth::authorization::is_authorized_admin_only;
-use actix_web::web::Data;
+use crate::auth::request_metadata::RequestMetadata;
 use actix_web::{web, HttpResponse, Responder};
 use chrono::{Duration, Utc};
 use serde_json::json;
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
 use crate::auth::password_hashing::{hash_password, validate_password};
-use crate::auth::token::{generate_token, get_expires_at, Claims, LoginResponse};
+use crate::auth::token::Claims;
+use crate::background::email_outbox_worker::send_queued_email;
+use crate::background::subscriber_emails::welcome_email;
 use crate::configuration::get_configuration;
+use crate::db::email_outbox_db_broker::queue_email;
 use crate::db::otp_db_broker::{get_otp_by_otp, insert_otp, set_to_used_by_otp};
+use crate::db::session_db_broker::revoke_all_user_sessions;
 use crate::db::subscribers_db_broker::insert_subscriber;
 use crate::db::users::{
     count_users_with_email_address, demote_admin_to_user, get_all_users, get_user_by_email_address,
-    get_user_by_user_id, insert_user, promote_user_to_admin, update_password,
+    get_user_by_user_id, insert_user, lock_enabled_admin_ids, promote_user_to_admin,
+    set_password_reset_required, set_user_disabled, update_password,
 };
+use crate::domain::audit_models::{AuditAction, AuditEvent};
+use crate::domain::email_outbox_models::OutboxEmail;
 use crate::domain::otp_models::OneTimePasscode;
+use crate::domain::session_models::{LoginFailureReason, LoginMethod};
 use crate::domain::subscriber_models::NewSubscriber;
 use crate::domain::user_models::{
     ForgotPassword, LogIn, OverTheWireUser, ResetPassword, ResetPasswordFromForgotPassword, SignUp,
-    UserGroup,
+    User, UserGroup,
 };
 use crate::domain::valid_email::ValidEmail;
 use crate::domain::valid_name::ValidName;
+use crate::email_client::templates::{render_email, EmailTemplate, DEFAULT_LOCALE};
 use crate::email_client::EmailClient;
+use crate::routes::audit::record_audit_event;
+use crate::routes::sessions::{record_failed_login, start_session};
 use crate::util::{generate_random_token, standardize_email};
 
 impl TryFrom<SignUp> for NewSubscriber {
@@ -41,12 +52,17 @@ impl TryFrom<SignUp> for NewSubscriber {
 
 #[tracing::instrument(
     name = "Singing up a new user",
-    skip(sign_up, pool),
+    skip(sign_up, pool, email_client, metadata),
     fields(
         user_username = %sign_up.email_address,
     )
 )]
-pub async fn sign_up(sign_up: web::Json<SignUp>, pool: web::Data<PgPool>) -> impl Responder {
+pub async fn sign_up(
+    sign_up: web::Json<SignUp>,
+    pool: web::Data<PgPool>,
+    email_client: web::Data<EmailClient>,
+    metadata: RequestMetadata,
+) -> impl Responder {
     let transformed_email = standardize_email(&sign_up.email_address.clone());
     match count_users_with_email_address(&transformed_email, &pool).await {
         Ok(count) => {
@@ -65,7 +81,7 @@ pub async fn sign_up(sign_up: web::Json<SignUp>, pool: web::Data<PgPool>) -> imp
             };
 
             let hashed_password = hash_password(sign_up.clone().password).await;
-            let login_response = match insert_user(
+            let user_id = match insert_user(
                 &transformed_email,
                 &hashed_password,
                 UserGroup::USER,
@@ -73,27 +89,41 @@ pub async fn sign_up(sign_up: web::Json<SignUp>, pool: web::Data<PgPool>) -> imp
             )
             .await
             {
-                Ok(user_id) => LoginResponse {
-                    user_id: user_id.clone(),
-                    token: generate_token(user_id, UserGroup::USER),
-                    expires_on: get_expires_at(Option::None),
-                    group: UserGroup::USER,
-                },
+                Ok(user_id) => user_id,
                 Err(_) => {
                     transaction.rollback().await.unwrap();
                     return HttpResponse::InternalServerError().finish();
                 }
             };
 
-            new_subscriber.user_id = login_response.user_id.clone();
-            match insert_subscriber(&new_subscriber, &mut transaction).await {
-                Ok(_) => {
+            new_subscriber.user_id = user_id.clone();
+            let welcome = welcome_email(&new_subscriber);
+            let stored = insert_subscriber(&new_subscriber, &mut transaction)
+                .await
+                .is_ok()
+                && queue_email(&welcome, &mut transaction).await.is_ok();
+            match stored {
+                true => {
                     if transaction.commit().await.is_err() {
-                        HttpResponse::InternalServerError().finish();
+                        return HttpResponse::InternalServerError().finish();
+                    }
+                    send_queued_email(welcome.id, &email_client, &pool).await;
+                    match start_session(
+                        Uuid::parse_str(&user_id).unwrap(),
+                        UserGroup::USER,
+                        &transformed_email,
+                        LoginMethod::SignUp,
+                        &metadata,
+                        &pool,
+                        &email_client,
+                    )
+                    .await
+                    {
+                        Ok(login_response) => HttpResponse::Ok().json(&login_response),
+                        Err(_) => HttpResponse::InternalServerError().finish(),
                     }
-                    HttpResponse::Ok().json(&login_response)
                 }
-                Err(_) => {
+                false => {
                     transaction.rollback().await.unwrap();
                     HttpResponse::InternalServerError().finish()
                 }
@@ -105,29 +135,77 @@ pub async fn sign_up(sign_up: web::Json<SignUp>, pool: web::Data<PgPool>) -> imp
 
 #[tracing::instrument(
     name = "Login user",
-    skip(log_in, pool),
+    skip(log_in, pool, email_client, metadata),
     fields(
         user_username = %log_in.email_address,
     )
 )]
-pub async fn login(log_in: web::Json<LogIn>, pool: web::Data<PgPool>) -> impl Responder {
+pub async fn login(
+    log_in: web::Json<LogIn>,
+    pool: web::Data<PgPool>,
+    email_client: web::Data<EmailClient>,
+    metadata: RequestMetadata,
+) -> impl Responder {
     let transformed_email = standardize_email(&log_in.email_address.clone());
     match get_user_by_email_address(&transformed_email, &pool).await {
         Ok(user) => {
-            let hashed_passwords_match =
-                validate_password(log_in.password.clone(), user.password).await;
-            if !hashed_passwords_match {
-                return HttpResponse::BadRequest().finish();
+            let failure_reason = if !validate_password(log_in.password.clone(), user.password).await
+            {
+                Some(LoginFailureReason::WrongPassword)
+            } else if user.disabled {
+                Some(LoginFailureReason::Disabled)
+            } else if user.password_reset_required {
+                Some(LoginFailureReason::PasswordResetRequired)
+            } else {
+                None
+            };
+
+            if let Some(failure_reason) = failure_reason {
+                record_failed_login(
+                    Some(user.user_id),
+                    Some(transformed_email),
+                    LoginMethod::Password,
+                    failure_reason,
+                    &metadata,
+                    &pool,
+                )
+                .await;
+                return match failure_reason {
+                    LoginFailureReason::Disabled => HttpResponse::Forbidden().finish(),
+                    LoginFailureReason::PasswordResetRequired => {
+                        HttpResponse::Forbidden().json(json!({ "password_reset_required": true }))
+                    }
+                    _ => HttpResponse::BadRequest().finish(),
+                };
             }
 
-            HttpResponse::Ok().json(LoginResponse {
-                user_id: user.user_id.to_string(),
-                token: generate_token(user.user_id.to_string(), user.user_group.clone()),
-                expires_on: get_expires_at(Option::None),
-                group: user.user_group.clone(),
-            })
+            match start_session(
+                user.user_id,
+                user.user_group,
+                &transformed_email,
+                LoginMethod::Password,
+                &metadata,
+                &pool,
+                &email_client,
+            )
+            .await
+            {
+                Ok(login_response) => HttpResponse::Ok().json(login_response),
+                Err(_) => HttpResponse::InternalServerError().finish(),
+            }
+        }
+        Err(_) => {
+            record_failed_login(
+                None,
+                Some(transformed_email),
+                LoginMethod::Password,
+                LoginFailureReason::UnknownUser,
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::BadRequest().finish()
         }
-        Err(_) => HttpResponse::BadRequest().finish(),
     }
 }
 
@@ -163,32 +241,45 @@ pub async fn check_admin_token(user_id: web::Path<String>, user: Claims) -> impl
 
 #[tracing::instrument(
     name = "Getting all users (admin only)",
-    skip(admin_user_id, pool, user),
+    skip(admin_user_id, pool, user, metadata),
     fields(admin_user_id = %admin_user_id)
 )]
 pub async fn get_all_users_admin(
     admin_user_id: web::Path<String>,
     pool: web::Data<PgPool>,
     user: Claims,
+    metadata: RequestMetadata,
 ) -> impl Responder {
-    if !is_authorized_admin_only(admin_user_id.into_inner(), user) {
+    let admin_user_id = admin_user_id.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
         return HttpResponse::Unauthorized().finish();
     }
 
     match get_all_users(&pool).await {
         Ok(users) => {
             let wire: Vec<OverTheWireUser> = users.into_iter().map(OverTheWireUser::from).collect();
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::ListUsers)
+                    .with_payload(json!({ "count": wire.len() })),
+                &metadata,
+                &pool,
+            )
+            .await;
             HttpResponse::Ok().json(wire)
         }
         Err(_) => HttpResponse::InternalServerError().finish(),
     }
 }
 
-#[tracing::instrument(name = "Promote user to admin (admin only)", skip(path, pool, user))]
+#[tracing::instrument(
+    name = "Promote user to admin (admin only)",
+    skip(path, pool, user, metadata)
+)]
 pub async fn admin_promote_user(
     path: web::Path<(String, String)>,
     pool: web::Data<PgPool>,
     user: Claims,
+    metadata: RequestMetadata,
 ) -> impl Responder {
     let (admin_user_id, target_user_id_str) = path.into_inner();
     if !is_authorized_admin_only(admin_user_id.clone(), user) {
@@ -202,16 +293,30 @@ pub async fn admin_promote_user(
         Err(_) => return HttpResponse::BadRequest().finish(),
     };
     match promote_user_to_admin(target_user_id, &pool).await {
-        Ok(_) => HttpResponse::Ok().finish(),
+        Ok(_) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::PromoteUser)
+                    .with_target("user", target_user_id)
+                    .with_payload(json!({ "user_group": UserGroup::ADMIN.as_str() })),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().finish()
+        }
         Err(_) => HttpResponse::InternalServerError().finish(),
     }
 }
 
-#[tracing::instrument(name = "Demote admin to user (admin only)", skip(path, pool, user))]
+#[tracing::instrument(
+    name = "Demote admin to user (admin only)",
+    skip(path, pool, user, metadata)
+)]
 pub async fn admin_demote_user(
     path: web::Path<(String, String)>,
     pool: web::Data<PgPool>,
     user: Claims,
+    metadata: RequestMetadata,
 ) -> impl Responder {
     let (admin_user_id, target_user_id_str) = path.into_inner();
     if !is_authorized_admin_only(admin_user_id.clone(), user) {
@@ -221,15 +326,202 @@ pub async fn admin_demote_user(
         Ok(id) => id,
         Err(_) => return HttpResponse::BadRequest().finish(),
     };
-    match demote_admin_to_user(target_user_id, &pool).await {
-        Ok(_) => HttpResponse::Ok().finish(),
-        Err(_) => HttpResponse::InternalServerError().finish(),
+
+    let mut transaction = match pool.begin().await {
+        Ok(transaction) => transaction,
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+    if let Err(response) = reject_removing_last_admin(target_user_id, &mut transaction).await {
+        transaction.rollback().await.unwrap();
+        return response;
+    }
+    if demote_admin_to_user(target_user_id, &mut transaction)
+        .await
+        .is_err()
+    {
+        transaction.rollback().await.unwrap();
+        return HttpResponse::InternalServerError().finish();
+    }
+    if transaction.commit().await.is_err() {
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    record_audit_event(
+        AuditEvent::new(&admin_user_id, AuditAction::DemoteUser)
+            .with_target("user", target_user_id)
+            .with_payload(json!({ "user_group": UserGroup::USER.as_str() })),
+        &metadata,
+        &pool,
+    )
+    .await;
+    HttpResponse::Ok().finish()
+}
+
+#[tracing::instrument(name = "Disable user (admin only)", skip(path, pool, user, metadata))]
+pub async fn admin_disable_user(
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, target_user_id_str) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    if admin_user_id == target_user_id_str {
+        return HttpResponse::BadRequest().finish();
+    }
+    let target_user = match get_target_user(&target_user_id_str, &pool).await {
+        Ok(target_user) => target_user,
+        Err(response) => return response,
+    };
+
+    let mut transaction = match pool.begin().await {
+        Ok(transaction) => transaction,
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+    if let Err(response) = reject_removing_last_admin(target_user.user_id, &mut transaction).await {
+        transaction.rollback().await.unwrap();
+        return response;
+    }
+    if set_user_disabled(target_user.user_id, true, &mut transaction)
+        .await
+        .is_err()
+    {
+        transaction.rollback().await.unwrap();
+        return HttpResponse::InternalServerError().finish();
+    }
+    if transaction.commit().await.is_err() {
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    record_audit_event(
+        AuditEvent::new(&admin_user_id, AuditAction::DisableUser)
+            .with_target("user", target_user.user_id),
+        &metadata,
+        &pool,
+    )
+    .await;
+    HttpResponse::Ok().finish()
+}
+
+#[tracing::instrument(name = "Re-enable user (admin only)", skip(path, pool, user, metadata))]
+pub async fn admin_enable_user(
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, target_user_id_str) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let target_user = match get_target_user(&target_user_id_str, &pool).await {
+        Ok(target_user) => target_user,
+        Err(response) => return response,
+    };
+
+    let mut transaction = match pool.begin().await {
+        Ok(transaction) => transaction,
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+    if set_user_disabled(target_user.user_id, false, &mut transaction)
+        .await
+        .is_err()
+    {
+        transaction.rollback().await.unwrap();
+        return HttpResponse::InternalServerError().finish();
+    }
+    if transaction.commit().await.is_err() {
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    record_audit_event(
+        AuditEvent::new(&admin_user_id, AuditAction::EnableUser)
+            .with_target("user", target_user.user_id),
+        &metadata,
+        &pool,
+    )
+    .await;
+    HttpResponse::Ok().finish()
+}
+
+#[tracing::instrument(
+    name = "Force a password reset (admin only)",
+    skip(path, pool, user, email_client, metadata)
+)]
+pub async fn admin_force_password_reset(
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    email_client: web::Data<EmailClient>,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, target_user_id_str) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let target_user = match get_target_user(&target_user_id_str, &pool).await {
+        Ok(target_user) => target_user,
+        Err(response) => return response,
+    };
+
+    let otp = new_one_time_passcode(target_user.user_id.to_string());
+    let email = match ValidEmail::parse(target_user.email_address) {
+        Ok(address) => admin_initiated_reset_email(address, &otp.one_time_passcode),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+    if force_password_reset(target_user.user_id, otp, &email, &pool)
+        .await
+        .is_err()
+    {
+        return HttpResponse::InternalServerError().finish();
+    }
+    send_queued_email(email.id, &email_client, &pool).await;
+
+    record_audit_event(
+        AuditEvent::new(&admin_user_id, AuditAction::ForcePasswordReset)
+            .with_target("user", target_user.user_id),
+        &metadata,
+        &pool,
+    )
+    .await;
+    HttpResponse::Ok().finish()
+}
+
+async fn get_target_user(target_user_id: &str, pool: &PgPool) -> Result<User, HttpResponse> {
+    if Uuid::parse_str(target_user_id).is_err() {
+        return Err(HttpResponse::BadRequest().finish());
+    }
+    match get_user_by_user_id(target_user_id, pool).await {
+        Ok(user) => Ok(user),
+        Err(sqlx::Error::RowNotFound) => Err(HttpResponse::NotFound().finish()),
+        Err(_) => Err(HttpResponse::InternalServerError().finish()),
     }
 }
 
+/// Demoting or disabling the only enabled admin would lock everyone out of the admin routes.
+async fn reject_removing_last_admin(
+    target_user_id: Uuid,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), HttpResponse> {
+    let admin_ids = lock_enabled_admin_ids(transaction)
+        .await
+        .map_err(|_| HttpResponse::InternalServerError().finish())?;
+
+    if admin_ids.contains(&target_user_id) && admin_ids.len() <= 1 {
+        tracing::info!(
+            "Refused to remove the last enabled admin {}",
+            target_user_id
+        );
+        return Err(HttpResponse::Conflict().finish());
+    }
+    Ok(())
+}
+
 #[tracing::instrument(
     name = "Reset password",
-    skip(reset_password, pool, user_claim),
+    skip(reset_password, pool, user_claim, metadata),
     fields(
         user_username = %reset_password.email_address,
     )
@@ -238,6 +530,7 @@ pub async fn reset_password(
     reset_password: web::Json<ResetPassword>,
     pool: web::Data<PgPool>,
     user_claim: Claims,
+    metadata: RequestMetadata,
 ) -> impl Responder {
     match get_user_by_email_address(&reset_password.email_address, &pool).await {
         Ok(user) => {
@@ -253,9 +546,18 @@ pub async fn reset_password(
 
             let new_hashed_password = hash_password(reset_password.new_password.clone()).await;
 
-            match update_password(&reset_password.email_address, &new_hashed_password, &pool).await
+            match change_password(&reset_password.email_address, &new_hashed_password, &pool).await
             {
-                Ok(_) => HttpResponse::Ok().finish(),
+                Ok(_) => {
+                    record_audit_event(
+                        AuditEvent::new(&user_claim.user_id, AuditAction::ResetPassword)
+                            .with_target("user", user.user_id),
+                        &metadata,
+                        &pool,
+                    )
+                    .await;
+                    HttpResponse::Ok().finish()
+                }
                 Err(_) => HttpResponse::InternalServerError().finish(),
             }
         }
@@ -277,21 +579,17 @@ pub async fn forgot_password(
 ) -> impl Responder {
     let email = standardize_email(&forgot_password.email_address);
     match get_user_by_email_address(email.as_str(), &pool).await {
+        Ok(user) if user.disabled => HttpResponse::Ok().json(json!({})),
         Ok(user) => {
-            let passcode = generate_random_token();
-            let otp = OneTimePasscode {
-                id: Uuid::new_v4(),
-                user_id: user.user_id.to_string(),
-                one_time_passcode: passcode.clone(),
-                issued_on: Utc::now(),
-                expires_on: Utc::now() + Duration::days(1),
-                used: false,
+            let otp = new_one_time_passcode(user.user_id.to_string());
+            let email = match ValidEmail::parse(email) {
+                Ok(address) => password_reset_email(address, &otp.one_time_passcode),
+                Err(_) => return HttpResponse::Ok().json(json!({})),
             };
-            match insert_otp(otp, &pool).await {
+            // A failed delivery is retried from the outbox, so it is not the caller's problem.
+            match store_otp_and_queue_email(otp, &email, &pool).await {
                 Ok(_) => {
-                    if email_user(email, passcode, email_client).await.is_err() {
-                        return HttpResponse::InternalServerError().finish();
-                    }
+                    send_queued_email(email.id, &email_client, &pool).await;
                     HttpResponse::Ok().json(json!({}))
                 }
                 Err(err) => {
@@ -309,7 +607,7 @@ pub async fn forgot_password(
 
 #[tracing::instrument(
     name = "Forgot password login",
-    skip(one_time_passcode, pool),
+    skip(one_time_passcode, pool, email_client, metadata),
     fields(
         one_time_passcode = %one_time_passcode,
     )
@@ -317,34 +615,86 @@ pub async fn forgot_password(
 pub async fn forgot_password_login(
     one_time_passcode: web::Path<String>,
     pool: web::Data<PgPool>,
+    email_client: web::Data<EmailClient>,
+    metadata: RequestMetadata,
 ) -> impl Responder {
     match get_otp_by_otp(one_time_passcode.into_inner().clone().as_str(), &pool).await {
         Ok(passcode) => {
             if is_invalid_one_time_passcode(&passcode) {
+                record_failed_login(
+                    Uuid::parse_str(&passcode.user_id).ok(),
+                    None,
+                    LoginMethod::Otp,
+                    LoginFailureReason::InvalidPasscode,
+                    &metadata,
+                    &pool,
+                )
+                .await;
                 return HttpResponse::BadRequest().finish();
             }
 
             //set it to used
             match set_to_used_by_otp(passcode.one_time_passcode.as_str(), &pool).await {
                 Ok(_) => match get_user_by_user_id(&passcode.user_id, &pool).await {
-                    Ok(user) => HttpResponse::Ok().json(LoginResponse {
-                        user_id: passcode.user_id.clone(),
-                        token: generate_token(passcode.user_id.clone(), user.user_group.clone()),
-                        expires_on: get_expires_at(Option::None),
-                        group: user.user_group,
-                    }),
+                    Ok(user) if user.disabled => {
+                        record_failed_login(
+                            Some(user.user_id),
+                            Some(user.email_address),
+                            LoginMethod::Otp,
+                            LoginFailureReason::Disabled,
+                            &metadata,
+                            &pool,
+                        )
+                        .await;
+                        HttpResponse::Forbidden().finish()
+                    }
+                    Ok(user) => {
+                        record_audit_event(
+                            AuditEvent::new(&passcode.user_id, AuditAction::ForgotPasswordLogin)
+                                .with_target("user", user.user_id)
+                                .with_payload(json!({ "one_time_passcode_id": passcode.id })),
+                            &metadata,
+                            &pool,
+                        )
+                        .await;
+                        match start_session(
+                            user.user_id,
+                            user.user_group,
+                            &user.email_address,
+                            LoginMethod::Otp,
+                            &metadata,
+                            &pool,
+                            &email_client,
+                        )
+                        .await
+                        {
+                            Ok(login_response) => HttpResponse::Ok().json(login_response),
+                            Err(_) => HttpResponse::InternalServerError().finish(),
+                        }
+                    }
                     Err(_) => HttpResponse::InternalServerError().finish(),
                 },
                 Err(_) => HttpResponse::InternalServerError().finish(),
             }
         }
-        Err(_) => HttpResponse::BadRequest().finish(),
+        Err(_) => {
+            record_failed_login(
+                None,
+                None,
+                LoginMethod::Otp,
+                LoginFailureReason::InvalidPasscode,
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::BadRequest().finish()
+        }
     }
 }
 
 #[tracing::instrument(
     name = "Reset password from forgot password",
-    skip(reset_password, pool, user_claim),
+    skip(reset_password, pool, user_claim, metadata),
     fields(
         user_user_id = %reset_password.user_id,
     )
@@ -353,6 +703,7 @@ pub async fn reset_password_from_forgot_password(
     reset_password: web::Json<ResetPasswordFromForgotPassword>,
     pool: web::Data<PgPool>,
     user_claim: Claims,
+    metadata: RequestMetadata,
 ) -> impl Responder {
     if user_claim.user_id != reset_password.user_id {
         return HttpResponse::Unauthorized().finish();
@@ -365,8 +716,20 @@ pub async fn reset_password_from_forgot_password(
 
             let new_hashed_password = hash_password(reset_password.new_password.clone()).await;
 
-            match update_password(&user.email_address, &new_hashed_password, &pool).await {
-                Ok(_) => HttpResponse::Ok().json(json!({})),
+            match change_password(&user.email_address, &new_hashed_password, &pool).await {
+                Ok(_) => {
+                    record_audit_event(
+                        AuditEvent::new(
+                            &user_claim.user_id,
+                            AuditAction::ResetPasswordFromForgotPassword,
+                        )
+                        .with_target("user", user.user_id),
+                        &metadata,
+                        &pool,
+                    )
+                    .await;
+                    HttpResponse::Ok().json(json!({}))
+                }
                 Err(_) => HttpResponse::InternalServerError().finish(),
             }
         }
@@ -374,30 +737,70 @@ pub async fn reset_password_from_forgot_password(
     }
 }
 
-pub async fn email_user(
-    email: String,
-    passcode: String,
-    email_client: Data<EmailClient>,
-) -> Result<(), anyhow::Error> {
+pub fn password_reset_email(address: ValidEmail, passcode: &str) -> OutboxEmail {
+    reset_email(EmailTemplate::PasswordReset, address, passcode)
+}
+
+pub fn admin_initiated_reset_email(address: ValidEmail, passcode: &str) -> OutboxEmail {
+    reset_email(EmailTemplate::AdminPasswordReset, address, passcode)
+}
+
+fn reset_email(template: EmailTemplate, address: ValidEmail, passcode: &str) -> OutboxEmail {
     let web_app_hostname = get_configuration().unwrap().application.web_app_host;
     let link = format!("{}/reset-password?otp={}", web_app_hostname, passcode);
+    let email = render_email(template, DEFAULT_LOCALE, json!({ "link": link }))
+        .expect("Email templates render");
 
-    email_client
-        .send_email(
-            Vec::from([ValidEmail::parse(email).unwrap()]),
-            "Password Reset",
-            format!(
-                "Here is a <a href=\"{}\">link</a> that will enable you to reset your password!",
-                link
-            )
-            .as_str(),
-            format!(
-                "Here is a link that will enable you to reset your password! {}",
-                link
-            )
-            .as_str(),
-        )
-        .await
+    OutboxEmail::from_template(vec![address], email)
+}
+
+async fn change_password(
+    email_address: &str,
+    hashed_password: &str,
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    update_password(email_address, hashed_password, &mut transaction).await?;
+    transaction.commit().await
+}
+
+/// Flags the user, signs them out everywhere and queues the email with their passcode in
+/// one transaction, so a reset is never forced without a way to complete it.
+async fn force_password_reset(
+    user_id: Uuid,
+    otp: OneTimePasscode,
+    email: &OutboxEmail,
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    set_password_reset_required(user_id, &mut transaction).await?;
+    revoke_all_user_sessions(user_id, &mut transaction).await?;
+    insert_otp(otp, &mut transaction).await?;
+    queue_email(email, &mut transaction).await?;
+    transaction.commit().await
+}
+
+/// Saves the passcode and queues the email carrying it in one transaction.
+async fn store_otp_and_queue_email(
+    otp: OneTimePasscode,
+    email: &OutboxEmail,
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    insert_otp(otp, &mut transaction).await?;
+    queue_email(email, &mut transaction).await?;
+    transaction.commit().await
+}
+
+fn new_one_time_passcode(user_id: String) -> OneTimePasscode {
+    OneTimePasscode {
+        id: Uuid::new_v4(),
+        user_id,
+        one_time_passcode: generate_random_token(),
+        issued_on: Utc::now(),
+        expires_on: Utc::now() + Duration::days(1),
+        used: false,
+    }
 }
 
 fn is_invalid_one_time_passcode(one_time_passcode: &OneTimePasscode) -> bool {
@@ -0,0 +1,227 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::authorization::is_authorized_admin_only;
+use crate::auth::request_metadata::RequestMetadata;
+use crate::auth::token::Claims;
+use crate::background::webhook_dispatcher::send_webhook_delivery;
+use crate::db::webhook_endpoints_db_broker::{
+    delete_webhook_endpoint, get_webhook_deliveries, get_webhook_delivery, get_webhook_endpoints,
+    insert_webhook_endpoint, replay_webhook_delivery, update_webhook_endpoint,
+};
+use crate::domain::audit_models::{AuditAction, AuditEvent};
+use crate::domain::webhook_endpoint_models::{
+    WebhookDeliveryQuery, WebhookEndpoint, WebhookEndpointRequest,
+};
+use crate::routes::audit::record_audit_event;
+
+const MAX_WEBHOOK_DELIVERIES: i64 = 200;
+
+#[tracing::instrument(
+    name = "Create a webhook endpoint (admin only)",
+    skip(admin_user_id, endpoint, pool, user, metadata),
+    fields(url = %endpoint.url)
+)]
+pub async fn create_webhook_endpoint_admin(
+    admin_user_id: web::Path<String>,
+    endpoint: web::Json<WebhookEndpointRequest>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let admin_user_id = admin_user_id.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    if let Err(e) = endpoint.validate() {
+        return HttpResponse::BadRequest().json(json!({ "error": e }));
+    }
+
+    let endpoint = WebhookEndpoint::new(&endpoint);
+    match insert_webhook_endpoint(&endpoint, &pool).await {
+        Ok(_) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::CreateWebhookEndpoint)
+                    .with_target("webhook_endpoint", endpoint.id)
+                    .with_payload(json!({
+                        "url": endpoint.url,
+                        "event_types": endpoint.event_types,
+                    })),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(endpoint)
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Get webhook endpoints (admin only)",
+    skip(admin_user_id, pool, user)
+)]
+pub async fn get_webhook_endpoints_admin(
+    admin_user_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    if !is_authorized_admin_only(admin_user_id.into_inner(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    match get_webhook_endpoints(&pool).await {
+        Ok(endpoints) => HttpResponse::Ok().json(endpoints),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Update a webhook endpoint (admin only)",
+    skip(path, endpoint, pool, user, metadata)
+)]
+pub async fn update_webhook_endpoint_admin(
+    path: web::Path<(String, String)>,
+    endpoint: web::Json<WebhookEndpointRequest>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, endpoint_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let endpoint_id = match Uuid::parse_str(&endpoint_id) {
+        Ok(endpoint_id) => endpoint_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+    if let Err(e) = endpoint.validate() {
+        return HttpResponse::BadRequest().json(json!({ "error": e }));
+    }
+
+    match update_webhook_endpoint(endpoint_id, &endpoint, &pool).await {
+        Ok(endpoint) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::UpdateWebhookEndpoint)
+                    .with_target("webhook_endpoint", endpoint.id)
+                    .with_payload(json!({
+                        "url": endpoint.url,
+                        "event_types": endpoint.event_types,
+                        "enabled": endpoint.enabled,
+                    })),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(endpoint)
+        }
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Delete a webhook endpoint (admin only)",
+    skip(path, pool, user, metadata)
+)]
+pub async fn delete_webhook_endpoint_admin(
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, endpoint_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let endpoint_id = match Uuid::parse_str(&endpoint_id) {
+        Ok(endpoint_id) => endpoint_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+
+    match delete_webhook_endpoint(endpoint_id, &pool).await {
+        Ok(true) => {
+            record_audit_event(
+                AuditEvent::new(&admin_user_id, AuditAction::DeleteWebhookEndpoint)
+                    .with_target("webhook_endpoint", endpoint_id),
+                &metadata,
+                &pool,
+            )
+            .await;
+            HttpResponse::Ok().json(json!({}))
+        }
+        Ok(false) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Get webhook deliveries (admin only)",
+    skip(admin_user_id, query, pool, user)
+)]
+pub async fn get_webhook_deliveries_admin(
+    admin_user_id: web::Path<String>,
+    query: web::Query<WebhookDeliveryQuery>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+) -> impl Responder {
+    if !is_authorized_admin_only(admin_user_id.into_inner(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    match get_webhook_deliveries(
+        query.endpoint_id,
+        query.status,
+        MAX_WEBHOOK_DELIVERIES,
+        &pool,
+    )
+    .await
+    {
+        Ok(deliveries) => HttpResponse::Ok().json(deliveries),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// Sends a delivery's event to its endpoint again, for example once the
+/// receiver is fixed. Responds with the new delivery after its first attempt.
+#[tracing::instrument(
+    name = "Replay a webhook delivery (admin only)",
+    skip(path, pool, user, metadata)
+)]
+pub async fn replay_webhook_delivery_admin(
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    user: Claims,
+    metadata: RequestMetadata,
+) -> impl Responder {
+    let (admin_user_id, delivery_id) = path.into_inner();
+    if !is_authorized_admin_only(admin_user_id.clone(), user) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let delivery_id = match Uuid::parse_str(&delivery_id) {
+        Ok(delivery_id) => delivery_id,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+
+    let replay_id = match replay_webhook_delivery(delivery_id, &pool).await {
+        Ok(replay_id) => replay_id,
+        Err(sqlx::Error::RowNotFound) => return HttpResponse::NotFound().finish(),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+    record_audit_event(
+        AuditEvent::new(&admin_user_id, AuditAction::ReplayWebhookDelivery)
+            .with_target("webhook_delivery", delivery_id)
+            .with_payload(json!({ "replay_id": replay_id })),
+        &metadata,
+        &pool,
+    )
+    .await;
+    send_webhook_delivery(replay_id, &pool).await;
+
+    match get_webhook_delivery(replay_id, &pool).await {
+        Ok(replay) => HttpResponse::Ok().json(replay),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
@@ -0,0 +1,324 @@
+//! Fake data for local development. Everything is drawn from one RNG seeded with
+//! `SeedOptions::seed`, and dates count back from a fixed epoch rather than now, so
+//! the same options always produce the same rows.
+
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use fake::faker::address::en::{
+    BuildingNumber, CityName, SecondaryAddress, StateAbbr, StreetName, ZipCode,
+};
+use fake::faker::name::en::{FirstName, LastName};
+use fake::Fake;
+use rand::rngs::StdRng;
+use rand::{RngExt, SeedableRng};
+use serde::Serialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::domain::checkout_models::{CheckoutSession, CheckoutSessionState};
+use crate::domain::subscriber_models::OverTheWireSubscriber;
+use crate::domain::subscription_history_models::{HistoryEventType, SubscriptionHistoryEvent};
+use crate::domain::subscription_models::{
+    BillingSource, OverTheWireSubscription, SubscriptionType,
+};
+use crate::domain::user_models::UserGroup;
+use crate::domain::webhook_event::WebhookEvent;
+
+/// Every seeded account can log in with this password unless another one is given.
+pub const DEFAULT_SEED_PASSWORD: &str = "seed-password";
+pub const SEED_EMAIL_DOMAIN: &str = "seed.example.com";
+const PRICE_LOOKUP_KEYS: [&str; 2] = ["digital_monthly", "paper_yearly"];
+
+#[derive(Debug, Clone, Copy)]
+pub struct SeedOptions {
+    pub seed: u64,
+    pub users: usize,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SeedUser {
+    pub user_id: Uuid,
+    pub email_address: String,
+    pub user_group: UserGroup,
+}
+
+#[derive(Serialize, Default)]
+pub struct SeedData {
+    pub users: Vec<SeedUser>,
+    pub subscribers: Vec<OverTheWireSubscriber>,
+    pub subscriptions: Vec<OverTheWireSubscription>,
+    pub history_events: Vec<SubscriptionHistoryEvent>,
+    pub checkout_sessions: Vec<CheckoutSession>,
+    pub webhook_events: Vec<WebhookEvent>,
+}
+
+/// Dates are generated in the two years before this instant.
+pub fn seed_epoch() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap()
+}
+
+/// The first user is an admin; the rest are readers with zero to two subscriptions
+/// of mixed type, billing source and state.
+pub fn generate_seed_data(options: SeedOptions) -> SeedData {
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let mut data = SeedData::default();
+
+    for index in 0..options.users {
+        let first_name: String = FirstName().fake_with_rng(&mut rng);
+        let last_name: String = LastName().fake_with_rng(&mut rng);
+        // The index keeps addresses unique however the names collide.
+        let email_address = format!(
+            "{}.{}.{}@{}",
+            first_name, last_name, index, SEED_EMAIL_DOMAIN
+        )
+        .to_lowercase()
+        .replace(['\'', ' '], "");
+        let user_id = random_uuid(&mut rng);
+        data.users.push(SeedUser {
+            user_id,
+            email_address: email_address.clone(),
+            user_group: if index == 0 {
+                UserGroup::ADMIN
+            } else {
+                UserGroup::USER
+            },
+        });
+
+        let has_stripe_customer = rng.random_bool(0.7);
+        let subscriber = OverTheWireSubscriber {
+            id: random_uuid(&mut rng),
+            name: format!("{} {}", first_name, last_name),
+            email_address,
+            user_id: user_id.to_string(),
+            stripe_customer_id: has_stripe_customer.then(|| stripe_id("cus", &mut rng)),
+        };
+
+        for _ in 0..rng.random_range(0..=2) {
+            add_subscription(&subscriber, has_stripe_customer, &mut rng, &mut data);
+        }
+        data.subscribers.push(subscriber);
+    }
+
+    data
+}
+
+fn add_subscription(
+    subscriber: &OverTheWireSubscriber,
+    has_stripe_customer: bool,
+    rng: &mut StdRng,
+    data: &mut SeedData,
+) {
+    let subscription_type = if rng.random_bool(0.5) {
+        SubscriptionType::Paper
+    } else {
+        SubscriptionType::Digital
+    };
+    let billing_source = match rng.random_range(0..10) {
+        _ if !has_stripe_customer => BillingSource::Complimentary,
+        0 => BillingSource::Complimentary,
+        1 => BillingSource::External,
+        _ => BillingSource::Stripe,
+    };
+    let created = seed_epoch() - Duration::minutes(rng.random_range(60..2 * 365 * 24 * 60));
+    let cancelled = rng
+        .random_bool(0.2)
+        .then(|| created + Duration::days(rng.random_range(1..180)))
+        .filter(|cancelled| *cancelled < seed_epoch());
+    let building: String = BuildingNumber().fake_with_rng(rng);
+    let street: String = StreetName().fake_with_rng(rng);
+
+    let subscription = OverTheWireSubscription {
+        id: random_uuid(rng),
+        subscriber_id: subscriber.id,
+        subscription_name: subscriber.name.clone(),
+        subscription_mailing_address_line_1: format!("{} {}", building, street),
+        subscription_mailing_address_line_2: if rng.random_bool(0.3) {
+            SecondaryAddress().fake_with_rng(rng)
+        } else {
+            String::new()
+        },
+        subscription_city: CityName().fake_with_rng(rng),
+        subscription_state: StateAbbr().fake_with_rng(rng),
+        subscription_postal_code: ZipCode().fake_with_rng(rng),
+        subscription_email_address: subscriber.email_address.clone(),
+        subscription_creation_date: created,
+        subscription_cancelled_on_date: cancelled,
+        subscription_anniversary_day: created.day(),
+        subscription_anniversary_month: created.month(),
+        subscription_renewal_date: "".to_string(),
+        active: cancelled.is_none(),
+        subscription_type,
+        stripe_subscription_id: match billing_source {
+            BillingSource::Stripe => stripe_id("sub", rng),
+            _ => String::new(),
+        },
+        billing_source,
+        publication_id: None,
+    };
+
+    if subscription.billing_source == BillingSource::Stripe {
+        let price_lookup_key = match subscription.subscription_type {
+            SubscriptionType::Digital => PRICE_LOOKUP_KEYS[0],
+            SubscriptionType::Paper => PRICE_LOOKUP_KEYS[1],
+        };
+        data.checkout_sessions.push(CheckoutSession {
+            id: random_uuid(rng),
+            user_id: subscriber.user_id.clone(),
+            session_state: CheckoutSessionState::CompletedSuccessfully,
+            created_at: created - Duration::minutes(5),
+            price_lookup_key: price_lookup_key.to_string(),
+            subscription: json!({
+                "subscriber_id": subscriber.id,
+                "subscription_name": subscription.subscription_name,
+                "subscription_type": subscription.subscription_type,
+            }),
+            stripe_session_id: stripe_id("cs", rng),
+        });
+        data.webhook_events.push(webhook_event(
+            "customer.subscription.created",
+            &subscription,
+            subscriber,
+            created,
+            rng,
+        ));
+        // Now and then a reader gave up at the payment page.
+        if rng.random_bool(0.2) {
+            data.checkout_sessions.push(CheckoutSession {
+                id: random_uuid(rng),
+                user_id: subscriber.user_id.clone(),
+                session_state: CheckoutSessionState::Cancelled,
+                created_at: created - Duration::days(rng.random_range(1..30)),
+                price_lookup_key: price_lookup_key.to_string(),
+                subscription: json!({ "subscriber_id": subscriber.id }),
+                stripe_session_id: stripe_id("cs", rng),
+            });
+        }
+    }
+
+    data.history_events.push(history_event(
+        &subscription,
+        HistoryEventType::Created,
+        created,
+        rng,
+    ));
+    if let Some(cancelled) = cancelled {
+        data.history_events.push(history_event(
+            &subscription,
+            HistoryEventType::Cancelled,
+            cancelled,
+            rng,
+        ));
+        if subscription.billing_source == BillingSource::Stripe {
+            data.webhook_events.push(webhook_event(
+                "customer.subscription.deleted",
+                &subscription,
+                subscriber,
+                cancelled,
+                rng,
+            ));
+        }
+    }
+    data.subscriptions.push(subscription);
+}
+
+fn history_event(
+    subscription: &OverTheWireSubscription,
+    event_type: HistoryEventType,
+    date: DateTime<Utc>,
+    rng: &mut StdRng,
+) -> SubscriptionHistoryEvent {
+    SubscriptionHistoryEvent {
+        id: random_uuid(rng),
+        subscription_id: subscription.id,
+        subscription_change_event_date: date,
+        subscription_change_event_type: event_type,
+        subscription: json!(subscription),
+    }
+}
+
+/// Shaped like the Stripe events the webhook endpoint stores.
+fn webhook_event(
+    event_type: &str,
+    subscription: &OverTheWireSubscription,
+    subscriber: &OverTheWireSubscriber,
+    sent_on: DateTime<Utc>,
+    rng: &mut StdRng,
+) -> WebhookEvent {
+    let event_id = stripe_id("evt", rng);
+    WebhookEvent {
+        id: random_uuid(rng),
+        event_text: json!({
+            "id": event_id,
+            "object": "event",
+            "type": event_type,
+            "created": sent_on.timestamp(),
+            "livemode": false,
+            "data": {
+                "object": {
+                    "id": subscription.stripe_subscription_id,
+                    "object": "subscription",
+                    "customer": subscriber.stripe_customer_id,
+                    "status": if subscription.active { "active" } else { "canceled" },
+                }
+            }
+        })
+        .to_string(),
+        sent_on,
+        processed: true,
+    }
+}
+
+fn random_uuid(rng: &mut StdRng) -> Uuid {
+    uuid::Builder::from_random_bytes(rng.random()).into_uuid()
+}
+
+fn stripe_id(prefix: &str, rng: &mut StdRng) -> String {
+    let suffix: String = (0..14)
+        .map(|_| char::from(rng.sample(rand::distr::Alphanumeric)))
+        .collect();
+    format!("{}_seed{}", prefix, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::seed::{generate_seed_data, SeedOptions};
+
+    fn as_json(options: SeedOptions) -> serde_json::Value {
+        serde_json::to_value(generate_seed_data(options)).unwrap()
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_data() {
+        let options = SeedOptions {
+            seed: 42,
+            users: 20,
+        };
+        assert_eq!(as_json(options), as_json(options));
+    }
+
+    #[test]
+    fn different_seeds_give_different_data() {
+        assert_ne!(
+            as_json(SeedOptions { seed: 1, users: 5 }),
+            as_json(SeedOptions { seed: 2, users: 5 })
+        );
+    }
+
+    #[test]
+    fn data_is_consistent() {
+        let data = generate_seed_data(SeedOptions { seed: 7, users: 50 });
+        assert_eq!(50, data.users.len());
+        assert_eq!(50, data.subscribers.len());
+        assert!(!data.subscriptions.is_empty());
+        for subscription in &data.subscriptions {
+            assert!(data
+                .subscribers
+                .iter()
+                .any(|s| s.id == subscription.subscriber_id));
+            assert_eq!(
+                subscription.active,
+                subscription.subscription_cancelled_on_date.is_none()
+            );
+        }
+        assert!(data.history_events.len() >= data.subscriptions.len());
+    }
+}
@@ -10,14 +10,23 @@ use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 use tracing_actix_web::TracingLogger;
 
+use crate::background::email_outbox_worker::start_email_outbox_worker;
+use crate::background::issue_broadcaster::resume_broadcasts;
+use crate::background::job_worker::{start_job_worker, JobWorker};
+use crate::background::staff_digest::start_staff_digest_scheduler;
+use crate::background::webhook_dispatcher::start_webhook_delivery_worker;
 use crate::configuration::{current_environment, DatabaseSettings, Environment, Settings};
+use crate::db::notification_channels_db_broker::import_notification_addresses;
+use crate::db::schema_migrations::{ensure_expected_schema_version, run_pending_migrations};
 use crate::email_client::EmailClient;
+use crate::oidc_client::OidcClient;
 use crate::routes;
 use crate::stripe_client::StripeClient;
 
 pub struct Application {
     port: u16,
     server: Server,
+    job_worker: JobWorker,
 }
 
 impl Application {
@@ -26,12 +35,45 @@ impl Application {
     pub async fn build(configuration: Settings) -> Result<Self, std::io::Error> {
         let connection_pool = get_connection_pool(&configuration.database, &current_environment());
 
-        sqlx::migrate!("./migrations")
-            .run(&connection_pool)
+        if configuration.database.auto_migrate {
+            run_pending_migrations(&connection_pool)
+                .await
+                .map_err(std::io::Error::other)?;
+        }
+        ensure_expected_schema_version(&connection_pool)
             .await
-            .expect("Failed to run database migrations");
+            .map_err(std::io::Error::other)?;
+        import_notification_addresses(
+            &configuration
+                .application_feature_settings
+                .subscription_notification_addresses,
+            &connection_pool,
+        )
+        .await
+        .map_err(std::io::Error::other)?;
 
-        let email_client = EmailClient::new(configuration.email_client.clone());
+        let mut email_client = EmailClient::new(configuration.email_client.clone())
+            .with_suppression_list(connection_pool.clone());
+        if configuration
+            .email_client
+            .captures_emails(&current_environment())
+        {
+            tracing::warn!("Capturing every email in captured_emails; see GET /dev/emails");
+            email_client = email_client.with_email_capture(connection_pool.clone());
+        } else if configuration.email_client.capture_emails {
+            tracing::warn!("Ignoring email_client.capture_emails in production");
+        }
+        start_email_outbox_worker(email_client.clone(), &connection_pool);
+        start_webhook_delivery_worker(&connection_pool);
+        let job_worker = start_job_worker(email_client.clone(), &connection_pool);
+        if let Some(staff_digest) = configuration
+            .application_feature_settings
+            .staff_digest
+            .clone()
+        {
+            start_staff_digest_scheduler(staff_digest, email_client.clone(), &connection_pool);
+        }
+        resume_broadcasts(&email_client, &connection_pool).await;
 
         let stripe_client_timeout = configuration.stripe_client.timeout();
         let stripe_client = StripeClient::new(
@@ -40,6 +82,8 @@ impl Application {
             stripe_client_timeout,
         );
 
+        let oidc_client = configuration.oidc.map(OidcClient::new);
+
         let address = format!(
             "{}:{}",
             configuration.application.host, configuration.application.port
@@ -47,15 +91,29 @@ impl Application {
         let listener = TcpListener::bind(address)?;
         let port = listener.local_addr().unwrap().port();
 
-        let server = run(listener, connection_pool, email_client, stripe_client)?;
-        Ok(Self { port, server })
+        let server = run(
+            listener,
+            connection_pool,
+            email_client,
+            stripe_client,
+            oidc_client,
+        )?;
+        Ok(Self {
+            port,
+            server,
+            job_worker,
+        })
     }
     pub fn port(&self) -> u16 {
         self.port
     }
 
+    /// Serves until the server is told to stop, then lets the job worker
+    /// finish the job in hand.
     pub async fn run_until_stopped(self) -> Result<(), std::io::Error> {
-        self.server.await
+        let result = self.server.await;
+        self.job_worker.drain().await;
+        result
     }
 }
 
@@ -106,10 +164,12 @@ pub fn run(
     connection: PgPool,
     email_client: EmailClient,
     stripe_client: StripeClient,
+    oidc_client: Option<OidcClient>,
 ) -> Result<Server, std::io::Error> {
     let connection = Data::new(connection);
     let email_client = Data::new(email_client);
     let stripe_client = Data::new(stripe_client);
+    let oidc_client = Data::new(oidc_client);
     let server = HttpServer::new(move || {
         App::new()
             .wrap(define_cors())
@@ -135,7 +195,26 @@ pub fn run(
                 web::post().to(routes::reset_password_from_forgot_password),
             )
             .route("/reset_password", web::post().to(routes::reset_password))
+            .route("/oidc/login", web::get().to(routes::oidc_login))
+            .route("/oidc/callback", web::get().to(routes::oidc_callback))
             .route("/health_check", web::get().to(routes::health_check))
+            .route("/dev/emails", web::get().to(routes::get_dev_emails))
+            .route(
+                "/newsletter/consent",
+                web::get().to(routes::get_newsletter_consent),
+            )
+            .route(
+                "/newsletter/subscribe",
+                web::post().to(routes::newsletter_subscribe),
+            )
+            .route(
+                "/newsletter/confirm",
+                web::post().to(routes::newsletter_confirm),
+            )
+            .route(
+                "/email_events/{token}",
+                web::post().to(routes::email_delivery_events),
+            )
             .route(
                 "/subscriptions/{id}",
                 web::get().to(routes::get_subscription_by_id),
@@ -148,6 +227,18 @@ pub fn run(
                 "/subscriptions/{id}",
                 web::put().to(routes::update_subscription),
             )
+            .route(
+                "/subscriptions/{id}/issues",
+                web::get().to(routes::get_subscription_issues),
+            )
+            .route(
+                "/users/{user_id}/sessions",
+                web::get().to(routes::get_user_sessions),
+            )
+            .route(
+                "/users/{user_id}/sessions/{session_id}",
+                web::delete().to(routes::revoke_user_session_by_id),
+            )
             .route("/subscribers", web::post().to(routes::post_subscriber))
             .route(
                 "/subscribers",
@@ -161,6 +252,22 @@ pub fn run(
                 "/subscribers/{id}/subscriptions",
                 web::get().to(routes::get_subscriptions_by_subscriber_id),
             )
+            .route(
+                "/subscribers/{id}/preferences",
+                web::get().to(routes::get_email_preferences_by_subscriber_id),
+            )
+            .route(
+                "/subscribers/{id}/preferences",
+                web::put().to(routes::update_email_preferences),
+            )
+            .route(
+                "/unsubscribe/{token}",
+                web::get().to(routes::get_unsubscribe),
+            )
+            .route(
+                "/unsubscribe/{token}",
+                web::post().to(routes::post_unsubscribe),
+            )
             .route(
                 "/admin/subscribers/{user_id}",
                 web::get().to(routes::get_all_subscribers_admin),
@@ -181,6 +288,191 @@ pub fn run(
                 "/admin/users/{admin_user_id}/demote/{target_user_id}",
                 web::post().to(routes::admin_demote_user),
             )
+            .route(
+                "/admin/users/{admin_user_id}/disable/{target_user_id}",
+                web::post().to(routes::admin_disable_user),
+            )
+            .route(
+                "/admin/users/{admin_user_id}/enable/{target_user_id}",
+                web::post().to(routes::admin_enable_user),
+            )
+            .route(
+                "/admin/users/{admin_user_id}/force_password_reset/{target_user_id}",
+                web::post().to(routes::admin_force_password_reset),
+            )
+            .route(
+                "/admin/audit/{admin_user_id}",
+                web::get().to(routes::get_audit_log_admin),
+            )
+            .route(
+                "/admin/invitations/{admin_user_id}",
+                web::post().to(routes::create_invitation),
+            )
+            .route(
+                "/admin/invitations/{admin_user_id}",
+                web::get().to(routes::get_pending_invitations_admin),
+            )
+            .route(
+                "/admin/invitations/{admin_user_id}/{invitation_id}",
+                web::delete().to(routes::revoke_invitation_admin),
+            )
+            .route(
+                "/admin/fulfillment/runs/{admin_user_id}",
+                web::post().to(routes::create_fulfillment_run_admin),
+            )
+            .route(
+                "/admin/fulfillment/runs/{admin_user_id}",
+                web::get().to(routes::get_fulfillment_runs_admin),
+            )
+            .route(
+                "/admin/fulfillment/runs/{admin_user_id}/{run_id}",
+                web::get().to(routes::export_fulfillment_run_admin),
+            )
+            .route(
+                "/admin/fulfillment/runs/{admin_user_id}/{run_id}/labels",
+                web::get().to(routes::get_fulfillment_run_labels),
+            )
+            .route(
+                "/admin/publications/{admin_user_id}",
+                web::post().to(routes::create_publication_admin),
+            )
+            .route(
+                "/admin/publications/{admin_user_id}",
+                web::get().to(routes::get_publications_admin),
+            )
+            .route(
+                "/admin/publications/{admin_user_id}/{publication_id}",
+                web::put().to(routes::update_publication_admin),
+            )
+            .route(
+                "/admin/publications/{admin_user_id}/{publication_id}",
+                web::delete().to(routes::delete_publication_admin),
+            )
+            .route(
+                "/admin/publications/{admin_user_id}/{publication_id}/issues",
+                web::post().to(routes::create_issue_admin),
+            )
+            .route(
+                "/admin/publications/{admin_user_id}/{publication_id}/issues",
+                web::get().to(routes::get_issues_admin),
+            )
+            .route(
+                "/admin/issues/{admin_user_id}/{issue_id}",
+                web::put().to(routes::update_issue_admin),
+            )
+            .route(
+                "/admin/issues/{admin_user_id}/{issue_id}",
+                web::delete().to(routes::delete_issue_admin),
+            )
+            .route(
+                "/admin/issues/{admin_user_id}/{issue_id}/broadcasts",
+                web::post().to(routes::create_broadcast_admin),
+            )
+            .route(
+                "/admin/issues/{admin_user_id}/{issue_id}/broadcasts",
+                web::get().to(routes::get_broadcasts_admin),
+            )
+            .route(
+                "/admin/broadcasts/{admin_user_id}/{broadcast_id}/recipients",
+                web::get().to(routes::get_broadcast_recipients_admin),
+            )
+            .route(
+                "/admin/broadcasts/{admin_user_id}/{broadcast_id}/resume",
+                web::post().to(routes::resume_broadcast_admin),
+            )
+            .route(
+                "/admin/email_outbox/{admin_user_id}",
+                web::get().to(routes::get_outbox_emails_admin),
+            )
+            .route(
+                "/admin/email_outbox/{admin_user_id}/{email_id}/requeue",
+                web::post().to(routes::requeue_outbox_email_admin),
+            )
+            .route(
+                "/admin/email_suppressions/{admin_user_id}",
+                web::get().to(routes::get_email_suppressions_admin),
+            )
+            .route(
+                "/admin/email_suppressions/{admin_user_id}/{email_address}",
+                web::delete().to(routes::clear_email_suppression_admin),
+            )
+            .route(
+                "/admin/notification_channels/{admin_user_id}",
+                web::post().to(routes::create_notification_channel_admin),
+            )
+            .route(
+                "/admin/notification_channels/{admin_user_id}",
+                web::get().to(routes::get_notification_channels_admin),
+            )
+            .route(
+                "/admin/notification_channels/{admin_user_id}/{channel_id}",
+                web::put().to(routes::update_notification_channel_admin),
+            )
+            .route(
+                "/admin/notification_channels/{admin_user_id}/{channel_id}",
+                web::delete().to(routes::delete_notification_channel_admin),
+            )
+            .route(
+                "/admin/webhook_endpoints/{admin_user_id}",
+                web::post().to(routes::create_webhook_endpoint_admin),
+            )
+            .route(
+                "/admin/webhook_endpoints/{admin_user_id}",
+                web::get().to(routes::get_webhook_endpoints_admin),
+            )
+            .route(
+                "/admin/webhook_endpoints/{admin_user_id}/{endpoint_id}",
+                web::put().to(routes::update_webhook_endpoint_admin),
+            )
+            .route(
+                "/admin/webhook_endpoints/{admin_user_id}/{endpoint_id}",
+                web::delete().to(routes::delete_webhook_endpoint_admin),
+            )
+            .route(
+                "/admin/webhook_deliveries/{admin_user_id}",
+                web::get().to(routes::get_webhook_deliveries_admin),
+            )
+            .route(
+                "/admin/webhook_deliveries/{admin_user_id}/{delivery_id}/replay",
+                web::post().to(routes::replay_webhook_delivery_admin),
+            )
+            .route(
+                "/admin/jobs/{admin_user_id}",
+                web::get().to(routes::get_jobs_admin),
+            )
+            .route(
+                "/admin/jobs/{admin_user_id}/{job_id}/retry",
+                web::post().to(routes::retry_job_admin),
+            )
+            .route(
+                "/admin/staff_digest/{admin_user_id}",
+                web::get().to(routes::preview_staff_digest_admin),
+            )
+            .route(
+                "/admin/email_templates/{admin_user_id}",
+                web::get().to(routes::get_email_templates_admin),
+            )
+            .route(
+                "/admin/email_templates/{admin_user_id}/{template}",
+                web::get().to(routes::preview_email_template_admin),
+            )
+            .route(
+                "/admin/subscriptions/{admin_user_id}/{subscription_id}/publication",
+                web::put().to(routes::set_subscription_publication_admin),
+            )
+            .route(
+                "/admin/subscriptions/{admin_user_id}/{subscription_id}/issues",
+                web::get().to(routes::get_subscription_issues_admin),
+            )
+            .service(
+                web::resource("/admin/import/subscriptions/{admin_user_id}")
+                    .app_data(web::PayloadConfig::new(routes::MAX_IMPORT_FILE_BYTES))
+                    .route(web::post().to(routes::import_subscriptions_admin)),
+            )
+            .route(
+                "/invitations/accept",
+                web::post().to(routes::accept_invitation),
+            )
             .route(
                 "/checkout/{user_id}",
                 web::post().to(routes::create_checkout_session),
@@ -196,6 +488,7 @@ pub fn run(
             .app_data(connection.clone())
             .app_data(email_client.clone())
             .app_data(stripe_client.clone())
+            .app_data(oidc_client.clone())
     })
     .listen(listener)?
     .run();
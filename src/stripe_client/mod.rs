@@ -367,6 +367,7 @@ mod tests {
             object: "something".to_string(),
             amount_subtotal: 500,
             amount_total: 500,
+            currency: Some("usd".to_string()),
             client_reference_id: None,
             customer: Uuid::new_v4().to_string(),
             subscription: Some(Uuid::new_v4().to_string()),
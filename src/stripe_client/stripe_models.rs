@@ -20,6 +20,8 @@ pub struct StripeSessionObject {
     pub object: String,
     pub amount_subtotal: u64,
     pub amount_total: u64,
+    #[serde(default)]
+    pub currency: Option<String>,
     pub client_reference_id: Option<String>,
     pub customer: String,
     pub subscription: Option<String>,
@@ -108,6 +110,7 @@ mod tests {
             object: Uuid::new_v4().to_string(),
             amount_subtotal: 0,
             amount_total: 0,
+            currency: Some("usd".to_string()),
             client_reference_id: None,
             customer: "".to_string(),
             subscription: None,
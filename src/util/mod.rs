@@ -4,6 +4,7 @@ use actix_web::{web, HttpResponse};
 use chrono::Datelike;
 use rand::distr::Alphanumeric;
 use rand::{rng, RngExt};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
 pub fn from_path_to_uuid(id: &web::Path<String>) -> Result<Uuid, HttpResponse> {
@@ -38,6 +39,11 @@ pub fn generate_random_token() -> String {
         .collect()
 }
 
+/// Hex SHA-256 of a bearer secret, so that only the hash has to be stored.
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
 pub trait NaiveDateExt {
     fn days_in_month(&self) -> i32;
     fn days_in_year(&self) -> i32;
@@ -82,9 +88,18 @@ mod tests {
     use uuid::Uuid;
 
     use crate::util::{
-        from_path_to_uuid, from_string_to_uuid, generate_random_token, NaiveDateExt,
+        from_path_to_uuid, from_string_to_uuid, generate_random_token, hash_token, NaiveDateExt,
     };
 
+    #[test]
+    fn hash_token_is_stable_hex_sha256() {
+        assert_eq!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            hash_token("abc")
+        );
+        assert_ne!(hash_token("abc"), hash_token("abd"));
+    }
+
     #[test]
     fn native_date_ext_days_in_month_test() {
         assert_eq!(
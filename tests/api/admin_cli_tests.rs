@@ -0,0 +1,462 @@
+use chrono::{Duration, Utc};
+use claims::assert_err;
+use clap::Parser;
+use secrecy::SecretString;
+use uuid::Uuid;
+
+use newsletter_signup_service::admin_cli::{
+    cli_actor, run, Cli, Command, CommandOutput, OutputFormat,
+};
+use newsletter_signup_service::db::audit_log_db_broker::search_audit_log;
+use newsletter_signup_service::db::subscriptions_db_broker::retrieve_subscription_by_subscription_id;
+use newsletter_signup_service::db::webhook_event_db_broker::{
+    get_unprocessed_webhook_events, insert_webhook_event, set_webhook_event_to_processed,
+};
+use newsletter_signup_service::domain::audit_models::{AuditAction, AuditLogQuery};
+use newsletter_signup_service::domain::subscription_models::SubscriptionType;
+use newsletter_signup_service::domain::user_models::{LogIn, OverTheWireUser, UserGroup};
+use newsletter_signup_service::domain::webhook_event::WebhookEvent;
+use newsletter_signup_service::seed::DEFAULT_SEED_PASSWORD;
+use newsletter_signup_service::stripe_client::StripeClient;
+
+use crate::helper::{
+    generate_over_the_wire_create_subscription, mock_cancel_stripe_subscription, spawn_app,
+    store_subscription, TestApp,
+};
+
+async fn run_command(app: &TestApp, args: &[&str]) -> Result<CommandOutput, anyhow::Error> {
+    let cli = Cli::try_parse_from(std::iter::once("newsletter-admin").chain(args.iter().copied()))
+        .expect("Failed to parse arguments.");
+    let stripe_client = StripeClient::new(
+        app.stripe_server.uri(),
+        SecretString::from("sk_test"),
+        std::time::Duration::from_secs(2),
+    );
+    run(cli.command, &app.db_pool, &stripe_client).await
+}
+
+#[tokio::test]
+async fn create_admin_bootstraps_an_admin_that_can_log_in() {
+    let app = spawn_app().await;
+    let email = format!("{}@example.com", Uuid::new_v4());
+
+    let output = run_command(
+        &app,
+        &["create-admin", "--email", &email, "--name", "First Admin"],
+    )
+    .await
+    .unwrap();
+
+    let password = output.json["password"].as_str().unwrap().to_string();
+    assert!(output.text.contains(&password));
+    let response = app
+        .login(
+            LogIn {
+                email_address: email.clone(),
+                password,
+            }
+            .to_json(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!("ADMIN", body["group"]);
+
+    assert_err!(
+        run_command(
+            &app,
+            &["create-admin", "--email", &email, "--name", "First Admin"]
+        )
+        .await
+    );
+}
+
+#[tokio::test]
+async fn promote_demote_and_list_users_work() {
+    let app = spawn_app().await;
+    let admin = run_command(
+        &app,
+        &[
+            "create-admin",
+            "--email",
+            &format!("{}@example.com", Uuid::new_v4()),
+            "--name",
+            "First Admin",
+            "--password",
+            "a-password",
+        ],
+    )
+    .await
+    .unwrap();
+    assert!(admin.json.get("password").is_none());
+    let admin_id = admin.json["user_id"].as_str().unwrap().to_string();
+    let user_id = app.sign_up().await.user_id;
+
+    assert_err!(run_command(&app, &["demote", "--user-id", &admin_id]).await);
+    run_command(&app, &["promote", "--user-id", &user_id])
+        .await
+        .unwrap();
+    run_command(&app, &["demote", "--user-id", &admin_id])
+        .await
+        .unwrap();
+
+    let output = run_command(&app, &["--output", "json", "list-users"])
+        .await
+        .unwrap();
+    let users: Vec<OverTheWireUser> = serde_json::from_value(output.json.clone()).unwrap();
+    let group_of = |id: &str| {
+        users
+            .iter()
+            .find(|u| u.user_id.to_string() == id)
+            .unwrap()
+            .user_group
+            .clone()
+    };
+    assert_eq!(UserGroup::ADMIN.as_str(), group_of(&user_id));
+    assert_eq!(UserGroup::USER.as_str(), group_of(&admin_id));
+    assert!(output.text.contains(&user_id));
+    let _: Vec<OverTheWireUser> = serde_json::from_str(&output.render(OutputFormat::Json)).unwrap();
+
+    assert_err!(run_command(&app, &["promote", "--user-id", &Uuid::new_v4().to_string()]).await);
+}
+
+#[tokio::test]
+async fn reset_password_sets_a_working_password_and_signs_the_user_out() {
+    let app = spawn_app().await;
+    let signup = crate::helper::generate_signup();
+    let response = app.user_signup(signup.to_json()).await;
+    let login: newsletter_signup_service::auth::token::LoginResponse =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+
+    let output = run_command(&app, &["reset-password", "--user-id", &login.user_id])
+        .await
+        .unwrap();
+    let response = app.check_token(login.user_id.clone(), login.token).await;
+    assert_eq!(401, response.status().as_u16());
+
+    let response = app
+        .login(
+            LogIn {
+                email_address: signup.email_address,
+                password: output.json["password"].as_str().unwrap().to_string(),
+            }
+            .to_json(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn cancel_subscription_cancels_in_stripe_and_locally() {
+    let app = spawn_app().await;
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = store_subscription(subscriber.id.to_string(), None, &app).await;
+    mock_cancel_stripe_subscription(
+        &app.stripe_server,
+        subscription.stripe_subscription_id.clone(),
+    )
+    .await;
+
+    let output = run_command(
+        &app,
+        &[
+            "cancel-subscription",
+            "--subscription-id",
+            &subscription.id.to_string(),
+        ],
+    )
+    .await
+    .unwrap();
+    assert_eq!(true, output.json["cancelled"]);
+    let (entries, _) = search_audit_log(
+        &AuditLogQuery {
+            action: Some(AuditAction::CancelSubscription.as_str().to_string()),
+            target_id: Some(subscription.id.to_string()),
+            ..Default::default()
+        },
+        &app.db_pool,
+    )
+    .await
+    .unwrap();
+    assert_eq!(Some(cli_actor()), entries[0].actor);
+    assert_eq!(None, entries[0].actor_user_id);
+
+    let output = run_command(
+        &app,
+        &[
+            "cancel-subscription",
+            "--subscription-id",
+            &subscription.id.to_string(),
+        ],
+    )
+    .await
+    .unwrap();
+    assert_eq!(false, output.json["cancelled"]);
+}
+
+#[tokio::test]
+async fn cancel_subscription_keeps_the_subscription_when_stripe_refuses() {
+    let app = spawn_app().await;
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = store_subscription(subscriber.id.to_string(), None, &app).await;
+
+    let outcome = run_command(
+        &app,
+        &[
+            "cancel-subscription",
+            "--subscription-id",
+            &subscription.id.to_string(),
+        ],
+    )
+    .await;
+
+    assert_err!(outcome);
+    let stored = retrieve_subscription_by_subscription_id(subscription.id, &app.db_pool)
+        .await
+        .unwrap();
+    assert!(stored.active);
+}
+
+#[tokio::test]
+async fn import_subscriptions_reads_a_file_and_cancels_without_stripe() {
+    let app = spawn_app().await;
+    let email_address = format!("{}@example.com", Uuid::new_v4());
+    let file = std::env::temp_dir().join(format!("{}.csv", Uuid::new_v4()));
+    std::fs::write(
+        &file,
+        format!(
+            "name,email_address,address_line_1,city,state,postal_code,subscription_type\n\
+             Paper Reader,{},1 Main St,Springfield,IL,62701,Paper\n",
+            email_address
+        ),
+    )
+    .unwrap();
+    let file_arg = file.to_str().unwrap();
+
+    let output = run_command(
+        &app,
+        &["import-subscriptions", "--file", file_arg, "--dry-run"],
+    )
+    .await
+    .unwrap();
+    assert_eq!("WouldCreate", output.json["rows"][0]["status"]);
+
+    let output = run_command(&app, &["import-subscriptions", "--file", file_arg])
+        .await
+        .unwrap();
+    std::fs::remove_file(&file).unwrap();
+    assert_eq!(1, output.json["created"]);
+    let subscription_id = output.json["rows"][0]["subscription_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    // Complimentary subscriptions have nothing to cancel in Stripe.
+    let output = run_command(
+        &app,
+        &["cancel-subscription", "--subscription-id", &subscription_id],
+    )
+    .await
+    .unwrap();
+    assert_eq!(true, output.json["cancelled"]);
+    assert!(app
+        .stripe_server
+        .received_requests()
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn seed_creates_an_admin_that_can_log_in() {
+    let app = spawn_app().await;
+
+    let output = run_command(&app, &["seed", "--seed", "3", "--users", "5"])
+        .await
+        .unwrap();
+    assert_eq!(5, output.json["users"]);
+
+    let response = app
+        .login(
+            LogIn {
+                email_address: output.json["admin_email_address"]
+                    .as_str()
+                    .unwrap()
+                    .to_string(),
+                password: DEFAULT_SEED_PASSWORD.to_string(),
+            }
+            .to_json(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!("ADMIN", body["group"]);
+}
+
+#[tokio::test]
+async fn fulfillment_export_stores_a_run_and_writes_the_file() {
+    let app = spawn_app().await;
+    let subscriber = app.store_subscriber(None).await;
+    store_subscription(
+        subscriber.id.to_string(),
+        Some(generate_over_the_wire_create_subscription(
+            subscriber.id.to_string(),
+            Some(SubscriptionType::Paper),
+        )),
+        &app,
+    )
+    .await;
+    let out = std::env::temp_dir().join(format!("{}.txt", Uuid::new_v4()));
+    let issue_date = (Utc::now() + Duration::days(1)).date_naive().to_string();
+
+    let output = run_command(
+        &app,
+        &[
+            "fulfillment-export",
+            "--issue-date",
+            &issue_date,
+            "--format",
+            "fixed-width",
+            "--out",
+            out.to_str().unwrap(),
+        ],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(1, output.json["run"]["recipient_count"]);
+    let file = std::fs::read_to_string(&out).unwrap();
+    std::fs::remove_file(&out).unwrap();
+    assert!(file.starts_with("000001"));
+}
+
+#[tokio::test]
+async fn fulfillment_labels_writes_a_pdf_of_a_stored_run() {
+    let app = spawn_app().await;
+    let issue_date = (Utc::now() + Duration::days(1)).date_naive().to_string();
+    let run = run_command(&app, &["fulfillment-export", "--issue-date", &issue_date])
+        .await
+        .unwrap();
+    let run_id = run.json["run"]["id"].as_str().unwrap().to_string();
+    let out = std::env::temp_dir().join(format!("{}.pdf", Uuid::new_v4()));
+
+    let output = run_command(
+        &app,
+        &[
+            "fulfillment-labels",
+            "--run-id",
+            &run_id,
+            "--layout",
+            "thermal-4x6",
+            "--out",
+            out.to_str().unwrap(),
+        ],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(1, output.json["pages"]);
+    let file = std::fs::read(&out).unwrap();
+    std::fs::remove_file(&out).unwrap();
+    assert!(file.starts_with(b"%PDF-"));
+
+    let error = run_command(
+        &app,
+        &[
+            "fulfillment-labels",
+            "--run-id",
+            &Uuid::new_v4().to_string(),
+            "--out",
+            out.to_str().unwrap(),
+        ],
+    )
+    .await
+    .unwrap_err();
+    assert!(error.to_string().contains("No fulfillment run"));
+}
+
+#[tokio::test]
+async fn replay_webhook_events_marks_events_unprocessed() {
+    let app = spawn_app().await;
+    let event = |sent_on| WebhookEvent {
+        id: Uuid::new_v4(),
+        event_text: "{}".to_string(),
+        sent_on,
+        processed: false,
+    };
+    let old = event(Utc::now() - Duration::days(10));
+    let recent = event(Utc::now());
+    for e in [&old, &recent] {
+        insert_webhook_event(e, &app.db_pool).await.unwrap();
+        set_webhook_event_to_processed(e.id, &app.db_pool)
+            .await
+            .unwrap();
+    }
+
+    run_command(
+        &app,
+        &["replay-webhook-events", "--id", &old.id.to_string()],
+    )
+    .await
+    .unwrap();
+    let unprocessed = get_unprocessed_webhook_events(&app.db_pool).await.unwrap();
+    assert_eq!(1, unprocessed.len());
+    assert_eq!(old.id, unprocessed[0].id);
+
+    let since = (Utc::now() - Duration::days(1)).to_rfc3339();
+    let output = run_command(&app, &["replay-webhook-events", "--since", &since])
+        .await
+        .unwrap();
+    assert_eq!(1, output.json["replayed"].as_array().unwrap().len());
+    assert_eq!(
+        2,
+        get_unprocessed_webhook_events(&app.db_pool)
+            .await
+            .unwrap()
+            .len()
+    );
+
+    assert_err!(run_command(&app, &["replay-webhook-events"]).await);
+}
+
+#[test]
+fn unknown_subcommands_are_rejected() {
+    assert_err!(Cli::try_parse_from(["newsletter-admin", "drop-database"]));
+    let cli = Cli::try_parse_from(["newsletter-admin", "list-users", "--output", "json"]).unwrap();
+    assert_eq!(OutputFormat::Json, cli.output);
+    assert!(matches!(cli.command, Command::ListUsers));
+}
+
+#[tokio::test]
+async fn migrate_reports_status_and_dry_runs() {
+    let app = spawn_app().await;
+
+    let output = run_command(&app, &["migrate", "status"]).await.unwrap();
+    let migrations = output.json["migrations"].as_array().unwrap();
+    assert!(!migrations.is_empty());
+    assert!(migrations.iter().all(|m| m["state"] == "Applied"));
+
+    let latest = migrations.last().unwrap()["version"].as_i64().unwrap();
+    sqlx::query("DELETE FROM _sqlx_migrations WHERE version = $1")
+        .bind(latest)
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    let output = run_command(&app, &["migrate", "up", "--dry-run"])
+        .await
+        .unwrap();
+    let pending = output.json["migrations"].as_array().unwrap();
+    assert_eq!(1, pending.len());
+    assert_eq!(latest, pending[0]["version"].as_i64().unwrap());
+    assert!(output.text.starts_with("Would apply 1 migration(s)"));
+
+    let output = run_command(&app, &["migrate", "status"]).await.unwrap();
+    assert!(output.json["migrations"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|m| m["state"] == "Pending"));
+}
@@ -0,0 +1,317 @@
+use mailtrap_rs::types::response::SendEmailResponse;
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use newsletter_signup_service::auth::token::{generate_token, LoginResponse};
+use newsletter_signup_service::domain::email_outbox_models::OutboxEmail;
+use newsletter_signup_service::domain::user_models::{
+    LogIn, OverTheWireUser, ResetPasswordFromForgotPassword, UserGroup,
+};
+
+use crate::helper::{generate_signup, spawn_app, TestApp};
+
+async fn sign_up_admin(app: &TestApp) -> LoginResponse {
+    let login = app.sign_up().await;
+    let bootstrap_admin_id = Uuid::new_v4().to_string();
+    let response = app
+        .admin_promote_user(
+            bootstrap_admin_id.clone(),
+            login.user_id.clone(),
+            generate_token(bootstrap_admin_id, UserGroup::ADMIN),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    LoginResponse {
+        token: generate_token(login.user_id.clone(), UserGroup::ADMIN),
+        group: UserGroup::ADMIN,
+        ..login
+    }
+}
+
+#[tokio::test]
+async fn disabled_users_cannot_login_or_use_existing_tokens() {
+    let app = spawn_app().await;
+    let signup = generate_signup();
+    let response = app.user_signup(signup.to_json()).await;
+    assert_eq!(200, response.status().as_u16());
+    let login: LoginResponse =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+
+    let admin_user_id = Uuid::new_v4().to_string();
+    let admin_token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+    let disable_response = app
+        .admin_disable_user(
+            admin_user_id.clone(),
+            login.user_id.clone(),
+            admin_token.clone(),
+        )
+        .await;
+    assert_eq!(200, disable_response.status().as_u16());
+
+    let login_response = app.login(signup.to_json()).await;
+    assert_eq!(403, login_response.status().as_u16());
+    let check_response = app
+        .check_token(login.user_id.clone(), login.token.clone())
+        .await;
+    assert_eq!(401, check_response.status().as_u16());
+
+    let enable_response = app
+        .admin_enable_user(admin_user_id, login.user_id.clone(), admin_token)
+        .await;
+    assert_eq!(200, enable_response.status().as_u16());
+
+    let login_response = app.login(signup.to_json()).await;
+    assert_eq!(200, login_response.status().as_u16());
+    let check_response = app.check_token(login.user_id, login.token).await;
+    assert_eq!(200, check_response.status().as_u16());
+}
+
+#[tokio::test]
+async fn disabled_users_are_flagged_in_the_admin_user_list() {
+    let app = spawn_app().await;
+    let login = app.sign_up().await;
+
+    let admin_user_id = Uuid::new_v4().to_string();
+    let admin_token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+    let response = app
+        .admin_disable_user(
+            admin_user_id.clone(),
+            login.user_id.clone(),
+            admin_token.clone(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let list_response = app.get_all_users_admin(admin_user_id, admin_token).await;
+    let users: Vec<OverTheWireUser> =
+        serde_json::from_str(list_response.text().await.unwrap().as_str()).unwrap();
+    let disabled = users
+        .iter()
+        .find(|u| u.user_id.to_string() == login.user_id)
+        .expect("disabled user in list");
+    assert!(disabled.disabled);
+}
+
+#[tokio::test]
+async fn disable_user_returns_404_for_unknown_user() {
+    let app = spawn_app().await;
+    let admin_user_id = Uuid::new_v4().to_string();
+
+    let response = app
+        .admin_disable_user(
+            admin_user_id.clone(),
+            Uuid::new_v4().to_string(),
+            generate_token(admin_user_id, UserGroup::ADMIN),
+        )
+        .await;
+
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn disable_user_returns_400_when_disabling_self() {
+    let app = spawn_app().await;
+    let admin = sign_up_admin(&app).await;
+
+    let response = app
+        .admin_disable_user(admin.user_id.clone(), admin.user_id, admin.token)
+        .await;
+
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn disable_user_returns_401_for_non_admin() {
+    let app = spawn_app().await;
+    let login = app.sign_up().await;
+
+    let response = app
+        .admin_disable_user(
+            login.user_id.clone(),
+            Uuid::new_v4().to_string(),
+            login.token,
+        )
+        .await;
+
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn the_last_admin_cannot_be_demoted_or_disabled() {
+    let app = spawn_app().await;
+    let admin = sign_up_admin(&app).await;
+
+    let other_admin_id = Uuid::new_v4().to_string();
+    let other_admin_token = generate_token(other_admin_id.clone(), UserGroup::ADMIN);
+    let demote_response = app
+        .admin_demote_user(
+            other_admin_id.clone(),
+            admin.user_id.clone(),
+            other_admin_token.clone(),
+        )
+        .await;
+    assert_eq!(409, demote_response.status().as_u16());
+
+    let disable_response = app
+        .admin_disable_user(other_admin_id, admin.user_id.clone(), other_admin_token)
+        .await;
+    assert_eq!(409, disable_response.status().as_u16());
+
+    let self_demote_response = app
+        .admin_demote_user(admin.user_id.clone(), admin.user_id, admin.token)
+        .await;
+    assert_eq!(409, self_demote_response.status().as_u16());
+}
+
+#[tokio::test]
+async fn a_disabled_admin_loses_access_to_admin_routes() {
+    let app = spawn_app().await;
+    let first_admin = sign_up_admin(&app).await;
+    let second_admin = sign_up_admin(&app).await;
+
+    let response = app
+        .admin_disable_user(
+            second_admin.user_id.clone(),
+            first_admin.user_id.clone(),
+            second_admin.token.clone(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = app
+        .get_all_users_admin(first_admin.user_id, first_admin.token)
+        .await;
+    assert_eq!(401, response.status().as_u16());
+
+    // The remaining admin is now the last one and stays protected.
+    let response = app
+        .admin_demote_user(
+            second_admin.user_id.clone(),
+            second_admin.user_id,
+            second_admin.token,
+        )
+        .await;
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn force_password_reset_signs_the_user_out_until_the_password_is_changed() {
+    let app = spawn_app().await;
+    let signup = generate_signup();
+    let response = app.user_signup(signup.to_json()).await;
+    assert_eq!(200, response.status().as_u16());
+    let login: LoginResponse =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+
+    Mock::given(path("api/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(SendEmailResponse {
+            success: true,
+            message_ids: vec!["test-id".to_string()],
+            errors: vec![],
+        }))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let admin_user_id = Uuid::new_v4().to_string();
+    let response = app
+        .admin_force_password_reset(
+            admin_user_id.clone(),
+            login.user_id.clone(),
+            generate_token(admin_user_id, UserGroup::ADMIN),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let response = app
+        .check_token(login.user_id.clone(), login.token.clone())
+        .await;
+    assert_eq!(401, response.status().as_u16());
+
+    let login_response = app.login(signup.to_json()).await;
+    assert_eq!(403, login_response.status().as_u16());
+    let body: serde_json::Value =
+        serde_json::from_str(login_response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!(true, body["password_reset_required"]);
+
+    let body = &app.received_emails("Password Reset Required").await[0];
+    let links: Vec<_> = linkify::LinkFinder::new()
+        .links(body["text"].as_str().unwrap())
+        .filter(|l| *l.kind() == linkify::LinkKind::Url)
+        .collect();
+    assert_eq!(1, links.len());
+    let otp_url = url::Url::parse(links[0].as_str()).unwrap();
+    let otp = otp_url.query_pairs().next().unwrap().1.to_string();
+
+    let response = app.forgot_password_login(otp).await;
+    assert_eq!(200, response.status().as_u16());
+    let otp_login: LoginResponse =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    // Until the password is changed, the passcode session can only reset it.
+    let response = app
+        .check_token(otp_login.user_id.clone(), otp_login.token.clone())
+        .await;
+    assert_eq!(401, response.status().as_u16());
+
+    let reset = ResetPasswordFromForgotPassword {
+        user_id: otp_login.user_id.clone(),
+        new_password: Uuid::new_v4().to_string(),
+    };
+    let response = app
+        .forgot_password_rest_password(reset.to_json(), otp_login.token.clone())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let response = app.check_token(otp_login.user_id, otp_login.token).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let login_response = app
+        .login(
+            LogIn {
+                email_address: signup.email_address,
+                password: reset.new_password,
+            }
+            .to_json(),
+        )
+        .await;
+    assert_eq!(200, login_response.status().as_u16());
+}
+
+#[tokio::test]
+async fn force_password_reset_queues_the_email_when_it_cannot_be_sent() {
+    let app = spawn_app().await;
+    let login = app.sign_up().await;
+
+    Mock::given(path("api/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&app.email_server)
+        .await;
+
+    let admin_user_id = Uuid::new_v4().to_string();
+    let response = app
+        .admin_force_password_reset(
+            admin_user_id.clone(),
+            login.user_id,
+            generate_token(admin_user_id.clone(), UserGroup::ADMIN),
+        )
+        .await;
+
+    assert_eq!(200, response.status().as_u16());
+    let response = app
+        .get_outbox_emails(
+            admin_user_id.clone(),
+            Some("Pending"),
+            generate_token(admin_user_id, UserGroup::ADMIN),
+        )
+        .await;
+    let queued: Vec<OutboxEmail> =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!(
+        1,
+        queued
+            .iter()
+            .filter(|email| email.subject == "Password Reset Required")
+            .count()
+    );
+}
@@ -0,0 +1,143 @@
+use chrono::{Duration, Utc};
+use claims::{assert_err, assert_ok};
+use serde_json::json;
+use uuid::Uuid;
+
+use newsletter_signup_service::db::audit_log_db_broker::{
+    insert_audit_log_entry, search_audit_log,
+};
+use newsletter_signup_service::domain::audit_models::{AuditAction, AuditEvent, AuditLogQuery};
+
+use crate::helper::spawn_app;
+
+#[tokio::test]
+async fn insert_and_search_audit_log_works() {
+    let app = spawn_app().await;
+    let actor = Uuid::new_v4();
+    let target = Uuid::new_v4();
+
+    let event = AuditEvent::new(&actor.to_string(), AuditAction::PromoteUser)
+        .with_target("user", target)
+        .with_payload(json!({ "user_group": "ADMIN" }));
+    assert_ok!(
+        insert_audit_log_entry(&event, Some("127.0.0.1"), Some("test-agent"), &app.db_pool).await
+    );
+    assert_ok!(
+        insert_audit_log_entry(
+            &AuditEvent::new(&actor.to_string(), AuditAction::ListUsers),
+            None,
+            None,
+            &app.db_pool
+        )
+        .await
+    );
+
+    let (entries, total) = search_audit_log(
+        &AuditLogQuery {
+            actor_user_id: Some(actor),
+            action: Some(AuditAction::PromoteUser.as_str().to_string()),
+            ..Default::default()
+        },
+        &app.db_pool,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(1, total);
+    let entry = &entries[0];
+    assert_eq!(Some(actor), entry.actor_user_id);
+    assert_eq!("PromoteUser", entry.action);
+    assert_eq!(Some("user".to_string()), entry.target_type);
+    assert_eq!(Some(target.to_string()), entry.target_id);
+    assert_eq!(Some("127.0.0.1".to_string()), entry.ip_address);
+    assert_eq!(Some("test-agent".to_string()), entry.user_agent);
+    assert_eq!(json!({ "user_group": "ADMIN" }), entry.payload);
+
+    let (_, total) = search_audit_log(
+        &AuditLogQuery {
+            actor_user_id: Some(actor),
+            ..Default::default()
+        },
+        &app.db_pool,
+    )
+    .await
+    .unwrap();
+    assert_eq!(2, total);
+}
+
+#[tokio::test]
+async fn search_audit_log_pages_and_filters_by_time() {
+    let app = spawn_app().await;
+    let actor = Uuid::new_v4().to_string();
+    for _ in 0..5 {
+        insert_audit_log_entry(
+            &AuditEvent::new(&actor, AuditAction::ListSubscribers),
+            None,
+            None,
+            &app.db_pool,
+        )
+        .await
+        .unwrap();
+    }
+
+    let (entries, total) = search_audit_log(
+        &AuditLogQuery {
+            page: Some(2),
+            page_size: Some(2),
+            ..Default::default()
+        },
+        &app.db_pool,
+    )
+    .await
+    .unwrap();
+    assert_eq!(5, total);
+    assert_eq!(2, entries.len());
+
+    let (entries, total) = search_audit_log(
+        &AuditLogQuery {
+            from: Some(Utc::now() + Duration::minutes(1)),
+            ..Default::default()
+        },
+        &app.db_pool,
+    )
+    .await
+    .unwrap();
+    assert_eq!(0, total);
+    assert!(entries.is_empty());
+}
+
+#[tokio::test]
+async fn audit_log_entries_cannot_be_changed_or_deleted() {
+    let app = spawn_app().await;
+    let id = insert_audit_log_entry(
+        &AuditEvent::new(&Uuid::new_v4().to_string(), AuditAction::ResetPassword),
+        None,
+        None,
+        &app.db_pool,
+    )
+    .await
+    .unwrap();
+
+    assert_err!(
+        sqlx::query!("UPDATE audit_log SET action = 'Tampered' WHERE id = $1", id)
+            .execute(&app.db_pool)
+            .await
+    );
+    assert_err!(
+        sqlx::query!("DELETE FROM audit_log WHERE id = $1", id)
+            .execute(&app.db_pool)
+            .await
+    );
+}
+
+#[tokio::test]
+async fn search_audit_log_failed() {
+    let app = spawn_app().await;
+
+    sqlx::query!("DROP TABLE audit_log")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    assert_err!(search_audit_log(&AuditLogQuery::default(), &app.db_pool).await);
+}
@@ -0,0 +1,176 @@
+use uuid::Uuid;
+
+use newsletter_signup_service::auth::token::{generate_token, LoginResponse};
+use newsletter_signup_service::domain::audit_models::AuditLogPage;
+use newsletter_signup_service::domain::user_models::UserGroup;
+
+use crate::helper::{generate_reset_password, generate_signup, spawn_app};
+
+async fn get_audit_log_page(
+    app: &crate::helper::TestApp,
+    admin_user_id: &str,
+    query: &[(&str, String)],
+) -> AuditLogPage {
+    let response = app
+        .get_audit_log_admin(
+            admin_user_id.to_string(),
+            query,
+            generate_token(admin_user_id.to_string(), UserGroup::ADMIN),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    serde_json::from_str(response.text().await.unwrap().as_str()).unwrap()
+}
+
+#[tokio::test]
+async fn promoting_a_user_is_recorded_in_the_audit_log() {
+    let app = spawn_app().await;
+    let login = app.sign_up().await;
+
+    let admin_user_id = Uuid::new_v4().to_string();
+    let promote_response = app
+        .admin_promote_user(
+            admin_user_id.clone(),
+            login.user_id.clone(),
+            generate_token(admin_user_id.clone(), UserGroup::ADMIN),
+        )
+        .await;
+    assert_eq!(200, promote_response.status().as_u16());
+
+    let page = get_audit_log_page(
+        &app,
+        &admin_user_id,
+        &[("action", "PromoteUser".to_string())],
+    )
+    .await;
+
+    assert_eq!(1, page.total);
+    let entry = &page.entries[0];
+    assert_eq!(admin_user_id, entry.actor_user_id.unwrap().to_string());
+    assert_eq!(Some("user".to_string()), entry.target_type);
+    assert_eq!(Some(login.user_id), entry.target_id);
+    assert_eq!(Some("127.0.0.1".to_string()), entry.ip_address);
+}
+
+#[tokio::test]
+async fn resetting_a_password_is_recorded_in_the_audit_log() {
+    let app = spawn_app().await;
+
+    let signup = generate_signup();
+    let response = app.user_signup(signup.to_json()).await;
+    assert_eq!(200, response.status().as_u16());
+    let login: LoginResponse =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+
+    let reset_password = generate_reset_password(signup.email_address, signup.password);
+    let reset_password_response = app
+        .reset_password(
+            reset_password.to_json(),
+            generate_token(login.user_id.clone(), UserGroup::USER),
+        )
+        .await;
+    assert_eq!(200, reset_password_response.status().as_u16());
+
+    let admin_user_id = Uuid::new_v4().to_string();
+    let page = get_audit_log_page(
+        &app,
+        &admin_user_id,
+        &[("actor_user_id", login.user_id.clone())],
+    )
+    .await;
+
+    assert_eq!(1, page.total);
+    assert_eq!("ResetPassword", page.entries[0].action);
+    assert_eq!(Some(login.user_id), page.entries[0].target_id);
+}
+
+#[tokio::test]
+async fn admin_list_endpoints_are_recorded_and_paginated() {
+    let app = spawn_app().await;
+    let admin_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+
+    for _ in 0..3 {
+        let response = app
+            .get_all_users_admin(admin_user_id.clone(), token.clone())
+            .await;
+        assert_eq!(200, response.status().as_u16());
+    }
+
+    let page = get_audit_log_page(
+        &app,
+        &admin_user_id,
+        &[
+            ("action", "ListUsers".to_string()),
+            ("page", "2".to_string()),
+            ("page_size", "2".to_string()),
+        ],
+    )
+    .await;
+
+    assert_eq!(3, page.total);
+    assert_eq!(2, page.page);
+    assert_eq!(2, page.page_size);
+    assert_eq!(1, page.entries.len());
+
+    // Reading the audit log is itself audited.
+    let page = get_audit_log_page(
+        &app,
+        &admin_user_id,
+        &[("action", "ListAuditLog".to_string())],
+    )
+    .await;
+    assert_eq!(1, page.total);
+    assert_eq!(
+        Some("audit-test-agent".to_string()),
+        page.entries[0].user_agent
+    );
+}
+
+#[tokio::test]
+async fn get_audit_log_returns_401_for_non_admin() {
+    let app = spawn_app().await;
+    let login = app.sign_up().await;
+
+    let response = app
+        .get_audit_log_admin(login.user_id.clone(), &[], login.token)
+        .await;
+
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn get_audit_log_returns_400_for_a_malformed_filter() {
+    let app = spawn_app().await;
+    let admin_user_id = Uuid::new_v4().to_string();
+
+    let response = app
+        .get_audit_log_admin(
+            admin_user_id.clone(),
+            &[("actor_user_id", "not-a-uuid".to_string())],
+            generate_token(admin_user_id, UserGroup::ADMIN),
+        )
+        .await;
+
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn get_audit_log_returns_500_when_the_table_is_missing() {
+    let app = spawn_app().await;
+    sqlx::query!("DROP TABLE audit_log")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    let admin_user_id = Uuid::new_v4().to_string();
+    let response = app
+        .get_audit_log_admin(
+            admin_user_id.clone(),
+            &[],
+            generate_token(admin_user_id, UserGroup::ADMIN),
+        )
+        .await;
+
+    assert_eq!(500, response.status().as_u16());
+}
@@ -0,0 +1,135 @@
+use chrono::{Duration, NaiveDate, Utc};
+use claims::assert_ok;
+use uuid::Uuid;
+
+use newsletter_signup_service::db::broadcast_db_broker::{
+    claim_broadcast_recipients, complete_broadcast_if_done, get_broadcast_recipients,
+    get_next_claim_expiry, get_unfinished_broadcasts, insert_broadcast, set_recipient_status,
+};
+use newsletter_signup_service::db::publications_db_broker::{insert_issue, insert_publication};
+use newsletter_signup_service::domain::broadcast_models::{
+    BroadcastRecipient, BroadcastStatus, IssueBroadcast, RecipientStatus,
+};
+use newsletter_signup_service::domain::publication_models::{Issue, Publication};
+
+use crate::helper::{spawn_app, TestApp};
+
+async fn store_broadcast(app: &TestApp, recipient_count: usize) -> IssueBroadcast {
+    let publication = Publication {
+        id: Uuid::new_v4(),
+        title: "The Weekly".to_string(),
+        description: None,
+        created_at: Utc::now(),
+    };
+    let issue = Issue {
+        id: Uuid::new_v4(),
+        publication_id: publication.id,
+        issue_number: 1,
+        title: None,
+        publish_date: NaiveDate::from_ymd_opt(2026, 11, 1).unwrap(),
+        cutoff_date: NaiveDate::from_ymd_opt(2026, 11, 1).unwrap(),
+        digital_asset_reference: Some("https://example.com/1.pdf".to_string()),
+        created_at: Utc::now(),
+    };
+    assert_ok!(insert_publication(&publication, &app.db_pool).await);
+    assert_ok!(insert_issue(&issue, &app.db_pool).await);
+
+    let broadcast = IssueBroadcast {
+        id: Uuid::new_v4(),
+        issue_id: issue.id,
+        test_send: false,
+        status: BroadcastStatus::Sending,
+        created_at: Utc::now(),
+        created_by: None,
+        completed_at: None,
+        recipient_count: recipient_count as i32,
+    };
+    let recipients: Vec<BroadcastRecipient> = (0..recipient_count)
+        .map(|index| BroadcastRecipient {
+            id: Uuid::new_v4(),
+            broadcast_id: broadcast.id,
+            subscription_id: Some(Uuid::new_v4()),
+            email_address: format!("reader{}@example.com", index),
+            status: RecipientStatus::Pending,
+            attempts: 0,
+            last_error: None,
+            sent_at: None,
+        })
+        .collect();
+    assert_ok!(insert_broadcast(&broadcast, &recipients, &app.db_pool).await);
+    broadcast
+}
+
+#[tokio::test]
+async fn recipients_are_claimed_in_batches_and_taken_over_once_a_claim_expires() {
+    let app = spawn_app().await;
+    let broadcast = store_broadcast(&app, 3).await;
+
+    let batch = claim_broadcast_recipients(broadcast.id, 2, &app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(2, batch.len());
+    assert!(batch.iter().all(|r| r.status == RecipientStatus::Sending));
+    assert_ok!(set_recipient_status(batch[0].id, RecipientStatus::Sent, None, &app.db_pool).await);
+    assert!(!complete_broadcast_if_done(broadcast.id, &app.db_pool)
+        .await
+        .unwrap());
+
+    // The server stops with one recipient claimed but not recorded. Another
+    // server only gets the unclaimed recipient while the claim holds.
+    let stranded = batch[1].id;
+    assert_eq!(
+        vec![broadcast.id],
+        get_unfinished_broadcasts(&app.db_pool).await.unwrap()
+    );
+    let batch = claim_broadcast_recipients(broadcast.id, 10, &app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(1, batch.len());
+    assert_ne!(stranded, batch[0].id);
+    assert_ok!(set_recipient_status(batch[0].id, RecipientStatus::Sent, None, &app.db_pool).await);
+    assert!(get_next_claim_expiry(broadcast.id, &app.db_pool)
+        .await
+        .unwrap()
+        .is_some_and(|claimed_until| claimed_until > Utc::now()));
+    assert!(claim_broadcast_recipients(broadcast.id, 10, &app.db_pool)
+        .await
+        .unwrap()
+        .is_empty());
+
+    sqlx::query!(
+        "UPDATE issue_broadcast_recipients SET claimed_until = $1 WHERE id = $2",
+        Utc::now() - Duration::seconds(1),
+        stranded
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+    let batch = claim_broadcast_recipients(broadcast.id, 10, &app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        vec![stranded],
+        batch.iter().map(|r| r.id).collect::<Vec<_>>()
+    );
+    assert_eq!(2, batch[0].attempts);
+    assert_ok!(set_recipient_status(stranded, RecipientStatus::Sent, None, &app.db_pool).await);
+
+    assert_eq!(
+        None,
+        get_next_claim_expiry(broadcast.id, &app.db_pool)
+            .await
+            .unwrap()
+    );
+    assert!(complete_broadcast_if_done(broadcast.id, &app.db_pool)
+        .await
+        .unwrap());
+    assert!(get_unfinished_broadcasts(&app.db_pool)
+        .await
+        .unwrap()
+        .is_empty());
+    let recipients = get_broadcast_recipients(broadcast.id, &app.db_pool)
+        .await
+        .unwrap();
+    assert!(recipients.iter().all(|r| r.status == RecipientStatus::Sent));
+}
@@ -0,0 +1,333 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use mailtrap_rs::types::response::SendEmailResponse;
+use serde_json::json;
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use newsletter_signup_service::auth::token::{generate_token, LoginResponse};
+use newsletter_signup_service::domain::broadcast_models::{
+    BroadcastProgress, BroadcastRecipient, BroadcastStatus, IssueBroadcast, RecipientStatus,
+};
+use newsletter_signup_service::domain::email_preference_models::{
+    unsubscribe_token, EmailCategory,
+};
+use newsletter_signup_service::domain::publication_models::{Issue, Publication};
+use newsletter_signup_service::domain::subscription_models::SubscriptionType;
+use newsletter_signup_service::domain::user_models::UserGroup;
+
+use crate::helper::{
+    generate_over_the_wire_create_subscription, generate_signup, spawn_app, store_subscription,
+    TestApp,
+};
+
+fn admin() -> (String, String) {
+    let admin_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+    (admin_user_id, token)
+}
+
+async fn mock_email_provider(app: &TestApp, status: u16) {
+    Mock::given(path("api/send"))
+        .and(method("POST"))
+        .respond_with(
+            ResponseTemplate::new(status).set_body_json(SendEmailResponse {
+                success: status == 200,
+                message_ids: vec!["test-id".to_string()],
+                errors: vec![],
+            }),
+        )
+        .mount(&app.email_server)
+        .await;
+}
+
+/// A publication with an issue whose cutoff is still ahead, so subscriptions
+/// created by the test are active at it.
+async fn create_issue(app: &TestApp, digital_asset_reference: Option<&str>) -> Issue {
+    let (admin_user_id, token) = admin();
+    let response = app
+        .create_publication(
+            admin_user_id.clone(),
+            json!({ "title": "The Weekly" }).to_string(),
+            token.clone(),
+        )
+        .await;
+    let publication: Publication =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+
+    let tomorrow = Utc::now().date_naive().succ_opt().unwrap();
+    let response = app
+        .create_issue(
+            admin_user_id,
+            publication.id.to_string(),
+            json!({
+                "issue_number": 1,
+                "publish_date": tomorrow,
+                "digital_asset_reference": digital_asset_reference,
+            })
+            .to_string(),
+            token,
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    serde_json::from_str(response.text().await.unwrap().as_str()).unwrap()
+}
+
+async fn store_subscriptions(app: &TestApp, subscription_type: SubscriptionType) -> String {
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = generate_over_the_wire_create_subscription(
+        subscriber.id.to_string(),
+        Some(subscription_type),
+    );
+    store_subscription(subscriber.id.to_string(), Some(subscription), app)
+        .await
+        .subscription_email_address
+}
+
+async fn create_broadcast(app: &TestApp, issue: &Issue, test_send: bool) -> IssueBroadcast {
+    let (admin_user_id, token) = admin();
+    let response = app
+        .create_broadcast(
+            admin_user_id,
+            issue.id.to_string(),
+            json!({ "test_send": test_send }).to_string(),
+            token,
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    serde_json::from_str(response.text().await.unwrap().as_str()).unwrap()
+}
+
+async fn wait_for_completion(app: &TestApp, issue: &Issue) -> Vec<BroadcastProgress> {
+    let (admin_user_id, token) = admin();
+    let mut broadcasts: Vec<BroadcastProgress> = Vec::new();
+    for _ in 0..50 {
+        let response = app
+            .get_broadcasts(admin_user_id.clone(), issue.id.to_string(), token.clone())
+            .await;
+        assert_eq!(200, response.status().as_u16());
+        broadcasts = serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+        if broadcasts
+            .iter()
+            .all(|b| b.broadcast.status == BroadcastStatus::Completed)
+        {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    broadcasts
+}
+
+async fn get_recipients(app: &TestApp, broadcast_id: Uuid) -> Vec<BroadcastRecipient> {
+    let (admin_user_id, token) = admin();
+    let response = app
+        .get_broadcast_recipients(admin_user_id, broadcast_id.to_string(), token)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    serde_json::from_str(response.text().await.unwrap().as_str()).unwrap()
+}
+
+#[tokio::test]
+async fn an_issue_is_emailed_to_each_active_digital_subscription_once() {
+    let app = spawn_app().await;
+    mock_email_provider(&app, 200).await;
+    let issue = create_issue(&app, Some("https://example.com/issues/1.pdf")).await;
+    let mut digital = vec![
+        store_subscriptions(&app, SubscriptionType::Digital).await,
+        store_subscriptions(&app, SubscriptionType::Digital).await,
+    ];
+    store_subscriptions(&app, SubscriptionType::Paper).await;
+
+    let broadcast = create_broadcast(&app, &issue, false).await;
+    assert_eq!(2, broadcast.recipient_count);
+
+    let broadcasts = wait_for_completion(&app, &issue).await;
+    assert_eq!(BroadcastStatus::Completed, broadcasts[0].broadcast.status);
+    assert_eq!(2, broadcasts[0].sent_count);
+    let recipients = get_recipients(&app, broadcast.id).await;
+    let mut addresses: Vec<String> = recipients.iter().map(|r| r.email_address.clone()).collect();
+    addresses.sort();
+    digital.sort();
+    assert_eq!(digital, addresses);
+    assert!(recipients
+        .iter()
+        .all(|r| r.status == RecipientStatus::Sent && r.sent_at.is_some()));
+    assert_eq!(2, app.email_server.received_requests().await.unwrap().len());
+
+    let (admin_user_id, token) = admin();
+    let response = app
+        .create_broadcast(
+            admin_user_id,
+            issue.id.to_string(),
+            json!({}).to_string(),
+            token,
+        )
+        .await;
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn issue_emails_can_be_unsubscribed_from() {
+    let app = spawn_app().await;
+    mock_email_provider(&app, 200).await;
+    let issue = create_issue(&app, Some("https://example.com/issues/1.pdf")).await;
+    let reader = store_subscriptions(&app, SubscriptionType::Digital).await;
+    let subscriber = app.store_subscriber(None).await;
+    store_subscription(
+        subscriber.id.to_string(),
+        Some(generate_over_the_wire_create_subscription(
+            subscriber.id.to_string(),
+            Some(SubscriptionType::Digital),
+        )),
+        &app,
+    )
+    .await;
+    let response = app
+        .one_click_unsubscribe(&unsubscribe_token(subscriber.id, EmailCategory::Issues))
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let broadcast = create_broadcast(&app, &issue, false).await;
+    assert_eq!(1, broadcast.recipient_count);
+    wait_for_completion(&app, &issue).await;
+
+    let received = app.email_server.received_requests().await.unwrap();
+    assert_eq!(1, received.len());
+    let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+    assert_eq!(reader, body["to"][0]["email"]);
+    let list_unsubscribe = body["headers"]["List-Unsubscribe"].as_str().unwrap();
+    assert!(list_unsubscribe.starts_with('<') && list_unsubscribe.contains("/unsubscribe/"));
+    assert_eq!(
+        "List-Unsubscribe=One-Click",
+        body["headers"]["List-Unsubscribe-Post"]
+    );
+    assert!(body["text"]
+        .as_str()
+        .unwrap()
+        .contains("/unsubscribe?token="));
+
+    // The link in the header works as it is.
+    let url = list_unsubscribe.trim_matches(|c| c == '<' || c == '>');
+    let token = url.rsplit('/').next().unwrap();
+    assert_eq!(
+        200,
+        app.one_click_unsubscribe(token).await.status().as_u16()
+    );
+}
+
+#[tokio::test]
+async fn a_test_send_only_goes_to_the_requesting_admin() {
+    let app = spawn_app().await;
+    mock_email_provider(&app, 200).await;
+    let issue = create_issue(&app, Some("https://example.com/issues/1.pdf")).await;
+    store_subscriptions(&app, SubscriptionType::Digital).await;
+    let signup = generate_signup();
+    let response = app.user_signup(signup.to_json()).await;
+    let login: LoginResponse =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    let token = generate_token(login.user_id.clone(), UserGroup::ADMIN);
+
+    for _ in 0..2 {
+        let response = app
+            .create_broadcast(
+                login.user_id.clone(),
+                issue.id.to_string(),
+                json!({ "test_send": true }).to_string(),
+                token.clone(),
+            )
+            .await;
+        assert_eq!(200, response.status().as_u16());
+    }
+
+    let broadcasts = wait_for_completion(&app, &issue).await;
+    assert_eq!(2, broadcasts.len());
+    assert!(broadcasts
+        .iter()
+        .all(|b| b.broadcast.test_send && b.broadcast.recipient_count == 1));
+    let received: Vec<serde_json::Value> = app
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| serde_json::from_slice(&request.body).unwrap())
+        .filter(|body: &serde_json::Value| body["subject"].as_str().unwrap().starts_with("[TEST] "))
+        .collect();
+    assert_eq!(2, received.len());
+    assert_eq!(signup.email_address, received[0]["to"][0]["email"]);
+
+    // Test sends do not stop the real one.
+    create_broadcast(&app, &issue, false).await;
+}
+
+#[tokio::test]
+async fn failed_deliveries_are_recorded_and_can_be_resumed() {
+    let app = spawn_app().await;
+    mock_email_provider(&app, 500).await;
+    let issue = create_issue(&app, Some("https://example.com/issues/1.pdf")).await;
+    store_subscriptions(&app, SubscriptionType::Digital).await;
+    store_subscriptions(&app, SubscriptionType::Digital).await;
+
+    let broadcast = create_broadcast(&app, &issue, false).await;
+    let broadcasts = wait_for_completion(&app, &issue).await;
+    assert_eq!(2, broadcasts[0].failed_count);
+    let recipients = get_recipients(&app, broadcast.id).await;
+    assert!(recipients
+        .iter()
+        .all(|r| r.status == RecipientStatus::Failed && r.last_error.is_some()));
+
+    app.email_server.reset().await;
+    mock_email_provider(&app, 200).await;
+    let (admin_user_id, token) = admin();
+    let response = app
+        .resume_broadcast(admin_user_id, broadcast.id.to_string(), token)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(2, body["requeued"]);
+
+    let broadcasts = wait_for_completion(&app, &issue).await;
+    assert_eq!(2, broadcasts[0].sent_count);
+    assert_eq!(0, broadcasts[0].failed_count);
+    let recipients = get_recipients(&app, broadcast.id).await;
+    assert!(recipients.iter().all(|r| r.attempts == 2));
+}
+
+#[tokio::test]
+async fn issues_without_a_digital_edition_cannot_be_broadcast() {
+    let app = spawn_app().await;
+    let issue = create_issue(&app, None).await;
+    let (admin_user_id, token) = admin();
+
+    let response = app
+        .create_broadcast(
+            admin_user_id.clone(),
+            issue.id.to_string(),
+            json!({}).to_string(),
+            token.clone(),
+        )
+        .await;
+    assert_eq!(400, response.status().as_u16());
+
+    let response = app
+        .create_broadcast(
+            admin_user_id.clone(),
+            Uuid::new_v4().to_string(),
+            json!({}).to_string(),
+            token,
+        )
+        .await;
+    assert_eq!(404, response.status().as_u16());
+
+    let response = app
+        .create_broadcast(
+            admin_user_id.clone(),
+            issue.id.to_string(),
+            json!({}).to_string(),
+            generate_token(admin_user_id, UserGroup::USER),
+        )
+        .await;
+    assert_eq!(401, response.status().as_u16());
+}
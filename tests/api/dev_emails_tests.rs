@@ -0,0 +1,68 @@
+use newsletter_signup_service::domain::user_models::ForgotPassword;
+
+use crate::helper::{generate_signup, spawn_app};
+
+#[tokio::test]
+async fn password_reset_links_can_be_followed_from_captured_emails() {
+    let app = spawn_app().await;
+    let signup = generate_signup();
+    let response = app.user_signup(signup.to_json()).await;
+    assert_eq!(200, response.status().as_u16());
+
+    // No provider mock: the email is captured although it cannot be delivered.
+    let response = app
+        .forgot_password(
+            ForgotPassword {
+                email_address: signup.email_address.clone(),
+            }
+            .to_json(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let emails = app
+        .captured_emails(&signup.email_address.to_uppercase())
+        .await;
+    let reset = emails
+        .iter()
+        .find(|email| email.subject == "Password Reset")
+        .unwrap();
+    assert_eq!(vec![signup.email_address.clone()], reset.recipients);
+    assert!(reset.text_content.contains(&reset.links[0]));
+    let link = reqwest::Url::parse(&reset.links[0]).unwrap();
+    let (_, otp) = link.query_pairs().next().unwrap();
+    let response = app.forgot_password_login(otp.to_string()).await;
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn captured_emails_can_be_filtered_by_recipient() {
+    let app = spawn_app().await;
+    let first = generate_signup();
+    let second = generate_signup();
+    for signup in [&first, &second] {
+        let response = app.user_signup(signup.to_json()).await;
+        assert_eq!(200, response.status().as_u16());
+        let response = app
+            .forgot_password(
+                ForgotPassword {
+                    email_address: signup.email_address.clone(),
+                }
+                .to_json(),
+            )
+            .await;
+        assert_eq!(200, response.status().as_u16());
+    }
+
+    let emails = app.captured_emails(&first.email_address).await;
+
+    assert!(!emails.is_empty());
+    assert!(emails
+        .iter()
+        .all(|email| email.recipients == vec![first.email_address.clone()]));
+    let response = app.get_dev_emails(None).await;
+    assert_eq!(200, response.status().as_u16());
+    let all: Vec<serde_json::Value> =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert!(all.len() > emails.len());
+}
@@ -0,0 +1,218 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use mailtrap_rs::types::response::SendEmailResponse;
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use newsletter_signup_service::auth::token::generate_token;
+use newsletter_signup_service::db::email_outbox_db_broker::{
+    claim_email, mark_email_failed, mark_email_sent, queue_email,
+};
+use newsletter_signup_service::domain::email_outbox_models::{
+    OutboxEmail, OutboxStatus, MAX_EMAIL_ATTEMPTS,
+};
+use newsletter_signup_service::domain::user_models::{ForgotPassword, UserGroup};
+use newsletter_signup_service::domain::valid_email::ValidEmail;
+
+use crate::helper::{generate_signup, spawn_app, TestApp};
+
+fn admin() -> (String, String) {
+    let admin_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+    (admin_user_id, token)
+}
+
+async fn mock_email_provider(app: &TestApp, status: u16) {
+    Mock::given(path("api/send"))
+        .and(method("POST"))
+        .respond_with(
+            ResponseTemplate::new(status).set_body_json(SendEmailResponse {
+                success: status == 200,
+                message_ids: vec!["test-id".to_string()],
+                errors: vec![],
+            }),
+        )
+        .mount(&app.email_server)
+        .await;
+}
+
+async fn get_outbox(app: &TestApp, status: Option<&str>) -> Vec<OutboxEmail> {
+    let (admin_user_id, token) = admin();
+    let response = app.get_outbox_emails(admin_user_id, status, token).await;
+    assert_eq!(200, response.status().as_u16());
+    serde_json::from_str(response.text().await.unwrap().as_str()).unwrap()
+}
+
+/// Signs up and asks for a password reset while the email provider is down.
+async fn queue_a_failed_email(app: &TestApp) -> OutboxEmail {
+    mock_email_provider(app, 500).await;
+    let signup = generate_signup();
+    let response = app.user_signup(signup.to_json()).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let forgot_password = ForgotPassword {
+        email_address: signup.email_address,
+    };
+    let response = app.forgot_password(forgot_password.to_json()).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let queued: Vec<OutboxEmail> = get_outbox(app, Some("Pending"))
+        .await
+        .into_iter()
+        .filter(|email| email.subject == "Password Reset")
+        .collect();
+    assert_eq!(1, queued.len());
+    queued[0].clone()
+}
+
+async fn wait_for_status(app: &TestApp, id: Uuid, status: OutboxStatus) -> OutboxEmail {
+    for _ in 0..50 {
+        let emails = get_outbox(app, None).await;
+        if let Some(email) = emails.into_iter().find(|email| email.id == id) {
+            if email.status == status {
+                return email;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("Email {} never became {:?}", id, status);
+}
+
+#[tokio::test]
+async fn forgot_password_succeeds_and_retries_later_when_the_provider_fails() {
+    let app = spawn_app().await;
+
+    let email = queue_a_failed_email(&app).await;
+    assert_eq!("Password Reset", email.subject);
+    assert_eq!(1, email.attempts);
+    assert!(email.last_error.is_some());
+    // The first retry waits for the backoff.
+    assert!(email.next_attempt_at > Utc::now() + chrono::Duration::seconds(20));
+
+    app.email_server.reset().await;
+    mock_email_provider(&app, 200).await;
+    sqlx::query("UPDATE email_outbox SET next_attempt_at = now() WHERE id = $1")
+        .bind(email.id)
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    let email = wait_for_status(&app, email.id, OutboxStatus::Sent).await;
+    assert_eq!(2, email.attempts);
+    assert!(email.sent_at.is_some());
+    assert_eq!(1, app.received_emails("Password Reset").await.len());
+}
+
+#[tokio::test]
+async fn dead_emails_can_be_requeued_by_an_admin() {
+    let app = spawn_app().await;
+    let email = queue_a_failed_email(&app).await;
+    sqlx::query("UPDATE email_outbox SET status = 'Dead', attempts = 6 WHERE id = $1")
+        .bind(email.id)
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(1, get_outbox(&app, Some("Dead")).await.len());
+
+    app.email_server.reset().await;
+    mock_email_provider(&app, 200).await;
+    let (admin_user_id, token) = admin();
+    let response = app
+        .requeue_outbox_email(admin_user_id.clone(), email.id.to_string(), token.clone())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let email = wait_for_status(&app, email.id, OutboxStatus::Sent).await;
+    assert_eq!(1, email.attempts);
+
+    let response = app
+        .requeue_outbox_email(admin_user_id.clone(), email.id.to_string(), token.clone())
+        .await;
+    assert_eq!(409, response.status().as_u16());
+    let response = app
+        .requeue_outbox_email(admin_user_id, Uuid::new_v4().to_string(), token)
+        .await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn an_email_whose_last_attempt_never_finished_is_dead_lettered() {
+    let app = spawn_app().await;
+    let email = queue_a_failed_email(&app).await;
+    app.email_server.reset().await;
+    mock_email_provider(&app, 200).await;
+    // As if the worker died while making the last attempt.
+    sqlx::query(
+        "UPDATE email_outbox SET status = 'Sending', attempts = $1, next_attempt_at = now() WHERE id = $2",
+    )
+    .bind(MAX_EMAIL_ATTEMPTS)
+    .bind(email.id)
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    let email = wait_for_status(&app, email.id, OutboxStatus::Dead).await;
+    assert_eq!(MAX_EMAIL_ATTEMPTS, email.attempts);
+    assert!(app.received_emails("Password Reset").await.is_empty());
+}
+
+#[tokio::test]
+async fn an_attempt_that_outlived_its_claim_does_not_settle_the_email() {
+    let app = spawn_app().await;
+    let email = OutboxEmail::new(
+        vec![ValidEmail::parse(format!("{}@example.com", Uuid::new_v4())).unwrap()],
+        "Claimed twice",
+        "<p>Hi</p>",
+        "Hi",
+    );
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    queue_email(&email, &mut transaction).await.unwrap();
+    transaction.commit().await.unwrap();
+    let claimed = claim_email(email.id, &app.db_pool).await.unwrap().unwrap();
+    // Another worker takes the email over once the first claim lapses.
+    sqlx::query("UPDATE email_outbox SET attempts = attempts + 1 WHERE id = $1")
+        .bind(email.id)
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    mark_email_sent(&claimed, None, &app.db_pool).await.unwrap();
+    mark_email_failed(&claimed, "too late", &app.db_pool)
+        .await
+        .unwrap();
+
+    let email = get_outbox(&app, Some("Sending"))
+        .await
+        .into_iter()
+        .find(|queued| queued.id == email.id)
+        .unwrap();
+    assert_eq!(2, email.attempts);
+    assert_eq!(None, email.last_error);
+    assert_eq!(None, email.sent_at);
+}
+
+#[tokio::test]
+async fn only_admins_can_see_the_outbox() {
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4().to_string();
+
+    let response = app
+        .get_outbox_emails(
+            user_id.clone(),
+            None,
+            generate_token(user_id.clone(), UserGroup::USER),
+        )
+        .await;
+    assert_eq!(401, response.status().as_u16());
+
+    let response = app
+        .requeue_outbox_email(
+            user_id.clone(),
+            Uuid::new_v4().to_string(),
+            generate_token(user_id, UserGroup::USER),
+        )
+        .await;
+    assert_eq!(401, response.status().as_u16());
+}
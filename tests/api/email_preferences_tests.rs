@@ -0,0 +1,127 @@
+use serde_json::json;
+use uuid::Uuid;
+
+use newsletter_signup_service::auth::token::generate_token;
+use newsletter_signup_service::domain::email_preference_models::{
+    unsubscribe_token, EmailCategory, EmailPreferences,
+};
+use newsletter_signup_service::domain::user_models::UserGroup;
+
+use crate::helper::spawn_app;
+
+#[tokio::test]
+async fn preferences_start_at_the_defaults_and_can_be_changed() {
+    let app = spawn_app().await;
+    let subscriber = app.store_subscriber(None).await;
+    let token = generate_token(subscriber.user_id.clone(), UserGroup::USER);
+
+    let response = app
+        .get_email_preferences(subscriber.id.to_string(), token.clone())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let preferences: EmailPreferences =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!(EmailPreferences::default(), preferences);
+
+    let changed = EmailPreferences {
+        issues: false,
+        renewal_reminders: true,
+        promotions: true,
+    };
+    let response = app
+        .put_email_preferences(subscriber.id.to_string(), changed.to_json(), token.clone())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = app
+        .get_email_preferences(subscriber.id.to_string(), token)
+        .await;
+    let preferences: EmailPreferences =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!(changed, preferences);
+}
+
+#[tokio::test]
+async fn preferences_belong_to_the_subscriber_only() {
+    let app = spawn_app().await;
+    let subscriber = app.store_subscriber(None).await;
+    let someone_else = generate_token(Uuid::new_v4().to_string(), UserGroup::USER);
+
+    let response = app
+        .get_email_preferences(subscriber.id.to_string(), someone_else.clone())
+        .await;
+    assert_eq!(401, response.status().as_u16());
+    let response = app
+        .put_email_preferences(
+            subscriber.id.to_string(),
+            EmailPreferences::default().to_json(),
+            someone_else.clone(),
+        )
+        .await;
+    assert_eq!(401, response.status().as_u16());
+    let response = app
+        .get_email_preferences(Uuid::new_v4().to_string(), someone_else.clone())
+        .await;
+    assert_eq!(404, response.status().as_u16());
+    let response = app
+        .put_email_preferences(
+            subscriber.id.to_string(),
+            json!({ "issues": "no" }).to_string(),
+            generate_token(subscriber.user_id.clone(), UserGroup::USER),
+        )
+        .await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn a_signed_link_unsubscribes_without_logging_in() {
+    let app = spawn_app().await;
+    let subscriber = app.store_subscriber(None).await;
+    let token = unsubscribe_token(subscriber.id, EmailCategory::Issues);
+
+    // Looking the link up changes nothing.
+    let response = app.get_unsubscribe(&token).await;
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!("issues", body["category"]);
+    assert_eq!(true, body["preferences"]["issues"]);
+
+    let response = app.one_click_unsubscribe(&token).await;
+    assert_eq!(200, response.status().as_u16());
+    // The link keeps working.
+    let response = app.one_click_unsubscribe(&token).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = app
+        .get_email_preferences(
+            subscriber.id.to_string(),
+            generate_token(subscriber.user_id.clone(), UserGroup::USER),
+        )
+        .await;
+    let preferences: EmailPreferences =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!(
+        EmailPreferences::default().without(EmailCategory::Issues),
+        preferences
+    );
+}
+
+#[tokio::test]
+async fn a_forged_unsubscribe_link_is_rejected() {
+    let app = spawn_app().await;
+    let subscriber = app.store_subscriber(None).await;
+    let token = unsubscribe_token(subscriber.id, EmailCategory::Issues);
+    let forged = token.replace(".issues.", ".promotions.");
+
+    assert_eq!(
+        400,
+        app.one_click_unsubscribe(&forged).await.status().as_u16()
+    );
+    assert_eq!(400, app.get_unsubscribe("nonsense").await.status().as_u16());
+    let unknown = unsubscribe_token(Uuid::new_v4(), EmailCategory::Issues);
+    assert_eq!(
+        404,
+        app.one_click_unsubscribe(&unknown).await.status().as_u16()
+    );
+}
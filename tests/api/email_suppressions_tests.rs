@@ -0,0 +1,195 @@
+use mailtrap_rs::types::response::SendEmailResponse;
+use secrecy::ExposeSecret;
+use serde_json::json;
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use newsletter_signup_service::auth::token::generate_token;
+use newsletter_signup_service::configuration::get_configuration;
+use newsletter_signup_service::domain::email_outbox_models::OutboxEmail;
+use newsletter_signup_service::domain::email_suppression_models::{
+    EmailSuppression, SuppressionReason,
+};
+use newsletter_signup_service::domain::user_models::{ForgotPassword, UserGroup};
+
+use crate::helper::{generate_signup, spawn_app, store_subscription, TestApp};
+
+fn admin() -> (String, String) {
+    let admin_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+    (admin_user_id, token)
+}
+
+fn webhook_token() -> String {
+    get_configuration()
+        .unwrap()
+        .email_client
+        .webhook_token
+        .unwrap()
+        .expose_secret()
+        .to_string()
+}
+
+async fn mock_email_provider(app: &TestApp) {
+    Mock::given(path("api/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(SendEmailResponse {
+            success: true,
+            message_ids: vec!["test-id".to_string()],
+            errors: vec![],
+        }))
+        .mount(&app.email_server)
+        .await;
+}
+
+async fn bounce(app: &TestApp, email_address: &str) {
+    let body = json!([{
+        "email": email_address,
+        "event": "bounce",
+        "type": "bounce",
+        "reason": "550 5.1.1 User unknown",
+    }]);
+    let response = app
+        .post_email_events(&webhook_token(), body.to_string())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+}
+
+async fn get_suppressions(app: &TestApp, reason: Option<&str>) -> Vec<EmailSuppression> {
+    let (admin_user_id, token) = admin();
+    let response = app
+        .get_email_suppressions(admin_user_id, reason, token)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    serde_json::from_str(response.text().await.unwrap().as_str()).unwrap()
+}
+
+#[tokio::test]
+async fn delivery_events_need_the_webhook_token() {
+    let app = spawn_app().await;
+    let body = json!([{ "email": "someone@example.com", "event": "spamreport" }]);
+
+    let response = app.post_email_events("wrong", body.to_string()).await;
+
+    assert_eq!(401, response.status().as_u16());
+    assert!(get_suppressions(&app, None).await.is_empty());
+}
+
+#[tokio::test]
+async fn suppressed_addresses_are_not_emailed() {
+    let app = spawn_app().await;
+    mock_email_provider(&app).await;
+    let signup = generate_signup();
+    let response = app.user_signup(signup.to_json()).await;
+    assert_eq!(200, response.status().as_u16());
+
+    bounce(&app, &signup.email_address.to_uppercase()).await;
+    let forgot_password = ForgotPassword {
+        email_address: signup.email_address.clone(),
+    };
+    let response = app.forgot_password(forgot_password.to_json()).await;
+    assert_eq!(200, response.status().as_u16());
+
+    assert!(app.received_emails("Password Reset").await.is_empty());
+    let (admin_user_id, token) = admin();
+    let response = app
+        .get_outbox_emails(admin_user_id, Some("Sent"), token)
+        .await;
+    let outbox: Vec<OutboxEmail> =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    let reset = outbox
+        .iter()
+        .find(|email| email.subject == "Password Reset")
+        .unwrap();
+    assert_eq!(
+        Some(format!("Suppressed: {}", signup.email_address)),
+        reset.last_error
+    );
+}
+
+#[tokio::test]
+async fn a_hard_bounce_on_a_subscription_is_flagged_to_staff() {
+    let app = spawn_app().await;
+    mock_email_provider(&app).await;
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = store_subscription(subscriber.id.to_string(), None, &app).await;
+    let email_address = subscription.subscription_email_address.to_lowercase();
+    let subject = format!("Subscription email bounced: {}", email_address);
+
+    bounce(&app, &email_address).await;
+
+    let notices = app.received_emails(&subject).await;
+    assert_eq!(1, notices.len());
+    let text = notices[0]["text"].as_str().unwrap();
+    assert!(text.contains(&subscription.subscription_name));
+    assert!(text.contains("550 5.1.1 User unknown"));
+
+    // Only the first bounce is flagged.
+    bounce(&app, &email_address).await;
+    assert_eq!(1, app.received_emails(&subject).await.len());
+}
+
+#[tokio::test]
+async fn complaints_and_unsubscribes_are_not_flagged_to_staff() {
+    let app = spawn_app().await;
+    mock_email_provider(&app).await;
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = store_subscription(subscriber.id.to_string(), None, &app).await;
+    let body = json!({ "events": [
+        { "email": subscription.subscription_email_address, "event": "spam" },
+        { "email": "leaving@example.com", "event": "unsubscribe" },
+        { "email": "full@example.com", "event": "soft bounce" },
+    ]});
+
+    let response = app
+        .post_email_events(&webhook_token(), body.to_string())
+        .await;
+
+    assert_eq!(200, response.status().as_u16());
+    let suppressions = get_suppressions(&app, None).await;
+    assert_eq!(2, suppressions.len());
+    let complaints = get_suppressions(&app, Some("Complaint")).await;
+    assert_eq!(1, complaints.len());
+    assert_eq!(SuppressionReason::Complaint, complaints[0].reason);
+    assert!(app
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn admins_can_clear_a_suppression() {
+    let app = spawn_app().await;
+    bounce(&app, "gone@example.com").await;
+    let (admin_user_id, token) = admin();
+
+    let user_id = Uuid::new_v4().to_string();
+    let response = app
+        .clear_email_suppression(
+            user_id.clone(),
+            "gone@example.com",
+            generate_token(user_id, UserGroup::USER),
+        )
+        .await;
+    assert_eq!(401, response.status().as_u16());
+
+    let response = app
+        .clear_email_suppression(admin_user_id.clone(), "Gone@Example.com", token.clone())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    assert!(get_suppressions(&app, None).await.is_empty());
+    let audited =
+        sqlx::query!("SELECT target_id FROM audit_log WHERE action = 'ClearEmailSuppression'")
+            .fetch_one(&app.db_pool)
+            .await
+            .unwrap();
+    assert_eq!(Some("gone@example.com".to_string()), audited.target_id);
+
+    let response = app
+        .clear_email_suppression(admin_user_id, "gone@example.com", token)
+        .await;
+    assert_eq!(404, response.status().as_u16());
+}
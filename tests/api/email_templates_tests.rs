@@ -0,0 +1,106 @@
+use uuid::Uuid;
+
+use newsletter_signup_service::auth::token::generate_token;
+use newsletter_signup_service::domain::user_models::UserGroup;
+
+use crate::helper::spawn_app;
+
+fn admin() -> (String, String) {
+    let admin_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+    (admin_user_id, token)
+}
+
+#[tokio::test]
+async fn admins_can_list_and_preview_every_template() {
+    let app = spawn_app().await;
+    let (admin_user_id, token) = admin();
+
+    let response = app
+        .get_email_templates(admin_user_id.clone(), token.clone())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    let templates = body["templates"].as_array().unwrap();
+    assert!(templates.contains(&serde_json::json!("password_reset")));
+
+    for template in templates {
+        let response = app
+            .preview_email_template(
+                admin_user_id.clone(),
+                template.as_str().unwrap(),
+                &[("locale", "es")],
+                token.clone(),
+            )
+            .await;
+        assert_eq!(200, response.status().as_u16());
+        let preview: serde_json::Value =
+            serde_json::from_str(&response.text().await.unwrap()).unwrap();
+        assert_eq!("es", preview["locale"]);
+        assert!(!preview["subject"].as_str().unwrap().is_empty());
+        assert!(preview["html_content"]
+            .as_str()
+            .unwrap()
+            .contains("<html lang=\"es\">"));
+    }
+}
+
+#[tokio::test]
+async fn a_preview_can_be_returned_as_html() {
+    let app = spawn_app().await;
+    let (admin_user_id, token) = admin();
+
+    let response = app
+        .preview_email_template(
+            admin_user_id.clone(),
+            "invitation",
+            &[("locale", "fr"), ("format", "html")],
+            token.clone(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    assert!(response
+        .headers()
+        .get("content-type")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .starts_with("text/html"));
+    // Unsupported locales fall back to English.
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("You have been invited to create an account."));
+
+    let response = app
+        .preview_email_template(
+            admin_user_id.clone(),
+            "invitation",
+            &[("format", "pdf")],
+            token.clone(),
+        )
+        .await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn unknown_templates_and_non_admins_are_rejected() {
+    let app = spawn_app().await;
+    let (admin_user_id, token) = admin();
+
+    let response = app
+        .preview_email_template(admin_user_id.clone(), "no_such_template", &[], token)
+        .await;
+    assert_eq!(404, response.status().as_u16());
+
+    let user_token = generate_token(admin_user_id.clone(), UserGroup::USER);
+    let response = app
+        .get_email_templates(admin_user_id.clone(), user_token.clone())
+        .await;
+    assert_eq!(401, response.status().as_u16());
+    let response = app
+        .preview_email_template(admin_user_id, "password_reset", &[], user_token)
+        .await;
+    assert_eq!(401, response.status().as_u16());
+}
@@ -0,0 +1,281 @@
+use chrono::{Duration, Utc};
+use serde_json::json;
+use uuid::Uuid;
+
+use newsletter_signup_service::auth::token::generate_token;
+use newsletter_signup_service::db::subscriptions_db_broker::cancel_subscription_by_subscription_id;
+use newsletter_signup_service::domain::fulfillment_models::{
+    FulfillmentEntry, FulfillmentRun, FIXED_WIDTH_COLUMNS,
+};
+use newsletter_signup_service::domain::subscription_models::{
+    OverTheWireSubscription, SubscriptionType,
+};
+use newsletter_signup_service::domain::user_models::UserGroup;
+
+use crate::helper::{
+    generate_over_the_wire_create_subscription, spawn_app, store_subscription, TestApp,
+};
+
+async fn store_paper_subscription(
+    app: &TestApp,
+    subscriber_id: String,
+    line_1: &str,
+) -> OverTheWireSubscription {
+    let mut subscription = generate_over_the_wire_create_subscription(
+        subscriber_id.clone(),
+        Some(SubscriptionType::Paper),
+    );
+    subscription.subscription_mailing_address_line_1 = line_1.to_string();
+    subscription.subscription_mailing_address_line_2 = None;
+    subscription.subscription_city = "Springfield".to_string();
+    subscription.subscription_state = "IL".to_string();
+    subscription.subscription_postal_code = "62701".to_string();
+    store_subscription(subscriber_id, Some(subscription), app).await
+}
+
+async fn create_run(app: &TestApp, body: serde_json::Value) -> FulfillmentRun {
+    let admin_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+    let response = app
+        .create_fulfillment_run(admin_user_id, body.to_string(), token)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    serde_json::from_str(response.text().await.unwrap().as_str()).unwrap()
+}
+
+#[tokio::test]
+async fn a_run_lists_each_active_paper_address_once() {
+    let app = spawn_app().await;
+    let subscriber_id = app.store_subscriber(None).await.id.to_string();
+    let first = store_paper_subscription(&app, subscriber_id.clone(), "1 Main Street").await;
+    let same_address = store_paper_subscription(&app, subscriber_id.clone(), "1 main st.").await;
+    store_paper_subscription(&app, subscriber_id.clone(), "2 Main St").await;
+    let cancelled = store_paper_subscription(&app, subscriber_id.clone(), "3 Main St").await;
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    cancel_subscription_by_subscription_id(cancelled.id, &mut transaction)
+        .await
+        .unwrap();
+    transaction.commit().await.unwrap();
+    store_subscription(
+        subscriber_id.clone(),
+        Some(generate_over_the_wire_create_subscription(
+            subscriber_id,
+            Some(SubscriptionType::Digital),
+        )),
+        &app,
+    )
+    .await;
+
+    let issue_date = (Utc::now() + Duration::days(1)).date_naive();
+    let run = create_run(&app, json!({ "issue_date": issue_date })).await;
+
+    assert_eq!(2, run.recipient_count);
+    assert_eq!(1, run.duplicate_count);
+    assert_eq!(issue_date, run.issue_date);
+
+    let admin_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+    let response = app
+        .export_fulfillment_run(admin_user_id, run.id.to_string(), "json", token)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    let entries: Vec<FulfillmentEntry> = serde_json::from_value(body["entries"].clone()).unwrap();
+    assert_eq!(2, entries.len());
+    assert_eq!(first.id, entries[0].subscription_id);
+    assert_eq!("1 MAIN ST", entries[0].address_line_1);
+    assert_eq!(vec![same_address.id], entries[0].merged_subscription_ids);
+    assert!(entries.iter().all(|e| e.subscription_id != cancelled.id));
+}
+
+#[tokio::test]
+async fn a_run_can_be_downloaded_as_csv_and_fixed_width() {
+    let app = spawn_app().await;
+    let subscriber_id = app.store_subscriber(None).await.id.to_string();
+    store_paper_subscription(&app, subscriber_id.clone(), "1 Main St").await;
+    store_paper_subscription(&app, subscriber_id, "2 Main St").await;
+    let run = create_run(
+        &app,
+        json!({ "issue_date": (Utc::now() + Duration::days(1)).date_naive() }),
+    )
+    .await;
+    let admin_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+
+    let response = app
+        .export_fulfillment_run(
+            admin_user_id.clone(),
+            run.id.to_string(),
+            "csv",
+            token.clone(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    assert!(response.headers()["Content-Type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/csv"));
+    assert!(response.headers()["Content-Disposition"]
+        .to_str()
+        .unwrap()
+        .contains(&format!("mailing-list-{}.csv", run.issue_date)));
+    let csv = response.text().await.unwrap();
+    assert_eq!(3, csv.lines().count());
+    assert!(csv.starts_with("sequence,name,"));
+
+    let response = app
+        .export_fulfillment_run(admin_user_id, run.id.to_string(), "fixed_width", token)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let width: usize = FIXED_WIDTH_COLUMNS.iter().map(|(_, width)| width).sum();
+    let fixed_width = response.text().await.unwrap();
+    let lines: Vec<&str> = fixed_width.lines().collect();
+    assert_eq!(2, lines.len());
+    assert!(lines.iter().all(|line| line.chars().count() == width));
+    assert!(lines[1].starts_with("000002"));
+}
+
+#[tokio::test]
+async fn subscriptions_created_after_the_cutoff_are_left_out_and_runs_are_kept() {
+    let app = spawn_app().await;
+    let subscriber_id = app.store_subscriber(None).await.id.to_string();
+    store_paper_subscription(&app, subscriber_id, "1 Main St").await;
+
+    let past = create_run(
+        &app,
+        json!({ "issue_date": (Utc::now() - Duration::days(30)).date_naive() }),
+    )
+    .await;
+    let explicit_cutoff = create_run(
+        &app,
+        json!({
+            "issue_date": (Utc::now() - Duration::days(30)).date_naive(),
+            "cutoff": Utc::now() + Duration::minutes(1),
+        }),
+    )
+    .await;
+    assert_eq!(0, past.recipient_count);
+    assert_eq!(1, explicit_cutoff.recipient_count);
+
+    let admin_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+    let response = app.get_fulfillment_runs(admin_user_id, token).await;
+    assert_eq!(200, response.status().as_u16());
+    let runs: Vec<FulfillmentRun> =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!(2, runs.len());
+    assert_eq!(explicit_cutoff.id, runs[0].id);
+}
+
+#[tokio::test]
+async fn exporting_an_unknown_run_is_a_404() {
+    let app = spawn_app().await;
+    let admin_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+
+    let response = app
+        .export_fulfillment_run(admin_user_id, Uuid::new_v4().to_string(), "csv", token)
+        .await;
+
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn fulfillment_runs_require_an_admin() {
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4().to_string();
+    let token = generate_token(user_id.clone(), UserGroup::USER);
+
+    let response = app
+        .create_fulfillment_run(
+            user_id.clone(),
+            json!({ "issue_date": "2026-11-01" }).to_string(),
+            token.clone(),
+        )
+        .await;
+    assert_eq!(401, response.status().as_u16());
+    let response = app.get_fulfillment_runs(user_id, token).await;
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn fulfillment_staff_can_download_runs_and_print_labels() {
+    let app = spawn_app().await;
+    let subscriber_id = app.store_subscriber(None).await.id.to_string();
+    for line_1 in ["1 Main St", "2 Main St", "3 Main St"] {
+        store_paper_subscription(&app, subscriber_id.clone(), line_1).await;
+    }
+    let run = create_run(
+        &app,
+        json!({ "issue_date": (Utc::now() + Duration::days(1)).date_naive() }),
+    )
+    .await;
+    let staff_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(staff_user_id.clone(), UserGroup::FULFILLMENT);
+
+    for (layout, pages) in [("avery_5160", 1), ("thermal_4x6", 3)] {
+        let response = app
+            .fulfillment_run_labels(
+                staff_user_id.clone(),
+                run.id.to_string(),
+                layout,
+                token.clone(),
+            )
+            .await;
+        assert_eq!(200, response.status().as_u16());
+        assert_eq!("application/pdf", response.headers()["Content-Type"]);
+        assert!(response.headers()["Content-Disposition"]
+            .to_str()
+            .unwrap()
+            .contains(&format!("mailing-labels-{}.pdf", run.issue_date)));
+        let pdf = response.bytes().await.unwrap();
+        assert!(pdf.starts_with(b"%PDF-"));
+        assert_eq!(
+            pages,
+            pdf.windows(11).filter(|w| w == b"/Type/Page/").count()
+        );
+    }
+
+    let response = app
+        .export_fulfillment_run(
+            staff_user_id.clone(),
+            run.id.to_string(),
+            "csv",
+            token.clone(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let response = app.get_fulfillment_runs(staff_user_id, token).await;
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn labels_are_not_available_to_readers() {
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4().to_string();
+    let token = generate_token(user_id.clone(), UserGroup::USER);
+
+    let response = app
+        .fulfillment_run_labels(user_id, Uuid::new_v4().to_string(), "avery_5160", token)
+        .await;
+
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn fulfillment_staff_cannot_create_runs() {
+    let app = spawn_app().await;
+    let staff_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(staff_user_id.clone(), UserGroup::FULFILLMENT);
+
+    let response = app
+        .create_fulfillment_run(
+            staff_user_id,
+            json!({ "issue_date": "2026-11-01" }).to_string(),
+            token,
+        )
+        .await;
+
+    assert_eq!(401, response.status().as_u16());
+}
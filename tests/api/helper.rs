@@ -2,6 +2,7 @@ use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use base64::Engine;
 use chrono::Utc;
 use claims::assert_ok;
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
 use once_cell::sync::Lazy;
 use reqwest::Response;
 use serde_json::json;
@@ -12,16 +13,24 @@ use wiremock::matchers::{header_exists, method, path, query_param};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
 use newsletter_signup_service::auth::token::{generate_token, LoginResponse};
-use newsletter_signup_service::configuration::{get_configuration, DatabaseSettings, Environment};
+use newsletter_signup_service::configuration::{
+    get_configuration, DatabaseSettings, EmailTransportKind, Environment, OidcSettings,
+};
 use newsletter_signup_service::db::subscriptions_db_broker::insert_subscription;
+use newsletter_signup_service::domain::captured_email_models::CapturedEmail;
 use newsletter_signup_service::domain::checkout_models::{CheckoutSession, CheckoutSessionState};
 use newsletter_signup_service::domain::subscriber_models::{
     OverTheWireCreateSubscriber, OverTheWireSubscriber,
 };
 use newsletter_signup_service::domain::subscription_models::{
-    NewSubscription, OverTheWireCreateSubscription, OverTheWireSubscription, SubscriptionType,
+    BillingSource, NewSubscription, OverTheWireCreateSubscription, OverTheWireSubscription,
+    SubscriptionType,
 };
 use newsletter_signup_service::domain::user_models::{ResetPassword, SignUp, UserGroup};
+use newsletter_signup_service::oidc_client::oidc_provider_models::{
+    OidcDiscoveryDocument, OidcTokenResponse,
+};
+use newsletter_signup_service::oidc_client::OIDC_DISCOVERY_PATH;
 use newsletter_signup_service::startup::Application;
 use newsletter_signup_service::stripe_client::stripe_models::{
     StripeBillingPortalSession, StripeCheckoutSession, StripeCustomer, StripeProductPrice,
@@ -50,86 +59,925 @@ pub fn jwt_with_payload_group_claim_tampered_to_admin(token: &str) -> String {
             serde_json::Value::String("ADMIN".to_string()),
         );
     }
-    let new_payload = serde_json::to_vec(&value).unwrap();
-    let b64 = URL_SAFE_NO_PAD.encode(&new_payload);
-    format!("{}.{}.{}", parts[0], b64, parts[2])
-}
+    let new_payload = serde_json::to_vec(&value).unwrap();
+    let b64 = URL_SAFE_NO_PAD.encode(&new_payload);
+    format!("{}.{}.{}", parts[0], b64, parts[2])
+}
+
+pub const OIDC_CLIENT_ID: &str = "newsletter-signup-service";
+pub const OIDC_SIGNING_KEY_ID: &str = "test-key";
+pub const OIDC_SIGNING_SECRET: &str = "a-signing-secret-that-is-only-used-in-tests";
+
+pub static TRACING: Lazy<()> = Lazy::new(|| {
+    let default_filter_level = "info".to_string();
+    let subscriber_name = "test".to_string();
+
+    if std::env::var("TEST_LOG").is_ok() {
+        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::stdout);
+        init_subscriber(subscriber);
+    } else {
+        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::sink);
+        init_subscriber(subscriber);
+    };
+});
+
+pub struct TestApp {
+    pub address: String,
+    pub db_pool: PgPool,
+    pub email_server: MockServer,
+    pub stripe_server: MockServer,
+    pub oidc_server: MockServer,
+}
+
+impl TestApp {
+    pub async fn user_signup(&self, body: String) -> Response {
+        reqwest::Client::new()
+            .post(format!("{}/sign_up", &self.address))
+            .header("Content-Type", "application/json")
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn login(&self, body: String) -> Response {
+        reqwest::Client::new()
+            .post(format!("{}/login", &self.address))
+            .header("Content-Type", "application/json")
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn login_with_user_agent(&self, body: String, user_agent: &str) -> Response {
+        reqwest::Client::new()
+            .post(format!("{}/login", &self.address))
+            .header("Content-Type", "application/json")
+            .header("User-Agent", user_agent)
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_user_sessions(&self, user_id: String, token: String) -> Response {
+        reqwest::Client::new()
+            .get(format!("{}/users/{}/sessions", &self.address, user_id))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn revoke_user_session(
+        &self,
+        user_id: String,
+        session_id: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .delete(format!(
+                "{}/users/{}/sessions/{}",
+                &self.address, user_id, session_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn check_token(&self, user_id: String, token: String) -> Response {
+        reqwest::Client::new()
+            .post(format!("{}/check_token/{}", &self.address, user_id))
+            .header("Content-Type", "application/json")
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn check_admin_token(&self, user_id: String, token: String) -> Response {
+        reqwest::Client::new()
+            .post(format!("{}/check_admin_token/{}", &self.address, user_id))
+            .header("Content-Type", "application/json")
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_all_subscribers_admin(&self, user_id: String, token: String) -> Response {
+        reqwest::Client::new()
+            .get(format!("{}/admin/subscribers/{}", &self.address, user_id))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_all_subscriptions_admin(&self, user_id: String, token: String) -> Response {
+        reqwest::Client::new()
+            .get(format!("{}/admin/subscriptions/{}", &self.address, user_id))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_all_users_admin(&self, user_id: String, token: String) -> Response {
+        reqwest::Client::new()
+            .get(format!("{}/admin/users/{}", &self.address, user_id))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn admin_promote_user(
+        &self,
+        admin_user_id: String,
+        target_user_id: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/admin/users/{}/promote/{}",
+                &self.address, admin_user_id, target_user_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn admin_demote_user(
+        &self,
+        admin_user_id: String,
+        target_user_id: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/admin/users/{}/demote/{}",
+                &self.address, admin_user_id, target_user_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn admin_disable_user(
+        &self,
+        admin_user_id: String,
+        target_user_id: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/admin/users/{}/disable/{}",
+                &self.address, admin_user_id, target_user_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn admin_enable_user(
+        &self,
+        admin_user_id: String,
+        target_user_id: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/admin/users/{}/enable/{}",
+                &self.address, admin_user_id, target_user_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn admin_force_password_reset(
+        &self,
+        admin_user_id: String,
+        target_user_id: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/admin/users/{}/force_password_reset/{}",
+                &self.address, admin_user_id, target_user_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn create_invitation(
+        &self,
+        admin_user_id: String,
+        body: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/admin/invitations/{}",
+                &self.address, admin_user_id
+            ))
+            .header("Content-Type", "application/json")
+            .bearer_auth(token)
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_pending_invitations(&self, admin_user_id: String, token: String) -> Response {
+        reqwest::Client::new()
+            .get(format!(
+                "{}/admin/invitations/{}",
+                &self.address, admin_user_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn revoke_invitation(
+        &self,
+        admin_user_id: String,
+        invitation_id: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .delete(format!(
+                "{}/admin/invitations/{}/{}",
+                &self.address, admin_user_id, invitation_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn accept_invitation(&self, body: String) -> Response {
+        reqwest::Client::new()
+            .post(format!("{}/invitations/accept", &self.address))
+            .header("Content-Type", "application/json")
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_audit_log_admin(
+        &self,
+        admin_user_id: String,
+        query: &[(&str, String)],
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .get(format!("{}/admin/audit/{}", &self.address, admin_user_id))
+            .query(query)
+            .header("User-Agent", "audit-test-agent")
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn import_subscriptions(
+        &self,
+        admin_user_id: String,
+        query: &[(&str, String)],
+        body: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/admin/import/subscriptions/{}",
+                &self.address, admin_user_id
+            ))
+            .query(query)
+            .header("Content-Type", "text/csv")
+            .bearer_auth(token)
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn create_fulfillment_run(
+        &self,
+        admin_user_id: String,
+        body: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/admin/fulfillment/runs/{}",
+                &self.address, admin_user_id
+            ))
+            .header("Content-Type", "application/json")
+            .bearer_auth(token)
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_fulfillment_runs(&self, admin_user_id: String, token: String) -> Response {
+        reqwest::Client::new()
+            .get(format!(
+                "{}/admin/fulfillment/runs/{}",
+                &self.address, admin_user_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn export_fulfillment_run(
+        &self,
+        admin_user_id: String,
+        run_id: String,
+        format: &str,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .get(format!(
+                "{}/admin/fulfillment/runs/{}/{}",
+                &self.address, admin_user_id, run_id
+            ))
+            .query(&[("format", format)])
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn fulfillment_run_labels(
+        &self,
+        user_id: String,
+        run_id: String,
+        layout: &str,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .get(format!(
+                "{}/admin/fulfillment/runs/{}/{}/labels",
+                &self.address, user_id, run_id
+            ))
+            .query(&[("layout", layout)])
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn create_publication(
+        &self,
+        admin_user_id: String,
+        body: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/admin/publications/{}",
+                &self.address, admin_user_id
+            ))
+            .header("Content-Type", "application/json")
+            .bearer_auth(token)
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_publications(&self, admin_user_id: String, token: String) -> Response {
+        reqwest::Client::new()
+            .get(format!(
+                "{}/admin/publications/{}",
+                &self.address, admin_user_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn update_publication(
+        &self,
+        admin_user_id: String,
+        publication_id: String,
+        body: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .put(format!(
+                "{}/admin/publications/{}/{}",
+                &self.address, admin_user_id, publication_id
+            ))
+            .header("Content-Type", "application/json")
+            .bearer_auth(token)
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_publication(
+        &self,
+        admin_user_id: String,
+        publication_id: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .delete(format!(
+                "{}/admin/publications/{}/{}",
+                &self.address, admin_user_id, publication_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn create_issue(
+        &self,
+        admin_user_id: String,
+        publication_id: String,
+        body: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/admin/publications/{}/{}/issues",
+                &self.address, admin_user_id, publication_id
+            ))
+            .header("Content-Type", "application/json")
+            .bearer_auth(token)
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_issues(
+        &self,
+        admin_user_id: String,
+        publication_id: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .get(format!(
+                "{}/admin/publications/{}/{}/issues",
+                &self.address, admin_user_id, publication_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn update_issue(
+        &self,
+        admin_user_id: String,
+        issue_id: String,
+        body: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .put(format!(
+                "{}/admin/issues/{}/{}",
+                &self.address, admin_user_id, issue_id
+            ))
+            .header("Content-Type", "application/json")
+            .bearer_auth(token)
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_issue(
+        &self,
+        admin_user_id: String,
+        issue_id: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .delete(format!(
+                "{}/admin/issues/{}/{}",
+                &self.address, admin_user_id, issue_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn set_subscription_publication(
+        &self,
+        admin_user_id: String,
+        subscription_id: String,
+        body: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .put(format!(
+                "{}/admin/subscriptions/{}/{}/publication",
+                &self.address, admin_user_id, subscription_id
+            ))
+            .header("Content-Type", "application/json")
+            .bearer_auth(token)
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_subscription_issues(
+        &self,
+        subscription_id: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .get(format!(
+                "{}/subscriptions/{}/issues",
+                &self.address, subscription_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_subscription_issues_admin(
+        &self,
+        admin_user_id: String,
+        subscription_id: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .get(format!(
+                "{}/admin/subscriptions/{}/{}/issues",
+                &self.address, admin_user_id, subscription_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn create_broadcast(
+        &self,
+        admin_user_id: String,
+        issue_id: String,
+        body: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/admin/issues/{}/{}/broadcasts",
+                &self.address, admin_user_id, issue_id
+            ))
+            .header("Content-Type", "application/json")
+            .bearer_auth(token)
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_broadcasts(
+        &self,
+        admin_user_id: String,
+        issue_id: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .get(format!(
+                "{}/admin/issues/{}/{}/broadcasts",
+                &self.address, admin_user_id, issue_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_broadcast_recipients(
+        &self,
+        admin_user_id: String,
+        broadcast_id: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .get(format!(
+                "{}/admin/broadcasts/{}/{}/recipients",
+                &self.address, admin_user_id, broadcast_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn resume_broadcast(
+        &self,
+        admin_user_id: String,
+        broadcast_id: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/admin/broadcasts/{}/{}/resume",
+                &self.address, admin_user_id, broadcast_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_outbox_emails(
+        &self,
+        admin_user_id: String,
+        status: Option<&str>,
+        token: String,
+    ) -> Response {
+        let mut request = reqwest::Client::new().get(format!(
+            "{}/admin/email_outbox/{}",
+            &self.address, admin_user_id
+        ));
+        if let Some(status) = status {
+            request = request.query(&[("status", status)]);
+        }
+        request
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn requeue_outbox_email(
+        &self,
+        admin_user_id: String,
+        email_id: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/admin/email_outbox/{}/{}/requeue",
+                &self.address, admin_user_id, email_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_jobs(
+        &self,
+        admin_user_id: String,
+        query: &[(&str, &str)],
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .get(format!("{}/admin/jobs/{}", &self.address, admin_user_id))
+            .query(query)
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
 
-pub static TRACING: Lazy<()> = Lazy::new(|| {
-    let default_filter_level = "info".to_string();
-    let subscriber_name = "test".to_string();
+    pub async fn retry_job(
+        &self,
+        admin_user_id: String,
+        job_id: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/admin/jobs/{}/{}/retry",
+                &self.address, admin_user_id, job_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
 
-    if std::env::var("TEST_LOG").is_ok() {
-        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::stdout);
-        init_subscriber(subscriber);
-    } else {
-        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::sink);
-        init_subscriber(subscriber);
-    };
-});
+    pub async fn post_email_events(&self, webhook_token: &str, body: String) -> Response {
+        reqwest::Client::new()
+            .post(format!("{}/email_events/{}", &self.address, webhook_token))
+            .header("Content-Type", "application/json")
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
 
-pub struct TestApp {
-    pub address: String,
-    pub db_pool: PgPool,
-    pub email_server: MockServer,
-    pub stripe_server: MockServer,
-}
+    pub async fn get_email_suppressions(
+        &self,
+        admin_user_id: String,
+        reason: Option<&str>,
+        token: String,
+    ) -> Response {
+        let mut request = reqwest::Client::new().get(format!(
+            "{}/admin/email_suppressions/{}",
+            &self.address, admin_user_id
+        ));
+        if let Some(reason) = reason {
+            request = request.query(&[("reason", reason)]);
+        }
+        request
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
 
-impl TestApp {
-    pub async fn user_signup(&self, body: String) -> Response {
+    pub async fn clear_email_suppression(
+        &self,
+        admin_user_id: String,
+        email_address: &str,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .delete(format!(
+                "{}/admin/email_suppressions/{}/{}",
+                &self.address, admin_user_id, email_address
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn create_notification_channel(
+        &self,
+        admin_user_id: String,
+        body: String,
+        token: String,
+    ) -> Response {
         reqwest::Client::new()
-            .post(&format!("{}/sign_up", &self.address))
+            .post(format!(
+                "{}/admin/notification_channels/{}",
+                &self.address, admin_user_id
+            ))
             .header("Content-Type", "application/json")
             .body(body)
+            .bearer_auth(token)
             .send()
             .await
             .expect("Failed to execute request.")
     }
 
-    pub async fn login(&self, body: String) -> Response {
+    pub async fn get_notification_channels(
+        &self,
+        admin_user_id: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .get(format!(
+                "{}/admin/notification_channels/{}",
+                &self.address, admin_user_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn update_notification_channel(
+        &self,
+        admin_user_id: String,
+        channel_id: String,
+        body: String,
+        token: String,
+    ) -> Response {
         reqwest::Client::new()
-            .post(&format!("{}/login", &self.address))
+            .put(format!(
+                "{}/admin/notification_channels/{}/{}",
+                &self.address, admin_user_id, channel_id
+            ))
             .header("Content-Type", "application/json")
             .body(body)
+            .bearer_auth(token)
             .send()
             .await
             .expect("Failed to execute request.")
     }
 
-    pub async fn check_token(&self, user_id: String, token: String) -> Response {
+    pub async fn delete_notification_channel(
+        &self,
+        admin_user_id: String,
+        channel_id: String,
+        token: String,
+    ) -> Response {
         reqwest::Client::new()
-            .post(&format!("{}/check_token/{}", &self.address, user_id))
-            .header("Content-Type", "application/json")
+            .delete(format!(
+                "{}/admin/notification_channels/{}/{}",
+                &self.address, admin_user_id, channel_id
+            ))
             .bearer_auth(token)
             .send()
             .await
             .expect("Failed to execute request.")
     }
 
-    pub async fn check_admin_token(&self, user_id: String, token: String) -> Response {
+    pub async fn preview_staff_digest(
+        &self,
+        admin_user_id: String,
+        frequency: Option<&str>,
+        token: String,
+    ) -> Response {
+        let mut request = reqwest::Client::new().get(format!(
+            "{}/admin/staff_digest/{}",
+            &self.address, admin_user_id
+        ));
+        if let Some(frequency) = frequency {
+            request = request.query(&[("frequency", frequency)]);
+        }
+        request
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn create_webhook_endpoint(
+        &self,
+        admin_user_id: String,
+        body: String,
+        token: String,
+    ) -> Response {
         reqwest::Client::new()
-            .post(&format!("{}/check_admin_token/{}", &self.address, user_id))
+            .post(format!(
+                "{}/admin/webhook_endpoints/{}",
+                &self.address, admin_user_id
+            ))
             .header("Content-Type", "application/json")
+            .body(body)
             .bearer_auth(token)
             .send()
             .await
             .expect("Failed to execute request.")
     }
 
-    pub async fn get_all_subscribers_admin(&self, user_id: String, token: String) -> Response {
+    pub async fn get_webhook_endpoints(&self, admin_user_id: String, token: String) -> Response {
         reqwest::Client::new()
-            .get(&format!("{}/admin/subscribers/{}", &self.address, user_id))
+            .get(format!(
+                "{}/admin/webhook_endpoints/{}",
+                &self.address, admin_user_id
+            ))
             .bearer_auth(token)
             .send()
             .await
             .expect("Failed to execute request.")
     }
 
-    pub async fn get_all_subscriptions_admin(&self, user_id: String, token: String) -> Response {
+    pub async fn update_webhook_endpoint(
+        &self,
+        admin_user_id: String,
+        endpoint_id: String,
+        body: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .put(format!(
+                "{}/admin/webhook_endpoints/{}/{}",
+                &self.address, admin_user_id, endpoint_id
+            ))
+            .header("Content-Type", "application/json")
+            .body(body)
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_webhook_endpoint(
+        &self,
+        admin_user_id: String,
+        endpoint_id: String,
+        token: String,
+    ) -> Response {
         reqwest::Client::new()
-            .get(&format!(
-                "{}/admin/subscriptions/{}",
-                &self.address, user_id
+            .delete(format!(
+                "{}/admin/webhook_endpoints/{}/{}",
+                &self.address, admin_user_id, endpoint_id
             ))
             .bearer_auth(token)
             .send()
@@ -137,25 +985,34 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
-    pub async fn get_all_users_admin(&self, user_id: String, token: String) -> Response {
+    pub async fn get_webhook_deliveries(
+        &self,
+        admin_user_id: String,
+        query: &[(&str, String)],
+        token: String,
+    ) -> Response {
         reqwest::Client::new()
-            .get(&format!("{}/admin/users/{}", &self.address, user_id))
+            .get(format!(
+                "{}/admin/webhook_deliveries/{}",
+                &self.address, admin_user_id
+            ))
+            .query(query)
             .bearer_auth(token)
             .send()
             .await
             .expect("Failed to execute request.")
     }
 
-    pub async fn admin_promote_user(
+    pub async fn replay_webhook_delivery(
         &self,
         admin_user_id: String,
-        target_user_id: String,
+        delivery_id: String,
         token: String,
     ) -> Response {
         reqwest::Client::new()
-            .post(&format!(
-                "{}/admin/users/{}/promote/{}",
-                &self.address, admin_user_id, target_user_id
+            .post(format!(
+                "{}/admin/webhook_deliveries/{}/{}/replay",
+                &self.address, admin_user_id, delivery_id
             ))
             .bearer_auth(token)
             .send()
@@ -163,17 +1020,31 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
-    pub async fn admin_demote_user(
+    pub async fn get_email_templates(&self, admin_user_id: String, token: String) -> Response {
+        reqwest::Client::new()
+            .get(format!(
+                "{}/admin/email_templates/{}",
+                &self.address, admin_user_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn preview_email_template(
         &self,
         admin_user_id: String,
-        target_user_id: String,
+        template: &str,
+        query: &[(&str, &str)],
         token: String,
     ) -> Response {
         reqwest::Client::new()
-            .post(&format!(
-                "{}/admin/users/{}/demote/{}",
-                &self.address, admin_user_id, target_user_id
+            .get(format!(
+                "{}/admin/email_templates/{}/{}",
+                &self.address, admin_user_id, template
             ))
+            .query(query)
             .bearer_auth(token)
             .send()
             .await
@@ -182,7 +1053,7 @@ impl TestApp {
 
     pub async fn reset_password(&self, body: String, token: String) -> Response {
         reqwest::Client::new()
-            .post(&format!("{}/reset_password", &self.address))
+            .post(format!("{}/reset_password", &self.address))
             .header("Content-Type", "application/json")
             .bearer_auth(token)
             .body(body)
@@ -193,7 +1064,7 @@ impl TestApp {
 
     pub async fn forgot_password(&self, body: String) -> Response {
         reqwest::Client::new()
-            .post(&format!("{}/forgot_password", &self.address))
+            .post(format!("{}/forgot_password", &self.address))
             .header("Content-Type", "application/json")
             .body(body)
             .send()
@@ -203,7 +1074,7 @@ impl TestApp {
 
     pub async fn forgot_password_login(&self, otp: String) -> Response {
         reqwest::Client::new()
-            .get(&format!("{}/forgot_password/otp/{}", &self.address, otp))
+            .get(format!("{}/forgot_password/otp/{}", &self.address, otp))
             .header("Content-Type", "application/json")
             .send()
             .await
@@ -212,7 +1083,7 @@ impl TestApp {
 
     pub async fn forgot_password_rest_password(&self, body: String, token: String) -> Response {
         reqwest::Client::new()
-            .post(&format!("{}/forgot_password/reset_password", &self.address))
+            .post(format!("{}/forgot_password/reset_password", &self.address))
             .header("Content-Type", "application/json")
             .bearer_auth(token)
             .body(body)
@@ -221,9 +1092,28 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    pub async fn oidc_login(&self) -> Response {
+        reqwest::Client::new()
+            .get(format!("{}/oidc/login", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn oidc_callback(&self, code: String, state: String) -> Response {
+        reqwest::Client::new()
+            .get(format!(
+                "{}/oidc/callback?code={}&state={}",
+                &self.address, code, state
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn post_subscriber(&self, body: String, token: String) -> Response {
         reqwest::Client::new()
-            .post(&format!("{}/subscribers", &self.address))
+            .post(format!("{}/subscribers", &self.address))
             .header("Content-Type", "application/json")
             .bearer_auth(token)
             .body(body)
@@ -234,16 +1124,87 @@ impl TestApp {
 
     pub async fn get_subscriber_by_id(&self, id: String, token: String) -> Response {
         reqwest::Client::new()
-            .get(&format!("{}/subscribers/{}", &self.address, id))
+            .get(format!("{}/subscribers/{}", &self.address, id))
             .bearer_auth(token)
             .send()
             .await
             .expect("Got a subscriber back")
     }
 
+    pub async fn newsletter_subscribe(&self, body: String) -> Response {
+        reqwest::Client::new()
+            .post(format!("{}/newsletter/subscribe", &self.address))
+            .header("Content-Type", "application/json")
+            .header("User-Agent", "newsletter-test")
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn newsletter_confirm(&self, body: String) -> Response {
+        reqwest::Client::new()
+            .post(format!("{}/newsletter/confirm", &self.address))
+            .header("Content-Type", "application/json")
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_email_preferences(&self, subscriber_id: String, token: String) -> Response {
+        reqwest::Client::new()
+            .get(format!(
+                "{}/subscribers/{}/preferences",
+                &self.address, subscriber_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_email_preferences(
+        &self,
+        subscriber_id: String,
+        body: String,
+        token: String,
+    ) -> Response {
+        reqwest::Client::new()
+            .put(format!(
+                "{}/subscribers/{}/preferences",
+                &self.address, subscriber_id
+            ))
+            .header("Content-Type", "application/json")
+            .bearer_auth(token)
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_unsubscribe(&self, token: &str) -> Response {
+        reqwest::Client::new()
+            .get(format!("{}/unsubscribe/{}", &self.address, token))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// The way a mail client sends an RFC 8058 one-click unsubscribe.
+    pub async fn one_click_unsubscribe(&self, token: &str) -> Response {
+        reqwest::Client::new()
+            .post(format!("{}/unsubscribe/{}", &self.address, token))
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body("List-Unsubscribe=One-Click")
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn get_subscriber_by_email(&self, email: String, token: String) -> Response {
         reqwest::Client::new()
-            .get(&format!("{}/subscribers?email={}", &self.address, email))
+            .get(format!("{}/subscribers?email={}", &self.address, email))
             .bearer_auth(token)
             .send()
             .await
@@ -256,10 +1217,7 @@ impl TestApp {
         token: String,
     ) -> Response {
         reqwest::Client::new()
-            .get(&format!(
-                "{}/subscribers?user_id={}",
-                &self.address, user_id
-            ))
+            .get(format!("{}/subscribers?user_id={}", &self.address, user_id))
             .bearer_auth(token)
             .send()
             .await
@@ -273,7 +1231,7 @@ impl TestApp {
         token: String,
     ) -> Response {
         reqwest::Client::new()
-            .get(&format!(
+            .get(format!(
                 "{}/subscribers?user_id={}&email={}",
                 &self.address, user_id, email
             ))
@@ -289,7 +1247,7 @@ impl TestApp {
         token: String,
     ) -> Response {
         reqwest::Client::new()
-            .get(&format!(
+            .get(format!(
                 "{}/subscribers/{}/subscriptions",
                 &self.address, subscriber_id
             ))
@@ -301,7 +1259,7 @@ impl TestApp {
 
     pub async fn get_subscription_by_id(&self, id: String, token: String) -> Response {
         reqwest::Client::new()
-            .get(&format!("{}/subscriptions/{}", &self.address, id))
+            .get(format!("{}/subscriptions/{}", &self.address, id))
             .bearer_auth(token)
             .send()
             .await
@@ -310,7 +1268,7 @@ impl TestApp {
 
     pub async fn post_checkout(&self, body: String, user_id: String, token: String) -> Response {
         reqwest::Client::new()
-            .post(&format!("{}/checkout/{}", &self.address, user_id))
+            .post(format!("{}/checkout/{}", &self.address, user_id))
             .header("Content-Type", "application/json")
             .bearer_auth(token)
             .body(body)
@@ -326,7 +1284,7 @@ impl TestApp {
         token: String,
     ) -> Response {
         reqwest::Client::new()
-            .post(&format!(
+            .post(format!(
                 "{}/checkout/{}/session/{}",
                 &self.address, user_id, session_id
             ))
@@ -343,7 +1301,7 @@ impl TestApp {
         token: String,
     ) -> Response {
         reqwest::Client::new()
-            .post(&format!("{}/checkout/{}/manage", &self.address, user_id))
+            .post(format!("{}/checkout/{}/manage", &self.address, user_id))
             .header("Content-Type", "application/json")
             .bearer_auth(token)
             .send()
@@ -353,7 +1311,7 @@ impl TestApp {
 
     pub async fn cancel_subscription_by_id(&self, id: String, token: String) -> Response {
         reqwest::Client::new()
-            .delete(&format!("{}/subscriptions/{}", &self.address, id))
+            .delete(format!("{}/subscriptions/{}", &self.address, id))
             .header("Content-Type", "application/json")
             .bearer_auth(token)
             .send()
@@ -368,7 +1326,7 @@ impl TestApp {
         token: String,
     ) -> Response {
         reqwest::Client::new()
-            .put(&format!("{}/subscriptions/{}", &self.address, id))
+            .put(format!("{}/subscriptions/{}", &self.address, id))
             .header("Content-Type", "application/json")
             .body(body)
             .bearer_auth(token)
@@ -389,7 +1347,7 @@ impl TestApp {
         &self,
         subscriber: Option<OverTheWireCreateSubscriber>,
     ) -> OverTheWireSubscriber {
-        let subscriber = subscriber.unwrap_or_else(|| generate_over_the_wire_subscriber());
+        let subscriber = subscriber.unwrap_or_else(generate_over_the_wire_subscriber);
         let response = self
             .post_subscriber(
                 subscriber.to_json(),
@@ -408,6 +1366,34 @@ impl TestApp {
         .await
     }
 
+    /// Request bodies of the emails sent with this subject, ignoring the
+    /// others (such as the welcome email every sign up sends).
+    pub async fn received_emails(&self, subject: &str) -> Vec<serde_json::Value> {
+        self.email_server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|request| serde_json::from_slice::<serde_json::Value>(&request.body).unwrap())
+            .filter(|body| body["subject"] == subject)
+            .collect()
+    }
+
+    pub async fn get_dev_emails(&self, recipient: Option<&str>) -> Response {
+        let mut request = reqwest::Client::new().get(format!("{}/dev/emails", &self.address));
+        if let Some(recipient) = recipient {
+            request = request.query(&[("recipient", recipient)]);
+        }
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    /// The captured emails to `recipient`, newest first.
+    pub async fn captured_emails(&self, recipient: &str) -> Vec<CapturedEmail> {
+        let response = self.get_dev_emails(Some(recipient)).await;
+        assert_eq!(200, response.status().as_u16());
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap()
+    }
+
     pub async fn sign_up(&self) -> LoginResponse {
         let sign_up = generate_signup();
         let sign_up_response = self.user_signup(sign_up.to_json()).await;
@@ -435,13 +1421,24 @@ pub async fn spawn_app() -> TestApp {
 
     let email_server = MockServer::start().await;
     let stripe_server = MockServer::start().await;
+    let oidc_server = MockServer::start().await;
 
     let configuration = {
         let mut c = get_configuration().expect("Failed to read configuration.");
         c.database.database_name = Uuid::new_v4().to_string();
         c.application.port = 0;
+        c.email_client.transport = EmailTransportKind::Mailtrap;
         c.email_client.base_url = format!("{}/", email_server.uri().trim_end_matches('/'));
         c.stripe_client.base_url = stripe_server.uri();
+        c.oidc = Some(OidcSettings {
+            provider_name: "test".to_string(),
+            issuer_url: oidc_server.uri(),
+            client_id: OIDC_CLIENT_ID.to_string(),
+            client_secret: Uuid::new_v4().to_string().into(),
+            redirect_url: "http://localhost:3000/oidc-callback".to_string(),
+            scopes: "openid email profile".to_string(),
+            timeout_milliseconds: 2000,
+        });
         c
     };
 
@@ -451,13 +1448,14 @@ pub async fn spawn_app() -> TestApp {
         .expect("Failed to build application.");
 
     let address = format!("http://127.0.0.1:{}", application.port());
-    let _ = tokio::spawn(application.run_until_stopped());
+    tokio::spawn(application.run_until_stopped());
 
     TestApp {
         address,
         db_pool: pool,
         email_server,
         stripe_server,
+        oidc_server,
     }
 }
 
@@ -485,7 +1483,7 @@ pub async fn configure_database(config: &DatabaseSettings) -> PgPool {
 
 pub fn generate_signup() -> SignUp {
     SignUp {
-        email_address: format!("{}@gmail.com", Uuid::new_v4().to_string()),
+        email_address: format!("{}@gmail.com", Uuid::new_v4()),
         password: Uuid::new_v4().to_string(),
         name: Uuid::new_v4().to_string(),
     }
@@ -502,7 +1500,7 @@ pub fn generate_reset_password(username: String, old_password: String) -> ResetP
 pub fn generate_over_the_wire_subscriber() -> OverTheWireCreateSubscriber {
     OverTheWireCreateSubscriber {
         name: Uuid::new_v4().to_string(),
-        email_address: format!("{}@gmail.com", Uuid::new_v4().to_string()),
+        email_address: format!("{}@gmail.com", Uuid::new_v4()),
         user_id: Uuid::new_v4().to_string(),
     }
 }
@@ -513,14 +1511,15 @@ pub fn generate_over_the_wire_create_subscription(
 ) -> OverTheWireCreateSubscription {
     OverTheWireCreateSubscription {
         subscriber_id,
-        subscription_type: subscription_type.unwrap_or_else(|| SubscriptionType::Digital),
+        subscription_type: subscription_type.unwrap_or(SubscriptionType::Digital),
         subscription_state: Uuid::new_v4().to_string(),
         subscription_name: Uuid::new_v4().to_string(),
         subscription_city: Uuid::new_v4().to_string(),
-        subscription_email_address: format!("{}@gmail.com", Uuid::new_v4().to_string()),
+        subscription_email_address: format!("{}@gmail.com", Uuid::new_v4()),
         subscription_postal_code: Uuid::new_v4().to_string(),
         subscription_mailing_address_line_2: Option::from(Uuid::new_v4().to_string()),
         subscription_mailing_address_line_1: Uuid::new_v4().to_string(),
+        publication_id: None,
     }
 }
 
@@ -538,7 +1537,7 @@ pub fn generate_over_the_wire_subscription() -> OverTheWireSubscription {
         subscription_state: Uuid::new_v4().to_string(),
         subscription_name: Uuid::new_v4().to_string(),
         subscription_city: Uuid::new_v4().to_string(),
-        subscription_email_address: format!("{}@gmail.com", Uuid::new_v4().to_string()),
+        subscription_email_address: format!("{}@gmail.com", Uuid::new_v4()),
         subscription_creation_date: Utc::now(),
         subscription_cancelled_on_date: None,
         subscription_anniversary_day: 0,
@@ -547,8 +1546,10 @@ pub fn generate_over_the_wire_subscription() -> OverTheWireSubscription {
         subscription_mailing_address_line_1: Uuid::new_v4().to_string(),
         active: false,
         stripe_subscription_id: Uuid::new_v4().to_string(),
+        billing_source: BillingSource::Stripe,
         subscription_anniversary_month: 0,
         subscription_renewal_date: "".to_string(),
+        publication_id: None,
     }
 }
 
@@ -584,7 +1585,7 @@ pub async fn mock_stripe_create_customer(mock_server: &MockServer, customer_emai
         .and(method("POST"))
         .respond_with(response)
         .expect(1)
-        .mount(&mock_server)
+        .mount(mock_server)
         .await;
 }
 
@@ -612,7 +1613,7 @@ pub async fn mock_stripe_price_lookup(mock_server: &MockServer, stripe_lookup_id
         .and(method("GET"))
         .respond_with(response)
         .expect(1)
-        .mount(&mock_server)
+        .mount(mock_server)
         .await;
 }
 
@@ -631,7 +1632,7 @@ pub async fn mock_create_checkout_session(mock_server: &MockServer, stripe_sessi
         .and(method("POST"))
         .respond_with(response)
         .expect(1)
-        .mount(&mock_server)
+        .mount(mock_server)
         .await;
 }
 
@@ -641,6 +1642,7 @@ pub async fn mock_get_stripe_session(mock_server: &MockServer, session_id: Strin
         object: "something".to_string(),
         amount_subtotal: 500,
         amount_total: 500,
+        currency: Some("usd".to_string()),
         client_reference_id: None,
         customer: Uuid::new_v4().to_string(),
         subscription: Some(Uuid::new_v4().to_string()),
@@ -658,7 +1660,7 @@ pub async fn mock_get_stripe_session(mock_server: &MockServer, session_id: Strin
         .and(method("GET"))
         .respond_with(response)
         .expect(1)
-        .mount(&mock_server)
+        .mount(mock_server)
         .await;
 }
 
@@ -672,7 +1674,7 @@ pub async fn mock_stripe_create_customer_returns_a_500(
         .and(method("POST"))
         .respond_with(ResponseTemplate::new(500))
         .expect(1)
-        .mount(&mock_server)
+        .mount(mock_server)
         .await;
 }
 
@@ -688,7 +1690,7 @@ pub async fn mock_stripe_price_lookup_by_id_returns_a_500(
         .and(method("GET"))
         .respond_with(ResponseTemplate::new(500))
         .expect(1)
-        .mount(&mock_server)
+        .mount(mock_server)
         .await;
 }
 
@@ -698,7 +1700,7 @@ pub async fn mock_stripe_create_session_returns_a_500(mock_server: &MockServer)
         .and(method("POST"))
         .respond_with(ResponseTemplate::new(500))
         .expect(1)
-        .mount(&mock_server)
+        .mount(mock_server)
         .await;
 }
 
@@ -729,7 +1731,7 @@ pub async fn mock_create_stripe_billing_portal_session(
         .and(method("POST"))
         .respond_with(response)
         .expect(1)
-        .mount(&mock_server)
+        .mount(mock_server)
         .await;
 }
 
@@ -744,7 +1746,7 @@ pub async fn mock_cancel_stripe_subscription(mock_server: &MockServer, subscript
         .and(method("DELETE"))
         .respond_with(response)
         .expect(1)
-        .mount(&mock_server)
+        .mount(mock_server)
         .await;
 }
 
@@ -772,3 +1774,77 @@ pub async fn store_subscription(
     assert_ok!(transaction.commit().await);
     response.unwrap()
 }
+
+pub async fn mock_oidc_discovery(mock_server: &MockServer) {
+    let discovery_document = OidcDiscoveryDocument {
+        issuer: mock_server.uri(),
+        authorization_endpoint: format!("{}/authorize", mock_server.uri()),
+        token_endpoint: format!("{}/token", mock_server.uri()),
+        jwks_uri: format!("{}/jwks", mock_server.uri()),
+        userinfo_endpoint: None,
+    };
+
+    Mock::given(path(OIDC_DISCOVERY_PATH))
+        .and(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!(discovery_document)))
+        .mount(mock_server)
+        .await;
+}
+
+pub async fn mock_oidc_signing_keys(mock_server: &MockServer) {
+    Mock::given(path("/jwks"))
+        .and(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "keys": [{
+                "kty": "oct",
+                "kid": OIDC_SIGNING_KEY_ID,
+                "alg": "HS256",
+                "k": URL_SAFE_NO_PAD.encode(OIDC_SIGNING_SECRET),
+            }]
+        })))
+        .mount(mock_server)
+        .await;
+}
+
+pub async fn mock_oidc_token_exchange(mock_server: &MockServer, id_token: String) {
+    let token_response = OidcTokenResponse {
+        access_token: Uuid::new_v4().to_string(),
+        token_type: "Bearer".to_string(),
+        id_token,
+        expires_in: Some(3600),
+    };
+
+    Mock::given(path("/token"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!(token_response)))
+        .mount(mock_server)
+        .await;
+}
+
+pub fn generate_oidc_id_token(
+    issuer: &str,
+    subject: &str,
+    email: &str,
+    email_verified: bool,
+    nonce: &str,
+) -> String {
+    let now = jsonwebtoken::get_current_timestamp();
+    let mut header = Header::new(Algorithm::HS256);
+    header.kid = Some(OIDC_SIGNING_KEY_ID.to_string());
+    encode(
+        &header,
+        &json!({
+            "iss": issuer,
+            "sub": subject,
+            "aud": OIDC_CLIENT_ID,
+            "exp": now + 300,
+            "iat": now,
+            "nonce": nonce,
+            "email": email,
+            "email_verified": email_verified,
+            "name": "Ursula Le Guin",
+        }),
+        &EncodingKey::from_secret(OIDC_SIGNING_SECRET.as_bytes()),
+    )
+    .unwrap()
+}
@@ -0,0 +1,89 @@
+use chrono::{Duration, Utc};
+use claims::{assert_err, assert_ok};
+use uuid::Uuid;
+
+use newsletter_signup_service::db::invitation_db_broker::{
+    consume_invitation, get_pending_invitations, insert_invitation, revoke_invitation,
+};
+use newsletter_signup_service::domain::invitation_models::Invitation;
+use newsletter_signup_service::domain::user_models::UserGroup;
+use newsletter_signup_service::util::hash_token;
+
+use crate::helper::spawn_app;
+
+fn generate_invitation(expires_in: Duration) -> Invitation {
+    Invitation {
+        id: Uuid::new_v4(),
+        email_address: format!("{}@example.com", Uuid::new_v4()),
+        user_group: UserGroup::ADMIN,
+        invited_by: Some(Uuid::new_v4()),
+        created_at: Utc::now(),
+        expires_at: Utc::now() + expires_in,
+        accepted_at: None,
+        accepted_user_id: None,
+        revoked_at: None,
+    }
+}
+
+#[tokio::test]
+async fn only_pending_invitations_are_listed() {
+    let app = spawn_app().await;
+    let pending = generate_invitation(Duration::hours(1));
+    let expired = generate_invitation(Duration::hours(-1));
+    let revoked = generate_invitation(Duration::hours(1));
+
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    for (invitation, token) in [(&pending, "a"), (&expired, "b"), (&revoked, "c")] {
+        assert_ok!(insert_invitation(invitation, &hash_token(token), &mut transaction).await);
+    }
+    transaction.commit().await.unwrap();
+    assert!(revoke_invitation(revoked.id, &app.db_pool).await.unwrap());
+
+    let invitations = get_pending_invitations(&app.db_pool).await.unwrap();
+    assert_eq!(1, invitations.len());
+    assert_eq!(pending.id, invitations[0].id);
+    assert_eq!(UserGroup::ADMIN, invitations[0].user_group);
+}
+
+#[tokio::test]
+async fn an_invitation_can_only_be_consumed_once() {
+    let app = spawn_app().await;
+    let invitation = generate_invitation(Duration::hours(1));
+    let token_hash = hash_token("secret");
+
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    assert_ok!(insert_invitation(&invitation, &token_hash, &mut transaction).await);
+    let consumed = consume_invitation(&token_hash, &mut transaction)
+        .await
+        .unwrap();
+    assert_eq!(invitation.id, consumed.id);
+    assert!(consumed.accepted_at.is_some());
+    assert_err!(consume_invitation(&token_hash, &mut transaction).await);
+    transaction.commit().await.unwrap();
+
+    assert!(!revoke_invitation(invitation.id, &app.db_pool)
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn an_expired_invitation_cannot_be_consumed() {
+    let app = spawn_app().await;
+    let invitation = generate_invitation(Duration::hours(-1));
+    let token_hash = hash_token("expired");
+
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    assert_ok!(insert_invitation(&invitation, &token_hash, &mut transaction).await);
+    assert_err!(consume_invitation(&token_hash, &mut transaction).await);
+}
+
+#[tokio::test]
+async fn get_pending_invitations_returns_err_when_table_is_missing() {
+    let app = spawn_app().await;
+    sqlx::query!("DROP TABLE invitations")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    assert_err!(get_pending_invitations(&app.db_pool).await);
+}
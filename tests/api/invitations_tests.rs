@@ -0,0 +1,297 @@
+use mailtrap_rs::types::response::SendEmailResponse;
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use newsletter_signup_service::auth::token::{generate_token, LoginResponse};
+use newsletter_signup_service::domain::email_outbox_models::OutboxEmail;
+use newsletter_signup_service::domain::invitation_models::{
+    AcceptInvitation, CreateInvitation, Invitation,
+};
+use newsletter_signup_service::domain::user_models::{LogIn, UserGroup};
+
+use crate::helper::{generate_signup, spawn_app, TestApp};
+
+async fn mock_email_sent(app: &TestApp) {
+    Mock::given(path("api/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(SendEmailResponse {
+            success: true,
+            message_ids: vec!["test-id".to_string()],
+            errors: vec![],
+        }))
+        .mount(&app.email_server)
+        .await;
+}
+
+/// Pulls the token out of the link in the nth email the mock server received.
+async fn invitation_token_from_email(app: &TestApp, index: usize) -> String {
+    let email_request = &app.email_server.received_requests().await.unwrap()[index];
+    let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    let links: Vec<_> = linkify::LinkFinder::new()
+        .links(body["text"].as_str().unwrap())
+        .filter(|l| *l.kind() == linkify::LinkKind::Url)
+        .collect();
+    assert_eq!(1, links.len());
+    let url = url::Url::parse(links[0].as_str()).unwrap();
+    assert_eq!("/accept-invitation", url.path());
+    url.query_pairs().next().unwrap().1.to_string()
+}
+
+fn generate_create_invitation(user_group: UserGroup) -> CreateInvitation {
+    CreateInvitation {
+        email_address: format!("{}@example.com", Uuid::new_v4()),
+        user_group,
+        expires_in_hours: None,
+    }
+}
+
+fn generate_accept_invitation(token: String) -> AcceptInvitation {
+    AcceptInvitation {
+        token,
+        password: Uuid::new_v4().to_string(),
+        name: "Invited Person".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn an_accepted_invitation_creates_the_account_in_the_invited_role() {
+    let app = spawn_app().await;
+    mock_email_sent(&app).await;
+    let admin_user_id = Uuid::new_v4().to_string();
+    let admin_token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+
+    let create = generate_create_invitation(UserGroup::ADMIN);
+    let response = app
+        .create_invitation(admin_user_id.clone(), create.to_json(), admin_token.clone())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let invitation: Invitation =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!(create.email_address, invitation.email_address);
+    assert_eq!(UserGroup::ADMIN, invitation.user_group);
+
+    let token = invitation_token_from_email(&app, 0).await;
+    let accept = generate_accept_invitation(token.clone());
+    let response = app.accept_invitation(accept.to_json()).await;
+    assert_eq!(200, response.status().as_u16());
+    let login: LoginResponse =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!(UserGroup::ADMIN, login.group);
+
+    let response = app
+        .check_admin_token(login.user_id.clone(), login.token)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = app
+        .login(
+            LogIn {
+                email_address: create.email_address,
+                password: accept.password,
+            }
+            .to_json(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    // The link is single-use.
+    let response = app
+        .accept_invitation(generate_accept_invitation(token).to_json())
+        .await;
+    assert_eq!(400, response.status().as_u16());
+
+    let response = app
+        .get_pending_invitations(admin_user_id, admin_token)
+        .await;
+    let pending: Vec<Invitation> =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert!(pending.is_empty());
+}
+
+#[tokio::test]
+async fn a_revoked_invitation_cannot_be_accepted() {
+    let app = spawn_app().await;
+    mock_email_sent(&app).await;
+    let admin_user_id = Uuid::new_v4().to_string();
+    let admin_token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+
+    let response = app
+        .create_invitation(
+            admin_user_id.clone(),
+            generate_create_invitation(UserGroup::USER).to_json(),
+            admin_token.clone(),
+        )
+        .await;
+    let invitation: Invitation =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+
+    let response = app
+        .get_pending_invitations(admin_user_id.clone(), admin_token.clone())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let pending: Vec<Invitation> =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!(1, pending.len());
+    assert_eq!(invitation.id, pending[0].id);
+
+    let response = app
+        .revoke_invitation(
+            admin_user_id.clone(),
+            invitation.id.to_string(),
+            admin_token.clone(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let response = app
+        .revoke_invitation(admin_user_id, invitation.id.to_string(), admin_token)
+        .await;
+    assert_eq!(404, response.status().as_u16());
+
+    let token = invitation_token_from_email(&app, 0).await;
+    let response = app
+        .accept_invitation(generate_accept_invitation(token).to_json())
+        .await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn accept_invitation_returns_409_once_the_address_has_signed_up() {
+    let app = spawn_app().await;
+    mock_email_sent(&app).await;
+    let admin_user_id = Uuid::new_v4().to_string();
+    let admin_token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+    let create = generate_create_invitation(UserGroup::ADMIN);
+    let response = app
+        .create_invitation(admin_user_id.clone(), create.to_json(), admin_token.clone())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let mut signup = generate_signup();
+    signup.email_address = create.email_address.clone();
+    let response = app.user_signup(signup.to_json()).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let token = invitation_token_from_email(&app, 0).await;
+    let response = app
+        .accept_invitation(generate_accept_invitation(token).to_json())
+        .await;
+    assert_eq!(409, response.status().as_u16());
+
+    // Nothing was accepted, so the invitation is still pending.
+    let response = app
+        .get_pending_invitations(admin_user_id, admin_token)
+        .await;
+    let pending: Vec<Invitation> =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!(1, pending.len());
+}
+
+#[tokio::test]
+async fn create_invitation_returns_409_for_a_registered_email() {
+    let app = spawn_app().await;
+    let signup = crate::helper::generate_signup();
+    let response = app.user_signup(signup.to_json()).await;
+    assert_eq!(200, response.status().as_u16());
+    let admin_user_id = Uuid::new_v4().to_string();
+
+    let create = CreateInvitation {
+        email_address: signup.email_address,
+        user_group: UserGroup::USER,
+        expires_in_hours: None,
+    };
+    let response = app
+        .create_invitation(
+            admin_user_id.clone(),
+            create.to_json(),
+            generate_token(admin_user_id, UserGroup::ADMIN),
+        )
+        .await;
+
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn create_invitation_returns_400_for_an_invalid_email() {
+    let app = spawn_app().await;
+    let admin_user_id = Uuid::new_v4().to_string();
+
+    let create = CreateInvitation {
+        email_address: "not-an-email".to_string(),
+        user_group: UserGroup::USER,
+        expires_in_hours: None,
+    };
+    let response = app
+        .create_invitation(
+            admin_user_id.clone(),
+            create.to_json(),
+            generate_token(admin_user_id, UserGroup::ADMIN),
+        )
+        .await;
+
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn create_invitation_returns_401_for_non_admin() {
+    let app = spawn_app().await;
+    let login = app.sign_up().await;
+
+    let response = app
+        .create_invitation(
+            login.user_id,
+            generate_create_invitation(UserGroup::ADMIN).to_json(),
+            login.token,
+        )
+        .await;
+
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn create_invitation_keeps_the_invitation_and_queues_the_email_when_sending_fails() {
+    let app = spawn_app().await;
+    Mock::given(path("api/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&app.email_server)
+        .await;
+    let admin_user_id = Uuid::new_v4().to_string();
+    let admin_token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+
+    let response = app
+        .create_invitation(
+            admin_user_id.clone(),
+            generate_create_invitation(UserGroup::USER).to_json(),
+            admin_token.clone(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = app
+        .get_pending_invitations(admin_user_id.clone(), admin_token.clone())
+        .await;
+    let pending: Vec<Invitation> =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!(1, pending.len());
+
+    let response = app
+        .get_outbox_emails(admin_user_id, Some("Pending"), admin_token)
+        .await;
+    let queued: Vec<OutboxEmail> =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!(1, queued.len());
+    assert_eq!(vec![pending[0].email_address.clone()], queued[0].recipients);
+    assert_eq!(1, queued[0].attempts);
+    assert!(queued[0].last_error.is_some());
+}
+
+#[tokio::test]
+async fn accept_invitation_returns_400_for_an_unknown_token() {
+    let app = spawn_app().await;
+
+    let response = app
+        .accept_invitation(generate_accept_invitation(Uuid::new_v4().to_string()).to_json())
+        .await;
+
+    assert_eq!(400, response.status().as_u16());
+}
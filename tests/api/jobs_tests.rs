@@ -0,0 +1,242 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use newsletter_signup_service::auth::token::generate_token;
+use newsletter_signup_service::background::job_worker::{schedule_job, start_job_worker};
+use newsletter_signup_service::configuration::get_configuration;
+use newsletter_signup_service::db::jobs_db_broker::{mark_job_done, mark_job_failed};
+use newsletter_signup_service::db::subscription_history_db_broker::retrieve_subscription_events_by_subscription_id;
+use newsletter_signup_service::domain::job_models::{Job, JobPayload, JobStatus, MAX_JOB_ATTEMPTS};
+use newsletter_signup_service::domain::subscription_history_models::HistoryEventType;
+use newsletter_signup_service::domain::subscription_models::OverTheWireSubscription;
+use newsletter_signup_service::domain::user_models::UserGroup;
+use newsletter_signup_service::email_client::EmailClient;
+
+use crate::helper::{spawn_app, store_subscription, TestApp};
+
+fn admin() -> (String, String) {
+    let admin_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+    (admin_user_id, token)
+}
+
+async fn get_jobs(app: &TestApp, query: &[(&str, &str)]) -> Vec<Job> {
+    let (admin_user_id, token) = admin();
+    let response = app.get_jobs(admin_user_id, query, token).await;
+    assert_eq!(200, response.status().as_u16());
+    serde_json::from_str(response.text().await.unwrap().as_str()).unwrap()
+}
+
+async fn get_job(app: &TestApp, job_id: Uuid) -> Job {
+    get_jobs(app, &[])
+        .await
+        .into_iter()
+        .find(|job| job.id == job_id)
+        .unwrap()
+}
+
+/// The server's worker polls for due jobs, so wait for it to attempt this one.
+async fn wait_for_attempt(app: &TestApp, job_id: Uuid) -> Job {
+    for _ in 0..50 {
+        let job = get_job(app, job_id).await;
+        if job.attempts > 0 && job.status != JobStatus::Running {
+            return job;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("Job {} was never run", job_id);
+}
+
+fn store_history(subscription: &OverTheWireSubscription) -> JobPayload {
+    JobPayload::StoreSubscriptionHistory {
+        subscription: Box::new(subscription.clone()),
+        event_type: HistoryEventType::UpdatedSubscriptionInformation,
+        occurred_at: Utc::now(),
+    }
+}
+
+#[tokio::test]
+async fn due_jobs_run_and_scheduled_ones_wait() {
+    let app = spawn_app().await;
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = store_subscription(subscriber.id.to_string(), None, &app).await;
+
+    let later = schedule_job(
+        &store_history(&subscription),
+        Utc::now() + chrono::Duration::hours(1),
+        &app.db_pool,
+    )
+    .await
+    .unwrap();
+    let now = schedule_job(&store_history(&subscription), Utc::now(), &app.db_pool)
+        .await
+        .unwrap();
+
+    let done = wait_for_attempt(&app, now).await;
+    assert_eq!(JobStatus::Done, done.status);
+    assert_eq!("StoreSubscriptionHistory", done.kind);
+    assert!(done.finished_at.is_some());
+    let events = retrieve_subscription_events_by_subscription_id(subscription.id, &app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(1, events.len());
+    let waiting = get_job(&app, later).await;
+    assert_eq!(JobStatus::Pending, waiting.status);
+    assert_eq!(0, waiting.attempts);
+    let pending = get_jobs(&app, &[("status", "Pending")]).await;
+    assert_eq!(
+        vec![later],
+        pending.iter().map(|job| job.id).collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn failed_jobs_are_visible_and_can_be_retried() {
+    let app = spawn_app().await;
+    let unknown = JobPayload::NotifyNewSubscription {
+        subscription_id: Uuid::new_v4(),
+    };
+    let job_id = schedule_job(&unknown, Utc::now(), &app.db_pool)
+        .await
+        .unwrap();
+
+    let failed = wait_for_attempt(&app, job_id).await;
+    assert_eq!(JobStatus::Pending, failed.status);
+    assert_eq!(1, failed.attempts);
+    assert!(failed.last_error.is_some());
+    assert!(failed.run_at > Utc::now() + chrono::Duration::seconds(20));
+    assert_eq!(
+        vec![job_id],
+        get_jobs(&app, &[("kind", "NotifyNewSubscription")])
+            .await
+            .iter()
+            .map(|job| job.id)
+            .collect::<Vec<_>>()
+    );
+
+    let (admin_user_id, token) = admin();
+    let response = app
+        .retry_job(admin_user_id.clone(), job_id.to_string(), token.clone())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    // The retry starts a fresh set of attempts straight away.
+    let retried = wait_for_attempt(&app, job_id).await;
+    assert_eq!(1, retried.attempts);
+    assert!(retried.last_error.is_some());
+
+    let response = app
+        .retry_job(
+            admin_user_id.clone(),
+            Uuid::new_v4().to_string(),
+            token.clone(),
+        )
+        .await;
+    assert_eq!(404, response.status().as_u16());
+    let user_id = Uuid::new_v4().to_string();
+    let response = app
+        .retry_job(
+            user_id.clone(),
+            job_id.to_string(),
+            generate_token(user_id, UserGroup::USER),
+        )
+        .await;
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn done_jobs_cannot_be_retried() {
+    let app = spawn_app().await;
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = store_subscription(subscriber.id.to_string(), None, &app).await;
+    let job_id = schedule_job(&store_history(&subscription), Utc::now(), &app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(JobStatus::Done, wait_for_attempt(&app, job_id).await.status);
+
+    let (admin_user_id, token) = admin();
+    let response = app
+        .retry_job(admin_user_id, job_id.to_string(), token)
+        .await;
+
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn a_job_whose_last_attempt_never_finished_is_dead_lettered() {
+    let app = spawn_app().await;
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = store_subscription(subscriber.id.to_string(), None, &app).await;
+    let job_id = schedule_job(
+        &store_history(&subscription),
+        Utc::now() + chrono::Duration::hours(1),
+        &app.db_pool,
+    )
+    .await
+    .unwrap();
+    // As if the worker died while making the last attempt.
+    sqlx::query("UPDATE jobs SET status = 'Running', attempts = $1, run_at = now() WHERE id = $2")
+        .bind(MAX_JOB_ATTEMPTS)
+        .bind(job_id)
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    let dead = wait_for_attempt(&app, job_id).await;
+    assert_eq!(JobStatus::Dead, dead.status);
+    assert_eq!(MAX_JOB_ATTEMPTS, dead.attempts);
+    assert!(dead.finished_at.is_some());
+    let events = retrieve_subscription_events_by_subscription_id(subscription.id, &app.db_pool)
+        .await
+        .unwrap();
+    assert!(events.is_empty());
+}
+
+#[tokio::test]
+async fn an_attempt_that_outlived_its_claim_does_not_settle_the_job() {
+    let app = spawn_app().await;
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = store_subscription(subscriber.id.to_string(), None, &app).await;
+    let job_id = schedule_job(
+        &store_history(&subscription),
+        Utc::now() + chrono::Duration::hours(1),
+        &app.db_pool,
+    )
+    .await
+    .unwrap();
+    sqlx::query("UPDATE jobs SET status = 'Running', attempts = 1 WHERE id = $1")
+        .bind(job_id)
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    let claimed = get_job(&app, job_id).await;
+    // Another worker takes the job over once the first claim lapses.
+    sqlx::query("UPDATE jobs SET attempts = attempts + 1 WHERE id = $1")
+        .bind(job_id)
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    mark_job_done(&claimed, &app.db_pool).await.unwrap();
+    mark_job_failed(&claimed, "too late", &app.db_pool)
+        .await
+        .unwrap();
+
+    let job = get_job(&app, job_id).await;
+    assert_eq!(JobStatus::Running, job.status);
+    assert_eq!(2, job.attempts);
+    assert_eq!(None, job.last_error);
+    assert_eq!(None, job.finished_at);
+}
+
+#[tokio::test]
+async fn draining_stops_an_idle_worker_promptly() {
+    let app = spawn_app().await;
+    let configuration = get_configuration().expect("Failed to read configuration.");
+    let worker = start_job_worker(EmailClient::new(configuration.email_client), &app.db_pool);
+
+    let drained = tokio::time::timeout(Duration::from_secs(2), worker.drain()).await;
+
+    assert!(drained.is_ok());
+}
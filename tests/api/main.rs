@@ -1,17 +1,45 @@
+mod admin_cli_tests;
+mod admin_users_tests;
+mod audit_db_test;
+mod audit_tests;
+mod broadcast_db_test;
+mod broadcasts_tests;
 mod checkout_session_db_tests;
 mod checkout_tests;
+mod dev_emails_tests;
+mod email_outbox_tests;
+mod email_preferences_tests;
+mod email_suppressions_tests;
+mod email_templates_tests;
 mod end_to_end_tests;
+mod fulfillment_tests;
 mod health_check;
 mod helper;
+mod invitation_db_test;
+mod invitations_tests;
+mod jobs_tests;
+mod newsletter_tests;
+mod notification_channels_tests;
+mod oidc_db_test;
+mod oidc_tests;
 mod otp_db_test;
 mod payment_tests;
+mod publication_db_test;
+mod publications_tests;
+mod schema_migrations_db_test;
+mod seed_db_test;
+mod session_db_test;
+mod sessions_tests;
+mod staff_digest_tests;
 mod subscriber_db_test;
+mod subscriber_emails_tests;
 mod subscribers_tests;
 mod subscription_db_test;
 mod subscription_event_history_db_tests;
+mod subscription_import_tests;
 mod subscription_notifier_test;
 mod subscriptions_tests;
 mod user_db_test;
 mod users_tests;
-
 mod webhook_db_tests;
+mod webhook_endpoints_tests;
@@ -0,0 +1,181 @@
+use mailtrap_rs::types::response::SendEmailResponse;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use newsletter_signup_service::configuration::get_configuration;
+use newsletter_signup_service::domain::newsletter_models::{
+    ConfirmNewsletterSignup, NewsletterSignup, NEWSLETTER_CONSENT_TEXT,
+};
+
+use crate::helper::{spawn_app, TestApp};
+
+async fn mock_email_provider(app: &TestApp) {
+    Mock::given(path("api/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(SendEmailResponse {
+            success: true,
+            message_ids: vec!["test-id".to_string()],
+            errors: vec![],
+        }))
+        .mount(&app.email_server)
+        .await;
+}
+
+fn signup(email_address: &str) -> NewsletterSignup {
+    NewsletterSignup {
+        name: "Joe Reader".to_string(),
+        email_address: email_address.to_string(),
+        consent_text: NEWSLETTER_CONSENT_TEXT.to_string(),
+    }
+}
+
+fn confirmation_subject() -> String {
+    format!(
+        "Confirm your subscription to {}",
+        get_configuration().unwrap().email_client.sender_name
+    )
+}
+
+/// The token from the newest confirmation email.
+async fn confirmation_token(app: &TestApp) -> String {
+    let emails = app.received_emails(&confirmation_subject()).await;
+    let text = emails.last().unwrap()["text"].as_str().unwrap().to_string();
+    let link = linkify::LinkFinder::new()
+        .links(&text)
+        .find(|l| *l.kind() == linkify::LinkKind::Url)
+        .unwrap();
+    let url = url::Url::parse(link.as_str()).unwrap();
+    url.query_pairs()
+        .find(|(key, _)| key == "token")
+        .unwrap()
+        .1
+        .to_string()
+}
+
+async fn member_status(app: &TestApp, email_address: &str) -> String {
+    sqlx::query!(
+        "SELECT status FROM newsletter_members WHERE email_address = $1",
+        email_address
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap()
+    .status
+}
+
+#[tokio::test]
+async fn a_sign_up_joins_the_list_only_once_confirmed() {
+    let app = spawn_app().await;
+    mock_email_provider(&app).await;
+
+    let response = app
+        .newsletter_subscribe(signup("Reader@Example.com").to_json())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!("Pending", member_status(&app, "reader@example.com").await);
+    let emails = app.received_emails(&confirmation_subject()).await;
+    assert_eq!(1, emails.len());
+    assert_eq!("reader@example.com", emails[0]["to"][0]["email"]);
+
+    let confirm = ConfirmNewsletterSignup {
+        token: confirmation_token(&app).await,
+    };
+    let response = app.newsletter_confirm(confirm.to_json()).await;
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!("Confirmed", body["status"]);
+
+    let member = sqlx::query!(
+        "SELECT consent_text, consent_ip_address, consent_user_agent, confirmed_at
+            FROM newsletter_members WHERE email_address = 'reader@example.com'"
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(NEWSLETTER_CONSENT_TEXT, member.consent_text);
+    assert!(member.consent_ip_address.is_some());
+    assert_eq!(
+        Some("newsletter-test".to_string()),
+        member.consent_user_agent
+    );
+    assert!(member.confirmed_at.is_some());
+
+    // The link works once.
+    let response = app.newsletter_confirm(confirm.to_json()).await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn signing_up_again_sends_a_new_link_until_confirmed() {
+    let app = spawn_app().await;
+    mock_email_provider(&app).await;
+
+    app.newsletter_subscribe(signup("again@example.com").to_json())
+        .await;
+    let first = confirmation_token(&app).await;
+    app.newsletter_subscribe(signup("again@example.com").to_json())
+        .await;
+    let second = confirmation_token(&app).await;
+    assert_ne!(first, second);
+
+    let response = app
+        .newsletter_confirm(ConfirmNewsletterSignup { token: first }.to_json())
+        .await;
+    assert_eq!(400, response.status().as_u16());
+    let response = app
+        .newsletter_confirm(ConfirmNewsletterSignup { token: second }.to_json())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    // A confirmed member is not told apart from a new address, and not emailed.
+    let response = app
+        .newsletter_subscribe(signup("again@example.com").to_json())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(2, app.received_emails(&confirmation_subject()).await.len());
+    assert_eq!("Confirmed", member_status(&app, "again@example.com").await);
+}
+
+#[tokio::test]
+async fn an_expired_link_does_not_confirm() {
+    let app = spawn_app().await;
+    mock_email_provider(&app).await;
+    app.newsletter_subscribe(signup("late@example.com").to_json())
+        .await;
+    let token = confirmation_token(&app).await;
+    sqlx::query!(
+        "UPDATE newsletter_members SET token_expires_at = now() - interval '1 minute'
+            WHERE email_address = 'late@example.com'"
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    let response = app
+        .newsletter_confirm(ConfirmNewsletterSignup { token }.to_json())
+        .await;
+    assert_eq!(400, response.status().as_u16());
+    assert_eq!("Pending", member_status(&app, "late@example.com").await);
+}
+
+#[tokio::test]
+async fn invalid_sign_ups_are_rejected() {
+    let app = spawn_app().await;
+    let mut bad_name = signup("name@example.com");
+    bad_name.name = "".to_string();
+    let bad_email = signup("not-an-email");
+    let mut other_wording = signup("wording@example.com");
+    other_wording.consent_text = "Sure".to_string();
+
+    for invalid in [bad_name, bad_email, other_wording] {
+        let response = app.newsletter_subscribe(invalid.to_json()).await;
+        assert_eq!(400, response.status().as_u16());
+    }
+    assert!(app
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,334 @@
+use std::time::Duration;
+
+use mailtrap_rs::types::response::SendEmailResponse;
+use serde_json::{json, Value};
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use newsletter_signup_service::auth::token::generate_token;
+use newsletter_signup_service::configuration::get_configuration;
+use newsletter_signup_service::domain::notification_channel_models::{
+    NotificationChannel, NotificationChannelKind, StaffEventType,
+};
+use newsletter_signup_service::domain::subscription_models::SubscriptionType;
+use newsletter_signup_service::domain::user_models::UserGroup;
+
+use crate::helper::{
+    generate_over_the_wire_create_subscription, mock_cancel_stripe_subscription, spawn_app,
+    store_subscription, TestApp,
+};
+
+fn admin() -> (String, String) {
+    let admin_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+    (admin_user_id, token)
+}
+
+async fn mock_email_provider(app: &TestApp) {
+    Mock::given(path("api/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(SendEmailResponse {
+            success: true,
+            message_ids: vec!["test-id".to_string()],
+            errors: vec![],
+        }))
+        .mount(&app.email_server)
+        .await;
+}
+
+async fn create_channel(app: &TestApp, body: Value) -> NotificationChannel {
+    let (admin_user_id, token) = admin();
+    let response = app
+        .create_notification_channel(admin_user_id, body.to_string(), token)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    serde_json::from_str(response.text().await.unwrap().as_str()).unwrap()
+}
+
+async fn get_channels(app: &TestApp) -> Vec<NotificationChannel> {
+    let (admin_user_id, token) = admin();
+    let response = app.get_notification_channels(admin_user_id, token).await;
+    assert_eq!(200, response.status().as_u16());
+    serde_json::from_str(response.text().await.unwrap().as_str()).unwrap()
+}
+
+/// Posts happen in the background, so wait for the channel to record one.
+async fn wait_for_post(app: &TestApp, channel_id: Uuid) -> NotificationChannel {
+    for _ in 0..50 {
+        let channel = get_channels(app)
+            .await
+            .into_iter()
+            .find(|channel| channel.id == channel_id)
+            .unwrap();
+        if channel.last_notified_at.is_some() {
+            return channel;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("Channel {} was never posted to", channel_id);
+}
+
+async fn posts_to(server: &MockServer, url_path: &str) -> Vec<Value> {
+    server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .filter(|request| request.url.path() == url_path)
+        .map(|request| serde_json::from_slice(&request.body).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn configured_notification_addresses_become_email_channels() {
+    let app = spawn_app().await;
+
+    let channels = get_channels(&app).await;
+
+    let addresses = get_configuration()
+        .unwrap()
+        .application_feature_settings
+        .subscription_notification_addresses;
+    assert_eq!(addresses.len(), channels.len());
+    for channel in channels {
+        assert_eq!(NotificationChannelKind::Email, channel.kind);
+        assert!(addresses.contains(&channel.target));
+        assert_eq!(StaffEventType::ALL.len(), channel.event_types.len());
+    }
+}
+
+#[tokio::test]
+async fn notification_channels_can_be_changed_and_removed() {
+    let app = spawn_app().await;
+    let (admin_user_id, token) = admin();
+    let channel = create_channel(
+        &app,
+        json!({
+            "name": "Office Slack",
+            "kind": "Slack",
+            "target": "https://hooks.slack.com/services/T0/B0/x",
+            "event_types": ["NewSubscription"],
+        }),
+    )
+    .await;
+    assert!(channel.enabled);
+
+    let response = app
+        .update_notification_channel(
+            admin_user_id.clone(),
+            channel.id.to_string(),
+            json!({
+                "name": "Office Slack",
+                "kind": "Slack",
+                "target": "https://hooks.slack.com/services/T0/B0/x",
+                "event_types": ["NewSubscription", "Cancellation"],
+                "enabled": false,
+            })
+            .to_string(),
+            token.clone(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let updated: NotificationChannel =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert!(!updated.enabled);
+    assert_eq!(2, updated.event_types.len());
+
+    let response = app
+        .delete_notification_channel(admin_user_id.clone(), channel.id.to_string(), token.clone())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let response = app
+        .delete_notification_channel(admin_user_id, channel.id.to_string(), token)
+        .await;
+    assert_eq!(404, response.status().as_u16());
+    assert!(get_channels(&app).await.iter().all(|c| c.id != channel.id));
+}
+
+#[tokio::test]
+async fn invalid_notification_channels_are_rejected() {
+    let app = spawn_app().await;
+    let (admin_user_id, token) = admin();
+
+    for body in [
+        json!({ "name": "Office", "kind": "Email", "target": "https://example.com", "event_types": ["Cancellation"] }),
+        json!({ "name": "Office", "kind": "Webhook", "target": "office@example.com", "event_types": ["Cancellation"] }),
+        json!({ "name": "Office", "kind": "Email", "target": "office@example.com", "event_types": [] }),
+        json!({ "name": "", "kind": "Email", "target": "office@example.com", "event_types": ["Cancellation"] }),
+    ] {
+        let response = app
+            .create_notification_channel(admin_user_id.clone(), body.to_string(), token.clone())
+            .await;
+        assert_eq!(400, response.status().as_u16(), "{}", body);
+    }
+
+    let response = app
+        .update_notification_channel(
+            admin_user_id,
+            "not-a-uuid".to_string(),
+            json!({ "name": "Office", "kind": "Email", "target": "office@example.com", "event_types": ["Cancellation"] })
+                .to_string(),
+            token,
+        )
+        .await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn notification_channels_are_admin_only() {
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4().to_string();
+    let token = generate_token(user_id.clone(), UserGroup::USER);
+
+    let response = app.get_notification_channels(user_id, token).await;
+
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn cancellations_go_to_the_channels_subscribed_to_them() {
+    let app = spawn_app().await;
+    mock_email_provider(&app).await;
+    let chat_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&chat_server)
+        .await;
+    let slack = create_channel(
+        &app,
+        json!({
+            "name": "Slack",
+            "kind": "Slack",
+            "target": format!("{}/slack", chat_server.uri()),
+            "event_types": ["Cancellation"],
+        }),
+    )
+    .await;
+    let webhook = create_channel(
+        &app,
+        json!({
+            "name": "Webhook",
+            "kind": "Webhook",
+            "target": format!("{}/webhook", chat_server.uri()),
+            "event_types": ["NewSubscription", "Cancellation"],
+        }),
+    )
+    .await;
+    create_channel(
+        &app,
+        json!({
+            "name": "Disabled",
+            "kind": "Webhook",
+            "target": format!("{}/disabled", chat_server.uri()),
+            "event_types": ["Cancellation"],
+            "enabled": false,
+        }),
+    )
+    .await;
+    create_channel(
+        &app,
+        json!({
+            "name": "New subscriptions only",
+            "kind": "Webhook",
+            "target": format!("{}/new", chat_server.uri()),
+            "event_types": ["NewSubscription"],
+        }),
+    )
+    .await;
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = store_subscription(subscriber.id.to_string(), None, &app).await;
+    mock_cancel_stripe_subscription(
+        &app.stripe_server,
+        subscription.stripe_subscription_id.clone(),
+    )
+    .await;
+
+    let response = app
+        .cancel_subscription_by_id(
+            subscription.id.to_string(),
+            generate_token(subscriber.user_id.clone(), UserGroup::USER),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    assert_eq!(None, wait_for_post(&app, slack.id).await.last_error);
+    assert_eq!(None, wait_for_post(&app, webhook.id).await.last_error);
+    let slack_posts = posts_to(&chat_server, "/slack").await;
+    assert_eq!(1, slack_posts.len());
+    let text = slack_posts[0]["text"].as_str().unwrap();
+    assert!(text.starts_with("Subscription Cancelled"));
+    assert!(text.contains(&subscription.id.to_string()));
+    let webhook_posts = posts_to(&chat_server, "/webhook").await;
+    assert_eq!(1, webhook_posts.len());
+    assert_eq!("Cancellation", webhook_posts[0]["event_type"]);
+    assert_eq!(
+        subscription.id.to_string(),
+        webhook_posts[0]["subscriptions"][0]["id"]
+    );
+    assert_eq!(false, webhook_posts[0]["subscriptions"][0]["active"]);
+    assert!(posts_to(&chat_server, "/disabled").await.is_empty());
+    assert!(posts_to(&chat_server, "/new").await.is_empty());
+    // The configured addresses share one email.
+    let received = app.received_emails("Subscription Cancelled").await;
+    assert_eq!(1, received.len());
+    assert_eq!(2, received[0]["to"].as_array().unwrap().len());
+}
+
+#[tokio::test]
+async fn failed_posts_are_recorded_on_the_channel() {
+    let app = spawn_app().await;
+    mock_email_provider(&app).await;
+    let chat_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&chat_server)
+        .await;
+    let webhook = create_channel(
+        &app,
+        json!({
+            "name": "Broken",
+            "kind": "Webhook",
+            "target": format!("{}/broken", chat_server.uri()),
+            "event_types": ["AddressChange"],
+        }),
+    )
+    .await;
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = store_subscription(
+        subscriber.id.to_string(),
+        Some(generate_over_the_wire_create_subscription(
+            subscriber.id.to_string(),
+            Some(SubscriptionType::Paper),
+        )),
+        &app,
+    )
+    .await;
+    let mut moved = subscription.clone();
+    moved.subscription_city = "Independence".to_string();
+
+    let response = app
+        .update_subscription_by_id(
+            subscription.id.to_string(),
+            moved.to_json(),
+            generate_token(subscriber.user_id.clone(), UserGroup::USER),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let channel = wait_for_post(&app, webhook.id).await;
+    assert!(channel.last_error.unwrap().contains("500"));
+    let posts = posts_to(&chat_server, "/broken").await;
+    assert_eq!(1, posts.len());
+    assert_eq!("AddressChange", posts[0]["event_type"]);
+    assert_eq!(
+        "Independence",
+        posts[0]["subscriptions"][0]["subscription_city"]
+    );
+    assert_eq!(
+        1,
+        app.received_emails("Subscription Address Changed")
+            .await
+            .len()
+    );
+}
@@ -0,0 +1,89 @@
+use chrono::{Duration, Utc};
+use claims::{assert_err, assert_ok};
+use uuid::Uuid;
+
+use newsletter_signup_service::db::oidc_db_broker::{
+    consume_oidc_login_state, get_user_identity_by_provider_and_subject, insert_oidc_login_state,
+    insert_user_identity,
+};
+use newsletter_signup_service::domain::oidc_models::OidcLoginState;
+
+use crate::helper::spawn_app;
+
+fn generate_login_state(expires_on: chrono::DateTime<Utc>) -> OidcLoginState {
+    OidcLoginState {
+        state: Uuid::new_v4().to_string(),
+        code_verifier: Uuid::new_v4().to_string(),
+        nonce: Uuid::new_v4().to_string(),
+        issued_on: Utc::now(),
+        expires_on,
+        used: false,
+    }
+}
+
+#[tokio::test]
+async fn insert_and_consume_oidc_login_state_works() {
+    let app = spawn_app().await;
+    let login_state = generate_login_state(Utc::now() + Duration::minutes(10));
+
+    assert_ok!(insert_oidc_login_state(&login_state, &app.db_pool).await);
+
+    let consumed = consume_oidc_login_state(&login_state.state, &app.db_pool).await;
+    assert_ok!(&consumed);
+    let consumed = consumed.unwrap();
+    assert_eq!(login_state.code_verifier, consumed.code_verifier);
+    assert!(consumed.used);
+
+    assert_err!(consume_oidc_login_state(&login_state.state, &app.db_pool).await);
+}
+
+#[tokio::test]
+async fn an_expired_oidc_login_state_cannot_be_consumed() {
+    let app = spawn_app().await;
+    let login_state = generate_login_state(Utc::now() - Duration::minutes(1));
+
+    assert_ok!(insert_oidc_login_state(&login_state, &app.db_pool).await);
+    assert_err!(consume_oidc_login_state(&login_state.state, &app.db_pool).await);
+}
+
+#[tokio::test]
+async fn insert_user_identity_works() {
+    let app = spawn_app().await;
+    let login = app.sign_up().await;
+    let user_id = Uuid::parse_str(&login.user_id).unwrap();
+    let subject = Uuid::new_v4().to_string();
+
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    assert_ok!(
+        insert_user_identity(
+            user_id,
+            "test",
+            &subject,
+            "someone@gmail.com",
+            &mut transaction
+        )
+        .await
+    );
+    assert_ok!(transaction.commit().await);
+
+    let identity = get_user_identity_by_provider_and_subject("test", &subject, &app.db_pool).await;
+    assert_ok!(&identity);
+    assert_eq!(user_id, identity.unwrap().user_id);
+
+    assert_err!(
+        get_user_identity_by_provider_and_subject("another-provider", &subject, &app.db_pool).await
+    );
+}
+
+#[tokio::test]
+async fn insert_oidc_login_state_failed() {
+    let app = spawn_app().await;
+
+    sqlx::query!("DROP TABLE oidc_login_states")
+        .execute(&app.db_pool)
+        .await
+        .expect("Failed to drop.");
+
+    let login_state = generate_login_state(Utc::now() + Duration::minutes(10));
+    assert_err!(insert_oidc_login_state(&login_state, &app.db_pool).await);
+}
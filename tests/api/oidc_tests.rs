@@ -0,0 +1,270 @@
+use uuid::Uuid;
+
+use newsletter_signup_service::auth::token::LoginResponse;
+use newsletter_signup_service::db::oidc_db_broker::get_user_identity_by_provider_and_subject;
+use newsletter_signup_service::db::subscribers_db_broker::retrieve_subscriber_by_user_id;
+use newsletter_signup_service::db::users::{get_user_by_user_id, insert_user};
+use newsletter_signup_service::domain::oidc_models::OidcLoginRedirect;
+use newsletter_signup_service::domain::user_models::UserGroup;
+
+use crate::helper::{
+    generate_oidc_id_token, mock_oidc_discovery, mock_oidc_signing_keys, mock_oidc_token_exchange,
+    spawn_app, TestApp,
+};
+
+/// Starts a login and hands back the `state` and `nonce` the server put in the redirect.
+async fn start_login(app: &TestApp) -> (String, String) {
+    let response = app.oidc_login().await;
+    assert_eq!(200, response.status().as_u16());
+    let redirect: OidcLoginRedirect = response.json().await.unwrap();
+
+    let location = url::Url::parse(&redirect.location).unwrap();
+    let param = |name: &str| {
+        location
+            .query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.to_string())
+            .unwrap()
+    };
+    (param("state"), param("nonce"))
+}
+
+#[tokio::test]
+async fn oidc_login_returns_an_authorization_url_with_pkce() {
+    let app = spawn_app().await;
+    mock_oidc_discovery(&app.oidc_server).await;
+
+    let response = app.oidc_login().await;
+    assert_eq!(200, response.status().as_u16());
+
+    let redirect: OidcLoginRedirect = response.json().await.unwrap();
+    assert!(redirect
+        .location
+        .starts_with(&format!("{}/authorize?", app.oidc_server.uri())));
+    assert!(redirect.location.contains("code_challenge="));
+    assert!(redirect.location.contains("code_challenge_method=S256"));
+}
+
+#[tokio::test]
+async fn oidc_login_returns_500_when_the_provider_is_down() {
+    let app = spawn_app().await;
+
+    let response = app.oidc_login().await;
+    assert_eq!(500, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn oidc_callback_creates_a_user_and_subscriber() {
+    let app = spawn_app().await;
+    mock_oidc_discovery(&app.oidc_server).await;
+    mock_oidc_signing_keys(&app.oidc_server).await;
+
+    let (state, nonce) = start_login(&app).await;
+    let subject = Uuid::new_v4().to_string();
+    let email = format!("{}@gmail.com", Uuid::new_v4());
+    mock_oidc_token_exchange(
+        &app.oidc_server,
+        generate_oidc_id_token(&app.oidc_server.uri(), &subject, &email, true, &nonce),
+    )
+    .await;
+
+    let response = app.oidc_callback(Uuid::new_v4().to_string(), state).await;
+    assert_eq!(200, response.status().as_u16());
+    let login: LoginResponse = response.json().await.unwrap();
+    assert_eq!(UserGroup::USER, login.group);
+
+    let identity = get_user_identity_by_provider_and_subject("test", &subject, &app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(login.user_id, identity.user_id.to_string());
+
+    let subscriber = retrieve_subscriber_by_user_id(&login.user_id, &app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(email, subscriber.email_address);
+}
+
+#[tokio::test]
+async fn oidc_callback_logs_the_same_subject_into_the_same_user() {
+    let app = spawn_app().await;
+    let subject = Uuid::new_v4().to_string();
+    let email = format!("{}@gmail.com", Uuid::new_v4());
+
+    let mut user_ids = Vec::new();
+    for _ in 0..2 {
+        app.oidc_server.reset().await;
+        mock_oidc_discovery(&app.oidc_server).await;
+        mock_oidc_signing_keys(&app.oidc_server).await;
+
+        let (state, nonce) = start_login(&app).await;
+        mock_oidc_token_exchange(
+            &app.oidc_server,
+            generate_oidc_id_token(&app.oidc_server.uri(), &subject, &email, true, &nonce),
+        )
+        .await;
+
+        let response = app.oidc_callback(Uuid::new_v4().to_string(), state).await;
+        assert_eq!(200, response.status().as_u16());
+        let login: LoginResponse = response.json().await.unwrap();
+        user_ids.push(login.user_id);
+    }
+
+    assert_eq!(user_ids[0], user_ids[1]);
+}
+
+#[tokio::test]
+async fn oidc_callback_links_an_existing_account_with_the_same_verified_email() {
+    let app = spawn_app().await;
+    mock_oidc_discovery(&app.oidc_server).await;
+    mock_oidc_signing_keys(&app.oidc_server).await;
+    let existing = app.sign_up().await;
+    let subscriber = retrieve_subscriber_by_user_id(&existing.user_id, &app.db_pool)
+        .await
+        .unwrap();
+
+    let (state, nonce) = start_login(&app).await;
+    mock_oidc_token_exchange(
+        &app.oidc_server,
+        generate_oidc_id_token(
+            &app.oidc_server.uri(),
+            &Uuid::new_v4().to_string(),
+            &subscriber.email_address.to_uppercase(),
+            true,
+            &nonce,
+        ),
+    )
+    .await;
+
+    let response = app.oidc_callback(Uuid::new_v4().to_string(), state).await;
+    assert_eq!(200, response.status().as_u16());
+    let login: LoginResponse = response.json().await.unwrap();
+    assert_eq!(existing.user_id, login.user_id);
+}
+
+#[tokio::test]
+async fn oidc_callback_does_not_link_an_admin_account() {
+    let app = spawn_app().await;
+    mock_oidc_discovery(&app.oidc_server).await;
+    mock_oidc_signing_keys(&app.oidc_server).await;
+    let email_address = format!("{}@gmail.com", Uuid::new_v4());
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    let admin_user_id = insert_user(
+        &email_address,
+        &Uuid::new_v4().to_string(),
+        UserGroup::ADMIN,
+        &mut transaction,
+    )
+    .await
+    .unwrap();
+    transaction.commit().await.unwrap();
+
+    let subject = Uuid::new_v4().to_string();
+    let (state, nonce) = start_login(&app).await;
+    mock_oidc_token_exchange(
+        &app.oidc_server,
+        generate_oidc_id_token(
+            &app.oidc_server.uri(),
+            &subject,
+            &email_address,
+            true,
+            &nonce,
+        ),
+    )
+    .await;
+
+    let response = app.oidc_callback(Uuid::new_v4().to_string(), state).await;
+    assert_eq!(409, response.status().as_u16());
+    assert!(
+        get_user_identity_by_provider_and_subject("test", &subject, &app.db_pool)
+            .await
+            .is_err()
+    );
+    let admin = get_user_by_user_id(&admin_user_id, &app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(UserGroup::ADMIN, admin.user_group);
+}
+
+#[tokio::test]
+async fn oidc_callback_rejects_an_unverified_email() {
+    let app = spawn_app().await;
+    mock_oidc_discovery(&app.oidc_server).await;
+    mock_oidc_signing_keys(&app.oidc_server).await;
+
+    let (state, nonce) = start_login(&app).await;
+    mock_oidc_token_exchange(
+        &app.oidc_server,
+        generate_oidc_id_token(
+            &app.oidc_server.uri(),
+            &Uuid::new_v4().to_string(),
+            &format!("{}@gmail.com", Uuid::new_v4()),
+            false,
+            &nonce,
+        ),
+    )
+    .await;
+
+    let response = app.oidc_callback(Uuid::new_v4().to_string(), state).await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn oidc_callback_rejects_a_nonce_from_another_login() {
+    let app = spawn_app().await;
+    mock_oidc_discovery(&app.oidc_server).await;
+    mock_oidc_signing_keys(&app.oidc_server).await;
+
+    let (state, _) = start_login(&app).await;
+    mock_oidc_token_exchange(
+        &app.oidc_server,
+        generate_oidc_id_token(
+            &app.oidc_server.uri(),
+            &Uuid::new_v4().to_string(),
+            &format!("{}@gmail.com", Uuid::new_v4()),
+            true,
+            "not-the-nonce",
+        ),
+    )
+    .await;
+
+    let response = app.oidc_callback(Uuid::new_v4().to_string(), state).await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn oidc_callback_state_can_only_be_used_once() {
+    let app = spawn_app().await;
+    mock_oidc_discovery(&app.oidc_server).await;
+    mock_oidc_signing_keys(&app.oidc_server).await;
+
+    let (state, nonce) = start_login(&app).await;
+    mock_oidc_token_exchange(
+        &app.oidc_server,
+        generate_oidc_id_token(
+            &app.oidc_server.uri(),
+            &Uuid::new_v4().to_string(),
+            &format!("{}@gmail.com", Uuid::new_v4()),
+            true,
+            &nonce,
+        ),
+    )
+    .await;
+
+    let first = app
+        .oidc_callback(Uuid::new_v4().to_string(), state.clone())
+        .await;
+    assert_eq!(200, first.status().as_u16());
+
+    let second = app.oidc_callback(Uuid::new_v4().to_string(), state).await;
+    assert_eq!(400, second.status().as_u16());
+}
+
+#[tokio::test]
+async fn oidc_callback_with_an_unknown_state_gives_a_400() {
+    let app = spawn_app().await;
+
+    let response = app
+        .oidc_callback(Uuid::new_v4().to_string(), Uuid::new_v4().to_string())
+        .await;
+    assert_eq!(400, response.status().as_u16());
+}
@@ -7,7 +7,13 @@ use newsletter_signup_service::db::otp_db_broker::{
 };
 use newsletter_signup_service::domain::otp_models::OneTimePasscode;
 
-use crate::helper::spawn_app;
+use crate::helper::{spawn_app, TestApp};
+
+async fn store_otp(otp: OneTimePasscode, app: &TestApp) -> Result<(), sqlx::Error> {
+    let mut transaction = app.db_pool.begin().await?;
+    insert_otp(otp, &mut transaction).await?;
+    transaction.commit().await
+}
 
 #[tokio::test]
 async fn insert_otp_works() {
@@ -22,7 +28,7 @@ async fn insert_otp_works() {
         used: false,
     };
 
-    let result = insert_otp(otp, &app.db_pool).await;
+    let result = store_otp(otp, &app).await;
     assert_ok!(result);
 }
 
@@ -39,8 +45,8 @@ async fn insert_duplicate_otp_blows_up() {
         used: false,
     };
 
-    let _discard = insert_otp(otp.clone(), &app.db_pool).await;
-    let result = insert_otp(otp.clone(), &app.db_pool).await;
+    let _discard = store_otp(otp.clone(), &app).await;
+    let result = store_otp(otp.clone(), &app).await;
     assert_err!(result);
 }
 
@@ -57,7 +63,7 @@ async fn get_otp_by_otp_works() {
         used: false,
     };
 
-    let result = insert_otp(otp.clone(), &app.db_pool).await;
+    let result = store_otp(otp.clone(), &app).await;
     assert_ok!(result);
 
     let saved = get_otp_by_otp(otp.one_time_passcode.as_str(), &app.db_pool).await;
@@ -86,7 +92,7 @@ async fn set_to_used_by_otp_works() {
         used: false,
     };
 
-    let result = insert_otp(otp.clone(), &app.db_pool).await;
+    let result = store_otp(otp.clone(), &app).await;
     assert_ok!(result);
 
     let saved = get_otp_by_otp(otp.clone().one_time_passcode.as_str(), &app.db_pool).await;
@@ -102,5 +108,5 @@ async fn set_to_used_by_otp_works() {
     let used_result = get_otp_by_otp(otp.clone().one_time_passcode.as_str(), &app.db_pool).await;
     assert_ok!(&used_result);
 
-    assert_eq!(true, used_result.unwrap().used);
+    assert!(used_result.unwrap().used);
 }
@@ -8,7 +8,7 @@ use newsletter_signup_service::domain::subscription_models::{
 };
 use newsletter_signup_service::domain::user_models::UserGroup;
 
-use crate::helper::spawn_app;
+use crate::helper::{generate_over_the_wire_create_subscription, spawn_app};
 
 #[tokio::test]
 async fn checkout_returns_a_400_when_fields_are_present_but_empty() {
@@ -28,6 +28,7 @@ async fn checkout_returns_a_400_when_fields_are_present_but_empty() {
                     subscription_postal_code: Uuid::new_v4().to_string(),
                     subscription_mailing_address_line_2: Option::from(Uuid::new_v4().to_string()),
                     subscription_mailing_address_line_1: Uuid::new_v4().to_string(),
+                    publication_id: None,
                 },
             },
             "empty name",
@@ -45,6 +46,7 @@ async fn checkout_returns_a_400_when_fields_are_present_but_empty() {
                     subscription_postal_code: Uuid::new_v4().to_string(),
                     subscription_mailing_address_line_2: Option::from(Uuid::new_v4().to_string()),
                     subscription_mailing_address_line_1: Uuid::new_v4().to_string(),
+                    publication_id: None,
                 },
             },
             "empty email",
@@ -62,10 +64,21 @@ async fn checkout_returns_a_400_when_fields_are_present_but_empty() {
                     subscription_postal_code: Uuid::new_v4().to_string(),
                     subscription_mailing_address_line_2: Option::from(Uuid::new_v4().to_string()),
                     subscription_mailing_address_line_1: Uuid::new_v4().to_string(),
+                    publication_id: None,
                 },
             },
             "invalid email",
         ),
+        (
+            CreateCheckoutSession {
+                price_lookup_key: Uuid::new_v4().to_string(),
+                subscription: OverTheWireCreateSubscription {
+                    publication_id: Some(Uuid::new_v4()),
+                    ..generate_over_the_wire_create_subscription(subscriber.id.to_string(), None)
+                },
+            },
+            "unknown publication",
+        ),
     ];
     for (body, description) in test_cases {
         // Act
@@ -0,0 +1,83 @@
+use chrono::{NaiveDate, Utc};
+use claims::{assert_err, assert_ok};
+use uuid::Uuid;
+
+use newsletter_signup_service::db::publications_db_broker::{
+    delete_publication, get_issue, insert_issue, insert_publication,
+};
+use newsletter_signup_service::db::subscriptions_db_broker::{
+    retrieve_subscription_by_subscription_id, set_subscription_publication,
+};
+use newsletter_signup_service::domain::publication_models::{Issue, Publication};
+
+use crate::helper::{spawn_app, store_subscription};
+
+fn generate_publication() -> Publication {
+    Publication {
+        id: Uuid::new_v4(),
+        title: "The Weekly".to_string(),
+        description: None,
+        created_at: Utc::now(),
+    }
+}
+
+fn generate_issue(publication_id: Uuid) -> Issue {
+    Issue {
+        id: Uuid::new_v4(),
+        publication_id,
+        issue_number: 1,
+        title: None,
+        publish_date: NaiveDate::from_ymd_opt(2026, 11, 1).unwrap(),
+        cutoff_date: NaiveDate::from_ymd_opt(2026, 10, 25).unwrap(),
+        digital_asset_reference: Some("s3://issues/1.pdf".to_string()),
+        created_at: Utc::now(),
+    }
+}
+
+#[tokio::test]
+async fn deleting_a_publication_deletes_its_issues() {
+    let app = spawn_app().await;
+    let publication = generate_publication();
+    let issue = generate_issue(publication.id);
+    assert_ok!(insert_publication(&publication, &app.db_pool).await);
+    assert_ok!(insert_issue(&issue, &app.db_pool).await);
+    assert_eq!(
+        issue.digital_asset_reference,
+        get_issue(issue.id, &app.db_pool)
+            .await
+            .unwrap()
+            .digital_asset_reference
+    );
+
+    assert!(delete_publication(publication.id, &app.db_pool)
+        .await
+        .unwrap());
+
+    assert_err!(get_issue(issue.id, &app.db_pool).await);
+}
+
+#[tokio::test]
+async fn a_subscription_can_be_linked_to_a_publication() {
+    let app = spawn_app().await;
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = store_subscription(subscriber.id.to_string(), None, &app).await;
+    let publication = generate_publication();
+    assert_ok!(insert_publication(&publication, &app.db_pool).await);
+
+    assert!(
+        set_subscription_publication(subscription.id, Some(publication.id), &app.db_pool)
+            .await
+            .unwrap()
+    );
+    assert!(
+        !set_subscription_publication(Uuid::new_v4(), Some(publication.id), &app.db_pool)
+            .await
+            .unwrap()
+    );
+
+    let stored = retrieve_subscription_by_subscription_id(subscription.id, &app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(Some(publication.id), stored.publication_id);
+    assert_err!(delete_publication(publication.id, &app.db_pool).await);
+}
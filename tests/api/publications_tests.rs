@@ -0,0 +1,336 @@
+use chrono::{Duration, Utc};
+use serde_json::json;
+use uuid::Uuid;
+
+use newsletter_signup_service::auth::token::generate_token;
+use newsletter_signup_service::domain::publication_models::{
+    Issue, Publication, SubscriptionIssues,
+};
+use newsletter_signup_service::domain::user_models::UserGroup;
+
+use crate::helper::{spawn_app, store_subscription, TestApp};
+
+fn admin() -> (String, String) {
+    let admin_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+    (admin_user_id, token)
+}
+
+async fn create_publication(app: &TestApp, title: &str) -> Publication {
+    let (admin_user_id, token) = admin();
+    let response = app
+        .create_publication(admin_user_id, json!({ "title": title }).to_string(), token)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    serde_json::from_str(response.text().await.unwrap().as_str()).unwrap()
+}
+
+async fn create_issue(app: &TestApp, publication_id: Uuid, body: serde_json::Value) -> Issue {
+    let (admin_user_id, token) = admin();
+    let response = app
+        .create_issue(
+            admin_user_id,
+            publication_id.to_string(),
+            body.to_string(),
+            token,
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    serde_json::from_str(response.text().await.unwrap().as_str()).unwrap()
+}
+
+#[tokio::test]
+async fn publications_can_be_created_listed_updated_and_deleted() {
+    let app = spawn_app().await;
+    let (admin_user_id, token) = admin();
+    let publication = create_publication(&app, "The Weekly").await;
+
+    let response = app
+        .update_publication(
+            admin_user_id.clone(),
+            publication.id.to_string(),
+            json!({ "title": "The Monthly", "description": "Now monthly" }).to_string(),
+            token.clone(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = app
+        .get_publications(admin_user_id.clone(), token.clone())
+        .await;
+    let publications: Vec<Publication> =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!(1, publications.len());
+    assert_eq!("The Monthly", publications[0].title);
+    assert_eq!(Some("Now monthly".to_string()), publications[0].description);
+
+    let response = app
+        .delete_publication(
+            admin_user_id.clone(),
+            publication.id.to_string(),
+            token.clone(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let response = app
+        .delete_publication(admin_user_id, publication.id.to_string(), token)
+        .await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn a_blank_title_is_rejected() {
+    let app = spawn_app().await;
+    let (admin_user_id, token) = admin();
+
+    let response = app
+        .create_publication(admin_user_id, json!({ "title": "  " }).to_string(), token)
+        .await;
+
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn issues_are_numbered_once_per_publication() {
+    let app = spawn_app().await;
+    let (admin_user_id, token) = admin();
+    let publication = create_publication(&app, "The Weekly").await;
+    let issue = create_issue(
+        &app,
+        publication.id,
+        json!({
+            "issue_number": 1,
+            "publish_date": "2026-11-01",
+            "cutoff_date": "2026-10-25",
+            "digital_asset_reference": "s3://issues/1.pdf",
+        }),
+    )
+    .await;
+    assert_eq!("2026-10-25", issue.cutoff_date.to_string());
+
+    let duplicate = json!({ "issue_number": 1, "publish_date": "2026-12-01" }).to_string();
+    let response = app
+        .create_issue(
+            admin_user_id.clone(),
+            publication.id.to_string(),
+            duplicate,
+            token.clone(),
+        )
+        .await;
+    assert_eq!(409, response.status().as_u16());
+
+    let late_cutoff = json!({
+        "issue_number": 2,
+        "publish_date": "2026-12-01",
+        "cutoff_date": "2026-12-02",
+    });
+    let response = app
+        .create_issue(
+            admin_user_id.clone(),
+            publication.id.to_string(),
+            late_cutoff.to_string(),
+            token.clone(),
+        )
+        .await;
+    assert_eq!(400, response.status().as_u16());
+
+    let response = app
+        .create_issue(
+            admin_user_id.clone(),
+            Uuid::new_v4().to_string(),
+            json!({ "issue_number": 1, "publish_date": "2026-12-01" }).to_string(),
+            token.clone(),
+        )
+        .await;
+    assert_eq!(404, response.status().as_u16());
+
+    let response = app
+        .update_issue(
+            admin_user_id.clone(),
+            issue.id.to_string(),
+            json!({ "issue_number": 1, "title": "Winter", "publish_date": "2026-11-02" })
+                .to_string(),
+            token.clone(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let response = app
+        .get_issues(
+            admin_user_id.clone(),
+            publication.id.to_string(),
+            token.clone(),
+        )
+        .await;
+    let issues: Vec<Issue> = serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!(1, issues.len());
+    assert_eq!(Some("Winter".to_string()), issues[0].title);
+    assert_eq!(issues[0].publish_date, issues[0].cutoff_date);
+    assert_eq!(None, issues[0].digital_asset_reference);
+
+    let response = app
+        .delete_issue(admin_user_id, issue.id.to_string(), token)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn a_subscription_lists_received_and_remaining_issues() {
+    let app = spawn_app().await;
+    let (admin_user_id, token) = admin();
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = store_subscription(subscriber.id.to_string(), None, &app).await;
+    sqlx::query!(
+        "UPDATE subscriptions SET subscription_creation_date = $1 WHERE id = $2",
+        Utc::now() - Duration::days(60),
+        subscription.id
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+    let publication = create_publication(&app, "The Weekly").await;
+    let today = Utc::now().date_naive();
+    for (issue_number, publish_date) in [
+        (1, today - Duration::days(90)),
+        (2, today - Duration::days(30)),
+        (3, today + Duration::days(30)),
+        (4, today + Duration::days(400)),
+    ] {
+        create_issue(
+            &app,
+            publication.id,
+            json!({ "issue_number": issue_number, "publish_date": publish_date }),
+        )
+        .await;
+    }
+
+    let response = app
+        .set_subscription_publication(
+            admin_user_id.clone(),
+            subscription.id.to_string(),
+            json!({ "publication_id": publication.id }).to_string(),
+            token.clone(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = app
+        .get_subscription_issues(
+            subscription.id.to_string(),
+            generate_token(subscriber.user_id.clone(), UserGroup::USER),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let issues: SubscriptionIssues =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!(Some(publication.id), issues.publication.map(|p| p.id));
+    assert_eq!(
+        vec![2],
+        issues
+            .received
+            .iter()
+            .map(|i| i.issue_number)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(1, issues.remaining_count);
+    assert_eq!(3, issues.upcoming[0].issue_number);
+
+    // Admins read other people's subscriptions through the admin route only.
+    let response = app
+        .get_subscription_issues(subscription.id.to_string(), token.clone())
+        .await;
+    assert_eq!(401, response.status().as_u16());
+    let response = app
+        .get_subscription_issues_admin(
+            admin_user_id.clone(),
+            subscription.id.to_string(),
+            token.clone(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let issues: SubscriptionIssues =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!(Some(publication.id), issues.publication.map(|p| p.id));
+    let response = app
+        .get_subscription_issues_admin(
+            admin_user_id.clone(),
+            Uuid::new_v4().to_string(),
+            token.clone(),
+        )
+        .await;
+    assert_eq!(404, response.status().as_u16());
+    let response = app
+        .get_subscription_issues_admin(
+            subscriber.user_id.clone(),
+            subscription.id.to_string(),
+            generate_token(subscriber.user_id.clone(), UserGroup::USER),
+        )
+        .await;
+    assert_eq!(401, response.status().as_u16());
+    let response = app
+        .get_subscription_issues(
+            subscription.id.to_string(),
+            generate_token(Uuid::new_v4().to_string(), UserGroup::USER),
+        )
+        .await;
+    assert_eq!(401, response.status().as_u16());
+
+    // A publication cannot be deleted while subscriptions link to it.
+    let response = app
+        .delete_publication(
+            admin_user_id.clone(),
+            publication.id.to_string(),
+            token.clone(),
+        )
+        .await;
+    assert_eq!(409, response.status().as_u16());
+    let response = app
+        .set_subscription_publication(
+            admin_user_id.clone(),
+            subscription.id.to_string(),
+            json!({ "publication_id": null }).to_string(),
+            token.clone(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let response = app
+        .delete_publication(admin_user_id, publication.id.to_string(), token)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn linking_to_an_unknown_publication_is_a_404() {
+    let app = spawn_app().await;
+    let (admin_user_id, token) = admin();
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = store_subscription(subscriber.id.to_string(), None, &app).await;
+
+    let response = app
+        .set_subscription_publication(
+            admin_user_id,
+            subscription.id.to_string(),
+            json!({ "publication_id": Uuid::new_v4() }).to_string(),
+            token,
+        )
+        .await;
+
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn publications_require_an_admin() {
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4().to_string();
+    let token = generate_token(user_id.clone(), UserGroup::USER);
+
+    let response = app
+        .create_publication(
+            user_id.clone(),
+            json!({ "title": "The Weekly" }).to_string(),
+            token.clone(),
+        )
+        .await;
+    assert_eq!(401, response.status().as_u16());
+    let response = app.get_publications(user_id, token).await;
+    assert_eq!(401, response.status().as_u16());
+}
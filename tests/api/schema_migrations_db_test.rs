@@ -0,0 +1,94 @@
+use claims::{assert_err, assert_ok};
+
+use newsletter_signup_service::configuration::get_configuration;
+use newsletter_signup_service::db::schema_migrations::{
+    ensure_expected_schema_version, get_migration_status, MigrationState, MIGRATOR,
+};
+use newsletter_signup_service::startup::Application;
+
+use crate::helper::{spawn_app, TestApp};
+
+const UNKNOWN_VERSION: i64 = 99990101000000;
+
+async fn record_unknown_migration(app: &TestApp) {
+    sqlx::query(
+        r#"INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time)
+            VALUES ($1, 'from a newer build', TRUE, '\x00', 0)"#,
+    )
+    .bind(UNKNOWN_VERSION)
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn a_fully_migrated_database_has_the_expected_schema_version() {
+    let app = spawn_app().await;
+
+    let statuses = get_migration_status(&app.db_pool).await.unwrap();
+    assert_eq!(MIGRATOR.iter().count(), statuses.len());
+    assert!(statuses
+        .iter()
+        .all(|status| status.state == MigrationState::Applied));
+    assert_ok!(ensure_expected_schema_version(&app.db_pool).await);
+}
+
+#[tokio::test]
+async fn pending_and_unknown_migrations_are_reported() {
+    let app = spawn_app().await;
+    let latest = MIGRATOR.iter().last().unwrap().version;
+    sqlx::query("DELETE FROM _sqlx_migrations WHERE version = $1")
+        .bind(latest)
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    record_unknown_migration(&app).await;
+
+    let statuses = get_migration_status(&app.db_pool).await.unwrap();
+    let state_of = |version| {
+        statuses
+            .iter()
+            .find(|status| status.version == version)
+            .unwrap()
+            .state
+    };
+    assert_eq!(MigrationState::Pending, state_of(latest));
+    assert_eq!(MigrationState::Unknown, state_of(UNKNOWN_VERSION));
+    assert_err!(ensure_expected_schema_version(&app.db_pool).await);
+}
+
+#[tokio::test]
+async fn the_server_refuses_to_start_against_an_unexpected_schema() {
+    let app = spawn_app().await;
+    record_unknown_migration(&app).await;
+
+    let mut configuration = get_configuration().unwrap();
+    configuration.database.database_name = app
+        .db_pool
+        .connect_options()
+        .get_database()
+        .unwrap()
+        .to_string();
+    configuration.application.port = 0;
+    configuration.database.auto_migrate = false;
+
+    assert!(Application::build(configuration.clone()).await.is_err());
+
+    // Auto-migrate cannot fix a database that is ahead of the code either.
+    configuration.database.auto_migrate = true;
+    assert!(Application::build(configuration).await.is_err());
+}
+
+#[tokio::test]
+async fn get_migration_status_treats_a_missing_table_as_nothing_applied() {
+    let app = spawn_app().await;
+    sqlx::query("DROP TABLE _sqlx_migrations")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    let statuses = get_migration_status(&app.db_pool).await.unwrap();
+    assert!(statuses
+        .iter()
+        .all(|status| status.state == MigrationState::Pending));
+}
@@ -0,0 +1,34 @@
+use claims::assert_ok;
+
+use newsletter_signup_service::db::seed_db_broker::store_seed_data;
+use newsletter_signup_service::db::subscription_history_db_broker::retrieve_subscription_events_by_subscription_id;
+use newsletter_signup_service::db::subscriptions_db_broker::retrieve_all_subscriptions;
+use newsletter_signup_service::db::users::get_all_users;
+use newsletter_signup_service::seed::{generate_seed_data, SeedOptions};
+
+use crate::helper::spawn_app;
+
+#[tokio::test]
+async fn store_seed_data_works_and_can_be_repeated() {
+    let app = spawn_app().await;
+    let data = generate_seed_data(SeedOptions {
+        seed: 11,
+        users: 30,
+    });
+    let users_before = get_all_users(&app.db_pool).await.unwrap().len();
+
+    assert_ok!(store_seed_data(&data, "not-a-real-hash", &app.db_pool).await);
+    assert_ok!(store_seed_data(&data, "not-a-real-hash", &app.db_pool).await);
+
+    assert_eq!(
+        users_before + 30,
+        get_all_users(&app.db_pool).await.unwrap().len()
+    );
+    let subscriptions = retrieve_all_subscriptions(&app.db_pool).await.unwrap();
+    assert_eq!(data.subscriptions.len(), subscriptions.len());
+    let events =
+        retrieve_subscription_events_by_subscription_id(data.subscriptions[0].id, &app.db_pool)
+            .await
+            .unwrap();
+    assert!(!events.is_empty());
+}
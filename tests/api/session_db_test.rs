@@ -0,0 +1,138 @@
+use chrono::{Duration, Utc};
+use claims::{assert_err, assert_ok};
+use uuid::Uuid;
+
+use newsletter_signup_service::db::session_db_broker::{
+    get_active_sessions_by_user_id, get_login_familiarity, insert_login_attempt,
+    insert_user_session, is_session_active, revoke_user_session,
+};
+use newsletter_signup_service::domain::session_models::{
+    LoginAttempt, LoginFailureReason, LoginMethod, UserSession,
+};
+
+use crate::helper::spawn_app;
+
+fn generate_session(user_id: Uuid, expires_in: Duration) -> UserSession {
+    UserSession {
+        id: Uuid::new_v4(),
+        user_id,
+        login_method: LoginMethod::Password,
+        ip_address: Some("127.0.0.1".to_string()),
+        user_agent: Some("test-agent".to_string()),
+        created_at: Utc::now(),
+        expires_at: Utc::now() + expires_in,
+        revoked_at: None,
+    }
+}
+
+#[tokio::test]
+async fn insert_list_and_revoke_sessions_works() {
+    let app = spawn_app().await;
+    let user_id = Uuid::parse_str(&app.sign_up().await.user_id).unwrap();
+
+    let active = generate_session(user_id, Duration::hours(1));
+    let expired = generate_session(user_id, Duration::hours(-1));
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    assert_ok!(insert_user_session(&active, &mut transaction).await);
+    assert_ok!(insert_user_session(&expired, &mut transaction).await);
+    assert_ok!(transaction.commit().await);
+
+    let sessions = get_active_sessions_by_user_id(user_id, &app.db_pool)
+        .await
+        .unwrap();
+    assert!(sessions.iter().any(|s| s.id == active.id));
+    assert!(!sessions.iter().any(|s| s.id == expired.id));
+
+    assert!(is_session_active(active.id, &app.db_pool).await.unwrap());
+    assert!(revoke_user_session(user_id, active.id, &app.db_pool)
+        .await
+        .unwrap());
+    assert!(!is_session_active(active.id, &app.db_pool).await.unwrap());
+    assert!(!revoke_user_session(user_id, active.id, &app.db_pool)
+        .await
+        .unwrap());
+    assert!(!is_session_active(Uuid::new_v4(), &app.db_pool)
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn login_familiarity_only_counts_successful_logins() {
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4();
+
+    let familiarity = get_login_familiarity(user_id, Some("10.0.0.1"), Some("agent"), &app.db_pool)
+        .await
+        .unwrap();
+    assert!(!familiarity.has_previous_logins);
+
+    let failed = LoginAttempt {
+        user_id: Some(user_id),
+        email_address: None,
+        login_method: LoginMethod::Password,
+        failure_reason: Some(LoginFailureReason::WrongPassword),
+        session_id: None,
+    };
+    assert_ok!(insert_login_attempt(&failed, Some("10.0.0.1"), Some("agent"), &app.db_pool).await);
+    let familiarity = get_login_familiarity(user_id, Some("10.0.0.1"), Some("agent"), &app.db_pool)
+        .await
+        .unwrap();
+    assert!(!familiarity.has_previous_logins);
+
+    let succeeded = LoginAttempt {
+        failure_reason: None,
+        ..failed
+    };
+    assert_ok!(
+        insert_login_attempt(&succeeded, Some("10.0.0.1"), Some("agent"), &app.db_pool).await
+    );
+    let familiarity = get_login_familiarity(user_id, Some("10.0.0.2"), Some("agent"), &app.db_pool)
+        .await
+        .unwrap();
+    assert!(familiarity.has_previous_logins);
+    assert!(familiarity.known_device);
+    assert!(!familiarity.known_network);
+    assert!(familiarity.is_new_device_or_network());
+}
+
+#[tokio::test]
+async fn sessions_with_an_unknown_login_method_are_left_out() {
+    let app = spawn_app().await;
+    let user_id = Uuid::parse_str(&app.sign_up().await.user_id).unwrap();
+    let legacy = generate_session(user_id, Duration::hours(1));
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    assert_ok!(insert_user_session(&legacy, &mut transaction).await);
+    assert_ok!(transaction.commit().await);
+    sqlx::query!(
+        "UPDATE user_sessions SET login_method = 'Legacy' WHERE id = $1",
+        legacy.id
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    let sessions = get_active_sessions_by_user_id(user_id, &app.db_pool)
+        .await
+        .unwrap();
+    assert!(!sessions.is_empty());
+    assert!(!sessions.iter().any(|s| s.id == legacy.id));
+}
+
+#[tokio::test]
+async fn insert_user_session_failed() {
+    let app = spawn_app().await;
+
+    sqlx::query!("DROP TABLE user_sessions")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    assert_err!(
+        insert_user_session(
+            &generate_session(Uuid::new_v4(), Duration::hours(1)),
+            &mut transaction
+        )
+        .await
+    );
+}
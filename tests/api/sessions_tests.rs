@@ -0,0 +1,209 @@
+use std::time::Duration;
+
+use mailtrap_rs::types::response::SendEmailResponse;
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use newsletter_signup_service::auth::token::LoginResponse;
+use newsletter_signup_service::domain::session_models::{LoginMethod, OverTheWireUserSession};
+use newsletter_signup_service::domain::user_models::LogIn;
+
+use crate::helper::{generate_signup, spawn_app, TestApp};
+
+async fn get_sessions(app: &TestApp, login: &LoginResponse) -> Vec<OverTheWireUserSession> {
+    let response = app
+        .get_user_sessions(login.user_id.clone(), login.token.clone())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    serde_json::from_str(response.text().await.unwrap().as_str()).unwrap()
+}
+
+async fn login_response(response: reqwest::Response) -> LoginResponse {
+    assert_eq!(200, response.status().as_u16());
+    serde_json::from_str(response.text().await.unwrap().as_str()).unwrap()
+}
+
+#[tokio::test]
+async fn sign_up_and_login_each_create_a_session() {
+    let app = spawn_app().await;
+    let signup = generate_signup();
+    let sign_up_login = login_response(app.user_signup(signup.to_json()).await).await;
+    let password_login = login_response(
+        app.login_with_user_agent(signup.to_json(), "sessions-test")
+            .await,
+    )
+    .await;
+
+    let sessions = get_sessions(&app, &password_login).await;
+
+    assert_eq!(2, sessions.len());
+    let current = sessions
+        .iter()
+        .find(|s| s.current)
+        .expect("current session");
+    assert_eq!(LoginMethod::Password, current.login_method);
+    assert_eq!(Some("sessions-test".to_string()), current.user_agent);
+    assert_eq!(Some("127.0.0.1".to_string()), current.ip_address);
+    assert!(sessions
+        .iter()
+        .any(|s| !s.current && s.login_method == LoginMethod::SignUp));
+    assert_eq!(2, get_sessions(&app, &sign_up_login).await.len());
+}
+
+#[tokio::test]
+async fn revoking_a_session_invalidates_its_token() {
+    let app = spawn_app().await;
+    let signup = generate_signup();
+    let first = login_response(app.user_signup(signup.to_json()).await).await;
+    let second = login_response(app.login(signup.to_json()).await).await;
+
+    let first_session = get_sessions(&app, &first)
+        .await
+        .into_iter()
+        .find(|s| s.current)
+        .unwrap();
+    let response = app
+        .revoke_user_session(
+            second.user_id.clone(),
+            first_session.id.to_string(),
+            second.token.clone(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = app
+        .check_token(first.user_id.clone(), first.token.clone())
+        .await;
+    assert_eq!(401, response.status().as_u16());
+    let response = app
+        .check_token(second.user_id.clone(), second.token.clone())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = app
+        .revoke_user_session(second.user_id, first_session.id.to_string(), second.token)
+        .await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn users_cannot_see_or_revoke_sessions_of_other_users() {
+    let app = spawn_app().await;
+    let victim = app.sign_up().await;
+    let attacker = app.sign_up().await;
+
+    let response = app
+        .get_user_sessions(victim.user_id.clone(), attacker.token.clone())
+        .await;
+    assert_eq!(401, response.status().as_u16());
+
+    let victim_session = get_sessions(&app, &victim).await.remove(0);
+    let response = app
+        .revoke_user_session(
+            attacker.user_id.clone(),
+            victim_session.id.to_string(),
+            attacker.token,
+        )
+        .await;
+    assert_eq!(404, response.status().as_u16());
+
+    let response = app.check_token(victim.user_id, victim.token).await;
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn failed_and_successful_logins_are_recorded() {
+    let app = spawn_app().await;
+    let signup = generate_signup();
+    let login = login_response(app.user_signup(signup.to_json()).await).await;
+
+    let wrong_password = LogIn {
+        email_address: signup.email_address.clone(),
+        password: Uuid::new_v4().to_string(),
+    };
+    assert_eq!(
+        400,
+        app.login(wrong_password.to_json()).await.status().as_u16()
+    );
+    let unknown_user = LogIn {
+        email_address: Uuid::new_v4().to_string(),
+        password: Uuid::new_v4().to_string(),
+    };
+    assert_eq!(
+        400,
+        app.login(unknown_user.to_json()).await.status().as_u16()
+    );
+
+    let user_id = Uuid::parse_str(&login.user_id).unwrap();
+    let rows = sqlx::query!(
+        r#"SELECT login_method, succeeded, failure_reason
+            FROM login_history
+            WHERE user_id = $1
+            ORDER BY occurred_at"#,
+        user_id
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(2, rows.len());
+    assert!(rows[0].succeeded);
+    assert_eq!("SignUp", rows[0].login_method);
+    assert!(!rows[1].succeeded);
+    assert_eq!(Some("WrongPassword".to_string()), rows[1].failure_reason);
+
+    let unknown = sqlx::query!(
+        r#"SELECT failure_reason FROM login_history WHERE email_address = $1"#,
+        unknown_user.email_address
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(Some("UnknownUser".to_string()), unknown.failure_reason);
+}
+
+#[tokio::test]
+async fn logging_in_from_a_new_device_sends_a_notice() {
+    let app = spawn_app().await;
+    let signup = generate_signup();
+    login_response(app.user_signup(signup.to_json()).await).await;
+
+    Mock::given(path("api/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(SendEmailResponse {
+            success: true,
+            message_ids: vec!["test-id".to_string()],
+            errors: vec![],
+        }))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    login_response(
+        app.login_with_user_agent(signup.to_json(), "new-device")
+            .await,
+    )
+    .await;
+    // The same device again is not new any more.
+    login_response(
+        app.login_with_user_agent(signup.to_json(), "new-device")
+            .await,
+    )
+    .await;
+
+    for _ in 0..20 {
+        if !app
+            .received_emails("New sign-in to your account")
+            .await
+            .is_empty()
+        {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let received = app.received_emails("New sign-in to your account").await;
+
+    assert_eq!(1, received.len());
+    assert!(received[0]["text"].as_str().unwrap().contains("new-device"));
+}
@@ -0,0 +1,247 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use mailtrap_rs::types::response::SendEmailResponse;
+use serde_json::json;
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use newsletter_signup_service::auth::token::generate_token;
+use newsletter_signup_service::background::staff_digest::send_due_staff_digest;
+use newsletter_signup_service::configuration::{
+    get_configuration, DigestFrequency, EmailTransportKind, StaffDigestSettings,
+};
+use newsletter_signup_service::db::subscription_history_db_broker::insert_subscription_history_event;
+use newsletter_signup_service::db::webhook_event_db_broker::insert_webhook_event;
+use newsletter_signup_service::domain::publication_models::Publication;
+use newsletter_signup_service::domain::staff_digest_models::StaffDigest;
+use newsletter_signup_service::domain::subscription_history_models::HistoryEventType;
+use newsletter_signup_service::domain::subscription_models::{
+    OverTheWireSubscription, SubscriptionType,
+};
+use newsletter_signup_service::domain::user_models::UserGroup;
+use newsletter_signup_service::domain::webhook_event::WebhookEvent;
+use newsletter_signup_service::email_client::EmailClient;
+
+use crate::helper::{
+    generate_over_the_wire_create_subscription, spawn_app, store_subscription, TestApp,
+};
+
+fn admin() -> (String, String) {
+    let admin_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+    (admin_user_id, token)
+}
+
+fn email_client(app: &TestApp) -> EmailClient {
+    let mut settings = get_configuration().unwrap().email_client;
+    settings.transport = EmailTransportKind::Mailtrap;
+    settings.base_url = format!("{}/", app.email_server.uri().trim_end_matches('/'));
+    EmailClient::new(settings)
+}
+
+async fn mock_email_provider(app: &TestApp) {
+    Mock::given(path("api/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(SendEmailResponse {
+            success: true,
+            message_ids: vec!["test-id".to_string()],
+            errors: vec![],
+        }))
+        .mount(&app.email_server)
+        .await;
+}
+
+async fn preview(app: &TestApp, frequency: &str) -> StaffDigest {
+    let (admin_user_id, token) = admin();
+    let response = app
+        .preview_staff_digest(admin_user_id, Some(frequency), token)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    serde_json::from_str(response.text().await.unwrap().as_str()).unwrap()
+}
+
+async fn store_created(
+    app: &TestApp,
+    subscriber_id: Uuid,
+    subscription_type: SubscriptionType,
+) -> OverTheWireSubscription {
+    let subscription = store_subscription(
+        subscriber_id.to_string(),
+        Some(generate_over_the_wire_create_subscription(
+            subscriber_id.to_string(),
+            Some(subscription_type),
+        )),
+        app,
+    )
+    .await;
+    insert_subscription_history_event(
+        subscription.clone(),
+        HistoryEventType::Created,
+        &app.db_pool,
+    )
+    .await
+    .unwrap();
+    subscription
+}
+
+#[tokio::test]
+async fn the_digest_reports_the_period() {
+    let app = spawn_app().await;
+    mock_email_provider(&app).await;
+    let subscriber = app.store_subscriber(None).await;
+    store_created(&app, subscriber.id, SubscriptionType::Digital).await;
+    let cancelled = store_created(&app, subscriber.id, SubscriptionType::Digital).await;
+    insert_subscription_history_event(cancelled, HistoryEventType::Cancelled, &app.db_pool)
+        .await
+        .unwrap();
+    // Stored today, so its anniversary is today.
+    let paper = store_created(&app, subscriber.id, SubscriptionType::Paper).await;
+    insert_webhook_event(
+        &WebhookEvent {
+            id: Uuid::new_v4(),
+            event_text: json!({
+                "type": "invoice.payment_failed",
+                "data": { "object": { "subscription": "sub_failed", "customer_email": "late@example.com" } },
+            })
+            .to_string(),
+            sent_on: Utc::now(),
+            processed: false,
+        },
+        &app.db_pool,
+    )
+    .await
+    .unwrap();
+    let (admin_user_id, token) = admin();
+    let response = app
+        .create_publication(
+            admin_user_id.clone(),
+            json!({ "title": "The Weekly" }).to_string(),
+            token.clone(),
+        )
+        .await;
+    let publication: Publication =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    let cutoff = Utc::now().date_naive() + chrono::Duration::days(3);
+    let response = app
+        .create_issue(
+            admin_user_id,
+            publication.id.to_string(),
+            json!({ "issue_number": 1, "publish_date": cutoff, "cutoff_date": cutoff }).to_string(),
+            token,
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let mut moved = paper.clone();
+    moved.subscription_city = "Independence".to_string();
+    let response = app
+        .update_subscription_by_id(
+            paper.id.to_string(),
+            moved.to_json(),
+            generate_token(subscriber.user_id.clone(), UserGroup::USER),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    // The update's history is stored in the background.
+    let mut digest = preview(&app, "daily").await;
+    for _ in 0..50 {
+        if !digest.pending_address_changes.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        digest = preview(&app, "daily").await;
+    }
+
+    assert_eq!(DigestFrequency::Daily, digest.frequency);
+    assert_eq!(
+        chrono::Duration::days(1),
+        digest.period_end - digest.period_start
+    );
+    assert_eq!(3, StaffDigest::total(&digest.new_subscriptions));
+    assert_eq!(1, StaffDigest::total(&digest.cancelled_subscriptions));
+    assert_eq!(2, digest.net_change);
+    assert_eq!(1, digest.failed_payments.len());
+    assert_eq!(
+        Some("sub_failed".to_string()),
+        digest.failed_payments[0].stripe_subscription_id
+    );
+    assert_eq!(1, digest.upcoming_renewals.len());
+    assert_eq!(paper.id, digest.upcoming_renewals[0].subscription_id);
+    assert_eq!(Some(cutoff), digest.next_print_cutoff);
+    assert_eq!(1, digest.pending_address_changes.len());
+    assert!(digest.pending_address_changes[0]
+        .mailing_address
+        .contains("Independence, "));
+}
+
+#[tokio::test]
+async fn due_digests_are_sent_once() {
+    let app = spawn_app().await;
+    mock_email_provider(&app).await;
+    let chat_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&chat_server)
+        .await;
+    let (admin_user_id, token) = admin();
+    let response = app
+        .create_notification_channel(
+            admin_user_id,
+            json!({
+                "name": "Slack",
+                "kind": "Slack",
+                "target": format!("{}/slack", chat_server.uri()),
+                "event_types": ["Digest"],
+            })
+            .to_string(),
+            token,
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let settings = StaffDigestSettings::new(DigestFrequency::Weekly);
+    let now = Utc::now();
+
+    let sent = send_due_staff_digest(&settings, now, &email_client(&app), &app.db_pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let again = send_due_staff_digest(&settings, now, &email_client(&app), &app.db_pool)
+        .await
+        .unwrap();
+
+    assert!(again.is_none());
+    assert_eq!(settings.last_due(now), sent.period_end);
+    let subject = format!(
+        "Weekly subscription digest for {}",
+        sent.period_end.format("%Y-%m-%d")
+    );
+    assert_eq!(1, app.received_emails(&subject).await.len());
+    for _ in 0..50 {
+        if !chat_server.received_requests().await.unwrap().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let posts = chat_server.received_requests().await.unwrap();
+    assert_eq!(1, posts.len());
+    let body: serde_json::Value = serde_json::from_slice(&posts[0].body).unwrap();
+    assert!(body["text"].as_str().unwrap().starts_with(&subject));
+}
+
+#[tokio::test]
+async fn the_digest_preview_is_admin_only() {
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4().to_string();
+
+    let response = app
+        .preview_staff_digest(
+            user_id.clone(),
+            None,
+            generate_token(user_id, UserGroup::USER),
+        )
+        .await;
+
+    assert_eq!(401, response.status().as_u16());
+}
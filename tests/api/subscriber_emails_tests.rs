@@ -0,0 +1,202 @@
+use mailtrap_rs::types::response::SendEmailResponse;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use newsletter_signup_service::auth::token::{generate_token, LoginResponse};
+use newsletter_signup_service::configuration::get_configuration;
+use newsletter_signup_service::domain::checkout_models::CreateCheckoutSession;
+use newsletter_signup_service::domain::subscriber_models::OverTheWireSubscriber;
+use newsletter_signup_service::domain::subscription_models::SubscriptionType;
+use newsletter_signup_service::domain::user_models::UserGroup;
+
+use crate::helper::{
+    generate_over_the_wire_create_subscription, generate_signup, mock_cancel_stripe_subscription,
+    mock_create_checkout_session, mock_get_stripe_session, mock_stripe_create_customer,
+    mock_stripe_price_lookup, spawn_app, store_subscription, TestApp,
+};
+
+async fn mock_email_provider(app: &TestApp) {
+    Mock::given(path("api/send"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(SendEmailResponse {
+            success: true,
+            message_ids: vec!["test-id".to_string()],
+            errors: vec![],
+        }))
+        .mount(&app.email_server)
+        .await;
+}
+
+#[tokio::test]
+async fn signing_up_sends_a_welcome_email() {
+    let app = spawn_app().await;
+    mock_email_provider(&app).await;
+    let organization = get_configuration().unwrap().email_client.sender_name;
+
+    let signup = generate_signup();
+    let response = app.user_signup(signup.to_json()).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let received = app
+        .received_emails(&format!("Welcome to {}", organization))
+        .await;
+    assert_eq!(1, received.len());
+    assert_eq!(signup.email_address, received[0]["to"][0]["email"]);
+    assert!(received[0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("/subscriber"));
+}
+
+#[tokio::test]
+async fn completing_a_checkout_confirms_the_subscription() {
+    let app = spawn_app().await;
+    mock_email_provider(&app).await;
+    let price_lookup_id = get_configuration().unwrap().stripe_client.digital_price_id;
+
+    let login: LoginResponse = app.sign_up().await;
+    let subscriber: OverTheWireSubscriber = app
+        .get_subscriber_by_user_id(login.user_id.clone(), login.token.clone())
+        .await;
+    let stripe_session_id = uuid::Uuid::new_v4().to_string();
+    mock_stripe_create_customer(&app.stripe_server, subscriber.email_address.clone()).await;
+    mock_stripe_price_lookup(&app.stripe_server, price_lookup_id.clone()).await;
+    mock_create_checkout_session(&app.stripe_server, stripe_session_id.clone()).await;
+    mock_get_stripe_session(&app.stripe_server, stripe_session_id.clone()).await;
+
+    let subscription = generate_over_the_wire_create_subscription(
+        subscriber.id.to_string(),
+        Some(SubscriptionType::Digital),
+    );
+    let subscription_email_address = subscription.subscription_email_address.clone();
+    let create_checkout_session = CreateCheckoutSession {
+        price_lookup_key: price_lookup_id,
+        subscription,
+    };
+    let response = app
+        .post_checkout(
+            create_checkout_session.to_json(),
+            login.user_id.clone(),
+            login.token.clone(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let response = app
+        .post_complete_session(login.user_id, stripe_session_id, login.token)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let received = app
+        .received_emails("Your Digital subscription is confirmed")
+        .await;
+    assert_eq!(1, received.len());
+    assert_eq!(subscription_email_address, received[0]["to"][0]["email"]);
+    let text = received[0]["text"].as_str().unwrap();
+    assert!(text.contains("5.00 USD"));
+    assert!(text.contains("Next renewal:"));
+    // Staff hear of it from a job queued with the subscription.
+    let notices: i64 =
+        sqlx::query_scalar("SELECT count(*) FROM jobs WHERE kind = 'NotifyNewSubscription'")
+            .fetch_one(&app.db_pool)
+            .await
+            .unwrap();
+    assert_eq!(1, notices);
+}
+
+#[tokio::test]
+async fn cancelling_a_subscription_emails_the_subscriber() {
+    let app = spawn_app().await;
+    mock_email_provider(&app).await;
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = store_subscription(subscriber.id.to_string(), None, &app).await;
+    mock_cancel_stripe_subscription(
+        &app.stripe_server,
+        subscription.stripe_subscription_id.clone(),
+    )
+    .await;
+    let token = generate_token(subscriber.user_id.clone(), UserGroup::USER);
+
+    let response = app
+        .cancel_subscription_by_id(subscription.id.to_string(), token.clone())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    // Cancelling again changes nothing, so it sends nothing.
+    let response = app
+        .cancel_subscription_by_id(subscription.id.to_string(), token)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let received = app
+        .received_emails("Your Digital subscription has been cancelled")
+        .await;
+    assert_eq!(1, received.len());
+    assert_eq!(
+        subscription.subscription_email_address,
+        received[0]["to"][0]["email"]
+    );
+}
+
+#[tokio::test]
+async fn changing_a_paper_mailing_address_emails_the_subscriber() {
+    let app = spawn_app().await;
+    mock_email_provider(&app).await;
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = store_subscription(
+        subscriber.id.to_string(),
+        Some(generate_over_the_wire_create_subscription(
+            subscriber.id.to_string(),
+            Some(SubscriptionType::Paper),
+        )),
+        &app,
+    )
+    .await;
+    let token = generate_token(subscriber.user_id.clone(), UserGroup::USER);
+
+    // Saving it unchanged is not an address change.
+    let response = app
+        .update_subscription_by_id(
+            subscription.id.to_string(),
+            subscription.to_json(),
+            token.clone(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let mut moved = subscription.clone();
+    moved.subscription_city = "Independence".to_string();
+    let response = app
+        .update_subscription_by_id(subscription.id.to_string(), moved.to_json(), token)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let received = app
+        .received_emails("Your mailing address has been updated")
+        .await;
+    assert_eq!(1, received.len());
+    assert!(received[0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("Independence, "));
+    // Only the address change is queued for staff, with the history of both saves.
+    let jobs: Vec<(String, serde_json::Value)> =
+        sqlx::query_as("SELECT kind, payload FROM jobs ORDER BY created_at")
+            .fetch_all(&app.db_pool)
+            .await
+            .unwrap();
+    let staff_notices: Vec<&serde_json::Value> = jobs
+        .iter()
+        .filter(|(kind, _)| kind == "NotifyStaff")
+        .map(|(_, payload)| payload)
+        .collect();
+    assert_eq!(1, staff_notices.len());
+    assert_eq!("AddressChange", staff_notices[0]["event_type"]);
+    assert_eq!(
+        "Independence",
+        staff_notices[0]["subscription"]["subscription_city"]
+    );
+    assert_eq!(
+        2,
+        jobs.iter()
+            .filter(|(kind, _)| kind == "StoreSubscriptionHistory")
+            .count()
+    );
+}
@@ -359,7 +359,7 @@ async fn requests_missing_authorization_are_rejected() {
     // Arrange
     let app = spawn_app().await;
     let response = reqwest::Client::new()
-        .post(&format!("{}/subscribers", app.address))
+        .post(format!("{}/subscribers", app.address))
         .header("Content-Type", "application/json")
         .body(generate_over_the_wire_subscriber().to_json())
         .send()
@@ -1,15 +1,16 @@
 use chrono::Utc;
 use claims::{assert_err, assert_ok};
 use newsletter_signup_service::db::subscribers_db_broker::{
-    insert_subscriber, retrieve_subscriber_by_user_id,
+    insert_subscriber, retrieve_subscriber_by_user_id, retrieve_subscriber_id_by_email,
 };
 use newsletter_signup_service::db::subscriptions_db_broker::{
+    count_active_subscriptions_by_email_and_type, insert_externally_billed_subscription,
     insert_subscription, retrieve_all_subscriptions, retrieve_subscription_by_subscription_id,
-    update_subscription_by_subscription_id,
+    retrieve_subscriptions_by_subscriber_id, update_subscription_by_subscription_id,
 };
 use newsletter_signup_service::domain::subscriber_models::NewSubscriber;
 use newsletter_signup_service::domain::subscription_models::{
-    OverTheWireSubscription, SubscriptionType,
+    BillingSource, OverTheWireSubscription, SubscriptionType,
 };
 use uuid::Uuid;
 
@@ -41,15 +42,15 @@ async fn update_subscription_by_subscription_id_works() {
     let subscription = insert_subscription_result.unwrap();
 
     let updates = OverTheWireSubscription {
-        id: subscription.id.clone(),
-        subscriber_id: subscription.subscriber_id.clone(),
+        id: subscription.id,
+        subscriber_id: subscription.subscriber_id,
         subscription_name: Uuid::new_v4().to_string(),
         subscription_mailing_address_line_1: Uuid::new_v4().to_string(),
         subscription_mailing_address_line_2: Uuid::new_v4().to_string(),
         subscription_city: Uuid::new_v4().to_string(),
         subscription_state: Uuid::new_v4().to_string(),
         subscription_postal_code: Uuid::new_v4().to_string(),
-        subscription_email_address: format!("{}@gmail.com", Uuid::new_v4().to_string()),
+        subscription_email_address: format!("{}@gmail.com", Uuid::new_v4()),
         subscription_creation_date: Utc::now(),
         subscription_cancelled_on_date: None,
         subscription_anniversary_day: 0,
@@ -58,15 +59,19 @@ async fn update_subscription_by_subscription_id_works() {
         active: false,
         subscription_type: SubscriptionType::Digital,
         stripe_subscription_id: subscription.stripe_subscription_id.clone(),
+        billing_source: BillingSource::Stripe,
+        publication_id: None,
     };
 
+    let mut transaction = app.db_pool.begin().await.unwrap();
     let update_subscription_result =
-        update_subscription_by_subscription_id(subscription.id, updates.clone(), &app.db_pool)
+        update_subscription_by_subscription_id(subscription.id, updates.clone(), &mut transaction)
             .await;
     assert_ok!(update_subscription_result);
+    assert_ok!(transaction.commit().await);
 
     let updated_subscription_retrieval_result =
-        retrieve_subscription_by_subscription_id(subscription.id.clone(), &app.db_pool).await;
+        retrieve_subscription_by_subscription_id(subscription.id, &app.db_pool).await;
     assert_ok!(&updated_subscription_retrieval_result);
 
     assert_eq!(
@@ -90,7 +95,7 @@ async fn update_subscription_by_subscription_id_err() {
         subscription_city: Uuid::new_v4().to_string(),
         subscription_state: Uuid::new_v4().to_string(),
         subscription_postal_code: Uuid::new_v4().to_string(),
-        subscription_email_address: format!("{}@gmail.com", Uuid::new_v4().to_string()),
+        subscription_email_address: format!("{}@gmail.com", Uuid::new_v4()),
         subscription_creation_date: Utc::now(),
         subscription_cancelled_on_date: None,
         subscription_anniversary_day: 0,
@@ -99,6 +104,8 @@ async fn update_subscription_by_subscription_id_err() {
         active: false,
         subscription_type: SubscriptionType::Digital,
         stripe_subscription_id: Uuid::new_v4().to_string(),
+        billing_source: BillingSource::Stripe,
+        publication_id: None,
     };
 
     // Sabotage the database
@@ -107,9 +114,9 @@ async fn update_subscription_by_subscription_id_err() {
         .await
         .unwrap();
 
+    let mut transaction = app.db_pool.begin().await.unwrap();
     let update_subscription_result =
-        update_subscription_by_subscription_id(updates.id.clone(), updates.clone(), &app.db_pool)
-            .await;
+        update_subscription_by_subscription_id(updates.id, updates.clone(), &mut transaction).await;
     assert_err!(update_subscription_result);
 }
 
@@ -307,3 +314,60 @@ async fn retrieve_all_subscriptions_returns_err_on_database_error() {
     let get_all_result = retrieve_all_subscriptions(&app.db_pool).await;
     assert_err!(&get_all_result);
 }
+
+#[tokio::test]
+async fn externally_billed_subscriptions_are_counted_by_email_and_type() {
+    let app = spawn_app().await;
+
+    let subscriber = generate_over_the_wire_subscriber();
+    let new_subscriber: NewSubscriber = subscriber.clone().try_into().unwrap();
+    let mut transaction = app.db_pool.clone().begin().await.unwrap();
+    assert_ok!(insert_subscriber(&new_subscriber, &mut transaction).await);
+    let subscriber_id =
+        retrieve_subscriber_id_by_email(&subscriber.email_address, &mut transaction)
+            .await
+            .unwrap()
+            .unwrap();
+    assert_eq!(
+        None,
+        retrieve_subscriber_id_by_email("nobody@example.com", &mut transaction)
+            .await
+            .unwrap()
+    );
+
+    // Subscriptions billed outside of Stripe have no Stripe id, so several can coexist.
+    for _ in 0..2 {
+        let subscription = generate_new_subscription(subscriber_id.to_string());
+        assert_ok!(
+            insert_externally_billed_subscription(
+                subscription,
+                BillingSource::Complimentary,
+                &mut transaction
+            )
+            .await
+        );
+    }
+    assert_ok!(transaction.commit().await);
+
+    let stored = retrieve_subscriptions_by_subscriber_id(subscriber_id, &app.db_pool)
+        .await
+        .unwrap();
+    assert!(stored
+        .iter()
+        .all(|s| s.billing_source == BillingSource::Complimentary));
+    let subscription_type = stored[0].subscription_type.clone();
+    let expected = stored
+        .iter()
+        .filter(|s| s.subscription_type.as_str() == subscription_type.as_str())
+        .count() as i64;
+    assert_eq!(
+        expected,
+        count_active_subscriptions_by_email_and_type(
+            &subscriber.email_address,
+            &subscription_type,
+            &app.db_pool
+        )
+        .await
+        .unwrap()
+    );
+}
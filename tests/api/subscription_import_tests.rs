@@ -0,0 +1,184 @@
+use uuid::Uuid;
+
+use newsletter_signup_service::auth::token::generate_token;
+use newsletter_signup_service::db::jobs_db_broker::get_jobs;
+use newsletter_signup_service::db::subscribers_db_broker::retrieve_subscriber_by_email;
+use newsletter_signup_service::db::subscriptions_db_broker::retrieve_subscriptions_by_subscriber_id;
+use newsletter_signup_service::domain::job_models::JobPayload;
+use newsletter_signup_service::domain::subscription_import_models::{
+    ImportReport, ImportRowStatus,
+};
+use newsletter_signup_service::domain::subscription_models::BillingSource;
+use newsletter_signup_service::domain::user_models::UserGroup;
+
+use crate::helper::{spawn_app, TestApp};
+
+const HEADER: &str =
+    "name,email_address,address_line_1,address_line_2,city,state,postal_code,subscription_type,billing_source";
+
+fn import_file(lines: &[String]) -> String {
+    format!("{}\n{}\n", HEADER, lines.join("\n"))
+}
+
+fn valid_line(email_address: &str) -> String {
+    format!(
+        "Paper Reader,{},1 Main St,Apt 2,Springfield,IL,62701,Paper,",
+        email_address
+    )
+}
+
+async fn import(app: &TestApp, body: String, query: &[(&str, String)]) -> ImportReport {
+    let admin_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+    let response = app
+        .import_subscriptions(admin_user_id, query, body, token)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    serde_json::from_str(response.text().await.unwrap().as_str()).unwrap()
+}
+
+#[tokio::test]
+async fn import_creates_subscribers_and_externally_billed_subscriptions() {
+    let app = spawn_app().await;
+    let email_address = format!("{}@example.com", Uuid::new_v4());
+
+    let report = import(
+        &app,
+        import_file(&[valid_line(&email_address)]),
+        &[("billing_source", "External".to_string())],
+    )
+    .await;
+
+    assert!(!report.dry_run);
+    assert_eq!(1, report.created);
+    assert_eq!(ImportRowStatus::Created, report.rows[0].status);
+    let subscriber = retrieve_subscriber_by_email(email_address, &app.db_pool)
+        .await
+        .unwrap();
+    let subscriptions = retrieve_subscriptions_by_subscriber_id(subscriber.id, &app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(1, subscriptions.len());
+    assert_eq!(Some(subscriptions[0].id), report.rows[0].subscription_id);
+    assert_eq!(BillingSource::External, subscriptions[0].billing_source);
+    assert_eq!("", subscriptions[0].stripe_subscription_id);
+    let jobs = get_jobs(None, Some("StoreSubscriptionHistory"), 10, &app.db_pool)
+        .await
+        .unwrap();
+    assert!(jobs.iter().any(|job| matches!(
+        job.payload(),
+        Ok(JobPayload::StoreSubscriptionHistory { subscription, .. })
+            if subscription.id == subscriptions[0].id
+    )));
+}
+
+#[tokio::test]
+async fn rerunning_the_same_file_creates_nothing_new() {
+    let app = spawn_app().await;
+    let body = import_file(&[
+        valid_line(&format!("{}@example.com", Uuid::new_v4())),
+        valid_line(&format!("{}@example.com", Uuid::new_v4())),
+    ]);
+
+    let first = import(&app, body.clone(), &[]).await;
+    let second = import(&app, body, &[]).await;
+
+    assert_eq!(2, first.created);
+    assert_eq!(0, second.created);
+    assert_eq!(2, second.skipped);
+}
+
+#[tokio::test]
+async fn a_dry_run_reports_without_storing_anything() {
+    let app = spawn_app().await;
+    let email_address = format!("{}@example.com", Uuid::new_v4());
+    let body = import_file(&[
+        valid_line(&email_address),
+        valid_line(&email_address),
+        "Bad Reader,not-an-email,1 Main St,,Springfield,ZZ,123,Paper,".to_string(),
+    ]);
+
+    let report = import(&app, body, &[("dry_run", "true".to_string())]).await;
+
+    assert!(report.dry_run);
+    assert_eq!(1, report.created);
+    assert_eq!(1, report.skipped);
+    assert_eq!(1, report.invalid);
+    assert_eq!(ImportRowStatus::WouldCreate, report.rows[0].status);
+    assert_eq!(4, report.rows[2].line);
+    assert_eq!(3, report.rows[2].errors.len());
+    assert!(retrieve_subscriber_by_email(email_address, &app.db_pool)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn invalid_rows_do_not_stop_the_valid_ones() {
+    let app = spawn_app().await;
+    let email_address = format!("{}@example.com", Uuid::new_v4());
+    let body = import_file(&[
+        "Bad Reader,bad@example.com,1 Main St,,Springfield,IL,62701,Carrier Pigeon,".to_string(),
+        valid_line(&email_address),
+    ]);
+
+    let report = import(&app, body, &[]).await;
+
+    assert_eq!(1, report.created);
+    assert_eq!(1, report.invalid);
+    assert_eq!(ImportRowStatus::Invalid, report.rows[0].status);
+    assert_eq!(ImportRowStatus::Created, report.rows[1].status);
+}
+
+#[tokio::test]
+async fn a_file_without_the_required_columns_is_rejected() {
+    let app = spawn_app().await;
+    let admin_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+
+    let response = app
+        .import_subscriptions(
+            admin_user_id,
+            &[],
+            "name,email_address\nJane,jane@example.com\n".to_string(),
+            token,
+        )
+        .await;
+
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn imported_subscriptions_cannot_default_to_stripe_billing() {
+    let app = spawn_app().await;
+    let admin_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+
+    let response = app
+        .import_subscriptions(
+            admin_user_id,
+            &[("billing_source", "Stripe".to_string())],
+            import_file(&[valid_line("jane@example.com")]),
+            token,
+        )
+        .await;
+
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn import_requires_an_admin() {
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4().to_string();
+    let token = generate_token(user_id.clone(), UserGroup::USER);
+
+    let response = app
+        .import_subscriptions(
+            user_id,
+            &[],
+            import_file(&[valid_line("jane@example.com")]),
+            token,
+        )
+        .await;
+
+    assert_eq!(401, response.status().as_u16());
+}
@@ -1,4 +1,4 @@
-use claims::assert_ok;
+use claims::{assert_err, assert_ok};
 use fake::faker::internet::en::SafeEmail;
 use fake::{Fake, Faker};
 use mailtrap_rs::types::response::SendEmailResponse;
@@ -10,6 +10,7 @@ use newsletter_signup_service::domain::subscriber_models::NewSubscriber;
 use newsletter_signup_service::domain::valid_email::ValidEmail;
 use newsletter_signup_service::email_client::EmailClient;
 use secrecy::SecretString;
+use uuid::Uuid;
 use wiremock::matchers::{header_exists, method, path};
 use wiremock::{Mock, ResponseTemplate};
 
@@ -45,12 +46,34 @@ async fn notify_of_new_subscriber_works() {
         .mount(&app.email_server)
         .await;
 
-    notify_subscriber(
-        stored_subscription.id.clone(),
+    // A job that runs again sends the staff email once.
+    let job_id = Uuid::new_v4();
+    for _ in 0..2 {
+        assert_ok!(
+            notify_subscriber(
+                job_id,
+                stored_subscription.id,
+                &email_client(app.email_server.uri().clone()),
+                &app.db_pool,
+            )
+            .await
+        );
+    }
+}
+
+#[tokio::test]
+async fn notify_of_an_unknown_subscription_fails() {
+    let app = spawn_app().await;
+
+    let result = notify_subscriber(
+        Uuid::new_v4(),
+        Uuid::new_v4(),
         &email_client(app.email_server.uri().clone()),
         &app.db_pool,
     )
     .await;
+
+    assert_err!(result);
 }
 
 fn email_client(base_url: String) -> EmailClient {
@@ -68,6 +91,11 @@ fn email_client(base_url: String) -> EmailClient {
             reply_to_name: "Test".to_string(),
             api_key: SecretString::new(Faker.fake::<String>().into_boxed_str()),
             timeout_milliseconds: 200,
+            transport: newsletter_signup_service::configuration::EmailTransportKind::Mailtrap,
+            smtp: None,
+            file_directory: "target/emails".to_string(),
+            webhook_token: None,
+            capture_emails: false,
         },
     )
 }
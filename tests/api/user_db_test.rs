@@ -254,9 +254,15 @@ async fn update_password_test() {
 
     assert_ok!(get_user_by_email_address(&sign_up.email_address, &app.db_pool).await);
 
-    let update_result =
-        update_password(&sign_up.email_address.as_str(), "newpassword", &app.db_pool).await;
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    let update_result = update_password(
+        sign_up.email_address.as_str(),
+        "newpassword",
+        &mut transaction,
+    )
+    .await;
     assert_ok!(update_result);
+    assert_ok!(transaction.commit().await);
 }
 
 #[tokio::test]
@@ -268,10 +274,11 @@ async fn update_password_failed() {
         .await
         .expect("Failed to drop.");
 
+    let mut transaction = app.db_pool.begin().await.unwrap();
     let update_result = update_password(
-        &Uuid::new_v4().to_string().as_str(),
+        Uuid::new_v4().to_string().as_str(),
         "newpassword",
-        &app.db_pool,
+        &mut transaction,
     )
     .await;
     assert_err!(update_result);
@@ -350,7 +357,9 @@ async fn demote_admin_to_user_test() {
         .unwrap();
     assert_eq!(UserGroup::ADMIN, user.user_group);
 
-    assert_ok!(demote_admin_to_user(user.user_id, &app.db_pool).await);
+    let mut transaction = app.db_pool.clone().begin().await.unwrap();
+    assert_ok!(demote_admin_to_user(user.user_id, &mut transaction).await);
+    assert_ok!(transaction.commit().await);
 
     let demoted = get_user_by_user_id(user.user_id.to_string().as_str(), &app.db_pool)
         .await
@@ -367,6 +376,7 @@ async fn demote_admin_to_user_failed() {
         .await
         .expect("Failed to drop.");
 
-    let demote_result = demote_admin_to_user(Uuid::new_v4(), &app.db_pool).await;
+    let mut transaction = app.db_pool.clone().begin().await.unwrap();
+    let demote_result = demote_admin_to_user(Uuid::new_v4(), &mut transaction).await;
     assert_err!(demote_result);
 }
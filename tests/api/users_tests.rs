@@ -254,7 +254,7 @@ async fn forgot_password_no_user_still_gives_200() {
     let app = spawn_app().await;
 
     let forgot_password = ForgotPassword {
-        email_address: format!("{}@GMAIL.COM", Uuid::new_v4().to_string()),
+        email_address: format!("{}@GMAIL.COM", Uuid::new_v4()),
     };
 
     let forgot_password_response = app.forgot_password(forgot_password.to_json()).await;
@@ -317,9 +317,7 @@ async fn forgot_password_sends_a_confirmation_email_with_a_link() {
     let forgot_password_response = app.forgot_password(forgot_password.to_json()).await;
     assert_eq!(200, forgot_password_response.status().as_u16());
 
-    let email_request = &app.email_server.received_requests().await.unwrap()[0];
-
-    let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    let body = &app.received_emails("Password Reset").await[0];
     let text_content = body["text"].as_str().unwrap();
 
     let get_link = |s: &str| {
@@ -332,7 +330,7 @@ async fn forgot_password_sends_a_confirmation_email_with_a_link() {
     };
 
     let text_link = get_link(text_content);
-    assert_eq!(false, text_link.is_empty());
+    assert!(!text_link.is_empty());
 }
 
 #[tokio::test]
@@ -361,9 +359,7 @@ async fn forgot_password_given_when_then() {
     let forgot_password_response = app.forgot_password(forgot_password.to_json()).await;
     assert_eq!(200, forgot_password_response.status().as_u16());
 
-    let email_request = &app.email_server.received_requests().await.unwrap()[0];
-
-    let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    let body = &app.received_emails("Password Reset").await[0];
     let text_content = body["text"].as_str().unwrap();
 
     let get_link = |s: &str| {
@@ -377,7 +373,7 @@ async fn forgot_password_given_when_then() {
 
     //WHEN: The user gets the email it contains a link.
     let text_link = get_link(text_content);
-    assert_eq!(false, text_link.is_empty());
+    assert!(!text_link.is_empty());
 
     //THEN: The user can pass that link to the server and get back a token
     let otp_url = url::Url::parse(text_link.as_str()).unwrap();
@@ -415,9 +411,7 @@ async fn forgot_password_reset_password_given_when_then() {
     let forgot_password_response = app.forgot_password(forgot_password.to_json()).await;
     assert_eq!(200, forgot_password_response.status().as_u16());
 
-    let email_request = &app.email_server.received_requests().await.unwrap()[0];
-
-    let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    let body = &app.received_emails("Password Reset").await[0];
     let text_content = body["text"].as_str().unwrap();
 
     let get_link = |s: &str| {
@@ -431,7 +425,7 @@ async fn forgot_password_reset_password_given_when_then() {
 
     //WHEN: The user gets the email it contains a link.
     let text_link = get_link(text_content);
-    assert_eq!(false, text_link.is_empty());
+    assert!(!text_link.is_empty());
 
     //THEN: The user can pass that link to the server and get back a token which can be used to reset their password
     let otp_url = url::Url::parse(text_link.as_str()).unwrap();
@@ -782,6 +776,19 @@ async fn admin_demote_user_returns_200_and_sets_target_to_user() {
         .status()
         .as_u16()
     );
+    // Keep another enabled admin around so the target is not the last one.
+    let other_admin = app.sign_up().await;
+    assert_eq!(
+        200,
+        app.admin_promote_user(
+            admin_user_id.clone(),
+            other_admin.user_id.clone(),
+            admin_token.clone(),
+        )
+        .await
+        .status()
+        .as_u16()
+    );
 
     let demote_response = app
         .admin_demote_user(
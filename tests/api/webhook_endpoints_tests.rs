@@ -0,0 +1,424 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use claims::assert_ok;
+use serde_json::{json, Value};
+use uuid::Uuid;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use newsletter_signup_service::auth::token::generate_token;
+use newsletter_signup_service::background::subscription_history_storer::record_subscription_history_event;
+use newsletter_signup_service::db::subscription_history_db_broker::retrieve_subscription_events_by_subscription_id;
+use newsletter_signup_service::domain::subscription_history_models::HistoryEventType;
+use newsletter_signup_service::domain::subscription_models::SubscriptionType;
+use newsletter_signup_service::domain::user_models::UserGroup;
+use newsletter_signup_service::domain::webhook_endpoint_models::{
+    webhook_signature, SubscriptionEventType, WebhookDelivery, WebhookDeliveryStatus,
+    WebhookEndpoint, MAX_WEBHOOK_ATTEMPTS,
+};
+
+use crate::helper::{
+    generate_over_the_wire_create_subscription, mock_cancel_stripe_subscription, spawn_app,
+    store_subscription, TestApp,
+};
+
+fn admin() -> (String, String) {
+    let admin_user_id = Uuid::new_v4().to_string();
+    let token = generate_token(admin_user_id.clone(), UserGroup::ADMIN);
+    (admin_user_id, token)
+}
+
+async fn create_endpoint(app: &TestApp, body: Value) -> WebhookEndpoint {
+    let (admin_user_id, token) = admin();
+    let response = app
+        .create_webhook_endpoint(admin_user_id, body.to_string(), token)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    serde_json::from_str(response.text().await.unwrap().as_str()).unwrap()
+}
+
+async fn get_deliveries(app: &TestApp, endpoint_id: Uuid) -> Vec<WebhookDelivery> {
+    let (admin_user_id, token) = admin();
+    let response = app
+        .get_webhook_deliveries(
+            admin_user_id,
+            &[("endpoint_id", endpoint_id.to_string())],
+            token,
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    serde_json::from_str(response.text().await.unwrap().as_str()).unwrap()
+}
+
+/// History events are stored in the background, so wait for the endpoint to
+/// have `count` attempted deliveries.
+async fn wait_for_deliveries(
+    app: &TestApp,
+    endpoint_id: Uuid,
+    count: usize,
+) -> Vec<WebhookDelivery> {
+    for _ in 0..50 {
+        let deliveries = get_deliveries(app, endpoint_id).await;
+        if deliveries.len() >= count
+            && deliveries.iter().all(|delivery| {
+                delivery.attempts > 0 && delivery.status != WebhookDeliveryStatus::Delivering
+            })
+        {
+            return deliveries;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("Endpoint {} never got {} deliveries", endpoint_id, count);
+}
+
+#[tokio::test]
+async fn subscribed_endpoints_receive_signed_events() {
+    let app = spawn_app().await;
+    let crm = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&crm)
+        .await;
+    let endpoint = create_endpoint(
+        &app,
+        json!({
+            "url": format!("{}/hooks", crm.uri()),
+            "description": "CRM",
+            "event_types": ["subscription.created", "subscription.cancelled"],
+        }),
+    )
+    .await;
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = store_subscription(subscriber.id.to_string(), None, &app).await;
+    mock_cancel_stripe_subscription(
+        &app.stripe_server,
+        subscription.stripe_subscription_id.clone(),
+    )
+    .await;
+
+    let response = app
+        .cancel_subscription_by_id(
+            subscription.id.to_string(),
+            generate_token(subscriber.user_id.clone(), UserGroup::USER),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    let deliveries = wait_for_deliveries(&app, endpoint.id, 1).await;
+    assert_eq!(1, deliveries.len());
+    assert_eq!(WebhookDeliveryStatus::Delivered, deliveries[0].status);
+    assert_eq!(Some(204), deliveries[0].last_response_status);
+    let requests = crm.received_requests().await.unwrap();
+    assert_eq!(1, requests.len());
+    let body = String::from_utf8(requests[0].body.clone()).unwrap();
+    let payload: Value = serde_json::from_str(&body).unwrap();
+    let signature = requests[0].headers["Webhook-Signature"].to_str().unwrap();
+    let timestamp: i64 = signature
+        .strip_prefix("t=")
+        .and_then(|rest| rest.split(',').next())
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert_eq!(
+        webhook_signature(&endpoint.secret, timestamp, &body),
+        signature
+    );
+    assert_eq!(
+        deliveries[0].event_id.to_string(),
+        requests[0].headers["Webhook-Id"].to_str().unwrap()
+    );
+    assert_eq!(deliveries[0].event_id.to_string(), payload["id"]);
+    assert_eq!("subscription.cancelled", payload["type"]);
+    assert_eq!(
+        subscription.id.to_string(),
+        payload["data"]["subscription"]["id"]
+    );
+    assert_eq!(false, payload["data"]["subscription"]["active"]);
+}
+
+#[tokio::test]
+async fn failed_deliveries_are_kept_for_retry_and_can_be_replayed() {
+    let app = spawn_app().await;
+    let crm = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .up_to_n_times(1)
+        .mount(&crm)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&crm)
+        .await;
+    let endpoint = create_endpoint(
+        &app,
+        json!({
+            "url": crm.uri(),
+            "event_types": [SubscriptionEventType::Updated],
+        }),
+    )
+    .await;
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = store_subscription(
+        subscriber.id.to_string(),
+        Some(generate_over_the_wire_create_subscription(
+            subscriber.id.to_string(),
+            Some(SubscriptionType::Paper),
+        )),
+        &app,
+    )
+    .await;
+    let mut moved = subscription.clone();
+    moved.subscription_city = "Independence".to_string();
+
+    let response = app
+        .update_subscription_by_id(
+            subscription.id.to_string(),
+            moved.to_json(),
+            generate_token(subscriber.user_id.clone(), UserGroup::USER),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    // Only the update is sent: the endpoint is not subscribed to creations.
+    let deliveries = wait_for_deliveries(&app, endpoint.id, 1).await;
+    assert_eq!(1, deliveries.len());
+    let failed = &deliveries[0];
+    assert_eq!(SubscriptionEventType::Updated, failed.event_type);
+    assert_eq!(WebhookDeliveryStatus::Pending, failed.status);
+    assert_eq!(1, failed.attempts);
+    assert_eq!(Some(500), failed.last_response_status);
+    assert!(failed.next_attempt_at > failed.created_at);
+    assert_eq!(
+        "Independence",
+        failed.payload["data"]["subscription"]["subscription_city"]
+    );
+
+    let (admin_user_id, token) = admin();
+    let response = app
+        .replay_webhook_delivery(admin_user_id, failed.id.to_string(), token)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let replay: WebhookDelivery =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_ne!(failed.id, replay.id);
+    assert_eq!(failed.event_id, replay.event_id);
+    assert_eq!(failed.payload, replay.payload);
+    assert_eq!(WebhookDeliveryStatus::Delivered, replay.status);
+    assert_eq!(Some(200), replay.last_response_status);
+    assert_eq!(2, get_deliveries(&app, endpoint.id).await.len());
+}
+
+#[tokio::test]
+async fn a_history_job_that_runs_twice_records_and_announces_the_change_once() {
+    let app = spawn_app().await;
+    let crm = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&crm)
+        .await;
+    let endpoint = create_endpoint(
+        &app,
+        json!({
+            "url": crm.uri(),
+            "event_types": [SubscriptionEventType::Cancelled],
+        }),
+    )
+    .await;
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = store_subscription(subscriber.id.to_string(), None, &app).await;
+    let job_id = Uuid::new_v4();
+    let occurred_at = Utc::now();
+
+    for _ in 0..2 {
+        assert_ok!(
+            record_subscription_history_event(
+                job_id,
+                &subscription,
+                HistoryEventType::Cancelled,
+                occurred_at,
+                &app.db_pool,
+            )
+            .await
+        );
+    }
+
+    let events = retrieve_subscription_events_by_subscription_id(subscription.id, &app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(1, events.len());
+    assert_eq!(job_id, events[0].id);
+    let deliveries = get_deliveries(&app, endpoint.id).await;
+    assert_eq!(1, deliveries.len());
+    assert_eq!(job_id, deliveries[0].event_id);
+    assert_eq!(1, crm.received_requests().await.unwrap().len());
+}
+
+/// Queues one delivery to an endpoint that refuses it, so it waits for a retry.
+async fn queue_a_failed_delivery(app: &TestApp, crm: &MockServer) -> WebhookDelivery {
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(crm)
+        .await;
+    let endpoint = create_endpoint(
+        app,
+        json!({
+            "url": crm.uri(),
+            "event_types": [SubscriptionEventType::Cancelled],
+        }),
+    )
+    .await;
+    let subscriber = app.store_subscriber(None).await;
+    let subscription = store_subscription(subscriber.id.to_string(), None, app).await;
+    assert_ok!(
+        record_subscription_history_event(
+            Uuid::new_v4(),
+            &subscription,
+            HistoryEventType::Cancelled,
+            Utc::now(),
+            &app.db_pool,
+        )
+        .await
+    );
+    let deliveries = get_deliveries(app, endpoint.id).await;
+    assert_eq!(1, deliveries.len());
+    assert_eq!(WebhookDeliveryStatus::Pending, deliveries[0].status);
+    deliveries[0].clone()
+}
+
+async fn wait_for_dead_delivery(app: &TestApp, id: Uuid) -> (i32, Option<String>) {
+    for _ in 0..50 {
+        let (status, attempts, last_error): (String, i32, Option<String>) = sqlx::query_as(
+            "SELECT status, attempts, last_error FROM webhook_deliveries WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+        if status == "Dead" {
+            return (attempts, last_error);
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("Delivery {} never died", id);
+}
+
+#[tokio::test]
+async fn a_delivery_whose_last_attempt_never_finished_is_dead_lettered() {
+    let app = spawn_app().await;
+    let crm = MockServer::start().await;
+    let delivery = queue_a_failed_delivery(&app, &crm).await;
+    // As if the worker died while making the last attempt.
+    sqlx::query(
+        "UPDATE webhook_deliveries SET status = 'Delivering', attempts = $1, next_attempt_at = now() WHERE id = $2",
+    )
+    .bind(MAX_WEBHOOK_ATTEMPTS)
+    .bind(delivery.id)
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    let (attempts, _) = wait_for_dead_delivery(&app, delivery.id).await;
+    assert_eq!(MAX_WEBHOOK_ATTEMPTS, attempts);
+    assert_eq!(1, crm.received_requests().await.unwrap().len());
+}
+
+#[tokio::test]
+async fn a_delivery_with_an_unknown_event_type_is_dead_lettered() {
+    let app = spawn_app().await;
+    let crm = MockServer::start().await;
+    let delivery = queue_a_failed_delivery(&app, &crm).await;
+    sqlx::query(
+        "UPDATE webhook_deliveries SET event_type = 'subscription.renamed', next_attempt_at = now() WHERE id = $1",
+    )
+    .bind(delivery.id)
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    let (_, last_error) = wait_for_dead_delivery(&app, delivery.id).await;
+    assert_eq!(
+        Some("Unknown event type subscription.renamed".to_string()),
+        last_error
+    );
+    assert_eq!(1, crm.received_requests().await.unwrap().len());
+}
+
+#[tokio::test]
+async fn endpoints_are_validated_and_managed_by_admins_only() {
+    let app = spawn_app().await;
+    let (admin_user_id, token) = admin();
+    let valid = json!({
+        "url": "https://crm.example.com/hooks",
+        "event_types": ["subscription.cancelled"],
+    });
+
+    for invalid in [
+        json!({ "url": "crm.example.com", "event_types": ["subscription.cancelled"] }),
+        json!({ "url": "https://crm.example.com/hooks", "event_types": [] }),
+    ] {
+        let response = app
+            .create_webhook_endpoint(admin_user_id.clone(), invalid.to_string(), token.clone())
+            .await;
+        assert_eq!(400, response.status().as_u16());
+    }
+    let user_id = Uuid::new_v4().to_string();
+    let response = app
+        .create_webhook_endpoint(
+            user_id.clone(),
+            valid.to_string(),
+            generate_token(user_id, UserGroup::USER),
+        )
+        .await;
+    assert_eq!(401, response.status().as_u16());
+
+    let endpoint = create_endpoint(&app, valid).await;
+    assert!(endpoint.secret.starts_with("whsec_"));
+    assert!(endpoint.enabled);
+    let response = app
+        .update_webhook_endpoint(
+            admin_user_id.clone(),
+            endpoint.id.to_string(),
+            json!({
+                "url": "https://crm.example.com/v2/hooks",
+                "event_types": ["subscription.updated", "subscription.paused"],
+                "enabled": false,
+            })
+            .to_string(),
+            token.clone(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let updated: WebhookEndpoint =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert_eq!(endpoint.secret, updated.secret);
+    assert!(!updated.enabled);
+    assert_eq!(
+        vec![
+            SubscriptionEventType::Updated,
+            SubscriptionEventType::Paused
+        ],
+        updated.event_types
+    );
+
+    let response = app
+        .delete_webhook_endpoint(
+            admin_user_id.clone(),
+            endpoint.id.to_string(),
+            token.clone(),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let response = app
+        .delete_webhook_endpoint(
+            admin_user_id.clone(),
+            endpoint.id.to_string(),
+            token.clone(),
+        )
+        .await;
+    assert_eq!(404, response.status().as_u16());
+    let response = app.get_webhook_endpoints(admin_user_id, token).await;
+    let endpoints: Vec<WebhookEndpoint> =
+        serde_json::from_str(response.text().await.unwrap().as_str()).unwrap();
+    assert!(endpoints.is_empty());
+}